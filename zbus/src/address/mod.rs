@@ -1,7 +1,9 @@
 //! D-Bus address handling.
 //!
 //! Server addresses consist of a transport name followed by a colon, and then an optional,
-//! comma-separated list of keys and values in the form key=value.
+//! comma-separated list of keys and values in the form key=value. Multiple addresses can be given
+//! in a semicolon-separated list (see [`Address::parse_list`]), in which case they're tried in
+//! order until one succeeds.
 //!
 //! See also:
 //!
@@ -14,7 +16,7 @@ pub mod transport;
 use crate::{Error, Guid, OwnedGuid, Result};
 #[cfg(all(unix, not(target_os = "macos")))]
 use rustix::process::geteuid;
-use std::{collections::HashMap, env, str::FromStr};
+use std::{collections::HashMap, env, str::FromStr, time::Duration};
 
 use std::fmt::{Display, Formatter};
 
@@ -54,20 +56,69 @@ impl Address {
         &self.transport
     }
 
+    /// Parse a semicolon-separated list of addresses.
+    ///
+    /// This is the format of the `DBUS_SESSION_BUS_ADDRESS` and `DBUS_SYSTEM_BUS_ADDRESS`
+    /// environment variables. Empty entries are ignored, but the list itself can't be empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use zbus::Address;
+    ///
+    /// let addresses = Address::parse_list("unix:path=/tmp/bus;tcp:host=localhost,port=4142")?;
+    /// assert_eq!(addresses.len(), 2);
+    /// assert_eq!(addresses[1].to_string(), "tcp:host=localhost,port=4142");
+    /// # Ok::<(), zbus::Error>(())
+    /// ```
+    pub fn parse_list(addresses: &str) -> Result<Vec<Self>> {
+        let addresses = addresses
+            .split(';')
+            .filter(|address| !address.is_empty())
+            .map(Self::from_str)
+            .collect::<Result<Vec<_>>>()?;
+        if addresses.is_empty() {
+            return Err(Error::Address("empty address list".into()));
+        }
+
+        Ok(addresses)
+    }
+
     #[cfg_attr(any(target_os = "macos", windows), async_recursion::async_recursion)]
     pub(crate) async fn connect(self) -> Result<Stream> {
         self.transport.connect().await
     }
 
+    // Use `timeout` for TCP connection attempts, unless the address has its own timeout.
+    pub(crate) fn default_connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        if let Transport::Tcp(tcp) = &mut self.transport {
+            if tcp.connect_timeout.is_none() {
+                tcp.connect_timeout = timeout;
+            }
+        }
+
+        self
+    }
+
     /// Get the address for the session socket respecting the `DBUS_SESSION_BUS_ADDRESS` environment
     /// variable. If we don't recognize the value (or it's not set) we fall back to
     /// `$XDG_RUNTIME_DIR/bus`.
+    ///
+    /// If the variable holds a list of addresses, this is the first one. Use
+    /// [`connection::Builder::session`] to try them all.
+    ///
+    /// [`connection::Builder::session`]: crate::connection::Builder::session
     pub fn session() -> Result<Self> {
+        Self::session_list().map(|mut addresses| addresses.remove(0))
+    }
+
+    // All the addresses of the session bus.
+    pub(crate) fn session_list() -> Result<Vec<Self>> {
         match env::var("DBUS_SESSION_BUS_ADDRESS") {
-            Ok(val) => Self::from_str(&val),
+            Ok(val) => Self::parse_list(&val),
             _ => {
                 #[cfg(windows)]
-                return Self::from_str("autolaunch:");
+                return Self::from_str("autolaunch:").map(|a| vec![a]);
 
                 #[cfg(all(unix, not(target_os = "macos")))]
                 {
@@ -75,11 +126,12 @@ impl Address {
                         .unwrap_or_else(|_| format!("/run/user/{}", geteuid().as_raw()));
                     let path = format!("unix:path={runtime_dir}/bus");
 
-                    Self::from_str(&path)
+                    Self::from_str(&path).map(|a| vec![a])
                 }
 
                 #[cfg(target_os = "macos")]
-                return Self::from_str("launchd:env=DBUS_LAUNCHD_SESSION_BUS_SOCKET");
+                return Self::from_str("launchd:env=DBUS_LAUNCHD_SESSION_BUS_SOCKET")
+                    .map(|a| vec![a]);
             }
         }
     }
@@ -87,18 +139,30 @@ impl Address {
     /// Get the address for the system bus respecting the `DBUS_SYSTEM_BUS_ADDRESS` environment
     /// variable. If we don't recognize the value (or it's not set) we fall back to
     /// `/var/run/dbus/system_bus_socket`.
+    ///
+    /// If the variable holds a list of addresses, this is the first one. Use
+    /// [`connection::Builder::system`] to try them all.
+    ///
+    /// [`connection::Builder::system`]: crate::connection::Builder::system
     pub fn system() -> Result<Self> {
+        Self::system_list().map(|mut addresses| addresses.remove(0))
+    }
+
+    // All the addresses of the system bus.
+    pub(crate) fn system_list() -> Result<Vec<Self>> {
         match env::var("DBUS_SYSTEM_BUS_ADDRESS") {
-            Ok(val) => Self::from_str(&val),
+            Ok(val) => Self::parse_list(&val),
             _ => {
                 #[cfg(all(unix, not(target_os = "macos")))]
-                return Self::from_str("unix:path=/var/run/dbus/system_bus_socket");
+                return Self::from_str("unix:path=/var/run/dbus/system_bus_socket")
+                    .map(|a| vec![a]);
 
                 #[cfg(windows)]
-                return Self::from_str("autolaunch:");
+                return Self::from_str("autolaunch:").map(|a| vec![a]);
 
                 #[cfg(target_os = "macos")]
-                return Self::from_str("launchd:env=DBUS_LAUNCHD_SESSION_BUS_SOCKET");
+                return Self::from_str("launchd:env=DBUS_LAUNCHD_SESSION_BUS_SOCKET")
+                    .map(|a| vec![a]);
             }
         }
     }
@@ -125,6 +189,8 @@ impl FromStr for Address {
     type Err = Error;
 
     /// Parse the transport part of a D-Bus address into a `Transport`.
+    ///
+    /// Use [`Address::parse_list`] for semicolon-separated lists of addresses.
     fn from_str(address: &str) -> Result<Self> {
         use std::str::from_utf8_unchecked;
         use winnow::{
@@ -148,6 +214,12 @@ impl FromStr for Address {
             unsafe { from_utf8_unchecked(bytes) }
         });
 
+        if address.contains(';') {
+            return Err(Error::Address(
+                "expected a single address, not a list (see `Address::parse_list`)".to_string(),
+            ));
+        }
+
         (transport_parse, b':', options_parse)
             .parse(address.as_bytes())
             .map_err(|_| {
@@ -293,6 +365,16 @@ mod tests {
             Address::from_str("launchd:env=my_cool_env_key").unwrap(),
             Transport::Launchd(Launchd::new("my_cool_env_key")).into(),
         );
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            Address::from_str("systemd:").unwrap(),
+            Transport::Systemd(super::transport::Systemd::new(None)).into(),
+        );
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            Address::from_str("systemd:name=my%20socket").unwrap(),
+            Transport::Systemd(super::transport::Systemd::new(Some("my socket"))).into(),
+        );
 
         #[cfg(all(feature = "vsock", feature = "p2p", not(feature = "tokio")))]
         {
@@ -390,6 +472,19 @@ mod tests {
             Address::from(Transport::Launchd(Launchd::new("my_cool_key"))).to_string(),
             "launchd:env=my_cool_key"
         );
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            Address::from(Transport::Systemd(super::transport::Systemd::new(None))).to_string(),
+            "systemd:"
+        );
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            Address::from(Transport::Systemd(super::transport::Systemd::new(Some(
+                "my socket"
+            ))))
+            .to_string(),
+            "systemd:name=my%20socket"
+        );
 
         #[cfg(all(feature = "vsock", feature = "p2p", not(feature = "tokio")))]
         {
@@ -404,6 +499,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_address_list() {
+        let addresses =
+            Address::parse_list("tcp:host=localhost,port=4142;;unix:path=/tmp/bus;").unwrap();
+        assert_eq!(
+            addresses,
+            [
+                Transport::Tcp(Tcp::new("localhost", 4142)).into(),
+                Transport::Unix(Unix::new(UnixSocket::File("/tmp/bus".into()))).into(),
+            ]
+        );
+        assert!(Address::parse_list("").is_err());
+        assert!(Address::parse_list("tcp:host=localhost,port=4142;foo").is_err());
+        assert!(Address::from_str("tcp:host=localhost,port=4142;unix:path=/tmp/bus").is_err());
+    }
+
     #[test]
     fn connect_tcp() {
         let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
@@ -53,6 +53,10 @@ pub use launchd::Launchd;
 mod vsock_transport;
 #[cfg(target_os = "linux")]
 use std::os::linux::net::SocketAddrExt;
+#[cfg(target_os = "linux")]
+pub(crate) mod systemd;
+#[cfg(target_os = "linux")]
+pub use systemd::Systemd;
 #[cfg(any(
     all(feature = "vsock", not(feature = "tokio")),
     feature = "tokio-vsock"
@@ -86,6 +90,9 @@ pub enum Transport {
     /// A `unixexec` address.
     #[cfg(unix)]
     Unixexec(Unixexec),
+    /// A `systemd` address, referring to the sockets passed through systemd socket activation.
+    #[cfg(target_os = "linux")]
+    Systemd(Systemd),
 }
 
 impl Transport {
@@ -147,6 +154,8 @@ impl Transport {
             }
             #[cfg(unix)]
             Transport::Unixexec(unixexec) => unixexec.connect().await.map(Stream::Unixexec),
+            #[cfg(target_os = "linux")]
+            Transport::Systemd(systemd) => systemd.connect().await,
             #[cfg(all(feature = "vsock", not(feature = "tokio")))]
             Transport::Vsock(addr) => {
                 let stream = VsockStream::connect_with_cid_port(addr.cid(), addr.port())?;
@@ -237,6 +246,8 @@ impl Transport {
             "autolaunch" => Autolaunch::from_options(options).map(Self::Autolaunch),
             #[cfg(target_os = "macos")]
             "launchd" => Launchd::from_options(options).map(Self::Launchd),
+            #[cfg(target_os = "linux")]
+            "systemd" => Systemd::from_options(options).map(Self::Systemd),
 
             _ => Err(Error::Address(format!(
                 "unsupported transport '{transport}'"
@@ -364,6 +375,8 @@ impl Display for Transport {
             Self::Autolaunch(autolaunch) => write!(f, "{autolaunch}")?,
             #[cfg(target_os = "macos")]
             Self::Launchd(launchd) => write!(f, "{launchd}")?,
+            #[cfg(target_os = "linux")]
+            Self::Systemd(systemd) => write!(f, "{systemd}")?,
         }
 
         Ok(())
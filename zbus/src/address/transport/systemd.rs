@@ -0,0 +1,335 @@
+//! systemd socket activation.
+//!
+//! See [`sd_listen_fds(3)`] for the details of the protocol.
+//!
+//! [`sd_listen_fds(3)`]: https://www.freedesktop.org/software/systemd/man/latest/sd_listen_fds.html
+
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{Display, Formatter},
+    os::fd::{AsFd, FromRawFd, OwnedFd, RawFd},
+    sync::{Mutex, OnceLock},
+};
+
+use rustix::{
+    io::{fcntl_setfd, FdFlags},
+    net::{getsockname, sockopt, AddressFamily, SocketType},
+};
+use tracing::debug;
+
+use super::{encode_percents, Stream};
+use crate::{Error, Result};
+
+// The first file descriptor passed by systemd.
+const LISTEN_FDS_START: RawFd = 3;
+
+/// A `systemd:` transport in a D-Bus address.
+///
+/// This refers to the sockets passed to the process through systemd [socket activation]. For
+/// connecting, the first passed socket that is connected is used. For listening (see
+/// [`Listener`]), the first passed socket that is listening is used. If a `name` is given, only
+/// the socket of that name (as set by `FileDescriptorName=` in the socket unit) is considered.
+///
+/// Each passed socket can only be used once. The `LISTEN_PID` environment variable is checked, so
+/// sockets meant for another process are never used.
+///
+/// This transport is only available on Linux.
+///
+/// [socket activation]: https://www.freedesktop.org/software/systemd/man/latest/sd_listen_fds.html
+/// [`Listener`]: crate::Listener
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Systemd {
+    name: Option<String>,
+}
+
+impl Systemd {
+    /// Create a new `systemd:` transport, optionally for the socket of the given name.
+    pub fn new(name: Option<&str>) -> Self {
+        Self {
+            name: name.map(ToOwned::to_owned),
+        }
+    }
+
+    /// The name of the socket.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub(super) fn from_options(opts: HashMap<&str, &str>) -> Result<Self> {
+        let name = opts
+            .get("name")
+            .map(|name| {
+                let name = super::decode_percents(name)?;
+
+                String::from_utf8(name)
+                    .map_err(|_| Error::Address("systemd socket name is invalid UTF-8".into()))
+            })
+            .transpose()?;
+
+        Ok(Self { name })
+    }
+
+    pub(super) async fn connect(self) -> Result<Stream> {
+        connect(self.name()).await
+    }
+}
+
+impl Display for Systemd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("systemd:")?;
+        if let Some(name) = &self.name {
+            f.write_str("name=")?;
+            encode_percents(f, name.as_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a socket is wanted for listening, or is already connected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SocketKind {
+    Listening,
+    Connected,
+}
+
+#[derive(Debug)]
+struct ListenFd {
+    fd: RawFd,
+    name: Option<String>,
+    taken: bool,
+}
+
+#[derive(Debug, Default)]
+struct ListenFds(Vec<ListenFd>);
+
+impl ListenFds {
+    fn from_env() -> Self {
+        let var = |name| env::var(name).ok();
+
+        match Self::parse(
+            var("LISTEN_PID").as_deref(),
+            var("LISTEN_FDS").as_deref(),
+            var("LISTEN_FDNAMES").as_deref(),
+            rustix::process::getpid().as_raw_nonzero().get(),
+        ) {
+            Ok(fds) => {
+                for fd in &fds.0 {
+                    // SAFETY: The fd was passed to us by systemd and isn't owned by anything yet.
+                    let fd = unsafe { rustix::fd::BorrowedFd::borrow_raw(fd.fd) };
+                    if let Err(e) = fcntl_setfd(fd, FdFlags::CLOEXEC) {
+                        debug!("Failed to set `FD_CLOEXEC` on socket-activation fd: {e}");
+                    }
+                }
+
+                fds
+            }
+            Err(e) => {
+                debug!("Ignoring socket-activation environment: {e}");
+
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(
+        pid: Option<&str>,
+        fds: Option<&str>,
+        names: Option<&str>,
+        our_pid: i32,
+    ) -> Result<Self> {
+        let (Some(pid), Some(fds)) = (pid, fds) else {
+            return Ok(Self::default());
+        };
+        let pid: i32 = pid
+            .parse()
+            .map_err(|_| Error::Address(format!("invalid `LISTEN_PID`: {pid}")))?;
+        if pid != our_pid {
+            return Err(Error::Address(format!(
+                "`LISTEN_PID` is {pid}, while our PID is {our_pid}"
+            )));
+        }
+        let count: RawFd = fds
+            .parse()
+            .map_err(|_| Error::Address(format!("invalid `LISTEN_FDS`: {fds}")))?;
+        let mut names = names.map(|names| names.split(':'));
+        let fds = (0..count)
+            .map(|i| ListenFd {
+                fd: LISTEN_FDS_START + i,
+                name: names
+                    .as_mut()
+                    .and_then(Iterator::next)
+                    .map(ToOwned::to_owned),
+                taken: false,
+            })
+            .collect();
+
+        Ok(Self(fds))
+    }
+
+    fn take(&mut self, name: Option<&str>, kind: SocketKind) -> Result<(OwnedFd, AddressFamily)> {
+        let candidates = self
+            .0
+            .iter_mut()
+            .filter(|fd| !fd.taken && name.map_or(true, |name| fd.name.as_deref() == Some(name)));
+        for listen_fd in candidates {
+            // SAFETY: The fd is not taken yet, so it's still open and not owned by anything.
+            let fd = unsafe { rustix::fd::BorrowedFd::borrow_raw(listen_fd.fd) };
+            match validate(fd, kind) {
+                Ok(family) => {
+                    listen_fd.taken = true;
+                    // SAFETY: Same as above, and the fd is marked as taken so it's owned once.
+                    let fd = unsafe { OwnedFd::from_raw_fd(listen_fd.fd) };
+
+                    return Ok((fd, family));
+                }
+                Err(e) => debug!("Skipping socket-activation fd {}: {e}", listen_fd.fd),
+            }
+        }
+
+        Err(Error::Address(match name {
+            Some(name) => format!("no suitable socket named `{name}` was passed by systemd"),
+            None => "no suitable socket was passed by systemd".to_owned(),
+        }))
+    }
+}
+
+// Check that `fd` is a stream socket of a supported family, listening or connected as wanted.
+fn validate(fd: impl AsFd, kind: SocketKind) -> Result<AddressFamily> {
+    let fd = fd.as_fd();
+    let io_err = |e: rustix::io::Errno| Error::InputOutput(std::io::Error::from(e).into());
+    if sockopt::socket_type(fd).map_err(io_err)? != SocketType::STREAM {
+        return Err(Error::Address("not a stream socket".to_owned()));
+    }
+    let listening = sockopt::socket_acceptconn(fd).map_err(io_err)?;
+    if listening != (kind == SocketKind::Listening) {
+        return Err(Error::Address(if listening {
+            "a listening socket".to_owned()
+        } else {
+            "not a listening socket".to_owned()
+        }));
+    }
+    let family = getsockname(fd).map_err(io_err)?.address_family();
+    if ![
+        AddressFamily::UNIX,
+        AddressFamily::INET,
+        AddressFamily::INET6,
+        AddressFamily::VSOCK,
+    ]
+    .contains(&family)
+    {
+        return Err(Error::Address(format!(
+            "unsupported address family {family:?}"
+        )));
+    }
+
+    Ok(family)
+}
+
+/// Take a socket passed by systemd, optionally by its name in `LISTEN_FDNAMES`.
+///
+/// Returns the socket along with its address family.
+pub(crate) fn take_fd(name: Option<&str>, kind: SocketKind) -> Result<(OwnedFd, AddressFamily)> {
+    static LISTEN_FDS: OnceLock<Mutex<ListenFds>> = OnceLock::new();
+
+    LISTEN_FDS
+        .get_or_init(|| Mutex::new(ListenFds::from_env()))
+        .lock()
+        .expect("lock poisoned")
+        .take(name, kind)
+}
+
+/// Connect through a connected socket passed by systemd.
+pub(crate) async fn connect(name: Option<&str>) -> Result<Stream> {
+    let (fd, family) = take_fd(name, SocketKind::Connected)?;
+
+    match family {
+        AddressFamily::UNIX => {
+            let stream = std::os::unix::net::UnixStream::from(fd);
+            stream.set_nonblocking(true)?;
+            #[cfg(not(feature = "tokio"))]
+            let stream = async_io::Async::new(stream)?;
+            #[cfg(feature = "tokio")]
+            let stream = tokio::net::UnixStream::from_std(stream)?;
+
+            Ok(Stream::Unix(stream))
+        }
+        AddressFamily::INET | AddressFamily::INET6 => {
+            let stream = std::net::TcpStream::from(fd);
+            stream.set_nonblocking(true)?;
+            #[cfg(not(feature = "tokio"))]
+            let stream = async_io::Async::new(stream)?;
+            #[cfg(feature = "tokio")]
+            let stream = tokio::net::TcpStream::from_std(stream)?;
+
+            Ok(Stream::Tcp(stream))
+        }
+        #[cfg(all(feature = "vsock", not(feature = "tokio")))]
+        AddressFamily::VSOCK => {
+            use std::os::fd::IntoRawFd;
+
+            // SAFETY: We own the fd.
+            let stream = unsafe { vsock::VsockStream::from_raw_fd(fd.into_raw_fd()) };
+            async_io::Async::new(stream)
+                .map(Stream::Vsock)
+                .map_err(Into::into)
+        }
+        _ => Err(Error::Unsupported),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::{
+        fd::{AsRawFd, IntoRawFd},
+        unix::net::{UnixListener, UnixStream},
+    };
+
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn parse() {
+        // Not for us.
+        assert!(ListenFds::parse(Some("1"), Some("2"), None, 2).is_err());
+        // Not socket-activated.
+        assert!(ListenFds::parse(None, None, None, 2).unwrap().0.is_empty());
+        assert!(ListenFds::parse(Some("2"), Some("two"), None, 2).is_err());
+
+        let fds = ListenFds::parse(Some("2"), Some("3"), Some("a:b"), 2).unwrap();
+        let fds: Vec<_> = fds.0.iter().map(|fd| (fd.fd, fd.name.as_deref())).collect();
+        assert_eq!(fds, [(3, Some("a")), (4, Some("b")), (5, None)]);
+    }
+
+    #[test]
+    fn take() {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = UnixListener::bind(dir.path().join("socket")).unwrap();
+        let (connected, _peer) = UnixStream::pair().unwrap();
+        let file = std::fs::File::open(dir.path()).unwrap();
+        let listen_fd = |fd: RawFd, name: &str| ListenFd {
+            fd,
+            name: Some(name.to_owned()),
+            taken: false,
+        };
+        let mut fds = ListenFds(vec![
+            listen_fd(file.as_raw_fd(), "file"),
+            listen_fd(connected.as_raw_fd(), "connected"),
+            listen_fd(listener.as_raw_fd(), "listener"),
+        ]);
+
+        // Unsuitable sockets are skipped, or rejected if asked for by name.
+        assert!(fds.take(Some("file"), SocketKind::Connected).is_err());
+        assert!(fds.take(Some("listener"), SocketKind::Connected).is_err());
+        let (fd, family) = fds.take(None, SocketKind::Listening).unwrap();
+        assert_eq!(family, AddressFamily::UNIX);
+        assert_eq!(fd.into_raw_fd(), listener.into_raw_fd());
+        let (fd, _) = fds.take(Some("connected"), SocketKind::Connected).unwrap();
+        assert_eq!(fd.into_raw_fd(), connected.into_raw_fd());
+
+        // Each socket can only be taken once.
+        assert!(fds.take(None, SocketKind::Listening).is_err());
+        assert!(fds.take(None, SocketKind::Connected).is_err());
+    }
+}
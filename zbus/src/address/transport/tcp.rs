@@ -1,17 +1,30 @@
 use super::encode_percents;
-use crate::{Error, Result};
+use crate::{
+    timeout::{sleep, timeout},
+    Error, Result,
+};
 #[cfg(not(feature = "tokio"))]
 use async_io::Async;
 #[cfg(not(feature = "tokio"))]
-use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::net::TcpStream;
 use std::{
     collections::HashMap,
     fmt::{Display, Formatter},
+    future::{poll_fn, Future},
+    io,
+    net::{SocketAddr, ToSocketAddrs},
+    pin::Pin,
     str::FromStr,
+    task::Poll,
+    time::Duration,
 };
 #[cfg(feature = "tokio")]
 use tokio::net::TcpStream;
 
+// How long to wait for a connection attempt before starting the next one in parallel, as
+// recommended by RFC 8305 ("Happy Eyeballs").
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
 /// A TCP transport in a D-Bus address.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Tcp {
@@ -20,6 +33,7 @@ pub struct Tcp {
     pub(super) port: u16,
     pub(super) family: Option<TcpTransportFamily>,
     pub(super) nonce_file: Option<Vec<u8>>,
+    pub(crate) connect_timeout: Option<Duration>,
 }
 
 impl Tcp {
@@ -31,6 +45,7 @@ impl Tcp {
             bind: None,
             family: None,
             nonce_file: None,
+            connect_timeout: None,
         }
     }
 
@@ -55,6 +70,17 @@ impl Tcp {
         self
     }
 
+    /// Set the timeout of each connection attempt.
+    ///
+    /// The host can resolve to multiple socket addresses, each of which is tried in turn (see
+    /// [`Tcp::connect_timeout`]). This timeout applies to each of these attempts. It's not part of
+    /// the D-Bus address, so it's not included in the string representation of the address.
+    pub fn set_connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+
+        self
+    }
+
     /// The `tcp:` address `host` value.
     pub fn host(&self) -> &str {
         &self.host
@@ -75,6 +101,17 @@ impl Tcp {
         self.family
     }
 
+    /// The timeout of each connection attempt, if any.
+    ///
+    /// When connecting, the host is resolved to all its socket addresses, of the requested
+    /// `family` if any. These are tried in the order recommended by RFC 8305 ("Happy Eyeballs"),
+    /// alternating between IPv6 and IPv4. If an attempt hasn't succeeded after 250ms, the next one
+    /// is started in parallel, and the first one to succeed wins. Without a timeout, attempts to
+    /// unreachable addresses are only given up by the OS.
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout
+    }
+
     /// The nonce file path, if any.
     pub fn nonce_file(&self) -> Option<&[u8]> {
         self.nonce_file.as_deref()
@@ -124,48 +161,157 @@ impl Tcp {
             port,
             family,
             nonce_file,
+            connect_timeout: None,
         })
     }
 
-    #[cfg(not(feature = "tokio"))]
-    pub(super) async fn connect(self) -> Result<Async<TcpStream>> {
-        let addrs = crate::Task::spawn_blocking(
+    /// Resolve the host to all its socket addresses of the requested family.
+    pub(crate) async fn resolve(&self) -> Result<Vec<SocketAddr>> {
+        let host = self.host().to_owned();
+        let port = self.port();
+        let family = self.family();
+
+        crate::Task::spawn_blocking(
             move || -> Result<Vec<SocketAddr>> {
-                let addrs = (self.host(), self.port()).to_socket_addrs()?.filter(|a| {
-                    if let Some(family) = self.family() {
-                        if family == TcpTransportFamily::Ipv4 {
-                            a.is_ipv4()
-                        } else {
-                            a.is_ipv6()
-                        }
-                    } else {
-                        true
-                    }
-                });
+                let addrs = (host.as_str(), port)
+                    .to_socket_addrs()?
+                    .filter(|a| match family {
+                        Some(TcpTransportFamily::Ipv4) => a.is_ipv4(),
+                        Some(TcpTransportFamily::Ipv6) => a.is_ipv6(),
+                        None => true,
+                    });
+
                 Ok(addrs.collect())
             },
-            "connect tcp",
+            "resolve tcp",
         )
         .await
-        .map_err(|e| Error::Address(format!("Failed to receive TCP addresses: {e}")))??;
-
-        // we could attempt connections in parallel?
-        let mut last_err = Error::Address("Failed to connect".into());
-        for addr in addrs {
-            match Async::<TcpStream>::connect(addr).await {
-                Ok(stream) => return Ok(stream),
-                Err(e) => last_err = e.into(),
-            }
-        }
+        .map_err(|e| Error::Address(format!("Failed to receive TCP addresses: {e}")))?
+    }
 
-        Err(last_err)
+    #[cfg(not(feature = "tokio"))]
+    pub(super) async fn connect(self) -> Result<Async<TcpStream>> {
+        let addrs = self.resolve().await?;
+
+        connect_any(addrs, self.connect_timeout, Async::<TcpStream>::connect).await
     }
 
     #[cfg(feature = "tokio")]
     pub(super) async fn connect(self) -> Result<TcpStream> {
-        TcpStream::connect((self.host(), self.port()))
-            .await
-            .map_err(|e| Error::InputOutput(e.into()))
+        let addrs = self.resolve().await?;
+
+        connect_any(addrs, self.connect_timeout, TcpStream::connect).await
+    }
+}
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+// Connect to the first of `addrs` that accepts the connection, Happy Eyeballs style.
+async fn connect_any<S, F, Fut>(
+    addrs: Vec<SocketAddr>,
+    connect_timeout: Option<Duration>,
+    connect: F,
+) -> Result<S>
+where
+    S: Send + 'static,
+    F: Fn(SocketAddr) -> Fut,
+    Fut: Future<Output = io::Result<S>> + Send + 'static,
+{
+    let mut addrs = sort_addrs(addrs).into_iter();
+    let mut attempts: Vec<(SocketAddr, BoxFuture<Result<S>>)> = Vec::new();
+    let mut errors = Vec::new();
+    // Until this elapses, the next attempt waits for the pending ones.
+    let mut delay: Option<BoxFuture<()>> = None;
+
+    poll_fn(|cx| loop {
+        let start_next = attempts.is_empty()
+            || delay
+                .as_mut()
+                .is_some_and(|delay| delay.as_mut().poll(cx).is_ready());
+        if start_next {
+            delay = None;
+            match addrs.next() {
+                Some(addr) => {
+                    let attempt = connect(addr);
+                    let attempt = async move { attempt.await.map_err(Error::from) };
+                    let attempt: BoxFuture<_> = match connect_timeout {
+                        Some(connect_timeout) => Box::pin(timeout(attempt, connect_timeout)),
+                        None => Box::pin(attempt),
+                    };
+                    attempts.push((addr, attempt));
+                    if addrs.len() > 0 {
+                        delay = Some(Box::pin(sleep(ATTEMPT_DELAY)));
+                    }
+                }
+                None if attempts.is_empty() => {
+                    return Poll::Ready(Err(aggregate_errors(std::mem::take(&mut errors))));
+                }
+                None => (),
+            }
+        }
+
+        let mut failed = false;
+        let mut i = 0;
+        while i < attempts.len() {
+            match attempts[i].1.as_mut().poll(cx) {
+                Poll::Ready(Ok(stream)) => return Poll::Ready(Ok(stream)),
+                Poll::Ready(Err(e)) => {
+                    let (addr, _) = attempts.swap_remove(i);
+                    tracing::debug!("Failed to connect to `{addr}`: {e}");
+                    errors.push((addr, e));
+                    failed = true;
+                }
+                Poll::Pending => i += 1,
+            }
+        }
+        if failed {
+            // No need to wait any longer for the next attempt.
+            delay = Some(Box::pin(std::future::ready(())));
+        } else if !start_next {
+            return Poll::Pending;
+        }
+    })
+    .await
+}
+
+// Interleave IPv6 and IPv4 addresses, starting with the family of the first address.
+fn sort_addrs(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_is_ipv6 = addrs.first().is_some_and(SocketAddr::is_ipv6);
+    let mut sorted = Vec::with_capacity(addrs.len());
+    let (first, second): (Vec<_>, Vec<_>) = addrs
+        .into_iter()
+        .partition(|addr| addr.is_ipv6() == first_is_ipv6);
+    let (mut first, mut second) = (first.into_iter(), second.into_iter());
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => break,
+            (a, b) => sorted.extend(a.into_iter().chain(b)),
+        }
+    }
+
+    sorted
+}
+
+fn aggregate_errors(mut errors: Vec<(SocketAddr, Error)>) -> Error {
+    match errors.len() {
+        0 => Error::Address("host resolved to no address".into()),
+        1 => errors.remove(0).1,
+        _ => {
+            let kind = match &errors[errors.len() - 1].1 {
+                Error::InputOutput(e) => e.kind(),
+                _ => io::ErrorKind::Other,
+            };
+            let errors: Vec<_> = errors
+                .iter()
+                .map(|(addr, e)| format!("`{addr}`: {e}"))
+                .collect();
+
+            io::Error::new(
+                kind,
+                format!("Failed to connect to any address ({})", errors.join(", ")),
+            )
+            .into()
+        }
     }
 }
 
@@ -227,3 +373,98 @@ impl Display for TcpTransportFamily {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        time::Instant,
+    };
+    use test_log::test;
+
+    fn v4(port: u16) -> SocketAddr {
+        (IpAddr::V4(Ipv4Addr::LOCALHOST), port).into()
+    }
+
+    fn v6(port: u16) -> SocketAddr {
+        (IpAddr::V6(Ipv6Addr::LOCALHOST), port).into()
+    }
+
+    #[test]
+    fn sort_addrs() {
+        assert_eq!(
+            super::sort_addrs(vec![v6(1), v6(2), v6(3), v4(4)]),
+            [v6(1), v4(4), v6(2), v6(3)]
+        );
+        assert_eq!(
+            super::sort_addrs(vec![v4(1), v4(2), v6(3), v6(4)]),
+            [v4(1), v6(3), v4(2), v6(4)]
+        );
+        assert!(super::sort_addrs(vec![]).is_empty());
+    }
+
+    #[test]
+    fn connect_any() {
+        crate::utils::block_on(async {
+            // A hanging address doesn't hold back the next one.
+            let start = Instant::now();
+            let port = super::connect_any(vec![v6(1), v4(2)], None, |addr| async move {
+                if addr.port() == 1 {
+                    std::future::pending::<()>().await;
+                }
+
+                Ok(addr.port())
+            })
+            .await
+            .unwrap();
+            assert_eq!(port, 2);
+            assert!(start.elapsed() >= ATTEMPT_DELAY);
+
+            // A failed attempt doesn't wait for the delay.
+            let start = Instant::now();
+            let port = super::connect_any(vec![v4(1), v4(2)], None, |addr| async move {
+                match addr.port() {
+                    1 => Err(io::ErrorKind::ConnectionRefused.into()),
+                    port => Ok(port),
+                }
+            })
+            .await
+            .unwrap();
+            assert_eq!(port, 2);
+            assert!(start.elapsed() < ATTEMPT_DELAY);
+
+            // Attempts time out, and the errors of all addresses are reported.
+            let res = super::connect_any(
+                vec![v4(1), v4(2)],
+                Some(Duration::from_millis(10)),
+                |_| async {
+                    std::future::pending::<()>().await;
+
+                    Ok(())
+                },
+            )
+            .await;
+            match res {
+                Err(Error::InputOutput(e)) => {
+                    assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+                    assert!(e.to_string().contains("127.0.0.1:1"));
+                    assert!(e.to_string().contains("127.0.0.1:2"));
+                }
+                res => panic!("unexpected result: {res:?}"),
+            }
+
+            // A single failure is reported as is.
+            let res = super::connect_any(vec![v4(1)], None, |_| async {
+                Err::<(), _>(io::ErrorKind::ConnectionRefused.into())
+            })
+            .await;
+            match res {
+                Err(Error::InputOutput(e)) => {
+                    assert_eq!(e.kind(), io::ErrorKind::ConnectionRefused)
+                }
+                res => panic!("unexpected result: {res:?}"),
+            }
+        });
+    }
+}
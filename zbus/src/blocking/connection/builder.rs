@@ -9,13 +9,27 @@ use tokio::net::UnixStream;
 #[cfg(all(windows, not(feature = "tokio")))]
 use uds_windows::UnixStream;
 
+use std::sync::Arc;
+
 use zvariant::ObjectPath;
 
 #[cfg(feature = "p2p")]
 use crate::Guid;
 use crate::{
-    address::Address, blocking::Connection, conn::AuthMechanism, connection::socket::BoxedSplit,
-    names::WellKnownName, object_server::Interface, utils::block_on, Error, Result,
+    address::Address,
+    blocking::Connection,
+    conn::{sasl, AuthMechanism},
+    connection::{
+        socket::{
+            record::{Capture, Recorder},
+            BoxedSplit,
+        },
+        MetricsSink, TracePropagator,
+    },
+    names::WellKnownName,
+    object_server::{AccessPolicy, Interface},
+    utils::block_on,
+    Error, Result,
 };
 
 /// A builder for [`zbus::blocking::Connection`].
@@ -82,6 +96,13 @@ impl<'a> Builder<'a> {
         crate::connection::Builder::authenticated_socket(socket, guid).map(Self)
     }
 
+    /// Create a builder for a connection replaying the given capture.
+    ///
+    /// See [`crate::connection::Builder::replay`] for details.
+    pub fn replay(capture: &Capture) -> Self {
+        Self(crate::connection::Builder::replay(capture))
+    }
+
     /// Create a builder for a connection that will use the given socket.
     pub fn socket<S: Into<BoxedSplit>>(socket: S) -> Self {
         Self(crate::connection::Builder::socket(socket))
@@ -92,6 +113,29 @@ impl<'a> Builder<'a> {
         Self(self.0.auth_mechanism(auth_mechanism))
     }
 
+    /// Use a custom authentication mechanism on the client-side of the handshake.
+    ///
+    /// This takes precedence over [`Builder::auth_mechanism`]. See the [`sasl`] module for
+    /// details and an example.
+    ///
+    /// [`sasl`]: crate::connection::sasl
+    pub fn client_auth_mechanism(self, mechanism: Box<dyn sasl::ClientMechanism>) -> Self {
+        Self(self.0.client_auth_mechanism(mechanism))
+    }
+
+    /// Use a custom authentication mechanism on the server-side of the handshake.
+    ///
+    /// This takes precedence over [`Builder::auth_mechanism`]. See the [`sasl`] module for
+    /// details and an example.
+    ///
+    /// This method is only available when the `p2p` feature is enabled.
+    ///
+    /// [`sasl`]: crate::connection::sasl
+    #[cfg(feature = "p2p")]
+    pub fn server_auth_mechanism(self, mechanism: Box<dyn sasl::ServerMechanism>) -> Self {
+        Self(self.0.server_auth_mechanism(mechanism))
+    }
+
     /// Specify the user id during authentication.
     ///
     /// This can be useful when using [`AuthMechanism::External`] with `socat`
@@ -102,6 +146,31 @@ impl<'a> Builder<'a> {
         Self(self.0.user_id(id))
     }
 
+    /// Set the cookie context (keyring name) to use for [`AuthMechanism::Cookie`].
+    ///
+    /// This is only used on the server-side of the handshake, as the client is told by the server
+    /// which keyring to use. Defaults to `org_freedesktop_general`.
+    ///
+    /// This method is only available when both the `p2p` and `cookie-sha1` features are enabled.
+    #[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+    pub fn cookie_context<C>(self, context: C) -> Result<Self>
+    where
+        C: Into<zvariant::Str<'a>>,
+    {
+        self.0.cookie_context(context).map(Self)
+    }
+
+    /// Set the ID of the cookie to use for [`AuthMechanism::Cookie`].
+    ///
+    /// This is only used on the server-side of the handshake. By default, the server uses the most
+    /// recent cookie of the keyring.
+    ///
+    /// This method is only available when both the `p2p` and `cookie-sha1` features are enabled.
+    #[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+    pub fn cookie_id(self, id: usize) -> Self {
+        Self(self.0.cookie_id(id))
+    }
+
     /// The to-be-created connection will be a peer-to-peer connection.
     ///
     /// This method is only available when the `p2p` feature is enabled.
@@ -167,6 +236,19 @@ impl<'a> Builder<'a> {
         self.0.serve_at(path, iface).map(Self)
     }
 
+    /// Set the access policy for the objects at the given path and beneath it.
+    ///
+    /// This is similar to [`zbus::blocking::ObjectServer::set_access_policy`], except that the
+    /// policy is in place before any interface registered through [`Builder::serve_at`] is served.
+    pub fn access_policy<P, A>(self, path: P, policy: A) -> Result<Self>
+    where
+        A: AccessPolicy,
+        P: TryInto<ObjectPath<'a>>,
+        P::Error: Into<Error>,
+    {
+        self.0.access_policy(path, policy).map(Self)
+    }
+
     /// Register a well-known name for this connection on the bus.
     ///
     /// This is similar to [`zbus::blocking::Connection::request_name`], except the name is
@@ -212,6 +294,13 @@ impl<'a> Builder<'a> {
         self.0.unique_name(unique_name).map(Self)
     }
 
+    /// Automatically reconnect if the connection to the peer is lost.
+    ///
+    /// See [`crate::connection::Builder::auto_reconnect`] for details.
+    pub fn auto_reconnect(self, enabled: bool) -> Self {
+        Self(self.0.auto_reconnect(enabled))
+    }
+
     /// Set a timeout for method calls.
     ///
     /// Method calls will return
@@ -221,6 +310,28 @@ impl<'a> Builder<'a> {
         Self(self.0.method_timeout(timeout))
     }
 
+    /// Record all the messages sent and received by the connection.
+    ///
+    /// Recording starts right after the authentication handshake. See the
+    /// [`record`](crate::connection::socket::record) module for details.
+    pub fn record(self, recorder: Recorder) -> Self {
+        Self(self.0.record(recorder))
+    }
+
+    /// Instrument the connection with the given metrics sink.
+    ///
+    /// See [`MetricsSink`] for details.
+    pub fn metrics_sink(self, sink: Arc<dyn MetricsSink>) -> Self {
+        Self(self.0.metrics_sink(sink))
+    }
+
+    /// Propagate the trace context across the method calls of the connection.
+    ///
+    /// See [`TracePropagator`] for details.
+    pub fn trace_propagator(self, propagator: Arc<dyn TracePropagator>) -> Self {
+        Self(self.0.trace_propagator(propagator))
+    }
+
     /// Build the connection, consuming the builder.
     ///
     /// # Errors
@@ -3,11 +3,16 @@
 use enumflags2::BitFlags;
 use event_listener::EventListener;
 use std::{io, ops::Deref, sync::Arc};
-use zbus_names::{BusName, ErrorName, InterfaceName, MemberName, OwnedUniqueName, WellKnownName};
+use zbus_names::{
+    BusName, ErrorName, InterfaceName, MemberName, OwnedBusName, OwnedUniqueName, WellKnownName,
+};
 use zvariant::ObjectPath;
 
 use crate::{
     blocking::ObjectServer,
+    connection::{
+        ConnectionState, ConnectionStateStream, NameEvent, NameWatchStream, WatchNameFlags,
+    },
     fdo::{ConnectionCredentials, RequestNameFlags, RequestNameReply},
     message::Message,
     utils::block_on,
@@ -57,6 +62,13 @@ impl Connection {
         self.inner.unique_name()
     }
 
+    /// The identity the peer authenticated as, if known.
+    ///
+    /// See [`crate::Connection::authenticated_identity`] for details.
+    pub fn authenticated_identity(&self) -> Option<&str> {
+        self.inner.authenticated_identity()
+    }
+
     /// Send `msg` to the peer.
     pub fn send(&self, msg: &Message) -> Result<()> {
         block_on(self.inner.send(msg))
@@ -210,6 +222,41 @@ impl Connection {
         block_on(self.inner.release_name(well_known_name))
     }
 
+    /// Watch the ownership of a bus name.
+    ///
+    /// Blocking version of [`crate::Connection::watch_name`]. See docs there for more details.
+    pub fn watch_name<'n, N>(&self, name: N) -> Result<NameWatchIterator>
+    where
+        N: TryInto<BusName<'n>>,
+        N::Error: Into<Error>,
+    {
+        block_on(self.inner.watch_name(name)).map(NameWatchIterator)
+    }
+
+    /// Watch the ownership of a bus name.
+    ///
+    /// Blocking version of [`crate::Connection::watch_name_with_flags`]. See docs there for more
+    /// details.
+    pub fn watch_name_with_flags<'n, N>(
+        &self,
+        name: N,
+        flags: BitFlags<WatchNameFlags>,
+    ) -> Result<NameWatchIterator>
+    where
+        N: TryInto<BusName<'n>>,
+        N::Error: Into<Error>,
+    {
+        block_on(self.inner.watch_name_with_flags(name, flags)).map(NameWatchIterator)
+    }
+
+    /// An iterator over the [`ConnectionState`] changes of the connection.
+    ///
+    /// Blocking version of [`crate::Connection::receive_state_changes`]. See docs there for more
+    /// details.
+    pub fn receive_state_changes(&self) -> ConnectionStateIterator {
+        ConnectionStateIterator(self.inner.receive_state_changes())
+    }
+
     /// Check if `self` is a connection to a message bus.
     ///
     /// This will return `false` for p2p connections.
@@ -299,6 +346,47 @@ impl Connection {
     }
 }
 
+/// An [`std::iter::Iterator`] implementation that yields [`NameEvent`]s for a bus name.
+///
+/// Use [`Connection::watch_name`] to create an instance of this type.
+#[derive(Debug)]
+pub struct NameWatchIterator(NameWatchStream);
+
+impl NameWatchIterator {
+    /// The bus name being watched.
+    pub fn name(&self) -> &OwnedBusName {
+        self.0.name()
+    }
+
+    /// The owner of the name, according to the latest ownership change received.
+    pub fn owner(&self) -> Option<&OwnedUniqueName> {
+        self.0.owner()
+    }
+}
+
+impl std::iter::Iterator for NameWatchIterator {
+    type Item = NameEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        block_on(futures_lite::StreamExt::next(&mut self.0))
+    }
+}
+
+/// An [`std::iter::Iterator`] implementation that yields the [`ConnectionState`] changes of a
+/// connection.
+///
+/// Use [`Connection::receive_state_changes`] to create an instance of this type.
+#[derive(Debug)]
+pub struct ConnectionStateIterator(ConnectionStateStream);
+
+impl std::iter::Iterator for ConnectionStateIterator {
+    type Item = ConnectionState;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        block_on(futures_lite::StreamExt::next(&mut self.0))
+    }
+}
+
 impl From<crate::Connection> for Connection {
     fn from(conn: crate::Connection) -> Self {
         Self { inner: conn }
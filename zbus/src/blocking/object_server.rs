@@ -3,7 +3,9 @@
 use zvariant::ObjectPath;
 
 use crate::{
-    object_server::{Interface, InterfaceDeref, InterfaceDerefMut, SignalEmitter},
+    object_server::{
+        AccessPolicy, Interface, InterfaceDeref, InterfaceDerefMut, Objects, SignalEmitter,
+    },
     utils::block_on,
     Error, Result,
 };
@@ -158,10 +160,17 @@ impl ObjectServer {
         block_on(self.azync.at(path, iface))
     }
 
+    /// Register multiple interfaces, possibly at multiple paths, at once.
+    ///
+    /// See [`crate::ObjectServer::add_objects`] for details.
+    pub fn add_objects(&self, objects: Objects) -> Result<()> {
+        block_on(self.azync.add_objects(objects))
+    }
+
     /// Unregister a D-Bus [`crate::object_server::Interface`] at a given path.
     ///
     /// If there are no more interfaces left at that path, destroys the object as well.
-    /// Returns whether the object was destroyed.
+    /// Returns whether the object was destroyed. The objects beneath it are not affected.
     pub fn remove<'p, I, P>(&self, path: P) -> Result<bool>
     where
         I: Interface,
@@ -171,6 +180,29 @@ impl ObjectServer {
         block_on(self.azync.remove::<I, P>(path))
     }
 
+    /// Set the access policy for the objects at the given path and beneath it.
+    ///
+    /// See [`crate::ObjectServer::set_access_policy`] for details.
+    pub fn set_access_policy<'p, P, A>(&self, path: P, policy: A) -> Result<()>
+    where
+        P: TryInto<ObjectPath<'p>>,
+        P::Error: Into<Error>,
+        A: AccessPolicy,
+    {
+        self.azync.set_access_policy(path, policy)
+    }
+
+    /// Remove the access policy set for the given path, if any.
+    ///
+    /// See [`crate::ObjectServer::remove_access_policy`] for details.
+    pub fn remove_access_policy<'p, P>(&self, path: P) -> Result<bool>
+    where
+        P: TryInto<ObjectPath<'p>>,
+        P::Error: Into<Error>,
+    {
+        self.azync.remove_access_policy(path)
+    }
+
     /// Get the interface at the given path.
     ///
     /// # Errors
@@ -153,6 +153,18 @@ impl<'a> Proxy<'a> {
         self.inner().interface()
     }
 
+    /// A proxy sharing everything with `self`, except for the timeout of its method calls.
+    ///
+    /// See [`crate::Proxy::with_timeout`] for details.
+    pub fn with_timeout(&self, timeout: std::time::Duration) -> Proxy<'a> {
+        self.inner().with_timeout(timeout).into()
+    }
+
+    /// The timeout set through [`Proxy::with_timeout`], if any.
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        self.inner().timeout()
+    }
+
     /// Introspect the associated object, and return the XML description.
     ///
     /// See the [xml](https://docs.rs/zbus_xml) crate for parsing the result.
@@ -0,0 +1,354 @@
+use enumflags2::BitFlags;
+use serde::Serialize;
+use std::collections::HashMap;
+use zvariant::{DynamicType, Value};
+
+use crate::{
+    fdo::{self, ConnectionCredentials, StartServiceReply},
+    message::{Message, Type},
+    names::{BusName, OwnedUniqueName, WellKnownName},
+    MatchRule, Result,
+};
+
+use super::router::{
+    deliver, error_reply, expects_reply, name_owner_changed, Delivery, Router, BUS_NAME,
+};
+
+impl Router {
+    /// Handle a method call to the bus itself.
+    pub(super) async fn handle_bus_call(
+        &self,
+        sender: &OwnedUniqueName,
+        msg: &Message,
+    ) -> Result<()> {
+        if msg.message_type() != Type::MethodCall {
+            return Ok(());
+        }
+        let Some(conn) = self
+            .state
+            .lock()
+            .await
+            .peers
+            .get(sender)
+            .map(|p| p.conn.clone())
+        else {
+            return Ok(());
+        };
+
+        let mut signals = vec![];
+        let reply = match self.call(sender, msg, &mut signals).await {
+            Ok(reply) => reply,
+            Err(e) => error_reply(msg, &e)?,
+        };
+        let mut deliveries = Vec::with_capacity(signals.len() + 1);
+        // The reply to `Hello` must come before the `NameAcquired` signal, while for all other
+        // calls the signals are emitted before replying.
+        let hello = msg.header().member().map(|m| m.as_str()) == Some("Hello");
+        if !hello {
+            deliveries.append(&mut signals);
+        }
+        if expects_reply(msg) {
+            deliveries.push((conn, reply));
+        }
+        deliveries.append(&mut signals);
+
+        deliver(deliveries).await;
+
+        Ok(())
+    }
+
+    async fn call(
+        &self,
+        sender: &OwnedUniqueName,
+        msg: &Message,
+        signals: &mut Vec<Delivery>,
+    ) -> fdo::Result<Message> {
+        let hdr = msg.header();
+        let interface = hdr.interface().map(|i| i.as_str()).unwrap_or(BUS_NAME);
+        let member = hdr.member().map(|m| m.as_str()).unwrap_or_default();
+        let body = msg.body();
+
+        if member != "Hello" {
+            let state = self.state.lock().await;
+            if !state.peers.get(sender).is_some_and(|p| p.registered) {
+                return Err(fdo::Error::AccessDenied(
+                    "Client tried to send a message other than Hello without being registered"
+                        .into(),
+                ));
+            }
+        }
+
+        match (interface, member) {
+            (BUS_NAME, "Hello") => {
+                let mut state = self.state.lock().await;
+                let peer = state
+                    .peers
+                    .get_mut(sender)
+                    .ok_or_else(|| fdo::Error::Disconnected(sender.to_string()))?;
+                if peer.registered {
+                    return Err(fdo::Error::Failed(
+                        "Already handled an Hello message".into(),
+                    ));
+                }
+                peer.registered = true;
+                let change = (sender.as_str(), "", sender.as_str());
+                state.broadcast(name_owner_changed(change), signals);
+                state.unicast_signal(sender, "NameAcquired", sender.as_str(), signals);
+
+                reply(msg, &sender)
+            }
+            (BUS_NAME, "RequestName") => {
+                let (name, flags) = args::<(WellKnownName<'_>, u32)>(&body)?;
+                if name.as_str() == BUS_NAME {
+                    return Err(fdo::Error::InvalidArgs(format!(
+                        "Connection is not allowed to own the service {BUS_NAME} because it is \
+                        reserved for D-Bus' use only"
+                    )));
+                }
+                let flags = BitFlags::from_bits_truncate(flags);
+
+                let mut state = self.state.lock().await;
+                let (ret, change) = state.names.request(name, sender.clone(), flags);
+                if let Some(change) = change {
+                    state.owner_changed(change, signals);
+                }
+
+                reply(msg, &ret)
+            }
+            (BUS_NAME, "ReleaseName") => {
+                let name = args::<WellKnownName<'_>>(&body)?;
+                if name.as_str() == BUS_NAME {
+                    return Err(fdo::Error::InvalidArgs(format!(
+                        "Connection is not allowed to release the service {BUS_NAME} because it \
+                        is reserved for D-Bus' use only"
+                    )));
+                }
+
+                let mut state = self.state.lock().await;
+                let (ret, change) = state.names.release(&name, sender);
+                if let Some(change) = change {
+                    state.owner_changed(change, signals);
+                }
+
+                reply(msg, &ret)
+            }
+            (BUS_NAME, "ListQueuedOwners") => {
+                let name = args::<WellKnownName<'_>>(&body)?;
+                if name.as_str() == BUS_NAME {
+                    return reply(msg, &vec![BUS_NAME]);
+                }
+                let owners = self.state.lock().await.names.queued_owners(&name);
+
+                match owners {
+                    Some(owners) => reply(msg, &owners),
+                    None => Err(no_owner(&name)),
+                }
+            }
+            (BUS_NAME, "ListNames") => {
+                let state = self.state.lock().await;
+                let unique_names = state
+                    .peers
+                    .iter()
+                    .filter(|(_, peer)| peer.registered)
+                    .map(|(name, _)| name.as_str());
+                let well_known_names = state.names.names().map(|n| n.as_str());
+                let names: Vec<_> = std::iter::once(BUS_NAME)
+                    .chain(unique_names)
+                    .chain(well_known_names)
+                    .collect();
+
+                reply(msg, &names)
+            }
+            (BUS_NAME, "ListActivatableNames") => reply(msg, &vec![BUS_NAME]),
+            (BUS_NAME, "NameHasOwner") => {
+                let name = args::<BusName<'_>>(&body)?;
+                let has_owner = name.as_str() == BUS_NAME
+                    || self.state.lock().await.owner_conn(&name).is_some();
+
+                reply(msg, &has_owner)
+            }
+            (BUS_NAME, "GetNameOwner") => {
+                let name = args::<BusName<'_>>(&body)?;
+                if name.as_str() == BUS_NAME {
+                    return reply(msg, &BUS_NAME);
+                }
+                let state = self.state.lock().await;
+
+                match state.owner_conn(&name) {
+                    Some((owner, _)) => reply(msg, owner),
+                    None => Err(no_owner(&name)),
+                }
+            }
+            (BUS_NAME, "StartServiceByName") => {
+                let (name, _flags) = args::<(WellKnownName<'_>, u32)>(&body)?;
+                let name = BusName::from(name);
+                if self.state.lock().await.owner_conn(&name).is_some() {
+                    return reply(msg, &StartServiceReply::AlreadyRunning);
+                }
+
+                Err(fdo::Error::ServiceUnknown(format!(
+                    "The name {name} was not provided by any .service files"
+                )))
+            }
+            (BUS_NAME, "AddMatch") => {
+                let rule = args::<&str>(&body)?;
+                let rule = MatchRule::try_from(rule)
+                    .map_err(|e| fdo::Error::MatchRuleInvalid(e.to_string()))?
+                    .to_owned();
+
+                let mut state = self.state.lock().await;
+                if let Some(peer) = state.peers.get_mut(sender) {
+                    peer.match_rules.push(rule.into());
+                }
+
+                reply(msg, &())
+            }
+            (BUS_NAME, "RemoveMatch") => {
+                let rule = args::<&str>(&body)?;
+                let rule = MatchRule::try_from(rule)
+                    .map_err(|e| fdo::Error::MatchRuleInvalid(e.to_string()))?;
+
+                let mut state = self.state.lock().await;
+                let rules = state
+                    .peers
+                    .get_mut(sender)
+                    .map(|p| &mut p.match_rules)
+                    .ok_or_else(|| fdo::Error::Disconnected(sender.to_string()))?;
+                match rules.iter().position(|r| **r == rule) {
+                    Some(pos) => {
+                        rules.remove(pos);
+
+                        reply(msg, &())
+                    }
+                    None => Err(fdo::Error::MatchRuleNotFound(format!(
+                        "The given match rule wasn't found and can't be removed: {rule}"
+                    ))),
+                }
+            }
+            (BUS_NAME, "GetId") => reply(msg, &self.guid()),
+            (BUS_NAME, "GetConnectionUnixUser") => {
+                let creds = self.credentials(args(&body)?).await?;
+                let uid = creds.unix_user_id().ok_or_else(|| {
+                    fdo::Error::Failed("Could not determine the UID of the connection".into())
+                })?;
+
+                reply(msg, &uid)
+            }
+            (BUS_NAME, "GetConnectionUnixProcessID") => {
+                let creds = self.credentials(args(&body)?).await?;
+                let pid = creds.process_id().ok_or_else(|| {
+                    fdo::Error::UnixProcessIdUnknown(
+                        "Could not determine the PID of the connection".into(),
+                    )
+                })?;
+
+                reply(msg, &pid)
+            }
+            (BUS_NAME, "GetConnectionCredentials") => {
+                let creds = self.credentials(args(&body)?).await?;
+
+                reply(msg, &creds)
+            }
+            (BUS_NAME, "GetAdtAuditSessionData") => Err(fdo::Error::AdtAuditDataUnknown(
+                "Auditing is not supported by this bus".into(),
+            )),
+            (BUS_NAME, "GetConnectionSELinuxSecurityContext") => {
+                Err(fdo::Error::SELinuxSecurityContextUnknown(
+                    "SELinux is not supported by this bus".into(),
+                ))
+            }
+            (BUS_NAME, "UpdateActivationEnvironment") => Err(fdo::Error::NotSupported(
+                "Service activation is not supported by this bus".into(),
+            )),
+            (BUS_NAME, "ReloadConfig") => reply(msg, &()),
+            ("org.freedesktop.DBus.Properties", "Get") => {
+                let (interface, property) = args::<(&str, &str)>(&body)?;
+                match (interface, property) {
+                    (BUS_NAME, "Features" | "Interfaces") => {
+                        reply(msg, &Value::from(Vec::<String>::new()))
+                    }
+                    _ => Err(fdo::Error::UnknownProperty(format!(
+                        "Unknown property `{interface}.{property}`"
+                    ))),
+                }
+            }
+            ("org.freedesktop.DBus.Properties", "GetAll") => {
+                let interface = args::<&str>(&body)?;
+                let mut properties = HashMap::new();
+                if interface == BUS_NAME {
+                    properties.insert("Features", Value::from(Vec::<String>::new()));
+                    properties.insert("Interfaces", Value::from(Vec::<String>::new()));
+                }
+
+                reply(msg, &properties)
+            }
+            ("org.freedesktop.DBus.Peer", "Ping") => reply(msg, &()),
+            ("org.freedesktop.DBus.Peer", "GetMachineId") => {
+                reply(msg, &fdo::peer::get_machine_id()?)
+            }
+            (interface, member) => Err(fdo::Error::UnknownMethod(format!(
+                "Unknown method `{member}` on interface `{interface}`"
+            ))),
+        }
+    }
+
+    // The credentials of the owner of the given name.
+    async fn credentials(&self, name: BusName<'_>) -> fdo::Result<ConnectionCredentials> {
+        if name.as_str() == BUS_NAME {
+            let creds = ConnectionCredentials::default()
+                .set_unix_user_id(rustix::process::geteuid().as_raw())
+                .set_process_id(rustix::process::getpid().as_raw_nonzero().get() as u32);
+
+            return Ok(creds);
+        }
+
+        let conn = match self.state.lock().await.owner_conn(&name) {
+            Some((_, conn)) => conn.clone(),
+            None => return Err(no_owner(&name)),
+        };
+        let peer_creds = conn
+            .peer_creds()
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+
+        // Leave out the process FD, as the peer may not support FD passing.
+        let mut creds = ConnectionCredentials::default();
+        if let Some(uid) = peer_creds.unix_user_id() {
+            creds = creds.set_unix_user_id(uid);
+        }
+        for gid in peer_creds.unix_group_ids().into_iter().flatten() {
+            creds = creds.add_unix_group_id(*gid);
+        }
+        if let Some(pid) = peer_creds.process_id() {
+            creds = creds.set_process_id(pid);
+        }
+
+        Ok(creds)
+    }
+}
+
+fn args<'b, B>(body: &'b crate::message::Body) -> fdo::Result<B>
+where
+    B: zvariant::DynamicDeserialize<'b>,
+{
+    body.deserialize()
+        .map_err(|e| fdo::Error::InvalidArgs(e.to_string()))
+}
+
+fn reply<B>(msg: &Message, body: &B) -> fdo::Result<Message>
+where
+    B: Serialize + DynamicType,
+{
+    let hdr = msg.header();
+
+    Message::method_return(&hdr)?
+        .sender(BUS_NAME)?
+        .build(body)
+        .map_err(Into::into)
+}
+
+fn no_owner(name: &str) -> fdo::Error {
+    fdo::Error::NameHasNoOwner(format!(
+        "Could not get owner of name '{name}': no such name"
+    ))
+}
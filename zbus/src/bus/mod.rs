@@ -0,0 +1,216 @@
+//! An in-process D-Bus message bus.
+//!
+//! This module provides [`Bus`], a (minimal) message bus broker implementing the
+//! `org.freedesktop.DBus` interface on top of the peer-to-peer API of zbus. It supports:
+//!
+//! * Assignment of unique names through the `Hello` method.
+//! * Ownership of well-known names, including queueing and replacement of owners.
+//! * Routing of method calls, replies and errors, to the peer owning the destination name.
+//! * Match-rule based routing of signals.
+//! * Emission of the `NameOwnerChanged`, `NameAcquired` and `NameLost` signals.
+//!
+//! Service activation, policies and monitoring are not supported. The main use case is to run a
+//! private bus within the same process, e.g. for hermetic tests that do not depend on a system
+//! installation of `dbus-daemon`.
+//!
+//! This module is only available on Unix, when the `bus-impl` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use zbus::{bus::Bus, connection::Builder};
+//!
+//! # let dir = tempfile::tempdir().unwrap();
+//! # let path = dir.path().join("bus");
+//! # zbus::block_on(async {
+//! let bus = Bus::unix_path(&path)?;
+//! let address = bus.address().clone();
+//! std::thread::spawn(move || zbus::block_on(bus.run()));
+//!
+//! let service = Builder::address(address.clone())?
+//!     .name("org.zbus.MyService")?
+//!     .build()
+//!     .await?;
+//! let client = Builder::address(address)?.build().await?;
+//!
+//! let dbus = zbus::fdo::DBusProxy::new(&client).await?;
+//! let owner = dbus.get_name_owner("org.zbus.MyService".try_into()?).await?;
+//! assert_eq!(Some(&owner), service.unique_name());
+//! # Ok::<(), zbus::Error>(())
+//! # }).unwrap();
+//! ```
+
+mod dbus;
+mod names;
+mod router;
+
+#[cfg(not(feature = "tokio"))]
+use async_io::Async;
+use futures_lite::{future, StreamExt};
+use std::{
+    future::Future,
+    os::unix::net::UnixListener,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::Poll,
+};
+#[cfg(feature = "tokio")]
+use tokio::net::UnixStream;
+use tracing::{debug, info, instrument, trace};
+
+#[cfg(not(feature = "tokio"))]
+use std::os::unix::net::UnixStream;
+
+use crate::{
+    address::{
+        transport::{Transport, Unix, UnixSocket},
+        Address,
+    },
+    connection, Executor, Guid, OwnedGuid, Result, Task,
+};
+
+use router::Router;
+
+/// An in-process D-Bus message bus.
+///
+/// See the [module-level documentation](self) for details and an example.
+///
+/// The socket file is removed when the bus is dropped, or once it stops running.
+#[derive(Debug)]
+pub struct Bus {
+    listener: UnixListener,
+    socket_file: SocketFile,
+    address: Address,
+    router: Arc<Router>,
+}
+
+impl Bus {
+    /// Create a bus listening on a Unix domain socket at `path`.
+    ///
+    /// The socket is bound right away, so peers can already connect to [`Bus::address`], even
+    /// though they are only served once [`Bus::run`] is called.
+    pub fn unix_path<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let listener = UnixListener::bind(path)?;
+        let guid = OwnedGuid::from(Guid::generate());
+        let transport = Transport::Unix(Unix::new(UnixSocket::File(path.to_path_buf())));
+        let address = Address::new(transport).set_guid(guid.clone())?;
+
+        Ok(Self {
+            listener,
+            socket_file: SocketFile(path.to_path_buf()),
+            address,
+            router: Arc::new(Router::new(guid)),
+        })
+    }
+
+    /// The address of the bus, to connect to it.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// The GUID of the bus.
+    pub fn guid(&self) -> &OwnedGuid {
+        self.router.guid()
+    }
+
+    /// Accept and serve peers, until an error occurs while accepting new connections.
+    ///
+    /// The peers are only served for as long as the returned future is polled. Dropping it shuts
+    /// the bus down: all peers are disconnected and the socket file is removed.
+    ///
+    /// With the `tokio` feature enabled, this must be called from within a tokio runtime.
+    pub async fn run(self) -> Result<()> {
+        let executor = Executor::new();
+
+        executor.run(self.accept(&executor)).await
+    }
+
+    async fn accept(self, executor: &Executor<'static>) -> Result<()> {
+        let Self {
+            listener,
+            // Only removed once we're done with the socket.
+            socket_file: _socket_file,
+            address,
+            router,
+        } = self;
+        listener.set_nonblocking(true)?;
+        #[cfg(not(feature = "tokio"))]
+        let listener = Async::new(listener)?;
+        #[cfg(feature = "tokio")]
+        let listener = tokio::net::UnixListener::from_std(listener)?;
+        info!("Bus listening on `{address}`");
+
+        // The tasks serving the peers, cancelled when dropped along with this future.
+        let mut peers: Vec<Task<()>> = vec![];
+        loop {
+            // Forget about the peers that disconnected while waiting for new ones.
+            let reap = future::poll_fn(|cx| {
+                peers.retain_mut(|peer| Pin::new(peer).poll(cx).is_pending());
+
+                Poll::Pending
+            });
+            let (stream, _) = future::or(listener.accept(), reap).await?;
+            #[cfg(not(feature = "tokio"))]
+            let stream = stream.into_inner()?;
+
+            let router = router.clone();
+            let peer = executor.spawn(
+                async move {
+                    if let Err(e) = serve_peer(router, stream).await {
+                        debug!("Failed to serve peer: {e}");
+                    }
+                },
+                "bus-peer",
+            );
+            peers.push(peer);
+        }
+    }
+}
+
+// Removes the socket file of the bus when dropped.
+#[derive(Debug)]
+struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.0) {
+            debug!("Failed to remove socket file `{}`: {e}", self.0.display());
+        }
+    }
+}
+
+// Authenticate a new peer and route all the messages it sends, until it disconnects.
+#[instrument(skip_all)]
+async fn serve_peer(router: Arc<Router>, stream: UnixStream) -> Result<()> {
+    let unique_name = router.next_unique_name().await;
+    let (conn, mut stream) = connection::Builder::unix_stream(stream)
+        .server(router.guid())?
+        .p2p()
+        .unique_name(unique_name.clone())?
+        .build_with_stream()
+        .await?;
+    trace!("Peer `{unique_name}` connected");
+    router.add_peer(unique_name.clone(), conn).await;
+
+    while let Some(msg) = stream.next().await {
+        let res = match msg {
+            Ok(msg) => router.route(&unique_name, msg).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = res {
+            // Most likely the peer sent something we can't make sense of.
+            debug!("Disconnecting peer `{unique_name}`: {e}");
+            break;
+        }
+    }
+
+    trace!("Peer `{unique_name}` disconnected");
+    router.remove_peer(&unique_name).await;
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+use enumflags2::BitFlags;
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    fdo::{ReleaseNameReply, RequestNameFlags, RequestNameReply},
+    names::{OwnedUniqueName, OwnedWellKnownName, WellKnownName},
+};
+
+/// The registry of well-known names on the bus, along with their owners and queues.
+#[derive(Debug, Default)]
+pub(super) struct NameRegistry {
+    names: HashMap<OwnedWellKnownName, NameEntry>,
+}
+
+#[derive(Debug)]
+struct NameEntry {
+    owner: NameOwner,
+    waiting: VecDeque<NameOwner>,
+}
+
+#[derive(Debug)]
+struct NameOwner {
+    unique_name: OwnedUniqueName,
+    flags: BitFlags<RequestNameFlags>,
+}
+
+/// A change in the primary owner of a well-known name.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct OwnerChange {
+    pub name: OwnedWellKnownName,
+    pub old_owner: Option<OwnedUniqueName>,
+    pub new_owner: Option<OwnedUniqueName>,
+}
+
+impl NameRegistry {
+    /// Handle a `RequestName` call from `unique_name`.
+    pub fn request(
+        &mut self,
+        name: WellKnownName<'_>,
+        unique_name: OwnedUniqueName,
+        flags: BitFlags<RequestNameFlags>,
+    ) -> (RequestNameReply, Option<OwnerChange>) {
+        let name = OwnedWellKnownName::from(name.to_owned());
+        let requester = NameOwner { unique_name, flags };
+        let Some(entry) = self.names.get_mut(&name) else {
+            let change = OwnerChange {
+                name: name.clone(),
+                old_owner: None,
+                new_owner: Some(requester.unique_name.clone()),
+            };
+            self.names.insert(
+                name,
+                NameEntry {
+                    owner: requester,
+                    waiting: VecDeque::new(),
+                },
+            );
+
+            return (RequestNameReply::PrimaryOwner, Some(change));
+        };
+
+        if entry.owner.unique_name == requester.unique_name {
+            entry.owner.flags = flags;
+
+            return (RequestNameReply::AlreadyOwner, None);
+        }
+
+        let queued = entry
+            .waiting
+            .iter()
+            .position(|w| w.unique_name == requester.unique_name);
+        if flags.contains(RequestNameFlags::ReplaceExisting)
+            && entry
+                .owner
+                .flags
+                .contains(RequestNameFlags::AllowReplacement)
+        {
+            if let Some(pos) = queued {
+                entry.waiting.remove(pos);
+            }
+            let new_owner = requester.unique_name.clone();
+            let old_owner = std::mem::replace(&mut entry.owner, requester);
+            let old_unique_name = old_owner.unique_name.clone();
+            if !old_owner.flags.contains(RequestNameFlags::DoNotQueue) {
+                entry.waiting.push_front(old_owner);
+            }
+
+            let change = OwnerChange {
+                name,
+                old_owner: Some(old_unique_name),
+                new_owner: Some(new_owner),
+            };
+
+            return (RequestNameReply::PrimaryOwner, Some(change));
+        }
+
+        if flags.contains(RequestNameFlags::DoNotQueue) {
+            if let Some(pos) = queued {
+                entry.waiting.remove(pos);
+            }
+
+            return (RequestNameReply::Exists, None);
+        }
+
+        match queued {
+            Some(pos) => entry.waiting[pos].flags = flags,
+            None => entry.waiting.push_back(requester),
+        }
+
+        (RequestNameReply::InQueue, None)
+    }
+
+    /// Handle a `ReleaseName` call from `unique_name`.
+    pub fn release(
+        &mut self,
+        name: &WellKnownName<'_>,
+        unique_name: &OwnedUniqueName,
+    ) -> (ReleaseNameReply, Option<OwnerChange>) {
+        let Some(entry) = self.names.get_mut(name.as_str()) else {
+            return (ReleaseNameReply::NonExistent, None);
+        };
+
+        if entry.owner.unique_name == *unique_name {
+            let change = self.pop_owner(name);
+
+            return (ReleaseNameReply::Released, Some(change));
+        }
+
+        match entry
+            .waiting
+            .iter()
+            .position(|w| w.unique_name == *unique_name)
+        {
+            Some(pos) => {
+                entry.waiting.remove(pos);
+
+                (ReleaseNameReply::Released, None)
+            }
+            None => (ReleaseNameReply::NotOwner, None),
+        }
+    }
+
+    /// Release all the names owned by (or queued for) `unique_name`.
+    ///
+    /// This is used when a peer disconnects.
+    pub fn release_all(&mut self, unique_name: &OwnedUniqueName) -> Vec<OwnerChange> {
+        let owned: Vec<_> = self
+            .names
+            .iter()
+            .filter(|(_, entry)| entry.owner.unique_name == *unique_name)
+            .map(|(name, _)| name.clone())
+            .collect();
+        for entry in self.names.values_mut() {
+            entry.waiting.retain(|w| w.unique_name != *unique_name);
+        }
+
+        owned.iter().map(|name| self.pop_owner(name)).collect()
+    }
+
+    /// The primary owner of `name`, if any.
+    pub fn owner(&self, name: &WellKnownName<'_>) -> Option<&OwnedUniqueName> {
+        self.names
+            .get(name.as_str())
+            .map(|entry| &entry.owner.unique_name)
+    }
+
+    /// The primary owner of `name`, followed by the queued owners.
+    pub fn queued_owners(&self, name: &WellKnownName<'_>) -> Option<Vec<OwnedUniqueName>> {
+        self.names.get(name.as_str()).map(|entry| {
+            std::iter::once(&entry.owner)
+                .chain(&entry.waiting)
+                .map(|o| o.unique_name.clone())
+                .collect()
+        })
+    }
+
+    /// All the names that currently have an owner.
+    pub fn names(&self) -> impl Iterator<Item = &OwnedWellKnownName> {
+        self.names.keys()
+    }
+
+    // Replace the primary owner of `name` with the next one in the queue, if any.
+    fn pop_owner(&mut self, name: &WellKnownName<'_>) -> OwnerChange {
+        let (name, mut entry) = self
+            .names
+            .remove_entry(name.as_str())
+            .expect("name not in registry");
+        let old_owner = Some(entry.owner.unique_name);
+        let new_owner = entry.waiting.pop_front().map(|next| {
+            let new_owner = next.unique_name.clone();
+            self.names.insert(
+                name.clone(),
+                NameEntry {
+                    owner: next,
+                    waiting: entry.waiting,
+                },
+            );
+
+            new_owner
+        });
+
+        OwnerChange {
+            name,
+            old_owner,
+            new_owner,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use enumflags2::BitFlags;
+    use test_log::test;
+
+    use super::*;
+
+    fn unique(name: &str) -> OwnedUniqueName {
+        OwnedUniqueName::try_from(name).unwrap()
+    }
+
+    #[test]
+    fn queueing() {
+        let mut registry = NameRegistry::default();
+        let name = WellKnownName::from_static_str("org.zbus.Test").unwrap();
+        let (a, b, c) = (unique(":1.1"), unique(":1.2"), unique(":1.3"));
+
+        let (reply, change) = registry.request(name.clone(), a.clone(), BitFlags::empty());
+        assert_eq!(reply, RequestNameReply::PrimaryOwner);
+        assert_eq!(change.unwrap().new_owner.as_ref(), Some(&a));
+        let (reply, change) = registry.request(name.clone(), a.clone(), BitFlags::empty());
+        assert_eq!(reply, RequestNameReply::AlreadyOwner);
+        assert!(change.is_none());
+
+        // No replacement allowed by `a`.
+        let flags = RequestNameFlags::ReplaceExisting.into();
+        let (reply, _) = registry.request(name.clone(), b.clone(), flags);
+        assert_eq!(reply, RequestNameReply::InQueue);
+        let flags = RequestNameFlags::DoNotQueue.into();
+        let (reply, _) = registry.request(name.clone(), c.clone(), flags);
+        assert_eq!(reply, RequestNameReply::Exists);
+        assert_eq!(
+            registry.queued_owners(&name).unwrap(),
+            vec![a.clone(), b.clone()]
+        );
+
+        let (reply, change) = registry.release(&name, &a);
+        assert_eq!(reply, ReleaseNameReply::Released);
+        let change = change.unwrap();
+        assert_eq!(change.old_owner, Some(a.clone()));
+        assert_eq!(change.new_owner, Some(b.clone()));
+        let (reply, _) = registry.release(&name, &a);
+        assert_eq!(reply, ReleaseNameReply::NotOwner);
+
+        let changes = registry.release_all(&b);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].new_owner, None);
+        assert!(registry.owner(&name).is_none());
+        let (reply, _) = registry.release(&name, &b);
+        assert_eq!(reply, ReleaseNameReply::NonExistent);
+    }
+
+    #[test]
+    fn replacement() {
+        let mut registry = NameRegistry::default();
+        let name = WellKnownName::from_static_str("org.zbus.Test").unwrap();
+        let (a, b) = (unique(":1.1"), unique(":1.2"));
+
+        registry.request(name.clone(), a.clone(), BitFlags::default());
+        let flags = RequestNameFlags::ReplaceExisting.into();
+        let (reply, change) = registry.request(name.clone(), b.clone(), flags);
+        assert_eq!(reply, RequestNameReply::PrimaryOwner);
+        let change = change.unwrap();
+        assert_eq!(change.old_owner, Some(a.clone()));
+        assert_eq!(change.new_owner, Some(b.clone()));
+        // `a` asked not to be queued.
+        assert_eq!(registry.queued_owners(&name).unwrap(), vec![b.clone()]);
+
+        // This time `a` gets queued when replaced.
+        let flags = RequestNameFlags::AllowReplacement | RequestNameFlags::ReplaceExisting;
+        let mut registry = NameRegistry::default();
+        registry.request(name.clone(), a.clone(), flags);
+        registry.request(name.clone(), b.clone(), flags);
+        assert_eq!(registry.queued_owners(&name).unwrap(), vec![b, a]);
+    }
+}
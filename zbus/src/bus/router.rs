@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use tracing::{debug, trace};
+
+use crate::{
+    async_lock::Mutex,
+    message::{self, Message, Type},
+    names::{BusName, OwnedUniqueName, UniqueName},
+    Connection, OwnedGuid, OwnedMatchRule, Result,
+};
+
+use super::names::{NameRegistry, OwnerChange};
+
+/// The name of the bus itself.
+pub(super) const BUS_NAME: &str = "org.freedesktop.DBus";
+/// The object path of the bus itself.
+pub(super) const BUS_PATH: &str = "/org/freedesktop/DBus";
+
+/// A message to be sent to a peer.
+pub(super) type Delivery = (Connection, Message);
+
+/// Keeps track of the peers and names on the bus, and routes messages between the peers.
+#[derive(Debug)]
+pub(super) struct Router {
+    guid: OwnedGuid,
+    pub(super) state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct State {
+    next_id: u64,
+    pub(super) peers: HashMap<OwnedUniqueName, Peer>,
+    pub(super) names: NameRegistry,
+}
+
+#[derive(Debug)]
+pub(super) struct Peer {
+    pub(super) conn: Connection,
+    /// Whether the peer has called `Hello` (only then it's really on the bus).
+    pub(super) registered: bool,
+    pub(super) match_rules: Vec<OwnedMatchRule>,
+}
+
+impl Router {
+    pub fn new(guid: OwnedGuid) -> Self {
+        Self {
+            guid,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn guid(&self) -> &OwnedGuid {
+        &self.guid
+    }
+
+    /// Reserve a unique name for a new peer.
+    pub async fn next_unique_name(&self) -> OwnedUniqueName {
+        let mut state = self.state.lock().await;
+        state.next_id += 1;
+        let name = format!(":1.{}", state.next_id);
+
+        // SAFETY: This is always a valid unique name.
+        OwnedUniqueName::try_from(name).unwrap()
+    }
+
+    pub async fn add_peer(&self, unique_name: OwnedUniqueName, conn: Connection) {
+        let peer = Peer {
+            conn,
+            registered: false,
+            match_rules: vec![],
+        };
+        self.state.lock().await.peers.insert(unique_name, peer);
+    }
+
+    /// Remove a disconnected peer, releasing all its names.
+    pub async fn remove_peer(&self, unique_name: &OwnedUniqueName) {
+        let deliveries = {
+            let mut state = self.state.lock().await;
+            let Some(peer) = state.peers.remove(unique_name) else {
+                return;
+            };
+            let mut deliveries = vec![];
+            for change in state.names.release_all(unique_name) {
+                state.owner_changed(change, &mut deliveries);
+            }
+            if peer.registered {
+                let change = (unique_name.as_str(), unique_name.as_str(), "");
+                state.broadcast(name_owner_changed(change), &mut deliveries);
+            }
+
+            deliveries
+        };
+
+        deliver(deliveries).await;
+    }
+
+    /// Route a message received from the peer named `sender`.
+    pub async fn route(&self, sender: &OwnedUniqueName, msg: Message) -> Result<()> {
+        let msg = with_sender(&msg, sender)?;
+        let hdr = msg.header();
+        trace!("Routing message: {msg}");
+
+        match hdr.destination() {
+            Some(name) if name.as_str() == BUS_NAME => {
+                return self.handle_bus_call(sender, &msg).await;
+            }
+            destination => {
+                let state = self.state.lock().await;
+                let registered = state
+                    .peers
+                    .get(sender)
+                    .map(|p| p.registered)
+                    .unwrap_or(false);
+                let deliveries = if !registered {
+                    let error = crate::fdo::Error::AccessDenied(
+                        "Client tried to send a message other than Hello without being registered"
+                            .into(),
+                    );
+                    if expects_reply(&msg) {
+                        state.error_reply(&msg, &error)?.into_iter().collect()
+                    } else {
+                        vec![]
+                    }
+                } else {
+                    match destination {
+                        Some(destination) => state.unicast(&msg, destination)?,
+                        None if msg.message_type() == Type::Signal => {
+                            let mut deliveries = vec![];
+                            state.broadcast(msg.clone(), &mut deliveries);
+
+                            deliveries
+                        }
+                        // Only signals can go without a destination.
+                        None => vec![],
+                    }
+                };
+                drop(state);
+
+                deliver(deliveries).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl State {
+    /// The connection of the peer owning the given name.
+    pub fn owner_conn(&self, name: &BusName<'_>) -> Option<(&OwnedUniqueName, &Connection)> {
+        let unique_name = match name {
+            BusName::Unique(name) => name.as_str(),
+            BusName::WellKnown(name) => self.names.owner(name)?.as_str(),
+        };
+
+        self.peers
+            .get_key_value(unique_name)
+            .filter(|(_, peer)| peer.registered)
+            .map(|(name, peer)| (name, &peer.conn))
+    }
+
+    /// Queue a message to all the peers with a matching match rule.
+    pub fn broadcast(&self, msg: Message, deliveries: &mut Vec<Delivery>) {
+        for peer in self.peers.values().filter(|p| p.registered) {
+            if peer.match_rules.iter().any(|r| self.rule_matches(r, &msg)) {
+                deliveries.push((peer.conn.clone(), msg.clone()));
+            }
+        }
+    }
+
+    /// Queue the signals for a change in a name owner.
+    pub fn owner_changed(&self, change: OwnerChange, deliveries: &mut Vec<Delivery>) {
+        let OwnerChange {
+            name,
+            old_owner,
+            new_owner,
+        } = change;
+
+        if let Some(old_owner) = &old_owner {
+            self.unicast_signal(old_owner, "NameLost", name.as_str(), deliveries);
+        }
+        let old = old_owner.as_ref().map(|o| o.as_str()).unwrap_or_default();
+        let new = new_owner.as_ref().map(|o| o.as_str()).unwrap_or_default();
+        self.broadcast(name_owner_changed((name.as_str(), old, new)), deliveries);
+        if let Some(new_owner) = &new_owner {
+            self.unicast_signal(new_owner, "NameAcquired", name.as_str(), deliveries);
+        }
+    }
+
+    /// Queue a `NameLost` or `NameAcquired` signal for `peer`.
+    pub fn unicast_signal(
+        &self,
+        peer: &OwnedUniqueName,
+        member: &'static str,
+        name: &str,
+        deliveries: &mut Vec<Delivery>,
+    ) {
+        let Some(conn) = self.peers.get(peer).map(|p| p.conn.clone()) else {
+            return;
+        };
+        let msg = Message::signal(BUS_PATH, BUS_NAME, member)
+            .and_then(|b| b.sender(BUS_NAME))
+            .and_then(|b| b.destination(peer.as_ref()))
+            .and_then(|b| b.build(&(name,)))
+            // SAFETY: All the header fields are valid.
+            .unwrap();
+
+        deliveries.push((conn, msg));
+    }
+
+    // Deliver a message to its destination.
+    fn unicast(&self, msg: &Message, destination: &BusName<'_>) -> Result<Vec<Delivery>> {
+        if let Some((_, conn)) = self.owner_conn(destination) {
+            return Ok(vec![(conn.clone(), msg.clone())]);
+        }
+
+        debug!("No owner for the destination `{destination}`");
+        if !expects_reply(msg) {
+            return Ok(vec![]);
+        }
+        let error = crate::fdo::Error::ServiceUnknown(format!(
+            "The name {destination} was not provided by any .service files"
+        ));
+
+        Ok(self.error_reply(msg, &error)?.into_iter().collect())
+    }
+
+    /// Create an error reply to `msg`, if its sender is still connected.
+    pub fn error_reply(
+        &self,
+        msg: &Message,
+        error: &crate::fdo::Error,
+    ) -> Result<Option<Delivery>> {
+        let hdr = msg.header();
+        let Some(conn) = hdr
+            .sender()
+            .and_then(|s| self.peers.get(s.as_str()))
+            .map(|p| p.conn.clone())
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some((conn, error_reply(msg, error)?)))
+    }
+
+    // Like `MatchRule::matches` but also resolves the well-known names in the rule.
+    fn rule_matches(&self, rule: &OwnedMatchRule, msg: &Message) -> bool {
+        if !rule.matches(msg).unwrap_or(false) {
+            return false;
+        }
+
+        match rule.sender() {
+            Some(name) if name.as_str() == BUS_NAME => {
+                msg.header().sender().map(UniqueName::as_str) == Some(BUS_NAME)
+            }
+            Some(BusName::WellKnown(name)) => {
+                let hdr = msg.header();
+                let sender = hdr.sender().map(UniqueName::as_str);
+
+                self.names.owner(name).map(|o| o.as_str()) == sender
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Send the messages to the peers.
+pub(super) async fn deliver(deliveries: Vec<Delivery>) {
+    for (conn, msg) in deliveries {
+        if let Err(e) = conn.send(&msg).await {
+            // The peer is probably gone and will be removed soon.
+            debug!("Failed to deliver message to {:?}: {e}", conn.unique_name());
+        }
+    }
+}
+
+/// The `NameOwnerChanged` signal for the given (name, old owner, new owner).
+pub(super) fn name_owner_changed(change: (&str, &str, &str)) -> Message {
+    Message::signal(BUS_PATH, BUS_NAME, "NameOwnerChanged")
+        .and_then(|b| b.sender(BUS_NAME))
+        .and_then(|b| b.build(&change))
+        // SAFETY: All the header fields are valid.
+        .unwrap()
+}
+
+/// Create an error reply to `msg`, sent by the bus.
+pub(super) fn error_reply(msg: &Message, error: &crate::fdo::Error) -> Result<Message> {
+    use crate::DBusError;
+
+    let hdr = msg.header();
+    let builder = Message::error(&hdr, error.name())?.sender(BUS_NAME)?;
+    match error.description() {
+        Some(description) => builder.build(&description),
+        None => builder.build(&()),
+    }
+}
+
+/// Whether `msg` is a method call expecting a reply.
+pub(super) fn expects_reply(msg: &Message) -> bool {
+    msg.message_type() == Type::MethodCall
+        && !msg
+            .primary_header()
+            .flags()
+            .contains(message::Flags::NoReplyExpected)
+}
+
+// Set the sender field of `msg` to the given unique name. The bus never trusts the sender field
+// set by the peers.
+fn with_sender(msg: &Message, sender: &UniqueName<'_>) -> Result<Message> {
+    let hdr = msg.header();
+    if hdr.sender() == Some(sender) {
+        return Ok(msg.clone());
+    }
+
+    let body = msg.body();
+    let data = body.data();
+    let fds = data
+        .fds()
+        .iter()
+        .map(|fd| fd.try_to_owned().map(Into::into))
+        .collect::<zvariant::Result<Vec<_>>>()?;
+    let builder = message::Builder::from(hdr.clone()).sender(sender)?;
+
+    // SAFETY: The body bytes and signature come from a valid message.
+    unsafe { builder.build_raw_body(data.bytes(), body.signature().clone(), fds) }
+}
@@ -8,6 +8,7 @@ use std::net::TcpStream;
 use std::os::unix::net::UnixStream;
 use std::{
     collections::{HashMap, HashSet},
+    sync::Arc,
     vec,
 };
 #[cfg(feature = "tokio")]
@@ -21,19 +22,30 @@ use uds_windows::UnixStream;
 #[cfg(all(feature = "vsock", not(feature = "tokio")))]
 use vsock::VsockStream;
 
+use tracing::debug;
 use zvariant::ObjectPath;
 
+#[cfg(feature = "bus-impl")]
+use crate::MessageStream;
 use crate::{
     address::{self, Address},
     fdo::RequestNameFlags,
     names::{InterfaceName, WellKnownName},
-    object_server::{ArcInterface, Interface},
+    object_server::{AccessPolicy, ArcAccessPolicy, ArcInterface, Interface},
+    timeout::timeout,
     Connection, Error, Executor, Guid, OwnedGuid, Result,
 };
 
+#[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+use super::handshake::CookieContext;
 use super::{
-    handshake::{AuthMechanism, Authenticated},
-    socket::{BoxedSplit, ReadHalf, Split, WriteHalf},
+    handshake::{sasl, AuthMechanism, Authenticated},
+    reconnect::Reconnector,
+    socket::{
+        record::{Capture, Recorder, Replay},
+        BoxedSplit, ReadHalf, Split, WriteHalf,
+    },
+    MetricsSink, TracePropagator,
 };
 
 const DEFAULT_MAX_QUEUED: usize = 64;
@@ -48,7 +60,8 @@ enum Target {
         feature = "tokio-vsock"
     ))]
     VsockStream(VsockStream),
-    Address(Address),
+    // Tried in order until one of them connects.
+    Addresses(Vec<Address>),
     Socket(Split<Box<dyn ReadHalf>, Box<dyn WriteHalf>>),
     AuthenticatedSocket(Split<Box<dyn ReadHalf>, Box<dyn WriteHalf>>),
 }
@@ -78,24 +91,41 @@ pub struct Builder<'a> {
     p2p: bool,
     internal_executor: bool,
     interfaces: Interfaces<'a>,
+    access_policies: HashMap<ObjectPath<'a>, ArcAccessPolicy>,
     names: HashSet<WellKnownName<'a>>,
     auth_mechanism: Option<AuthMechanism>,
-    #[cfg(feature = "bus-impl")]
+    client_mechanism: Option<Box<dyn sasl::ClientMechanism>>,
+    #[cfg(feature = "p2p")]
+    server_mechanism: Option<Box<dyn sasl::ServerMechanism>>,
+    // Set by `Builder::unique_name` (bus implementations) or `Builder::replay`.
     unique_name: Option<crate::names::UniqueName<'a>>,
     request_name_flags: BitFlags<RequestNameFlags>,
     method_timeout: Option<std::time::Duration>,
+    connect_timeout: Option<std::time::Duration>,
+    auto_reconnect: bool,
+    recorder: Option<Recorder>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    trace_propagator: Option<Arc<dyn TracePropagator>>,
     user_id: Option<u32>,
+    #[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+    cookie_context: Option<CookieContext<'a>>,
+    #[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+    cookie_id: Option<usize>,
 }
 
 impl<'a> Builder<'a> {
     /// Create a builder for the session/user message bus connection.
+    ///
+    /// If the session bus has multiple addresses, they're tried in order until one succeeds.
     pub fn session() -> Result<Self> {
-        Ok(Self::new(Target::Address(Address::session()?)))
+        Ok(Self::new(Target::Addresses(Address::session_list()?)))
     }
 
     /// Create a builder for the system-wide message bus connection.
+    ///
+    /// If the system bus has multiple addresses, they're tried in order until one succeeds.
     pub fn system() -> Result<Self> {
-        Ok(Self::new(Target::Address(Address::system()?)))
+        Ok(Self::new(Target::Addresses(Address::system_list()?)))
     }
 
     /// Create a builder for a connection that will use the given [D-Bus bus address].
@@ -134,9 +164,46 @@ impl<'a> Builder<'a> {
         A: TryInto<Address>,
         A::Error: Into<Error>,
     {
-        Ok(Self::new(Target::Address(
-            address.try_into().map_err(Into::into)?,
-        )))
+        Ok(Self::new(Target::Addresses(vec![address
+            .try_into()
+            .map_err(Into::into)?])))
+    }
+
+    /// Create a builder for a connection that will use the first of the given addresses that can
+    /// be connected to.
+    ///
+    /// The addresses are tried in order. If none of them can be connected to, the error lists the
+    /// failure of each address.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use zbus::{connection::Builder, Address};
+    /// # zbus::block_on(async {
+    /// let addresses = Address::parse_list("tcp:host=192.168.1.1,port=4242;unix:path=/tmp/bus")?;
+    /// let conn = Builder::addresses(addresses)?
+    ///     .connect_timeout(std::time::Duration::from_secs(5))
+    ///     .build()
+    ///     .await?;
+    /// #     drop(conn);
+    /// #     Ok::<(), zbus::Error>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn addresses<I, A>(addresses: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = A>,
+        A: TryInto<Address>,
+        A::Error: Into<Error>,
+    {
+        let addresses = addresses
+            .into_iter()
+            .map(|address| address.try_into().map_err(Into::into))
+            .collect::<Result<Vec<_>>>()?;
+        if addresses.is_empty() {
+            return Err(Error::Address("empty address list".into()));
+        }
+
+        Ok(Self::new(Target::Addresses(addresses)))
     }
 
     /// Create a builder for a connection that will use the given unix stream.
@@ -176,6 +243,26 @@ impl<'a> Builder<'a> {
         Self::new(Target::VsockStream(stream))
     }
 
+    /// Create a builder for a connection that will use a connected socket passed through systemd
+    /// socket activation.
+    ///
+    /// If `name` is given, the socket of that name (as set by `FileDescriptorName=` in the socket
+    /// unit) is used. Otherwise, the first connected socket that was passed and isn't used yet is.
+    /// This is the same as using a `systemd:` [address](Builder::address), and is typically useful
+    /// for services launched through socket units with `Accept=yes`.
+    ///
+    /// Like with any other stream, the resulting connection is a bus connection, unless
+    /// `Builder::p2p` is called.
+    ///
+    /// This method is only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn systemd_socket(name: Option<&str>) -> Self {
+        let transport =
+            address::transport::Transport::Systemd(address::transport::Systemd::new(name));
+
+        Self::new(Target::Addresses(vec![transport.into()]))
+    }
+
     /// Create a builder for a connection that will use the given socket.
     pub fn socket<S: Into<BoxedSplit>>(socket: S) -> Self {
         Self::new(Target::Socket(socket.into()))
@@ -197,6 +284,20 @@ impl<'a> Builder<'a> {
         Ok(builder)
     }
 
+    /// Create a builder for a connection replaying the given capture.
+    ///
+    /// This is similar to [`Builder::authenticated_socket`] with a [`Replay`] socket, except that
+    /// the GUID of the server and the unique name of the connection are also set from the capture.
+    /// If the recorded connection was a peer-to-peer one, `Builder::p2p` must also be called.
+    /// See the [`record`](super::socket::record) module for details.
+    pub fn replay(capture: &Capture) -> Self {
+        let mut builder = Self::new(Target::AuthenticatedSocket(Replay::new(capture).into()));
+        builder.guid = Some(capture.guid().clone().into());
+        builder.unique_name = capture.unique_name().map(|n| n.inner().clone());
+
+        builder
+    }
+
     /// Specify the mechanism to use during authentication.
     pub fn auth_mechanism(mut self, auth_mechanism: AuthMechanism) -> Self {
         self.auth_mechanism = Some(auth_mechanism);
@@ -204,6 +305,33 @@ impl<'a> Builder<'a> {
         self
     }
 
+    /// Use a custom authentication mechanism on the client-side of the handshake.
+    ///
+    /// This takes precedence over [`Builder::auth_mechanism`]. See the [`sasl`] module for
+    /// details and an example.
+    ///
+    /// [`sasl`]: crate::connection::sasl
+    pub fn client_auth_mechanism(mut self, mechanism: Box<dyn sasl::ClientMechanism>) -> Self {
+        self.client_mechanism = Some(mechanism);
+
+        self
+    }
+
+    /// Use a custom authentication mechanism on the server-side of the handshake.
+    ///
+    /// This takes precedence over [`Builder::auth_mechanism`]. See the [`sasl`] module for
+    /// details and an example.
+    ///
+    /// This method is only available when the `p2p` feature is enabled.
+    ///
+    /// [`sasl`]: crate::connection::sasl
+    #[cfg(feature = "p2p")]
+    pub fn server_auth_mechanism(mut self, mechanism: Box<dyn sasl::ServerMechanism>) -> Self {
+        self.server_mechanism = Some(mechanism);
+
+        self
+    }
+
     /// Specify the user id during authentication.
     ///
     /// This can be useful when using [`AuthMechanism::External`] with `socat`
@@ -216,6 +344,41 @@ impl<'a> Builder<'a> {
         self
     }
 
+    /// Set the cookie context (keyring name) to use for [`AuthMechanism::Cookie`].
+    ///
+    /// This is only used on the server-side of the handshake, as the client is told by the server
+    /// which keyring to use. Defaults to `org_freedesktop_general`.
+    ///
+    /// This method is only available when both the `p2p` and `cookie-sha1` features are enabled.
+    ///
+    /// # Errors
+    ///
+    /// If the given context contains characters that are not allowed in a cookie context (`/`,
+    /// `\`, `.`, whitespace or non-ASCII characters), an [`Error::Handshake`] is returned.
+    #[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+    pub fn cookie_context<C>(mut self, context: C) -> Result<Self>
+    where
+        C: Into<zvariant::Str<'a>>,
+    {
+        self.cookie_context = Some(context.into().try_into()?);
+
+        Ok(self)
+    }
+
+    /// Set the ID of the cookie to use for [`AuthMechanism::Cookie`].
+    ///
+    /// This is only used on the server-side of the handshake. By default, the server uses the most
+    /// recent cookie of the keyring and takes care of creating new cookies and removing expired
+    /// ones. If a cookie ID is set, the keyring is only read and the cookie must exist in it.
+    ///
+    /// This method is only available when both the `p2p` and `cookie-sha1` features are enabled.
+    #[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+    pub fn cookie_id(mut self, id: usize) -> Self {
+        self.cookie_id = Some(id);
+
+        self
+    }
+
     /// The to-be-created connection will be a peer-to-peer connection.
     ///
     /// This method is only available when the `p2p` feature is enabled.
@@ -310,6 +473,22 @@ impl<'a> Builder<'a> {
         Ok(self)
     }
 
+    /// Set the access policy for the objects at the given path and beneath it.
+    ///
+    /// This is similar to [`zbus::ObjectServer::set_access_policy`], except that the policy is in
+    /// place before any interface registered through [`Builder::serve_at`] is served.
+    pub fn access_policy<P, A>(mut self, path: P, policy: A) -> Result<Self>
+    where
+        A: AccessPolicy,
+        P: TryInto<ObjectPath<'a>>,
+        P::Error: Into<Error>,
+    {
+        let path = path.try_into().map_err(Into::into)?;
+        self.access_policies
+            .insert(path, ArcAccessPolicy::new(policy));
+        Ok(self)
+    }
+
     /// Register a well-known name for this connection on the bus.
     ///
     /// This is similar to [`zbus::Connection::request_name`], except the name is requested as part
@@ -370,6 +549,58 @@ impl<'a> Builder<'a> {
         Ok(self)
     }
 
+    /// Set a timeout for each TCP connection attempt.
+    ///
+    /// This applies to TCP addresses that don't have their own timeout set (see
+    /// [`Tcp::connect_timeout`]). A host can resolve to multiple socket addresses, and each of them
+    /// gets its own attempt. By default, an attempt is only given up by the OS.
+    ///
+    /// The timeout also applies to the authentication handshake on each address, so that an
+    /// unresponsive peer doesn't prevent falling back to the next address.
+    ///
+    /// [`Tcp::connect_timeout`]: crate::address::transport::Tcp::connect_timeout
+    pub fn connect_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+
+        self
+    }
+
+    /// Automatically reconnect if the connection to the peer is lost.
+    ///
+    /// When enabled and the connection is lost (e.g. the bus is restarted), the address(es) the
+    /// connection was built with are connected to again, with an exponential backoff between the
+    /// attempts, until it succeeds or the connection is dropped or [closed]. On a bus connection,
+    /// the state of the connection is then restored:
+    ///
+    /// * All match rules are registered again, so signal streams keep working.
+    /// * All the names the connection owned (or was queued for) are requested again, with the flags
+    ///   they were originally requested with.
+    /// * The [`ObjectServer`] keeps serving its objects under the new unique name.
+    ///
+    /// Method calls pending while the connection is lost fail with the error that caused it, and
+    /// so do the ones made before the connection is re-established. Use
+    /// [`Connection::receive_state_changes`] to be notified of the disconnection and reconnection.
+    ///
+    /// Since the unique name of a bus connection is assigned by the bus, it changes on
+    /// reconnection. Also note that the signal streams of [`Proxy`] track the owner of the
+    /// destination name through `NameOwnerChanged` signals, and hence can miss an owner change
+    /// that happens while reconnecting.
+    ///
+    /// # Errors
+    ///
+    /// Building the connection fails with [`Error::Unsupported`] if reconnection is enabled for a
+    /// connection that isn't created from address(es), a server connection, a connection with a
+    /// custom client authentication mechanism or a recorded connection.
+    ///
+    /// [closed]: Connection::close
+    /// [`ObjectServer`]: crate::ObjectServer
+    /// [`Proxy`]: crate::Proxy
+    pub fn auto_reconnect(mut self, enabled: bool) -> Self {
+        self.auto_reconnect = enabled;
+
+        self
+    }
+
     /// Set a timeout for method calls.
     ///
     /// Method calls will return
@@ -381,6 +612,34 @@ impl<'a> Builder<'a> {
         self
     }
 
+    /// Record all the messages sent and received by the connection.
+    ///
+    /// Recording starts right after the authentication handshake. See the
+    /// [`record`](super::socket::record) module for details.
+    pub fn record(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+
+        self
+    }
+
+    /// Instrument the connection with the given metrics sink.
+    ///
+    /// See [`MetricsSink`] for details.
+    pub fn metrics_sink(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(sink);
+
+        self
+    }
+
+    /// Propagate the trace context across the method calls of the connection.
+    ///
+    /// See [`TracePropagator`] for details.
+    pub fn trace_propagator(mut self, propagator: Arc<dyn TracePropagator>) -> Self {
+        self.trace_propagator = Some(propagator);
+
+        self
+    }
+
     /// Build the connection, consuming the builder.
     ///
     /// # Errors
@@ -392,7 +651,7 @@ impl<'a> Builder<'a> {
         #[cfg(not(feature = "tokio"))]
         let internal_executor = self.internal_executor;
         // Box the future as it's large and can cause stack overflow.
-        let conn = Box::pin(executor.run(self.build_(executor.clone()))).await?;
+        let (conn, ()) = Box::pin(executor.run(self.build_(executor.clone(), |_| ()))).await?;
 
         #[cfg(not(feature = "tokio"))]
         start_internal_executor(&executor, internal_executor)?;
@@ -400,35 +659,76 @@ impl<'a> Builder<'a> {
         Ok(conn)
     }
 
-    async fn build_(mut self, executor: Executor<'static>) -> Result<Connection> {
+    /// Build the connection, along with a stream of all its incoming messages.
+    ///
+    /// Unlike creating a [`MessageStream`] after the connection is built, this ensures that no
+    /// message sent by the peer right after the handshake is missed.
+    #[cfg(feature = "bus-impl")]
+    pub(crate) async fn build_with_stream(self) -> Result<(Connection, MessageStream)> {
+        let executor = Executor::new();
+        #[cfg(not(feature = "tokio"))]
+        let internal_executor = self.internal_executor;
+        let build = self.build_(executor.clone(), |conn| MessageStream::from(conn));
+        let (conn, stream) = Box::pin(executor.run(build)).await?;
+
+        #[cfg(not(feature = "tokio"))]
+        start_internal_executor(&executor, internal_executor)?;
+
+        Ok((conn, stream))
+    }
+
+    async fn build_<T>(
+        mut self,
+        executor: Executor<'static>,
+        // Called right before the socket reader is started.
+        before_reading: impl FnOnce(&Connection) -> T,
+    ) -> Result<(Connection, T)> {
         #[cfg(feature = "p2p")]
         let is_bus_conn = !self.p2p;
         #[cfg(not(feature = "p2p"))]
         let is_bus_conn = true;
 
+        let reconnector = if self.auto_reconnect {
+            Some(self.reconnector(is_bus_conn)?)
+        } else {
+            None
+        };
         let mut auth = self.connect(is_bus_conn).await?;
 
         // SAFETY: `Authenticated` is always built with these fields set to `Some`.
-        let socket_read = auth.socket_read.take().unwrap();
+        let mut socket_read = auth.socket_read.take().unwrap();
+        if let Some(recorder) = self.recorder.take() {
+            recorder.start(&auth.server_guid, auth.unique_name.as_ref())?;
+            let (read, write) = recorder.wrap(socket_read, auth.socket_write);
+            socket_read = read;
+            auth.socket_write = write;
+        }
         let already_received_bytes = auth.already_received_bytes.drain(..).collect();
         #[cfg(unix)]
         let already_received_fds = auth.already_received_fds.drain(..).collect();
 
-        let mut conn = Connection::new(auth, is_bus_conn, executor, self.method_timeout).await?;
+        let mut conn = Connection::new(
+            auth,
+            is_bus_conn,
+            executor,
+            self.method_timeout,
+            self.metrics.take(),
+            self.trace_propagator.take(),
+        )
+        .await?;
         conn.set_max_queued(self.max_queued.unwrap_or(DEFAULT_MAX_QUEUED));
 
-        if !self.interfaces.is_empty() {
+        if !self.interfaces.is_empty() || !self.access_policies.is_empty() {
             let object_server = conn.ensure_object_server(false);
-            for (path, interfaces) in self.interfaces {
-                for (name, iface) in interfaces {
-                    let added = object_server
-                        .add_arc_interface(path.clone(), name.clone(), iface.clone())
-                        .await?;
-                    if !added {
-                        return Err(Error::InterfaceExists(name.clone(), path.to_owned()));
-                    }
-                }
+            for (path, policy) in self.access_policies {
+                object_server.set_arc_access_policy(path.into(), policy);
             }
+            let objects = self
+                .interfaces
+                .into_iter()
+                .map(|(path, interfaces)| (path.into(), interfaces))
+                .collect();
+            object_server.add_arc_interfaces(objects).await?;
 
             let started_event = Event::new();
             let listener = started_event.listen();
@@ -437,12 +737,15 @@ impl<'a> Builder<'a> {
             listener.await;
         }
 
+        let ret = before_reading(&conn);
+
         // Start the socket reader task.
         conn.init_socket_reader(
             socket_read,
             already_received_bytes,
             #[cfg(unix)]
             already_received_fds,
+            reconnector,
         );
 
         for name in self.names {
@@ -450,7 +753,7 @@ impl<'a> Builder<'a> {
                 .await?;
         }
 
-        Ok(conn)
+        Ok((conn, ret))
     }
 
     fn new(target: Target) -> Self {
@@ -462,24 +765,80 @@ impl<'a> Builder<'a> {
             guid: None,
             internal_executor: true,
             interfaces: HashMap::new(),
+            access_policies: HashMap::new(),
             names: HashSet::new(),
             auth_mechanism: None,
-            #[cfg(feature = "bus-impl")]
+            client_mechanism: None,
+            #[cfg(feature = "p2p")]
+            server_mechanism: None,
             unique_name: None,
             request_name_flags: BitFlags::default(),
             method_timeout: None,
+            connect_timeout: None,
+            auto_reconnect: false,
+            recorder: None,
+            metrics: None,
+            trace_propagator: None,
             user_id: None,
+            #[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+            cookie_context: None,
+            #[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+            cookie_id: None,
         }
     }
 
+    // What's needed to reconnect, if that's supported for this connection.
+    fn reconnector(&self, is_bus_conn: bool) -> Result<Reconnector> {
+        let addresses = match &self.target {
+            Some(Target::Addresses(addresses)) => addresses.clone(),
+            _ => return Err(Error::Unsupported),
+        };
+        if self.guid.is_some() || self.client_mechanism.is_some() || self.recorder.is_some() {
+            return Err(Error::Unsupported);
+        }
+
+        Ok(Reconnector {
+            addresses,
+            connect_timeout: self.connect_timeout,
+            auth_mechanism: self.auth_mechanism,
+            user_id: self.user_id,
+            bus: is_bus_conn,
+        })
+    }
+
     async fn connect(&mut self, is_bus_conn: bool) -> Result<Authenticated> {
-        #[cfg(not(feature = "bus-impl"))]
-        let unique_name = None;
-        #[cfg(feature = "bus-impl")]
         let unique_name = self.unique_name.take().map(Into::into);
 
-        #[allow(unused_mut)]
-        let (mut stream, server_guid, authenticated) = self.target_connect().await?;
+        // SAFETY: `self.target` is always `Some` from the beginning and this method is only called
+        // once.
+        let (stream, server_guid, authenticated) = match self.target.take().unwrap() {
+            Target::Addresses(addresses) => {
+                let connect_timeout = self.connect_timeout;
+                #[cfg(feature = "p2p")]
+                let custom_mechanism =
+                    self.client_mechanism.is_some() || self.server_mechanism.is_some();
+                #[cfg(not(feature = "p2p"))]
+                let custom_mechanism = self.client_mechanism.is_some();
+                let mut attempted = false;
+
+                return connect_addresses(addresses, connect_timeout, |address, stream| {
+                    let server_guid = address.guid().map(|g| g.to_owned().into());
+                    // The custom mechanisms are consumed by the first handshake.
+                    let mechanism_used =
+                        custom_mechanism && std::mem::replace(&mut attempted, true);
+
+                    self.handshake(
+                        stream,
+                        server_guid,
+                        is_bus_conn,
+                        unique_name.clone(),
+                        mechanism_used,
+                    )
+                })
+                .await;
+            }
+            target => self.target_split(target)?,
+        };
         if authenticated {
             let (socket_read, socket_write) = stream.take();
             Ok(Authenticated {
@@ -493,64 +852,92 @@ impl<'a> Builder<'a> {
                 unique_name,
                 #[cfg(unix)]
                 already_received_fds: vec![],
+                authenticated_identity: None,
             })
         } else {
+            self.handshake(stream, server_guid, is_bus_conn, unique_name, false)
+                .await
+        }
+    }
+
+    // The SASL handshake over `stream`.
+    //
+    // The custom mechanisms can only be used for a single handshake, so this fails if
+    // `mechanism_used` is set, instead of silently using the default ones.
+    fn handshake(
+        &mut self,
+        #[allow(unused_mut)] mut stream: BoxedSplit,
+        server_guid: Option<OwnedGuid>,
+        is_bus_conn: bool,
+        #[allow(unused_variables)] unique_name: Option<crate::names::OwnedUniqueName>,
+        mechanism_used: bool,
+    ) -> impl std::future::Future<Output = Result<Authenticated>> {
+        let auth_mechanism = self.auth_mechanism;
+        let user_id = self.user_id;
+        let client_mechanism = self.client_mechanism.take();
+        #[cfg(feature = "p2p")]
+        let p2p = self.p2p;
+        #[cfg(feature = "p2p")]
+        let guid: Option<OwnedGuid> = self.guid.as_ref().map(|g| g.to_owned().into());
+        #[cfg(feature = "p2p")]
+        let server_mechanism = self.server_mechanism.take();
+        #[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+        let cookie_context = self.cookie_context.clone().unwrap_or_default().into_owned();
+        #[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+        let cookie_id = self.cookie_id;
+
+        async move {
+            if mechanism_used {
+                return Err(Error::Failure(
+                    "The custom SASL mechanism was used by a previous attempt".into(),
+                ));
+            }
+
             #[cfg(feature = "p2p")]
-            match self.guid.take() {
-                None => {
-                    // SASL Handshake
-                    Authenticated::client(
-                        stream,
-                        server_guid,
-                        self.auth_mechanism,
-                        is_bus_conn,
-                        self.user_id,
-                    )
-                    .await
+            if let Some(guid) = guid {
+                if !p2p {
+                    return Err(Error::Unsupported);
                 }
-                Some(guid) => {
-                    if !self.p2p {
-                        return Err(Error::Unsupported);
-                    }
 
-                    let creds = stream.read_mut().peer_credentials().await?;
+                let creds = stream.read_mut().peer_credentials().await?;
+                #[cfg(unix)]
+                let client_uid = user_id.or_else(|| creds.unix_user_id());
+                #[cfg(windows)]
+                let client_sid = creds.into_windows_sid();
+
+                return Authenticated::server(
+                    stream,
+                    guid,
                     #[cfg(unix)]
-                    let client_uid = self.user_id.or_else(|| creds.unix_user_id());
+                    client_uid,
                     #[cfg(windows)]
-                    let client_sid = creds.into_windows_sid();
-
-                    Authenticated::server(
-                        stream,
-                        guid.to_owned().into(),
-                        #[cfg(unix)]
-                        client_uid,
-                        #[cfg(windows)]
-                        client_sid,
-                        self.auth_mechanism,
-                        unique_name,
-                    )
-                    .await
-                }
+                    client_sid,
+                    auth_mechanism,
+                    server_mechanism,
+                    #[cfg(feature = "cookie-sha1")]
+                    cookie_context,
+                    #[cfg(feature = "cookie-sha1")]
+                    cookie_id,
+                    unique_name,
+                )
+                .await;
             }
-
-            #[cfg(not(feature = "p2p"))]
             Authenticated::client(
                 stream,
                 server_guid,
-                self.auth_mechanism,
+                auth_mechanism,
+                client_mechanism,
                 is_bus_conn,
-                self.user_id,
+                user_id,
             )
             .await
         }
     }
 
-    async fn target_connect(&mut self) -> Result<(BoxedSplit, Option<OwnedGuid>, bool)> {
+    fn target_split(&mut self, target: Target) -> Result<(BoxedSplit, Option<OwnedGuid>, bool)> {
         let mut authenticated = false;
         let mut guid = None;
-        // SAFETY: `self.target` is always `Some` from the beginning and this method is only called
-        // once.
-        let split = match self.target.take().unwrap() {
+        let split = match target {
             #[cfg(not(feature = "tokio"))]
             Target::UnixStream(stream) => Async::new(stream)?.into(),
             #[cfg(all(unix, feature = "tokio"))]
@@ -563,9 +950,37 @@ impl<'a> Builder<'a> {
             Target::VsockStream(stream) => Async::new(stream)?.into(),
             #[cfg(feature = "tokio-vsock")]
             Target::VsockStream(stream) => stream.into(),
-            Target::Address(address) => {
-                guid = address.guid().map(|g| g.to_owned().into());
-                match address.connect().await? {
+            Target::Addresses(_) => unreachable!("addresses are connected to separately"),
+            Target::Socket(stream) => stream,
+            Target::AuthenticatedSocket(stream) => {
+                authenticated = true;
+                guid = self.guid.take().map(Into::into);
+                stream
+            }
+        };
+
+        Ok((split, guid, authenticated))
+    }
+}
+
+// Connect to the first of `addresses` that can be connected to, and that `handshake` succeeds on.
+//
+// The handshake is bounded by `connect_timeout`, if set.
+pub(super) async fn connect_addresses<F, Fut, T>(
+    addresses: Vec<Address>,
+    connect_timeout: Option<std::time::Duration>,
+    mut handshake: F,
+) -> Result<T>
+where
+    F: FnMut(&Address, BoxedSplit) -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut errors = Vec::new();
+    for address in addresses {
+        let address = address.default_connect_timeout(connect_timeout);
+        let res = match address.clone().connect().await {
+            Ok(stream) => {
+                let split = match stream {
                     #[cfg(any(unix, not(feature = "tokio")))]
                     address::transport::Stream::Unix(stream) => stream.into(),
                     #[cfg(unix)]
@@ -576,18 +991,36 @@ impl<'a> Builder<'a> {
                         feature = "tokio-vsock"
                     ))]
                     address::transport::Stream::Vsock(stream) => stream.into(),
+                };
+                let handshake = handshake(&address, split);
+                match connect_timeout {
+                    Some(connect_timeout) => timeout(handshake, connect_timeout).await,
+                    None => handshake.await,
                 }
             }
-            Target::Socket(stream) => stream,
-            Target::AuthenticatedSocket(stream) => {
-                authenticated = true;
-                guid = self.guid.take().map(Into::into);
-                stream
-            }
+            Err(e) => Err(e),
         };
+        match res {
+            Ok(t) => return Ok(t),
+            Err(e) => {
+                debug!("Failed to connect to `{address}`: {e}");
+                errors.push((address, e));
+            }
+        }
+    }
 
-        Ok((split, guid, authenticated))
+    if errors.len() == 1 {
+        return Err(errors.remove(0).1);
     }
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|(address, e)| format!("`{address}`: {e}"))
+        .collect();
+
+    Err(Error::Failure(format!(
+        "Failed to connect to any address ({})",
+        errors.join(", ")
+    )))
 }
 
 /// Start the internal executor thread.
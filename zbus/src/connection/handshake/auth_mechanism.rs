@@ -4,11 +4,11 @@ use crate::{Error, Result};
 
 /// Authentication mechanisms
 ///
-/// Note that the `DBUS_COOKIE_SHA1` mechanism is not supported by zbus since version 5.0. The
-/// reasons are:
+/// Note that the `DBUS_COOKIE_SHA1` mechanism is only available when the `cookie-sha1` feature is
+/// enabled (disabled by default). The reasons are:
 ///
 /// * It drags the `sha1` crate as a dependency, which can be [problematic for some users].
-/// * It makes the handshake more complex, now allowing use to pipeline all the commands.
+/// * It makes the handshake more complex, not allowing us to pipeline all the commands.
 /// * It's not widely used. If `EXTERNAL` is not an option, you might as well just use `ANONYMOUS`.
 ///
 /// See <https://dbus.freedesktop.org/doc/dbus-specification.html#auth-mechanisms>
@@ -24,6 +24,16 @@ pub enum AuthMechanism {
     /// Does not perform any authentication at all, and should not be accepted by message buses.
     /// However, it might sometimes be useful for non-message-bus uses of D-Bus.
     Anonymous,
+
+    /// This mechanism is designed to establish that a client has the ability to read a private
+    /// file owned by the user being authenticated.
+    ///
+    /// The cookies are read from (and on the server-side, written to) the keyrings in the
+    /// `~/.dbus-keyrings` directory.
+    ///
+    /// This variant is only available when the `cookie-sha1` feature is enabled.
+    #[cfg(feature = "cookie-sha1")]
+    Cookie,
 }
 
 impl AuthMechanism {
@@ -31,6 +41,8 @@ impl AuthMechanism {
         match self {
             AuthMechanism::External => "EXTERNAL",
             AuthMechanism::Anonymous => "ANONYMOUS",
+            #[cfg(feature = "cookie-sha1")]
+            AuthMechanism::Cookie => "DBUS_COOKIE_SHA1",
         }
     }
 }
@@ -49,6 +61,8 @@ impl FromStr for AuthMechanism {
         match s {
             "EXTERNAL" => Ok(AuthMechanism::External),
             "ANONYMOUS" => Ok(AuthMechanism::Anonymous),
+            #[cfg(feature = "cookie-sha1")]
+            "DBUS_COOKIE_SHA1" => Ok(AuthMechanism::Cookie),
             _ => Err(Error::Handshake(format!("Unsupported mechanism: {s}"))),
         }
     }
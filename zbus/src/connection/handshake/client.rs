@@ -4,8 +4,8 @@ use tracing::{instrument, trace, warn};
 use crate::{conn::socket::ReadHalf, is_flatpak, names::OwnedUniqueName, Message};
 
 use super::{
-    sasl_auth_id, AuthMechanism, Authenticated, BoxedSplit, Command, Common, Error, Handshake,
-    OwnedGuid, Result,
+    mechanisms, sasl::ClientMechanism, sasl_auth_id, AuthMechanism, Authenticated, BoxedSplit,
+    Command, Common, Error, Handshake, OwnedGuid, Result,
 };
 
 /// A representation of an in-progress handshake, client-side
@@ -15,9 +15,9 @@ use super::{
 #[derive(Debug)]
 pub struct Client {
     common: Common,
+    mechanism: Box<dyn ClientMechanism>,
     server_guid: Option<OwnedGuid>,
     bus: bool,
-    user_id: Result<String>,
 }
 
 impl Client {
@@ -30,18 +30,24 @@ impl Client {
         user_id: Option<u32>,
     ) -> Client {
         let mechanism = mechanism.unwrap_or_else(|| socket.read().auth_mechanism());
+        let user_id = match user_id {
+            Some(value) => Ok(value.to_string()),
+            None => sasl_auth_id(),
+        };
 
         Client {
-            common: Common::new(socket, mechanism),
+            common: Common::new(socket),
+            mechanism: mechanisms::client(mechanism, user_id),
             server_guid,
             bus,
-            user_id: match user_id {
-                Some(value) => Ok(value.to_string()),
-                None => sasl_auth_id(),
-            },
         }
     }
 
+    /// Use the given mechanism, instead of the one given to [`Client::new`].
+    pub fn set_mechanism(&mut self, mechanism: Box<dyn ClientMechanism>) {
+        self.mechanism = mechanism;
+    }
+
     fn set_guid(&mut self, guid: OwnedGuid) -> Result<()> {
         match &self.server_guid {
             Some(server_guid) if *server_guid != guid => {
@@ -84,32 +90,42 @@ impl Client {
     /// Perform the authentication handshake with the server.
     #[instrument(skip(self), level = "trace")]
     async fn authenticate(&mut self) -> Result<()> {
-        let mechanism = self.common.mechanism();
+        let mechanism = self.mechanism.name().to_string();
         trace!("Trying {mechanism} mechanism");
-        let user_id = self.user_id.clone();
-        let auth_cmd = match mechanism {
-            AuthMechanism::Anonymous => Command::Auth(Some(mechanism), Some("zbus".into())),
-            AuthMechanism::External => Command::Auth(Some(mechanism), Some(user_id?.into_bytes())),
-        };
+        let initial_response = self.mechanism.initial_response().await?;
+        let auth_cmd = Command::Auth(Some(mechanism.clone()), initial_response);
         self.common.write_command(auth_cmd).await?;
 
-        match self.common.read_command().await? {
-            Command::Ok(guid) => {
-                trace!("Received OK from server");
-                self.set_guid(guid)?;
+        loop {
+            match self.common.read_command().await? {
+                Command::Ok(guid) => {
+                    trace!("Received OK from server");
+                    self.set_guid(guid)?;
 
-                Ok(())
-            }
-            Command::Rejected(accepted) => {
-                let list = accepted.replace(" ", ", ");
-                Err(Error::Handshake(format!(
-                    "{mechanism} rejected by the server. Accepted mechanisms: [{list}]"
-                )))
+                    return Ok(());
+                }
+                Command::Data(data) => {
+                    trace!("Received challenge from server");
+                    let response = self.mechanism.challenge(data).await?;
+                    self.common
+                        .write_command(Command::Data(Some(response)))
+                        .await?;
+                }
+                Command::Rejected(accepted) => {
+                    let list = accepted.replace(" ", ", ");
+                    return Err(Error::Handshake(format!(
+                        "{mechanism} rejected by the server. Accepted mechanisms: [{list}]"
+                    )));
+                }
+                Command::Error(e) => {
+                    return Err(Error::Handshake(format!("Received error from server: {e}")))
+                }
+                cmd => {
+                    return Err(Error::Handshake(format!(
+                        "Unexpected command from server: {cmd}"
+                    )))
+                }
             }
-            Command::Error(e) => Err(Error::Handshake(format!("Received error from server: {e}"))),
-            cmd => Err(Error::Handshake(format!(
-                "Unexpected command from server: {cmd}"
-            ))),
         }
     }
 
@@ -194,9 +210,9 @@ impl Handshake for Client {
 
         trace!("Handshake done");
         #[cfg(unix)]
-        let (socket, mut recv_buffer, received_fds, cap_unix_fd, _) = self.common.into_components();
+        let (socket, mut recv_buffer, received_fds, cap_unix_fd) = self.common.into_components();
         #[cfg(not(unix))]
-        let (socket, mut recv_buffer, _, _) = self.common.into_components();
+        let (socket, mut recv_buffer, _) = self.common.into_components();
         let (mut read, write) = socket.take();
 
         // If we're a bus connection, we need to read the unique name from `Hello` response.
@@ -218,6 +234,7 @@ impl Handshake for Client {
             #[cfg(unix)]
             already_received_fds: received_fds,
             unique_name,
+            authenticated_identity: None,
         })
     }
 }
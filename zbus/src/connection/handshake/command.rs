@@ -1,6 +1,6 @@
 use std::{borrow::Cow, fmt, str::FromStr};
 
-use crate::{conn::AuthMechanism, Error, Guid, OwnedGuid, Result};
+use crate::{Error, Guid, OwnedGuid, Result};
 
 // The plain-text SASL profile authentication protocol described here:
 // <https://dbus.freedesktop.org/doc/dbus-specification.html#auth-protocol>
@@ -9,7 +9,7 @@ use crate::{conn::AuthMechanism, Error, Guid, OwnedGuid, Result};
 #[derive(Debug)]
 #[allow(clippy::upper_case_acronyms)]
 pub(super) enum Command {
-    Auth(Option<AuthMechanism>, Option<Vec<u8>>),
+    Auth(Option<String>, Option<Vec<u8>>),
     Cancel,
     Begin,
     Data(Option<Vec<u8>>),
@@ -37,8 +37,8 @@ impl fmt::Display for Command {
             Command::Cancel => write!(f, "CANCEL"),
             Command::Begin => write!(f, "BEGIN"),
             Command::Data(data) => match data {
-                None => write!(f, "DATA"),
-                Some(data) => write!(f, "DATA {}", hex::encode(data)),
+                Some(data) if !data.is_empty() => write!(f, "DATA {}", hex::encode(data)),
+                _ => write!(f, "DATA"),
             },
             Command::Error(expl) => write!(f, "ERROR {expl}"),
             Command::NegotiateUnixFD => write!(f, "NEGOTIATE_UNIX_FD"),
@@ -56,11 +56,7 @@ impl FromStr for Command {
         let mut words = s.split_ascii_whitespace();
         let cmd = match words.next() {
             Some("AUTH") => {
-                let mech = if let Some(m) = words.next() {
-                    Some(m.parse()?)
-                } else {
-                    None
-                };
+                let mech = words.next().map(str::to_string);
                 let resp = match words.next() {
                     Some(resp) => Some(hex::decode(resp)?),
                     None => None,
@@ -0,0 +1,585 @@
+#[cfg(feature = "p2p")]
+use std::io;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use sha1::{Digest, Sha1};
+use tracing::trace;
+#[cfg(feature = "p2p")]
+use tracing::warn;
+use zvariant::Str;
+
+use crate::{Error, Result};
+
+// The timeouts (in seconds) and lock parameters are the same as the ones used by the reference
+// implementation.
+//
+// A new cookie is created if none of the existing ones is younger than this.
+#[cfg(feature = "p2p")]
+const NEW_COOKIE_TIMEOUT: u64 = 60 * 5;
+// Cookies older than this are removed from the keyring.
+#[cfg(feature = "p2p")]
+const EXPIRE_COOKIE_TIMEOUT: u64 = NEW_COOKIE_TIMEOUT + 60 * 2;
+// Cookies that claim to be created this far in the future are considered invalid.
+#[cfg(feature = "p2p")]
+const MAX_TIME_TRAVEL: u64 = 60 * 5;
+#[cfg(feature = "p2p")]
+const MAX_LOCK_ATTEMPTS: usize = 32;
+#[cfg(feature = "p2p")]
+const LOCK_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+// Number of random bytes in a cookie secret.
+#[cfg(feature = "p2p")]
+const COOKIE_SECRET_LEN: usize = 24;
+
+/// A `DBUS_COOKIE_SHA1` cookie, as stored in a keyring file.
+#[derive(Debug, Clone)]
+pub(super) struct Cookie {
+    id: usize,
+    #[cfg_attr(not(feature = "p2p"), allow(dead_code))]
+    created: u64,
+    secret: String,
+}
+
+impl Cookie {
+    #[cfg(feature = "p2p")]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    #[cfg(feature = "p2p")]
+    fn generate(existing: &[Cookie], now: u64) -> Result<Self> {
+        let id = loop {
+            let mut bytes = [0; 4];
+            fill_random(&mut bytes)?;
+            // Keep the IDs positive, as the reference implementation uses signed 32-bit integers.
+            let id = (u32::from_ne_bytes(bytes) & 0x7fff_ffff) as usize;
+            if existing.iter().all(|c| c.id != id) {
+                break id;
+            }
+        };
+
+        Ok(Self {
+            id,
+            created: now,
+            secret: random_hex(COOKIE_SECRET_LEN)?,
+        })
+    }
+
+    #[cfg(feature = "p2p")]
+    fn is_expired(&self, now: u64) -> bool {
+        self.created.saturating_add(EXPIRE_COOKIE_TIMEOUT) < now
+            || self.created > now.saturating_add(MAX_TIME_TRAVEL)
+    }
+
+    #[cfg(feature = "p2p")]
+    fn is_recent(&self, now: u64) -> bool {
+        self.created.saturating_add(NEW_COOKIE_TIMEOUT) > now
+    }
+}
+
+/// The cookie context (keyring name) used by `DBUS_COOKIE_SHA1` authentication.
+#[derive(Debug, Clone)]
+pub(crate) struct CookieContext<'c>(Str<'c>);
+
+impl CookieContext<'_> {
+    pub fn into_owned(self) -> CookieContext<'static> {
+        CookieContext(self.0.into_owned())
+    }
+}
+
+impl<'c> TryFrom<Str<'c>> for CookieContext<'c> {
+    type Error = Error;
+
+    fn try_from(value: Str<'c>) -> Result<Self> {
+        if value.is_empty() {
+            return Err(Error::Handshake("Empty cookie context".into()));
+        } else if !value.is_ascii() || value.contains(['/', '\\', ' ', '\n', '\r', '\t', '.']) {
+            return Err(Error::Handshake(
+                "Invalid characters in cookie context".into(),
+            ));
+        }
+
+        Ok(Self(value))
+    }
+}
+
+impl fmt::Display for CookieContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Default for CookieContext<'_> {
+    fn default() -> Self {
+        Self(Str::from_static("org_freedesktop_general"))
+    }
+}
+
+/// A cookie keyring, i.e a file named after the cookie context in the keyring directory.
+///
+/// By default, the keyring directory is `~/.dbus-keyrings`.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Keyring {
+    dir: Option<PathBuf>,
+    context: CookieContext<'static>,
+}
+
+impl Keyring {
+    #[cfg(feature = "p2p")]
+    pub fn new(context: CookieContext<'static>) -> Self {
+        Self { dir: None, context }
+    }
+
+    #[cfg(all(test, unix, feature = "p2p"))]
+    pub fn with_dir(dir: PathBuf, context: CookieContext<'static>) -> Self {
+        Self {
+            dir: Some(dir),
+            context,
+        }
+    }
+
+    /// The keyring for `context` in the same directory as `self`.
+    pub fn for_context(&self, context: CookieContext<'static>) -> Self {
+        Self {
+            dir: self.dir.clone(),
+            context,
+        }
+    }
+
+    #[cfg(feature = "p2p")]
+    pub fn context(&self) -> &CookieContext<'static> {
+        &self.context
+    }
+
+    /// Look up the cookie with the given `id`.
+    pub async fn lookup(&self, id: usize) -> Result<Cookie> {
+        let keyring = self.clone();
+        crate::Task::spawn_blocking(
+            move || {
+                let dir = keyring.dir()?;
+                check_private_dir(&dir)?;
+                let path = dir.join(keyring.context.to_string());
+                trace!("Reading keyring {}", path.display());
+                let content = fs::read_to_string(&path)?;
+
+                parse_cookies(&content, &path)?
+                    .into_iter()
+                    .find(|c| c.id == id)
+                    .ok_or_else(|| Error::Handshake(format!("DBus cookie ID {id} not found")))
+            },
+            "keyring lookup",
+        )
+        .await?
+    }
+
+    /// Get the most recent cookie from the keyring.
+    ///
+    /// This takes care of creating the keyring (and directory) if needed, removing expired cookies
+    /// and adding a new cookie if none of the existing ones is recent enough.
+    #[cfg(feature = "p2p")]
+    pub async fn current(&self) -> Result<Cookie> {
+        let keyring = self.clone();
+        crate::Task::spawn_blocking(move || keyring.load_or_create(), "keyring update").await?
+    }
+
+    #[cfg(feature = "p2p")]
+    fn load_or_create(&self) -> Result<Cookie> {
+        let dir = self.dir()?;
+        create_private_dir(&dir)?;
+        let context = self.context.to_string();
+        let path = dir.join(&context);
+        let _lock = KeyringLock::acquire(dir.join(format!("{context}.lock")))?;
+
+        trace!("Loading keyring {}", path.display());
+        let mut cookies = match fs::read_to_string(&path) {
+            Ok(content) => parse_cookies(&content, &path)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+        let now = now()?;
+        let n_cookies = cookies.len();
+        cookies.retain(|c| !c.is_expired(now));
+        let mut changed = cookies.len() != n_cookies;
+
+        let recent = cookies
+            .iter()
+            .filter(|c| c.is_recent(now))
+            .max_by_key(|c| c.created)
+            .cloned();
+        let cookie = match recent {
+            Some(cookie) => cookie,
+            None => {
+                let cookie = Cookie::generate(&cookies, now)?;
+                trace!("Adding new cookie {} to keyring", cookie.id);
+                cookies.push(cookie.clone());
+                changed = true;
+
+                cookie
+            }
+        };
+
+        if changed {
+            write_cookies(&dir, &path, &cookies)?;
+        }
+
+        Ok(cookie)
+    }
+
+    fn dir(&self) -> Result<PathBuf> {
+        if let Some(dir) = &self.dir {
+            return Ok(dir.clone());
+        }
+
+        let mut path = xdg_home::home_dir()
+            .ok_or_else(|| Error::Handshake("Failed to determine home directory".into()))?;
+        path.push(".dbus-keyrings");
+
+        Ok(path)
+    }
+}
+
+/// Compute the `DBUS_COOKIE_SHA1` response for the given challenges and cookie.
+pub(super) fn cookie_response(
+    server_challenge: &str,
+    client_challenge: &str,
+    cookie: &Cookie,
+) -> String {
+    let sec = format!("{server_challenge}:{client_challenge}:{}", cookie.secret());
+
+    hex::encode(Sha1::digest(sec))
+}
+
+/// Compare `a` and `b` in a time that only depends on their lengths, so that comparing a
+/// response against the expected one doesn't tell how much of it was right.
+#[cfg(feature = "p2p")]
+pub(super) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Generate `len` random bytes, hex-encoded.
+pub(super) fn random_hex(len: usize) -> Result<String> {
+    let mut bytes = vec![0; len];
+    fill_random(&mut bytes)?;
+
+    Ok(hex::encode(bytes))
+}
+
+fn fill_random(bytes: &mut [u8]) -> Result<()> {
+    getrandom::fill(bytes)
+        .map_err(|e| Error::Handshake(format!("Failed to generate random data: {e}")))
+}
+
+fn parse_cookies(content: &str, path: &Path) -> Result<Vec<Cookie>> {
+    let mut cookies = vec![];
+    for (n, line) in content.lines().enumerate() {
+        let mut split = line.split_whitespace();
+        let id = split
+            .next()
+            .ok_or_else(|| {
+                Error::Handshake(format!(
+                    "DBus cookie `{}` missing ID at line {n}",
+                    path.display(),
+                ))
+            })?
+            .parse()
+            .map_err(|e| {
+                Error::Handshake(format!(
+                    "Failed to parse cookie ID in file `{}` at line {n}: {e}",
+                    path.display(),
+                ))
+            })?;
+        let created = split
+            .next()
+            .ok_or_else(|| {
+                Error::Handshake(format!(
+                    "DBus cookie `{}` missing creation time at line {n}",
+                    path.display(),
+                ))
+            })?
+            .parse()
+            .map_err(|e| {
+                Error::Handshake(format!(
+                    "Failed to parse cookie creation time in file `{}` at line {n}: {e}",
+                    path.display(),
+                ))
+            })?;
+        let secret = split
+            .next()
+            .ok_or_else(|| {
+                Error::Handshake(format!(
+                    "DBus cookie `{}` missing cookie data at line {n}",
+                    path.display(),
+                ))
+            })?
+            .to_string();
+        cookies.push(Cookie {
+            id,
+            created,
+            secret,
+        });
+    }
+
+    Ok(cookies)
+}
+
+#[cfg(feature = "p2p")]
+fn write_cookies(dir: &Path, path: &Path, cookies: &[Cookie]) -> Result<()> {
+    use std::io::Write;
+
+    let content: String = cookies
+        .iter()
+        .map(|c| format!("{} {} {}\n", c.id, c.created, c.secret))
+        .collect();
+
+    // Write to a temporary file first so readers never see a partially written keyring.
+    let tmp_path = dir.join(format!(
+        "{}.{}.tmp",
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default(),
+        random_hex(4)?,
+    ));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+    let res = options
+        .open(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if let Err(e) = res {
+        let _ = fs::remove_file(&tmp_path);
+
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "p2p")]
+fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+
+        builder.mode(0o700);
+    }
+    match builder.create(dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => check_private_dir(dir),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn check_private_dir(dir: &Path) -> Result<()> {
+    let metadata = fs::metadata(dir)?;
+    if !metadata.is_dir() {
+        return Err(Error::Handshake(format!(
+            "DBus keyring `{}` is not a directory",
+            dir.display(),
+        )));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if metadata.uid() != rustix::process::geteuid().as_raw() {
+            return Err(Error::Handshake(format!(
+                "DBus keyring `{}` is not owned by the current user",
+                dir.display(),
+            )));
+        }
+        if metadata.mode() & 0o077 != 0 {
+            return Err(Error::Handshake(format!(
+                "DBus keyring `{}` has invalid permissions",
+                dir.display(),
+            )));
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        // FIXME: add code to check directory permissions
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "p2p")]
+fn now() -> Result<u64> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .map_err(|e| Error::Handshake(format!("System time is before UNIX epoch: {e}")))
+}
+
+/// An exclusive lock on a keyring, released on drop.
+#[cfg(feature = "p2p")]
+#[derive(Debug)]
+struct KeyringLock(PathBuf);
+
+#[cfg(feature = "p2p")]
+impl KeyringLock {
+    fn acquire(path: PathBuf) -> Result<Self> {
+        for _ in 0..MAX_LOCK_ATTEMPTS {
+            match Self::try_acquire(&path) {
+                Ok(()) => return Ok(Self(path)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    std::thread::sleep(LOCK_RETRY_INTERVAL)
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        // Whoever holds the lock has been at it for too long. Assume they died and left a stale
+        // lock file behind.
+        warn!("Removing stale keyring lock `{}`", path.display());
+        fs::remove_file(&path)?;
+        Self::try_acquire(&path)?;
+
+        Ok(Self(path))
+    }
+
+    fn try_acquire(path: &Path) -> io::Result<()> {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map(|_| ())
+    }
+}
+
+#[cfg(feature = "p2p")]
+impl Drop for KeyringLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.0) {
+            warn!("Failed to remove keyring lock `{}`: {e}", self.0.display());
+        }
+    }
+}
+
+#[cfg(feature = "p2p")]
+#[cfg(unix)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    fn keyring(dir: &Path) -> Keyring {
+        Keyring::with_dir(dir.join("keyrings"), CookieContext::default())
+    }
+
+    #[test]
+    fn context() {
+        assert!(CookieContext::try_from(Str::from("org_zbus_test")).is_ok());
+        assert!(CookieContext::try_from(Str::from("")).is_err());
+        assert!(CookieContext::try_from(Str::from("../etc")).is_err());
+        assert!(CookieContext::try_from(Str::from("with space")).is_err());
+    }
+
+    #[cfg(feature = "p2p")]
+    #[test]
+    fn constant_time_comparison() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"0a1b2c", b"0a1b2c"));
+        assert!(!constant_time_eq(b"0a1b2c", b"0a1b2d"));
+        assert!(!constant_time_eq(b"0a1b2c", b"0a1b2"));
+    }
+
+    #[test]
+    fn create_and_reuse() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = keyring(dir.path());
+
+        let cookie = keyring.load_or_create().unwrap();
+        let mode = {
+            use std::os::unix::fs::MetadataExt;
+
+            fs::metadata(dir.path().join("keyrings")).unwrap().mode()
+        };
+        assert_eq!(mode & 0o777, 0o700);
+        assert_eq!(cookie.secret().len(), COOKIE_SECRET_LEN * 2);
+
+        // A recent cookie is reused and can be looked up.
+        let again = keyring.load_or_create().unwrap();
+        assert_eq!(cookie.id(), again.id());
+        let found = crate::utils::block_on(keyring.lookup(cookie.id())).unwrap();
+        assert_eq!(found.secret(), cookie.secret());
+        // The lock is released.
+        assert!(!dir
+            .path()
+            .join("keyrings/org_freedesktop_general.lock")
+            .exists());
+    }
+
+    #[test]
+    fn rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = keyring(dir.path());
+        let keyring_dir = dir.path().join("keyrings");
+        create_private_dir(&keyring_dir).unwrap();
+
+        let now = now().unwrap();
+        let expired = Cookie {
+            id: 1,
+            created: now - EXPIRE_COOKIE_TIMEOUT - 1,
+            secret: "aa".into(),
+        };
+        let old = Cookie {
+            id: 2,
+            created: now - NEW_COOKIE_TIMEOUT - 1,
+            secret: "bb".into(),
+        };
+        let future = Cookie {
+            id: 3,
+            created: now + MAX_TIME_TRAVEL + 60,
+            secret: "cc".into(),
+        };
+        let path = keyring_dir.join("org_freedesktop_general");
+        write_cookies(&keyring_dir, &path, &[expired, old, future]).unwrap();
+
+        let cookie = keyring.load_or_create().unwrap();
+        assert!(![1, 2, 3].contains(&cookie.id()));
+
+        let content = fs::read_to_string(&path).unwrap();
+        let ids: Vec<_> = parse_cookies(&content, &path)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec![2, cookie.id()]);
+    }
+
+    #[test]
+    fn public_dir_rejected() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = keyring(dir.path());
+        let keyring_dir = dir.path().join("keyrings");
+        fs::create_dir(&keyring_dir).unwrap();
+        fs::set_permissions(&keyring_dir, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(matches!(keyring.load_or_create(), Err(Error::Handshake(_))));
+    }
+}
@@ -0,0 +1,333 @@
+// The built-in authentication mechanisms, implemented on top of the `sasl` traits.
+
+use async_trait::async_trait;
+#[cfg(feature = "cookie-sha1")]
+use tracing::trace;
+
+#[cfg(feature = "p2p")]
+use super::sasl::{ServerMechanism, ServerStep};
+#[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+use super::{cookies::constant_time_eq, sasl_auth_id, Cookie};
+#[cfg(feature = "cookie-sha1")]
+use super::{
+    cookies::{cookie_response, random_hex},
+    CookieContext, Keyring,
+};
+use super::{sasl::ClientMechanism, AuthMechanism, Result};
+#[cfg(any(feature = "p2p", feature = "cookie-sha1"))]
+use crate::Error;
+
+/// Create the client-side of the given built-in mechanism.
+pub(super) fn client(
+    mechanism: AuthMechanism,
+    user_id: Result<String>,
+) -> Box<dyn ClientMechanism> {
+    match mechanism {
+        AuthMechanism::External => Box::new(ExternalClient { user_id }),
+        AuthMechanism::Anonymous => Box::new(AnonymousClient),
+        #[cfg(feature = "cookie-sha1")]
+        AuthMechanism::Cookie => Box::new(CookieClient::new(user_id, Keyring::default())),
+    }
+}
+
+/// Create the server-side of the given built-in mechanism.
+#[cfg(feature = "p2p")]
+pub(super) fn server(
+    mechanism: AuthMechanism,
+    #[cfg(unix)] client_uid: Option<u32>,
+    #[cfg(windows)] client_sid: Option<String>,
+) -> Box<dyn ServerMechanism> {
+    match mechanism {
+        AuthMechanism::External => Box::new(ExternalServer {
+            #[cfg(unix)]
+            client_uid,
+            #[cfg(windows)]
+            client_sid,
+        }),
+        AuthMechanism::Anonymous => Box::new(AnonymousServer),
+        #[cfg(feature = "cookie-sha1")]
+        AuthMechanism::Cookie => Box::new(CookieServer::new(Keyring::default(), None)),
+    }
+}
+
+#[derive(Debug)]
+struct ExternalClient {
+    user_id: Result<String>,
+}
+
+#[async_trait]
+impl ClientMechanism for ExternalClient {
+    fn name(&self) -> &str {
+        AuthMechanism::External.as_str()
+    }
+
+    async fn initial_response(&mut self) -> Result<Option<Vec<u8>>> {
+        self.user_id.clone().map(|id| Some(id.into_bytes()))
+    }
+
+    async fn challenge(&mut self, _data: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        self.user_id.clone().map(String::into_bytes)
+    }
+}
+
+#[derive(Debug)]
+struct AnonymousClient;
+
+#[async_trait]
+impl ClientMechanism for AnonymousClient {
+    fn name(&self) -> &str {
+        AuthMechanism::Anonymous.as_str()
+    }
+
+    async fn initial_response(&mut self) -> Result<Option<Vec<u8>>> {
+        Ok(Some("zbus".into()))
+    }
+
+    async fn challenge(&mut self, _data: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        Ok("zbus".into())
+    }
+}
+
+#[cfg(feature = "cookie-sha1")]
+#[derive(Debug)]
+pub(super) struct CookieClient {
+    user_id: Result<String>,
+    keyring: Keyring,
+}
+
+#[cfg(feature = "cookie-sha1")]
+impl CookieClient {
+    /// Only the directory of `keyring` is used, as the server decides on the cookie context.
+    pub fn new(user_id: Result<String>, keyring: Keyring) -> Self {
+        Self { user_id, keyring }
+    }
+}
+
+#[cfg(feature = "cookie-sha1")]
+#[async_trait]
+impl ClientMechanism for CookieClient {
+    fn name(&self) -> &str {
+        AuthMechanism::Cookie.as_str()
+    }
+
+    async fn initial_response(&mut self) -> Result<Option<Vec<u8>>> {
+        self.user_id.clone().map(|id| Some(id.into_bytes()))
+    }
+
+    async fn challenge(&mut self, data: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        trace!("Received cookie challenge from server");
+        let data =
+            data.ok_or_else(|| Error::Handshake("Missing cookie challenge from server".into()))?;
+        let context = std::str::from_utf8(&data)
+            .map_err(|_| Error::Handshake("Cookie context was not valid UTF-8".into()))?;
+        let mut split = context.split_ascii_whitespace();
+        let context = split
+            .next()
+            .ok_or_else(|| Error::Handshake("Missing cookie context name".into()))?;
+        let context = CookieContext::try_from(zvariant::Str::from(context))?.into_owned();
+        let id = split
+            .next()
+            .ok_or_else(|| Error::Handshake("Missing cookie ID".into()))?;
+        let id = id
+            .parse()
+            .map_err(|e| Error::Handshake(format!("Invalid cookie ID `{id}`: {e}")))?;
+        let server_challenge = split
+            .next()
+            .ok_or_else(|| Error::Handshake("Missing cookie challenge".into()))?;
+
+        let cookie = self.keyring.for_context(context).lookup(id).await?;
+        let client_challenge = random_hex(16)?;
+        let sha1 = cookie_response(server_challenge, &client_challenge, &cookie);
+
+        Ok(format!("{client_challenge} {sha1}").into_bytes())
+    }
+}
+
+#[cfg(feature = "p2p")]
+#[derive(Debug)]
+struct ExternalServer {
+    #[cfg(unix)]
+    client_uid: Option<u32>,
+    #[cfg(windows)]
+    client_sid: Option<String>,
+}
+
+#[cfg(feature = "p2p")]
+impl ExternalServer {
+    fn check(&self, sasl_id: &[u8]) -> Result<ServerStep> {
+        let id = std::str::from_utf8(sasl_id)
+            .map_err(|e| Error::Handshake(format!("Invalid ID: {e}")))?;
+        let auth_ok = {
+            #[cfg(unix)]
+            {
+                let uid = id
+                    .parse::<u32>()
+                    .map_err(|e| Error::Handshake(format!("Invalid UID: {e}")))?;
+                self.client_uid.map(|u| u == uid).unwrap_or(false)
+            }
+            #[cfg(windows)]
+            {
+                self.client_sid.as_ref().map(|u| u == id).unwrap_or(false)
+            }
+        };
+
+        if auth_ok {
+            Ok(ServerStep::Accepted(Some(id.to_string())))
+        } else {
+            Ok(ServerStep::Rejected)
+        }
+    }
+
+    // The identity derived from the socket credentials, for when the client doesn't send one.
+    fn credentials_identity(&self) -> Option<String> {
+        #[cfg(unix)]
+        {
+            self.client_uid.map(|uid| uid.to_string())
+        }
+        #[cfg(windows)]
+        {
+            self.client_sid.clone()
+        }
+    }
+}
+
+#[cfg(feature = "p2p")]
+#[async_trait]
+impl ServerMechanism for ExternalServer {
+    fn name(&self) -> &str {
+        AuthMechanism::External.as_str()
+    }
+
+    async fn start(&mut self, initial_response: Option<Vec<u8>>) -> Result<ServerStep> {
+        match initial_response {
+            None => Ok(ServerStep::Challenge(vec![])),
+            Some(sasl_id) => self.check(&sasl_id),
+        }
+    }
+
+    async fn data(&mut self, data: Option<Vec<u8>>) -> Result<ServerStep> {
+        match data {
+            Some(sasl_id) if !sasl_id.is_empty() => self.check(&sasl_id),
+            // The client asks us to use the identity from the socket credentials.
+            _ => Ok(ServerStep::Accepted(self.credentials_identity())),
+        }
+    }
+}
+
+#[cfg(feature = "p2p")]
+#[derive(Debug)]
+struct AnonymousServer;
+
+#[cfg(feature = "p2p")]
+#[async_trait]
+impl ServerMechanism for AnonymousServer {
+    fn name(&self) -> &str {
+        AuthMechanism::Anonymous.as_str()
+    }
+
+    async fn start(&mut self, initial_response: Option<Vec<u8>>) -> Result<ServerStep> {
+        match initial_response {
+            None => Ok(ServerStep::Challenge(vec![])),
+            Some(_) => Ok(ServerStep::Accepted(None)),
+        }
+    }
+
+    async fn data(&mut self, _data: Option<Vec<u8>>) -> Result<ServerStep> {
+        Ok(ServerStep::Accepted(None))
+    }
+}
+
+#[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+#[derive(Debug)]
+pub(super) struct CookieServer {
+    keyring: Keyring,
+    cookie_id: Option<usize>,
+    // The cookie and server challenge of the ongoing exchange.
+    pending: Option<(Cookie, String)>,
+}
+
+#[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+impl CookieServer {
+    /// If `cookie_id` is `None`, the most recent cookie of the keyring is used.
+    pub fn new(keyring: Keyring, cookie_id: Option<usize>) -> Self {
+        Self {
+            keyring,
+            cookie_id,
+            pending: None,
+        }
+    }
+
+    async fn challenge(&mut self, sasl_id: &[u8]) -> Result<ServerStep> {
+        let id = std::str::from_utf8(sasl_id)
+            .map_err(|e| Error::Handshake(format!("Invalid ID: {e}")))?;
+        if sasl_auth_id()? != id {
+            // While the spec will make you believe that DBUS_COOKIE_SHA1 can be used to
+            // authenticate any user, it is not even possible (or correct) for the server to manage
+            // contents in random users' home directories.
+            //
+            // The dbus reference implementation also has the same limitation/behavior.
+            return Ok(ServerStep::Rejected);
+        }
+
+        let cookie = match self.cookie_id {
+            Some(id) => self.keyring.lookup(id).await?,
+            None => self.keyring.current().await?,
+        };
+        let server_challenge = random_hex(16)?;
+        let data = format!(
+            "{} {} {server_challenge}",
+            self.keyring.context(),
+            cookie.id()
+        );
+        trace!("Sending DBUS_COOKIE_SHA1 authentication challenge");
+        self.pending = Some((cookie, server_challenge));
+
+        Ok(ServerStep::Challenge(data.into_bytes()))
+    }
+
+    fn verify(&self, cookie: &Cookie, server_challenge: &str, data: &[u8]) -> Result<ServerStep> {
+        let client_auth = std::str::from_utf8(data)
+            .map_err(|e| Error::Handshake(format!("Invalid COOKIE authentication data: {e}")))?;
+        let mut split = client_auth.split_ascii_whitespace();
+        let (client_challenge, client_sha1) = match (split.next(), split.next()) {
+            (Some(challenge), Some(sha1)) => (challenge, sha1),
+            _ => return Ok(ServerStep::Rejected),
+        };
+
+        let expected = cookie_response(server_challenge, client_challenge, cookie);
+        if constant_time_eq(expected.as_bytes(), client_sha1.as_bytes()) {
+            Ok(ServerStep::Accepted(Some(sasl_auth_id()?)))
+        } else {
+            Ok(ServerStep::Rejected)
+        }
+    }
+}
+
+#[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+#[async_trait]
+impl ServerMechanism for CookieServer {
+    fn name(&self) -> &str {
+        AuthMechanism::Cookie.as_str()
+    }
+
+    async fn start(&mut self, initial_response: Option<Vec<u8>>) -> Result<ServerStep> {
+        self.pending = None;
+
+        match initial_response {
+            // Ask for the user ID first.
+            None => Ok(ServerStep::Challenge(vec![])),
+            Some(sasl_id) => self.challenge(&sasl_id).await,
+        }
+    }
+
+    async fn data(&mut self, data: Option<Vec<u8>>) -> Result<ServerStep> {
+        let Some(data) = data else {
+            return Ok(ServerStep::Rejected);
+        };
+
+        match self.pending.take() {
+            None => self.challenge(&data).await,
+            Some((cookie, server_challenge)) => self.verify(&cookie, &server_challenge, &data),
+        }
+    }
+}
@@ -2,6 +2,10 @@ mod auth_mechanism;
 mod client;
 mod command;
 mod common;
+#[cfg(feature = "cookie-sha1")]
+mod cookies;
+mod mechanisms;
+pub mod sasl;
 #[cfg(feature = "p2p")]
 mod server;
 
@@ -21,6 +25,14 @@ pub use auth_mechanism::AuthMechanism;
 use client::Client;
 use command::Command;
 use common::Common;
+#[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+use cookies::Cookie;
+#[cfg(feature = "cookie-sha1")]
+pub(crate) use cookies::CookieContext;
+#[cfg(feature = "cookie-sha1")]
+use cookies::Keyring;
+#[cfg(all(feature = "p2p", feature = "cookie-sha1"))]
+use mechanisms::CookieServer;
 #[cfg(feature = "p2p")]
 use server::Server;
 
@@ -44,6 +56,8 @@ pub struct Authenticated {
     #[cfg(unix)]
     pub(crate) already_received_fds: Vec<std::os::fd::OwnedFd>,
     pub(crate) unique_name: Option<OwnedUniqueName>,
+    /// The identity the client authenticated as (server-side only)
+    pub(crate) authenticated_identity: Option<String>,
 }
 
 impl Authenticated {
@@ -52,27 +66,43 @@ impl Authenticated {
         socket: BoxedSplit,
         server_guid: Option<OwnedGuid>,
         mechanism: Option<AuthMechanism>,
+        custom_mechanism: Option<Box<dyn sasl::ClientMechanism>>,
         bus: bool,
         user_id: Option<u32>,
     ) -> Result<Self> {
-        Client::new(socket, mechanism, server_guid, bus, user_id)
-            .perform()
-            .await
+        let mut client = Client::new(socket, mechanism, server_guid, bus, user_id);
+        if let Some(mechanism) = custom_mechanism {
+            client.set_mechanism(mechanism);
+        }
+
+        client.perform().await
     }
 
     /// Create a server-side `Authenticated` for the given `socket`.
     ///
     /// The function takes `client_uid` on Unix only. On Windows, it takes `client_sid` instead.
+    ///
+    /// If `custom_mechanism` is given, it is used instead of `auth_mechanism`.
+    ///
+    /// The `cookie_context` and `cookie_id` are only used for the `DBUS_COOKIE_SHA1` mechanism. If
+    /// `cookie_id` is `None`, the most recent cookie of the keyring is used, creating (and
+    /// rotating) cookies as needed.
     #[cfg(feature = "p2p")]
+    #[allow(clippy::too_many_arguments)]
     pub async fn server(
         socket: BoxedSplit,
         guid: OwnedGuid,
         #[cfg(unix)] client_uid: Option<u32>,
         #[cfg(windows)] client_sid: Option<String>,
         auth_mechanism: Option<AuthMechanism>,
+        custom_mechanism: Option<Box<dyn sasl::ServerMechanism>>,
+        #[cfg(feature = "cookie-sha1")] cookie_context: CookieContext<'static>,
+        #[cfg(feature = "cookie-sha1")] cookie_id: Option<usize>,
         unique_name: Option<OwnedUniqueName>,
     ) -> Result<Self> {
-        Server::new(
+        #[cfg(feature = "cookie-sha1")]
+        let cookie = auth_mechanism == Some(AuthMechanism::Cookie);
+        let mut server = Server::new(
             socket,
             guid,
             #[cfg(unix)]
@@ -81,9 +111,17 @@ impl Authenticated {
             client_sid,
             auth_mechanism,
             unique_name,
-        )?
-        .perform()
-        .await
+        )?;
+        #[cfg(feature = "cookie-sha1")]
+        if cookie {
+            let keyring = Keyring::new(cookie_context);
+            server.set_mechanism(Box::new(CookieServer::new(keyring, cookie_id)));
+        }
+        if let Some(mechanism) = custom_mechanism {
+            server.set_mechanism(mechanism);
+        }
+
+        server.perform().await
     }
 }
 
@@ -276,4 +314,185 @@ mod tests {
             .unwrap();
         crate::utils::block_on(server.perform()).unwrap();
     }
+
+    #[derive(Debug)]
+    struct TokenClient(&'static str);
+
+    #[async_trait]
+    impl sasl::ClientMechanism for TokenClient {
+        fn name(&self) -> &str {
+            "X_ZBUS_TOKEN"
+        }
+
+        async fn initial_response(&mut self) -> Result<Option<Vec<u8>>> {
+            Ok(None)
+        }
+
+        async fn challenge(&mut self, data: Option<Vec<u8>>) -> Result<Vec<u8>> {
+            assert_eq!(data.as_deref(), Some(&b"token?"[..]));
+
+            Ok(self.0.as_bytes().to_vec())
+        }
+    }
+
+    #[derive(Debug)]
+    struct TokenServer;
+
+    #[async_trait]
+    impl sasl::ServerMechanism for TokenServer {
+        fn name(&self) -> &str {
+            "X_ZBUS_TOKEN"
+        }
+
+        async fn start(&mut self, initial_response: Option<Vec<u8>>) -> Result<sasl::ServerStep> {
+            assert!(initial_response.is_none());
+
+            Ok(sasl::ServerStep::Challenge(b"token?".to_vec()))
+        }
+
+        async fn data(&mut self, data: Option<Vec<u8>>) -> Result<sasl::ServerStep> {
+            if data.as_deref() == Some(&b"s3cr3t"[..]) {
+                Ok(sasl::ServerStep::Accepted(Some("token-holder".into())))
+            } else {
+                Ok(sasl::ServerStep::Rejected)
+            }
+        }
+    }
+
+    fn custom_handshake(token: &'static str) -> (Result<Authenticated>, Result<Authenticated>) {
+        let (p0, p1) = create_async_socket_pair();
+        let guid = OwnedGuid::from(Guid::generate());
+        let mut client = Client::new(p0.into(), None, Some(guid.clone()), false, None);
+        client.set_mechanism(Box::new(TokenClient(token)));
+        let mut server = Server::new(p1.into(), guid, None, None, None).unwrap();
+        server.set_mechanism(Box::new(TokenServer));
+
+        crate::utils::block_on(join(client.perform(), server.perform()))
+    }
+
+    #[test]
+    #[timeout(15000)]
+    fn custom_mechanism() {
+        let (client, server) = custom_handshake("s3cr3t");
+        let (client, server) = (client.unwrap(), server.unwrap());
+
+        assert_eq!(client.server_guid, server.server_guid);
+        assert_eq!(client.authenticated_identity, None);
+        assert_eq!(
+            server.authenticated_identity.as_deref(),
+            Some("token-holder")
+        );
+    }
+
+    #[test]
+    #[timeout(15000)]
+    fn custom_mechanism_rejected() {
+        let (client, _) = custom_handshake("guess");
+        match client {
+            Err(Error::Handshake(e)) => assert!(e.contains("X_ZBUS_TOKEN"), "{e}"),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "cookie-sha1")]
+    #[test]
+    #[timeout(15000)]
+    fn cookie_handshake() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring_dir = dir.path().join("keyrings");
+        let context = CookieContext::try_from(zvariant::Str::from("org_zbus_test")).unwrap();
+
+        // Twice, so that the second time the cookie created by the first handshake is reused.
+        for _ in 0..2 {
+            let (p0, p1) = create_async_socket_pair();
+            let guid = OwnedGuid::from(Guid::generate());
+            let mut client = Client::new(
+                p0.into(),
+                Some(AuthMechanism::Cookie),
+                Some(guid.clone()),
+                false,
+                None,
+            );
+            client.set_mechanism(Box::new(mechanisms::CookieClient::new(
+                sasl_auth_id(),
+                Keyring::with_dir(keyring_dir.clone(), Default::default()),
+            )));
+            let mut server = Server::new(
+                p1.into(),
+                guid,
+                Some(geteuid().as_raw()),
+                Some(AuthMechanism::Cookie),
+                None,
+            )
+            .unwrap();
+            server.set_mechanism(Box::new(CookieServer::new(
+                Keyring::with_dir(keyring_dir.clone(), context.clone()),
+                None,
+            )));
+
+            let (client, server) = crate::utils::block_on(join(
+                async move { client.perform().await.unwrap() },
+                async move { server.perform().await.unwrap() },
+            ));
+
+            assert_eq!(client.server_guid, server.server_guid);
+            assert_eq!(client.cap_unix_fd, server.cap_unix_fd);
+        }
+
+        let keyring = std::fs::read_to_string(keyring_dir.join("org_zbus_test")).unwrap();
+        assert_eq!(keyring.lines().count(), 1);
+    }
+
+    #[cfg(feature = "cookie-sha1")]
+    #[test]
+    #[timeout(15000)]
+    fn cookie_handshake_wrong_cookie() {
+        let server_dir = tempfile::tempdir().unwrap();
+        let client_dir = tempfile::tempdir().unwrap();
+        let context = CookieContext::default();
+        // Same cookie ID, different secrets.
+        for (dir, secret) in [(&server_dir, "deadbeef"), (&client_dir, "cafebabe")] {
+            let keyring_dir = dir.path().join("keyrings");
+            std::fs::create_dir(&keyring_dir).unwrap();
+            std::fs::set_permissions(
+                &keyring_dir,
+                std::os::unix::fs::PermissionsExt::from_mode(0o700),
+            )
+            .unwrap();
+            std::fs::write(
+                keyring_dir.join(context.to_string()),
+                format!("42 1000 {secret}\n"),
+            )
+            .unwrap();
+        }
+
+        let (p0, p1) = create_async_socket_pair();
+        let guid = OwnedGuid::from(Guid::generate());
+        let mut client = Client::new(
+            p0.into(),
+            Some(AuthMechanism::Cookie),
+            Some(guid.clone()),
+            false,
+            None,
+        );
+        client.set_mechanism(Box::new(mechanisms::CookieClient::new(
+            sasl_auth_id(),
+            Keyring::with_dir(client_dir.path().join("keyrings"), Default::default()),
+        )));
+        let mut server = Server::new(
+            p1.into(),
+            guid,
+            Some(geteuid().as_raw()),
+            Some(AuthMechanism::Cookie),
+            None,
+        )
+        .unwrap();
+        server.set_mechanism(Box::new(CookieServer::new(
+            Keyring::with_dir(server_dir.path().join("keyrings"), context),
+            Some(42),
+        )));
+
+        let (client, _) = crate::utils::block_on(join(client.perform(), server.perform()));
+        assert!(matches!(client, Err(Error::Handshake(_))));
+    }
 }
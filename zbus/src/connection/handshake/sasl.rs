@@ -0,0 +1,157 @@
+//! Pluggable [SASL] authentication mechanisms.
+//!
+//! The built-in mechanisms (see [`AuthMechanism`]) cover the common cases. If you need something
+//! else, for example token-based authentication on a peer-to-peer socket, you can implement
+//! [`ClientMechanism`] and/or [`ServerMechanism`] and hand them to the connection builder, through
+//! [`Builder::client_auth_mechanism`] and [`Builder::server_auth_mechanism`] respectively.
+//!
+//! The D-Bus authentication protocol is a line-based SASL profile. Both traits deal only with the
+//! mechanism-specific data, i.e. the (already hex-decoded) data carried by the `AUTH` and `DATA`
+//! commands. Everything else (including the negotiation of file descriptor passing) is taken care
+//! of by zbus.
+//!
+//! # Example
+//!
+//! A (rather naive) mechanism where the client proves that it knows a shared token:
+//!
+//! ```
+//! use zbus::{
+//!     connection::sasl::{ClientMechanism, ServerMechanism, ServerStep},
+//!     Result,
+//! };
+//!
+//! #[derive(Debug)]
+//! struct TokenClient(&'static str);
+//!
+//! #[zbus::export::async_trait::async_trait]
+//! impl ClientMechanism for TokenClient {
+//!     fn name(&self) -> &str {
+//!         "X_ZBUS_TOKEN"
+//!     }
+//!
+//!     async fn initial_response(&mut self) -> Result<Option<Vec<u8>>> {
+//!         Ok(Some(self.0.as_bytes().to_vec()))
+//!     }
+//!
+//!     async fn challenge(&mut self, _data: Option<Vec<u8>>) -> Result<Vec<u8>> {
+//!         Err(zbus::Error::Handshake("Unexpected challenge".into()))
+//!     }
+//! }
+//!
+//! #[derive(Debug)]
+//! struct TokenServer(&'static str);
+//!
+//! #[zbus::export::async_trait::async_trait]
+//! impl ServerMechanism for TokenServer {
+//!     fn name(&self) -> &str {
+//!         "X_ZBUS_TOKEN"
+//!     }
+//!
+//!     async fn start(&mut self, initial_response: Option<Vec<u8>>) -> Result<ServerStep> {
+//!         match initial_response {
+//!             // Ask for the token.
+//!             None => Ok(ServerStep::Challenge(vec![])),
+//!             Some(token) => self.data(Some(token)).await,
+//!         }
+//!     }
+//!
+//!     async fn data(&mut self, data: Option<Vec<u8>>) -> Result<ServerStep> {
+//!         if data.as_deref() == Some(self.0.as_bytes()) {
+//!             Ok(ServerStep::Accepted(Some("token-holder".into())))
+//!         } else {
+//!             Ok(ServerStep::Rejected)
+//!         }
+//!     }
+//! }
+//!
+//! # #[cfg(all(unix, feature = "p2p"))]
+//! # zbus::block_on(async {
+//! #     #[cfg(not(feature = "tokio"))]
+//! #     use std::os::unix::net::UnixStream;
+//! #     #[cfg(feature = "tokio")]
+//! #     use tokio::net::UnixStream;
+//! #     use zbus::{connection::Builder, Guid};
+//! #
+//! #     let (p0, p1) = UnixStream::pair().unwrap();
+//! let guid = Guid::generate();
+//! let (client, server) = futures_util::try_join!(
+//!     Builder::unix_stream(p0)
+//!         .p2p()
+//!         .client_auth_mechanism(Box::new(TokenClient("s3cr3t")))
+//!         .build(),
+//!     Builder::unix_stream(p1)
+//!         .server(guid)?
+//!         .p2p()
+//!         .server_auth_mechanism(Box::new(TokenServer("s3cr3t")))
+//!         .build(),
+//! )?;
+//! assert_eq!(server.authenticated_identity(), Some("token-holder"));
+//! #     drop(client);
+//! #     Ok::<(), zbus::Error>(())
+//! # }).unwrap();
+//! ```
+//!
+//! [SASL]: https://datatracker.ietf.org/doc/html/rfc4422
+//! [`AuthMechanism`]: super::AuthMechanism
+//! [`Builder::client_auth_mechanism`]: crate::connection::Builder::client_auth_mechanism
+//! [`Builder::server_auth_mechanism`]: crate::connection::Builder::server_auth_mechanism
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+
+use crate::Result;
+
+/// The client-side of a SASL authentication mechanism.
+#[async_trait]
+pub trait ClientMechanism: Debug + Send + Sync {
+    /// The name of the mechanism, as sent in the `AUTH` command.
+    fn name(&self) -> &str;
+
+    /// The initial response to send along with the `AUTH` command.
+    ///
+    /// If `None` is returned, the server is expected to send a challenge first.
+    async fn initial_response(&mut self) -> Result<Option<Vec<u8>>>;
+
+    /// Respond to a challenge (the `DATA` command) from the server.
+    ///
+    /// The returned data is sent to the server in a `DATA` command. Returning an error aborts the
+    /// handshake.
+    async fn challenge(&mut self, data: Option<Vec<u8>>) -> Result<Vec<u8>>;
+}
+
+/// The next step in the server-side authentication exchange.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerStep {
+    /// Send the given challenge to the client and wait for its response.
+    Challenge(Vec<u8>),
+    /// The client is authenticated, optionally as the given identity.
+    ///
+    /// The identity is available through [`Connection::authenticated_identity`] after the
+    /// connection has been established.
+    ///
+    /// [`Connection::authenticated_identity`]: crate::Connection::authenticated_identity
+    Accepted(Option<String>),
+    /// The authentication failed.
+    ///
+    /// The client is free to try again, in which case [`ServerMechanism::start`] will be called
+    /// again.
+    Rejected,
+}
+
+/// The server-side of a SASL authentication mechanism.
+#[async_trait]
+pub trait ServerMechanism: Debug + Send + Sync {
+    /// The name of the mechanism, as expected in the `AUTH` command.
+    fn name(&self) -> &str;
+
+    /// Start a new authentication exchange.
+    ///
+    /// This is called when the client requests authentication with this mechanism, with the
+    /// initial response if the client sent one. Implementations should discard any state from a
+    /// previous (rejected or cancelled) exchange.
+    async fn start(&mut self, initial_response: Option<Vec<u8>>) -> Result<ServerStep>;
+
+    /// Handle data (the `DATA` command) sent by the client in response to a challenge.
+    async fn data(&mut self, data: Option<Vec<u8>>) -> Result<ServerStep>;
+}
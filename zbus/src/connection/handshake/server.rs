@@ -4,7 +4,9 @@ use tracing::{instrument, trace};
 use crate::names::OwnedUniqueName;
 
 use super::{
-    AuthMechanism, Authenticated, BoxedSplit, Command, Common, Error, Handshake, OwnedGuid, Result,
+    mechanisms,
+    sasl::{ServerMechanism, ServerStep},
+    AuthMechanism, Authenticated, BoxedSplit, Command, Common, Handshake, OwnedGuid, Result,
 };
 
 /*
@@ -14,7 +16,7 @@ use super::{
 #[allow(clippy::upper_case_acronyms)]
 enum ServerHandshakeStep {
     WaitingForAuth,
-    WaitingForData(AuthMechanism),
+    WaitingForData,
     WaitingForBegin,
     Done,
 }
@@ -27,10 +29,8 @@ pub struct Server {
     common: Common,
     step: ServerHandshakeStep,
     guid: OwnedGuid,
-    #[cfg(unix)]
-    client_uid: Option<u32>,
-    #[cfg(windows)]
-    client_sid: Option<String>,
+    mechanism: Box<dyn ServerMechanism>,
+    identity: Option<String>,
     unique_name: Option<OwnedUniqueName>,
 }
 
@@ -44,19 +44,29 @@ impl Server {
         unique_name: Option<OwnedUniqueName>,
     ) -> Result<Self> {
         let mechanism = mechanism.unwrap_or_else(|| socket.read().auth_mechanism());
-
-        Ok(Server {
-            common: Common::new(socket, mechanism),
-            step: ServerHandshakeStep::WaitingForAuth,
+        let mechanism = mechanisms::server(
+            mechanism,
             #[cfg(unix)]
             client_uid,
             #[cfg(windows)]
             client_sid,
+        );
+
+        Ok(Server {
+            common: Common::new(socket),
+            step: ServerHandshakeStep::WaitingForAuth,
             guid,
+            mechanism,
+            identity: None,
             unique_name,
         })
     }
 
+    /// Use the given mechanism, instead of the one given to [`Server::new`].
+    pub fn set_mechanism(&mut self, mechanism: Box<dyn ServerMechanism>) {
+        self.mechanism = mechanism;
+    }
+
     #[instrument(skip(self))]
     async fn auth_ok(&mut self) -> Result<()> {
         let guid = self.guid.clone();
@@ -68,27 +78,22 @@ impl Server {
         Ok(())
     }
 
-    async fn check_external_auth(&mut self, sasl_id: &[u8]) -> Result<()> {
-        let auth_ok = {
-            let id = std::str::from_utf8(sasl_id)
-                .map_err(|e| Error::Handshake(format!("Invalid ID: {e}")))?;
-            #[cfg(unix)]
-            {
-                let uid = id
-                    .parse::<u32>()
-                    .map_err(|e| Error::Handshake(format!("Invalid UID: {e}")))?;
-                self.client_uid.map(|u| u == uid).unwrap_or(false)
-            }
-            #[cfg(windows)]
-            {
-                self.client_sid.as_ref().map(|u| u == id).unwrap_or(false)
+    /// Act on the outcome of an authentication step.
+    async fn handle_step(&mut self, step: ServerStep) -> Result<()> {
+        match step {
+            ServerStep::Challenge(data) => {
+                trace!("Sending authentication challenge");
+                self.common.write_command(Command::Data(Some(data))).await?;
+                self.step = ServerHandshakeStep::WaitingForData;
+
+                Ok(())
             }
-        };
+            ServerStep::Accepted(identity) => {
+                self.identity = identity;
 
-        if auth_ok {
-            self.auth_ok().await
-        } else {
-            self.rejected_error().await
+                self.auth_ok().await
+            }
+            ServerStep::Rejected => self.rejected_error().await,
         }
     }
 
@@ -102,7 +107,7 @@ impl Server {
 
     #[instrument(skip(self))]
     async fn rejected_error(&mut self) -> Result<()> {
-        let cmd = Command::Rejected(self.common.mechanism().as_str().into());
+        let cmd = Command::Rejected(self.mechanism.name().to_string().into());
         trace!("Sending authentication error");
         self.common.write_command(cmd).await?;
         self.step = ServerHandshakeStep::WaitingForAuth;
@@ -115,7 +120,7 @@ impl Server {
     async fn next_step(&mut self) -> Result<bool> {
         match self.step {
             ServerHandshakeStep::WaitingForAuth => self.handle_auth().await?,
-            ServerHandshakeStep::WaitingForData(mech) => self.handle_auth_data(mech).await?,
+            ServerHandshakeStep::WaitingForData => self.handle_auth_data().await?,
             ServerHandshakeStep::WaitingForBegin => self.finalize().await?,
             ServerHandshakeStep::Done => return Ok(true),
         }
@@ -132,24 +137,14 @@ impl Server {
         let reply = self.common.read_command().await?;
         match reply {
             Command::Auth(requested_mech, resp) => {
-                let mech = self.common.mechanism();
-                if requested_mech != Some(mech) {
+                if requested_mech.as_deref() != Some(self.mechanism.name()) {
                     self.rejected_error().await?;
 
                     return Ok(());
                 }
 
-                match &resp {
-                    None => {
-                        trace!("Sending data request");
-                        self.common.write_command(Command::Data(None)).await?;
-                        self.step = ServerHandshakeStep::WaitingForData(mech);
-                    }
-                    Some(sasl_id) => match mech {
-                        AuthMechanism::Anonymous => self.auth_ok().await?,
-                        AuthMechanism::External => self.check_external_auth(sasl_id).await?,
-                    },
-                }
+                let step = self.mechanism.start(resp).await?;
+                self.handle_step(step).await?;
             }
             Command::Cancel | Command::Error(_) => {
                 trace!("Received CANCEL or ERROR command from the client");
@@ -163,19 +158,23 @@ impl Server {
 
     /// Handle the authentication data receiving step of the handshake.
     #[instrument(skip(self))]
-    async fn handle_auth_data(&mut self, mech: AuthMechanism) -> Result<()> {
-        assert!(matches!(self.step, ServerHandshakeStep::WaitingForData(_)));
+    async fn handle_auth_data(&mut self) -> Result<()> {
+        assert_eq!(self.step, ServerHandshakeStep::WaitingForData);
 
         trace!("Waiting for authentication data");
         let reply = self.common.read_command().await?;
-        match (mech, reply) {
-            (AuthMechanism::External, Command::Data(None)) => self.auth_ok().await?,
-            (AuthMechanism::External, Command::Data(Some(data))) => {
-                self.check_external_auth(&data).await?;
+        match reply {
+            Command::Data(data) => {
+                let step = self.mechanism.data(data).await?;
+                self.handle_step(step).await?;
+            }
+            Command::Cancel | Command::Error(_) => {
+                trace!("Received CANCEL or ERROR command from the client");
+                self.rejected_error().await?;
             }
-            (AuthMechanism::Anonymous, Command::Data(_)) => self.auth_ok().await?,
-            (_, _) => self.unsupported_command_error().await?,
+            _ => self.unsupported_command_error().await?,
         }
+
         Ok(())
     }
 
@@ -224,9 +223,9 @@ impl Handshake for Server {
 
         trace!("Handshake done");
         #[cfg(unix)]
-        let (socket, recv_buffer, received_fds, cap_unix_fd, _) = self.common.into_components();
+        let (socket, recv_buffer, received_fds, cap_unix_fd) = self.common.into_components();
         #[cfg(not(unix))]
-        let (socket, recv_buffer, _, _) = self.common.into_components();
+        let (socket, recv_buffer, _) = self.common.into_components();
         let (read, write) = socket.take();
         Ok(Authenticated {
             socket_write: write,
@@ -238,6 +237,7 @@ impl Handshake for Server {
             #[cfg(unix)]
             already_received_fds: received_fds,
             unique_name: self.unique_name,
+            authenticated_identity: self.identity,
         })
     }
 }
@@ -0,0 +1,123 @@
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{message::Type, Message, OwnedMatchRule};
+
+/// A sink for the metrics of a [`Connection`].
+///
+/// Set one through [`Builder::metrics_sink`] to instrument a connection. All methods have a no-op
+/// default implementation, so implementors only need to override the ones they're interested in.
+///
+/// The methods are called from the internal tasks of the connection and while sending messages,
+/// so they must not block. Typically, an implementation would just update some counters or
+/// forward the data to a metrics library. Messages exchanged during the handshake, such as the
+/// `Hello` call to the bus, are not reported.
+///
+/// The size of a message in bytes is given by the length of its [`Message::data`] and, on Unix,
+/// the file descriptors passed along with it by [`Data::fds`].
+///
+/// # Example
+///
+/// ```
+/// use std::sync::{
+///     atomic::{AtomicUsize, Ordering},
+///     Arc,
+/// };
+/// use zbus::{connection::{Builder, MetricsSink}, Message};
+///
+/// #[derive(Debug, Default)]
+/// struct Bytes {
+///     sent: AtomicUsize,
+///     received: AtomicUsize,
+/// }
+///
+/// impl MetricsSink for Bytes {
+///     fn message_sent(&self, msg: &Message) {
+///         self.sent.fetch_add(msg.data().len(), Ordering::Relaxed);
+///     }
+///
+///     fn message_received(&self, msg: &Message) {
+///         self.received.fetch_add(msg.data().len(), Ordering::Relaxed);
+///     }
+/// }
+///
+/// # zbus::block_on(async {
+/// let bytes = Arc::new(Bytes::default());
+/// let conn = Builder::session()?.metrics_sink(bytes.clone()).build().await?;
+/// zbus::fdo::DBusProxy::new(&conn).await?.get_id().await?;
+/// assert!(bytes.sent.load(Ordering::Relaxed) > 0);
+/// assert!(bytes.received.load(Ordering::Relaxed) > 0);
+/// # Ok::<(), zbus::Error>(())
+/// # }).unwrap();
+/// ```
+///
+/// [`Connection`]: super::Connection
+/// [`Builder::metrics_sink`]: super::Builder::metrics_sink
+/// [`Data::fds`]: zvariant::serialized::Data::fds
+pub trait MetricsSink: Send + Sync + Debug {
+    /// A message was sent to the peer.
+    fn message_sent(&self, msg: &Message) {
+        let _ = msg;
+    }
+
+    /// A message was received from the peer.
+    fn message_received(&self, msg: &Message) {
+        let _ = msg;
+    }
+
+    /// A received message was queued for the streams of `rule`.
+    ///
+    /// `rule` is `None` for the queue of the unfiltered streams, whose `capacity` is the
+    /// [`max_queued`] of the connection. A `depth` reaching the `capacity` means that the reading
+    /// of the socket is held back until the streams catch up.
+    ///
+    /// [`max_queued`]: super::Connection::max_queued
+    fn message_queued(&self, rule: Option<&OwnedMatchRule>, depth: usize, capacity: usize) {
+        let _ = (rule, depth, capacity);
+    }
+
+    /// A received message for the streams of `rule` was dropped, as none of them were active.
+    ///
+    /// Note that, unlike the ones for match rules, the queue of the unfiltered streams (`rule` is
+    /// `None`) exists even if no such stream was ever created.
+    fn message_dropped(&self, rule: Option<&OwnedMatchRule>, msg: &Message) {
+        let _ = (rule, msg);
+    }
+
+    /// A method call sent through the connection completed.
+    ///
+    /// `reply` is the method return or error message, if any was received. Otherwise, the call
+    /// either failed, timed out or was cancelled. The interface and member of the call can be
+    /// retrieved from the header of the `call` message.
+    fn method_call_completed(&self, call: &Message, reply: Option<&Message>, latency: Duration) {
+        let _ = (call, reply, latency);
+    }
+}
+
+// The metrics of a method call in progress.
+#[derive(Debug)]
+pub(super) struct CallMetrics {
+    sink: Arc<dyn MetricsSink>,
+    call: Message,
+    start: Instant,
+}
+
+impl CallMetrics {
+    pub(super) fn new(sink: Arc<dyn MetricsSink>, call: Message) -> Self {
+        debug_assert_eq!(call.message_type(), Type::MethodCall);
+
+        Self {
+            sink,
+            call,
+            start: Instant::now(),
+        }
+    }
+
+    pub(super) fn complete(self, reply: Option<&Message>) {
+        self.sink
+            .method_call_completed(&self.call, reply, self.start.elapsed());
+    }
+}
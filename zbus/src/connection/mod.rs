@@ -8,7 +8,10 @@ use std::{
     io::{self, ErrorKind},
     num::NonZeroU32,
     pin::Pin,
-    sync::{Arc, OnceLock, Weak},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock, Weak,
+    },
     task::{Context, Poll},
     time::Duration,
 };
@@ -38,9 +41,23 @@ pub use socket::Socket;
 mod socket_reader;
 use socket_reader::SocketReader;
 
+mod name_watch;
+pub use name_watch::{NameEvent, NameWatchStream, WatchNameFlags};
+
+mod reconnect;
+pub use reconnect::{ConnectionState, ConnectionStateStream};
+
+mod metrics;
+use metrics::CallMetrics;
+pub use metrics::MetricsSink;
+
+mod trace;
+use reconnect::{Latest, Reconnector};
+pub use trace::TracePropagator;
+
 pub(crate) mod handshake;
-pub use handshake::AuthMechanism;
 use handshake::Authenticated;
+pub use handshake::{sasl, AuthMechanism};
 
 const DEFAULT_MAX_QUEUED: usize = 64;
 const DEFAULT_MAX_METHOD_RETURN_QUEUED: usize = 8;
@@ -48,13 +65,18 @@ const DEFAULT_MAX_METHOD_RETURN_QUEUED: usize = 8;
 /// Inner state shared by Connection and WeakConnection
 #[derive(Debug)]
 pub(crate) struct ConnectionInner {
-    server_guid: OwnedGuid,
+    // Only replaced on reconnection.
+    server_guid: Latest<OwnedGuid>,
     #[cfg(unix)]
     cap_unix_fd: bool,
     #[cfg(feature = "p2p")]
     bus_conn: bool,
-    unique_name: OnceLock<OwnedUniqueName>,
-    registered_names: Mutex<HashMap<WellKnownName<'static>, NameStatus>>,
+    // Only replaced on reconnection.
+    unique_name: Latest<OwnedUniqueName>,
+    authenticated_identity: Option<String>,
+    // The flags the names were requested with, to request them again on reconnection.
+    registered_names:
+        Mutex<HashMap<WellKnownName<'static>, (NameStatus, BitFlags<RequestNameFlags>)>>,
 
     activity_event: Arc<Event>,
     socket_write: Mutex<Box<dyn socket::WriteHalf>>,
@@ -71,13 +93,22 @@ pub(crate) struct ConnectionInner {
     msg_senders: Arc<Mutex<HashMap<Option<OwnedMatchRule>, MsgBroadcaster>>>,
 
     subscriptions: Mutex<Subscriptions>,
+    // The match rules of live subscriptions that were replaced on reconnection, to the rules that
+    // replaced them.
+    match_rule_aliases: Mutex<HashMap<OwnedMatchRule, OwnedMatchRule>>,
 
     object_server: OnceLock<ObjectServer>,
     object_server_dispatch_task: OnceLock<Task<()>>,
 
     drop_event: Event,
 
+    state_sender: Broadcaster<ConnectionState>,
+    state_receiver: InactiveReceiver<ConnectionState>,
+    closed: AtomicBool,
+
     method_timeout: Option<Duration>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    trace_propagator: Option<Arc<dyn TracePropagator>>,
     // Cache the credentials.
     credentials: OnceLock<Arc<ConnectionCredentials>>,
 }
@@ -223,6 +254,22 @@ pub struct Connection {
 pub(crate) struct PendingMethodCall {
     stream: Option<MessageStream>,
     serial: NonZeroU32,
+    // Reported on completion, or on drop for cancelled calls.
+    metrics: Option<CallMetrics>,
+}
+
+impl PendingMethodCall {
+    fn complete_metrics(&mut self, reply: Option<&Message>) {
+        if let Some(metrics) = self.metrics.take() {
+            metrics.complete(reply);
+        }
+    }
+}
+
+impl Drop for PendingMethodCall {
+    fn drop(&mut self) {
+        self.complete_metrics(None);
+    }
 }
 
 impl Future for PendingMethodCall {
@@ -260,8 +307,14 @@ impl OrderedFuture for PendingMethodCall {
                             continue;
                         }
                         let res = match msg.message_type() {
-                            Type::Error => Err(msg.into()),
-                            Type::MethodReturn => Ok(msg),
+                            Type::Error => {
+                                this.complete_metrics(Some(&msg));
+                                Err(msg.into())
+                            }
+                            Type::MethodReturn => {
+                                this.complete_metrics(Some(&msg));
+                                Ok(msg)
+                            }
                             _ => continue,
                         };
                         this.stream = None;
@@ -271,6 +324,7 @@ impl OrderedFuture for PendingMethodCall {
                         data: Err(e),
                         ordering,
                     }) => {
+                        this.complete_metrics(None);
                         return Poll::Ready(Some((ordering, Err(e))));
                     }
 
@@ -278,6 +332,7 @@ impl OrderedFuture for PendingMethodCall {
                         return Poll::Ready(None);
                     }
                     Poll::Ready(PollResult::Terminated) => {
+                        this.complete_metrics(None);
                         return Poll::Ready(None);
                     }
                     Poll::Pending => return Poll::Pending,
@@ -298,8 +353,12 @@ impl Connection {
 
         self.inner.activity_event.notify(usize::MAX);
         let mut write = self.inner.socket_write.lock().await;
+        write.send_message(msg).await?;
+        if let Some(metrics) = &self.inner.metrics {
+            metrics.message_sent(msg);
+        }
 
-        write.send_message(msg).await
+        Ok(())
     }
 
     /// Send a method call.
@@ -379,8 +438,8 @@ impl Connection {
         let _permit = acquire_serial_num_semaphore().await;
 
         let mut builder = Message::method_call(path, method_name)?;
-        if let Some(sender) = self.unique_name() {
-            builder = builder.sender(sender)?
+        if let Some(sender) = self.inner.unique_name.get_owned() {
+            builder = builder.sender((*sender).clone())?
         }
         if let Some(destination) = destination {
             builder = builder.destination(destination)?
@@ -405,7 +464,17 @@ impl Connection {
         if flags.contains(Flags::NoReplyExpected) {
             Ok(None)
         } else {
-            Ok(Some(PendingMethodCall { stream, serial }))
+            let metrics = self
+                .inner
+                .metrics
+                .clone()
+                .map(|sink| CallMetrics::new(sink, msg));
+
+            Ok(Some(PendingMethodCall {
+                stream,
+                serial,
+                metrics,
+            }))
         }
     }
 
@@ -434,8 +503,8 @@ impl Connection {
         let _permit = acquire_serial_num_semaphore().await;
 
         let mut b = Message::signal(path, interface, signal_name)?;
-        if let Some(sender) = self.unique_name() {
-            b = b.sender(sender)?;
+        if let Some(sender) = self.inner.unique_name.get_owned() {
+            b = b.sender((*sender).clone())?;
         }
         if let Some(destination) = destination {
             b = b.destination(destination)?;
@@ -456,8 +525,8 @@ impl Connection {
         let _permit = acquire_serial_num_semaphore().await;
 
         let mut b = Message::method_return(call)?;
-        if let Some(sender) = self.unique_name() {
-            b = b.sender(sender)?;
+        if let Some(sender) = self.inner.unique_name.get_owned() {
+            b = b.sender((*sender).clone())?;
         }
         let m = b.build(body)?;
         self.send(&m).await
@@ -481,8 +550,8 @@ impl Connection {
         let _permit = acquire_serial_num_semaphore().await;
 
         let mut b = Message::error(call, error_name)?;
-        if let Some(sender) = self.unique_name() {
-            b = b.sender(sender)?;
+        if let Some(sender) = self.inner.unique_name.get_owned() {
+            b = b.sender((*sender).clone())?;
         }
         let m = b.build(body)?;
         self.send(&m).await
@@ -640,13 +709,13 @@ impl Connection {
         let mut names = self.inner.registered_names.lock().await;
 
         match names.get(&well_known_name) {
-            Some(NameStatus::Owner(_)) => return Ok(RequestNameReply::AlreadyOwner),
-            Some(NameStatus::Queued(_)) => return Ok(RequestNameReply::InQueue),
-            None => (),
+            Some((NameStatus::Owner(_), _)) => return Ok(RequestNameReply::AlreadyOwner),
+            Some((NameStatus::Queued(_), _)) => return Ok(RequestNameReply::InQueue),
+            Some((NameStatus::Pending, _)) | None => (),
         }
 
         if !self.is_bus() {
-            names.insert(well_known_name.to_owned(), NameStatus::Owner(None));
+            names.insert(well_known_name.to_owned(), (NameStatus::Owner(None), flags));
 
             return Ok(RequestNameReply::PrimaryOwner);
         }
@@ -693,7 +762,7 @@ impl Connection {
                                         "Connection `{}` lost name `{}`",
                                         // SAFETY: This is bus connection so unique name can't be
                                         // None.
-                                        inner.unique_name.get().unwrap(),
+                                        inner.unique_name.get_owned().unwrap(),
                                         well_known_name
                                     );
                                     inner.registered_names.lock().await.remove(&well_known_name);
@@ -740,7 +809,7 @@ impl Connection {
                                 Some(signal) => match signal {
                                     Ok(_) => {
                                         let mut names = inner.registered_names.lock().await;
-                                        if let Some(status) = names.get_mut(&well_known_name) {
+                                        if let Some((status, _)) = names.get_mut(&well_known_name) {
                                             let task = name_lost_fut.map(|fut| {
                                                 inner.executor.spawn(fut, &lost_task_name)
                                             });
@@ -775,7 +844,7 @@ impl Connection {
             RequestNameReply::Exists => return Err(Error::NameTaken),
         };
 
-        names.insert(well_known_name.to_owned(), status);
+        names.insert(well_known_name.to_owned(), (status, flags));
 
         Ok(reply)
     }
@@ -817,6 +886,66 @@ impl Connection {
         .map(|r| r == ReleaseNameReply::Released)
     }
 
+    /// Watch the ownership of a bus name.
+    ///
+    /// This is the equivalent of GDBus' `g_bus_watch_name`. The returned stream first yields the
+    /// current state of the name, and then a [`NameEvent`] for each change of ownership. It's
+    /// subscribed to the changes before the current owner is queried, so no change is ever
+    /// missed.
+    ///
+    /// Unlike [`crate::Proxy::receive_owner_changed`], this doesn't need a proxy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # zbus::block_on(async {
+    /// use futures_util::stream::StreamExt;
+    /// use zbus::{connection::NameEvent, Connection};
+    ///
+    /// let name = "org.freedesktop.zbus.WatchNameTest";
+    /// let conn = Connection::session().await?;
+    /// let mut watch = conn.watch_name(name).await?;
+    /// assert_eq!(watch.next().await.unwrap(), NameEvent::Vanished);
+    ///
+    /// let service = Connection::session().await?;
+    /// service.request_name(name).await?;
+    /// let owner = service.unique_name().unwrap().clone();
+    /// assert_eq!(watch.next().await.unwrap(), NameEvent::Appeared(owner));
+    ///
+    /// drop(service);
+    /// assert_eq!(watch.next().await.unwrap(), NameEvent::Vanished);
+    /// # Ok::<(), zbus::Error>(())
+    /// # }).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Unsupported`] if `self` is not a bus connection.
+    pub async fn watch_name<'n, N>(&self, name: N) -> Result<NameWatchStream>
+    where
+        N: TryInto<BusName<'n>>,
+        N::Error: Into<Error>,
+    {
+        self.watch_name_with_flags(name, BitFlags::default()).await
+    }
+
+    /// Watch the ownership of a bus name.
+    ///
+    /// This is the same as [`Connection::watch_name`] but allows to specify the flags to use.
+    pub async fn watch_name_with_flags<'n, N>(
+        &self,
+        name: N,
+        flags: BitFlags<WatchNameFlags>,
+    ) -> Result<NameWatchStream>
+    where
+        N: TryInto<BusName<'n>>,
+        N::Error: Into<Error>,
+    {
+        let name = name.try_into().map_err(Into::into)?;
+
+        NameWatchStream::new(self, name, flags).await
+    }
+
     /// Check if `self` is a connection to a message bus.
     ///
     /// This will return `false` for p2p connections. When the `p2p` feature is disabled, this will
@@ -835,11 +964,22 @@ impl Connection {
     /// The unique name of the connection, if set/applicable.
     ///
     /// The unique name is assigned by the message bus, or set manually using
-    /// [`Connection::set_unique_name`].
+    /// [`Connection::set_unique_name`]. It's updated on reconnection, up to the 15th one, after
+    /// which it keeps the value it had then. This is a limitation of returning a reference.
     pub fn unique_name(&self) -> Option<&OwnedUniqueName> {
         self.inner.unique_name.get()
     }
 
+    /// The identity the peer authenticated as, if known.
+    ///
+    /// This is only set on the server-side of a peer-to-peer connection, if the authentication
+    /// mechanism provided one. For the built-in mechanisms, this is the user ID (or SID on
+    /// Windows) of the client. For custom mechanisms, this is the identity returned in
+    /// [`sasl::ServerStep::Accepted`].
+    pub fn authenticated_identity(&self) -> Option<&str> {
+        self.inner.authenticated_identity.as_deref()
+    }
+
     /// Set the unique name of the connection (if not already set).
     ///
     /// This is mainly provided for bus implementations. All other users should not need to use this
@@ -872,8 +1012,13 @@ impl Connection {
     }
 
     /// The server's GUID.
+    ///
+    /// Just like [`Connection::unique_name`], it's updated on reconnection, up to the 15th one.
     pub fn server_guid(&self) -> &OwnedGuid {
-        &self.inner.server_guid
+        self.inner
+            .server_guid
+            .get()
+            .expect("server GUID is always set")
     }
 
     /// The underlying executor.
@@ -967,7 +1112,7 @@ impl Connection {
                     let mut stream = match weak_conn.upgrade() {
                         Some(conn) => {
                             let mut builder = MatchRule::builder().msg_type(Type::MethodCall);
-                            if let Some(unique_name) = conn.unique_name() {
+                            if let Some(unique_name) = conn.inner.unique_name.get_owned() {
                                 builder = builder.destination(&**unique_name).expect("unique name");
                             }
                             let rule = builder.build();
@@ -1059,6 +1204,7 @@ impl Connection {
         }
 
         let mut subscriptions = self.inner.subscriptions.lock().await;
+        let rule = self.resolve_match_rule(rule).await;
         let msg_type = rule.msg_type().unwrap_or(Type::Signal);
         match subscriptions.entry(rule.clone()) {
             Entry::Vacant(e) => {
@@ -1101,6 +1247,7 @@ impl Connection {
     pub(crate) async fn remove_match(&self, rule: OwnedMatchRule) -> Result<bool> {
         use std::collections::hash_map::Entry;
         let mut subscriptions = self.inner.subscriptions.lock().await;
+        let rule = self.resolve_match_rule(rule).await;
         // TODO when it becomes stable, use HashMap::raw_entry and only require expr: &str
         // (both here and in add_match)
         let msg_type = rule.msg_type().unwrap_or(Type::Signal);
@@ -1120,18 +1267,27 @@ impl Connection {
                         )
                         .await?;
                     }
-                    e.remove();
+                    let rule = e.remove_entry().0;
                     self.inner
-                        .msg_senders
+                        .match_rule_aliases
                         .lock()
                         .await
-                        .remove(&Some(rule.into()));
+                        .retain(|_, r| *r != rule);
+                    self.inner.msg_senders.lock().await.remove(&Some(rule));
                 }
                 Ok(true)
             }
         }
     }
 
+    // The rule that replaced `rule` on reconnection, if any, or `rule` itself.
+    async fn resolve_match_rule(&self, rule: OwnedMatchRule) -> OwnedMatchRule {
+        match self.inner.match_rule_aliases.lock().await.get(&rule) {
+            Some(replacement) => replacement.clone(),
+            None => rule,
+        }
+    }
+
     pub(crate) fn queue_remove_match(&self, rule: OwnedMatchRule) {
         let conn = self.clone();
         let task_name = format!("Remove match `{}`", *rule);
@@ -1145,11 +1301,18 @@ impl Connection {
         self.inner.method_timeout
     }
 
+    /// The trace propagator (if any). See [Builder::trace_propagator] for details.
+    pub fn trace_propagator(&self) -> Option<&dyn TracePropagator> {
+        self.inner.trace_propagator.as_deref()
+    }
+
     pub(crate) async fn new(
         auth: Authenticated,
         #[allow(unused)] bus_connection: bool,
         executor: Executor<'static>,
         method_timeout: Option<Duration>,
+        metrics: Option<Arc<dyn MetricsSink>>,
+        trace_propagator: Option<Arc<dyn TracePropagator>>,
     ) -> Result<Self> {
         #[cfg(unix)]
         let cap_unix_fd = auth.cap_unix_fd;
@@ -1180,18 +1343,24 @@ impl Connection {
         msg_senders.insert(Some(rule), method_return_sender);
         let msg_senders = Arc::new(Mutex::new(msg_senders));
         let subscriptions = Mutex::new(HashMap::new());
+        let (mut state_sender, state_receiver) = broadcast(DEFAULT_MAX_QUEUED);
+        // Lagging behind on the state changes is better than holding back the socket reader.
+        state_sender.set_overflow(true);
+        state_sender.set_await_active(false);
 
         let connection = Self {
             inner: Arc::new(ConnectionInner {
                 activity_event: Arc::new(Event::new()),
                 socket_write: Mutex::new(auth.socket_write),
-                server_guid: auth.server_guid,
+                server_guid: Latest::new(auth.server_guid),
                 #[cfg(unix)]
                 cap_unix_fd,
                 #[cfg(feature = "p2p")]
                 bus_conn: bus_connection,
-                unique_name: OnceLock::new(),
+                unique_name: Latest::empty(),
+                authenticated_identity: auth.authenticated_identity,
                 subscriptions,
+                match_rule_aliases: Mutex::new(HashMap::new()),
                 object_server: OnceLock::new(),
                 object_server_dispatch_task: OnceLock::new(),
                 executor,
@@ -1201,7 +1370,12 @@ impl Connection {
                 method_return_receiver,
                 registered_names: Mutex::new(HashMap::new()),
                 drop_event: Event::new(),
+                state_sender,
+                state_receiver: state_receiver.deactivate(),
+                closed: AtomicBool::new(false),
                 method_timeout,
+                metrics,
+                trace_propagator,
                 credentials: OnceLock::new(),
             }),
         };
@@ -1282,6 +1456,7 @@ impl Connection {
     ///
     /// After this call, all reading and writing operations will fail.
     pub async fn close(self) -> Result<()> {
+        self.inner.closed.store(true, Ordering::SeqCst);
         self.inner.activity_event.notify(usize::MAX);
         self.inner
             .socket_write
@@ -1346,8 +1521,10 @@ impl Connection {
         socket_read: Box<dyn socket::ReadHalf>,
         already_read: Vec<u8>,
         #[cfg(unix)] already_received_fds: Vec<std::os::fd::OwnedFd>,
+        reconnector: Option<Reconnector>,
     ) {
         let inner = &self.inner;
+        let reconnect = reconnector.map(|reconnector| (reconnector, WeakConnection::from(self)));
         inner
             .socket_reader_task
             .set(
@@ -1358,6 +1535,9 @@ impl Connection {
                     #[cfg(unix)]
                     already_received_fds,
                     inner.activity_event.clone(),
+                    inner.state_sender.clone(),
+                    reconnect,
+                    inner.metrics.clone(),
                 )
                 .spawn(&inner.executor),
             )
@@ -1371,6 +1551,11 @@ impl Connection {
             // programmer (probably our) error if this fails.
             .expect("unique name already set");
     }
+
+    // Whether `close` was called.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.inner.closed.load(Ordering::SeqCst)
+    }
 }
 
 #[cfg(feature = "blocking-api")]
@@ -1407,6 +1592,8 @@ enum NameStatus {
     Owner(#[allow(unused)] Option<Task<()>>),
     // The task waits for name acquisition signal.
     Queued(#[allow(unused)] Task<()>),
+    // The name couldn't be requested again after reconnection. It's retried on the next one.
+    Pending,
 }
 
 static SERIAL_NUM_SEMAPHORE: Semaphore = Semaphore::new(1);
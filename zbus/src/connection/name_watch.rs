@@ -0,0 +1,192 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use enumflags2::{bitflags, BitFlags};
+use futures_core::{ready, Stream};
+use tracing::{debug, trace};
+use zbus_names::{BusName, OwnedBusName, OwnedUniqueName};
+
+use super::Connection;
+use crate::{fdo::NameOwnerChanged, message::Sequence, Error, MatchRule, MessageStream, Result};
+
+/// Flags to use with [`Connection::watch_name_with_flags`].
+#[bitflags]
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WatchNameFlags {
+    /// If the watched name is a well-known name without an owner, ask the bus to start the service
+    /// owning it, through `StartServiceByName`.
+    ///
+    /// Failures to start the service are not fatal: the name is simply reported as vanished.
+    AutoStart = 0x1,
+}
+
+/// A change to the ownership of a watched bus name.
+///
+/// See [`Connection::watch_name`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameEvent {
+    /// The name is owned by the given peer.
+    Appeared(OwnedUniqueName),
+    /// The name has no owner.
+    Vanished,
+}
+
+/// A [`stream::Stream`] of [`NameEvent`]s for a bus name.
+///
+/// The first item is always the state of the name at the time of the creation of the stream.
+/// Afterwards, an item is yielded for each change. If the ownership moves directly from one peer
+/// to another, a [`NameEvent::Vanished`] is yielded before the [`NameEvent::Appeared`] of the new
+/// owner.
+///
+/// Use [`Connection::watch_name`] to create an instance of this type.
+///
+/// [`stream::Stream`]: futures_core::stream::Stream
+#[derive(Debug)]
+pub struct NameWatchStream {
+    name: OwnedBusName,
+    stream: MessageStream,
+    // Signals received before the `GetNameOwner` reply are already accounted for.
+    after: Sequence,
+    owner: Option<OwnedUniqueName>,
+    pending: VecDeque<NameEvent>,
+}
+
+impl NameWatchStream {
+    pub(super) async fn new(
+        conn: &Connection,
+        name: BusName<'_>,
+        flags: BitFlags<WatchNameFlags>,
+    ) -> Result<Self> {
+        if !conn.is_bus() {
+            return Err(Error::Unsupported);
+        }
+
+        // Subscribe first so that no change is missed after we get the initial owner.
+        let rule = MatchRule::fdo_signal_builder("NameOwnerChanged")
+            .arg(0, name.as_str())?
+            .build();
+        let stream = MessageStream::for_match_rule(rule, conn, None).await?;
+
+        if flags.contains(WatchNameFlags::AutoStart) {
+            if let BusName::WellKnown(name) = &name {
+                let res = conn
+                    .call_method(
+                        Some("org.freedesktop.DBus"),
+                        "/org/freedesktop/DBus",
+                        Some("org.freedesktop.DBus"),
+                        "StartServiceByName",
+                        &(name, 0u32),
+                    )
+                    .await;
+                if let Err(e) = res {
+                    debug!("Failed to start the service for `{name}`: {e}");
+                }
+            }
+        }
+
+        let reply = conn
+            .call_method(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                Some("org.freedesktop.DBus"),
+                "GetNameOwner",
+                &name,
+            )
+            .await;
+        let (owner, after) = match reply {
+            Ok(reply) => {
+                let owner: OwnedUniqueName = reply.body().deserialize()?;
+
+                (Some(owner), reply.recv_position())
+            }
+            Err(Error::MethodError(error, _, reply))
+                if error.as_str() == "org.freedesktop.DBus.Error.NameHasNoOwner" =>
+            {
+                (None, reply.recv_position())
+            }
+            Err(e) => return Err(e),
+        };
+        trace!("Initial owner of `{name}`: {owner:?}");
+        let initial = match &owner {
+            Some(owner) => NameEvent::Appeared(owner.clone()),
+            None => NameEvent::Vanished,
+        };
+
+        Ok(Self {
+            name: name.into(),
+            stream,
+            after,
+            owner,
+            pending: VecDeque::from([initial]),
+        })
+    }
+
+    /// The bus name being watched.
+    pub fn name(&self) -> &OwnedBusName {
+        &self.name
+    }
+
+    /// The owner of the name, according to the latest ownership change received.
+    pub fn owner(&self) -> Option<&OwnedUniqueName> {
+        self.owner.as_ref()
+    }
+
+    fn handle_owner_change(&mut self, new_owner: Option<OwnedUniqueName>) {
+        if new_owner == self.owner {
+            return;
+        }
+
+        if self.owner.is_some() {
+            self.pending.push_back(NameEvent::Vanished);
+        }
+        if let Some(new_owner) = &new_owner {
+            self.pending
+                .push_back(NameEvent::Appeared(new_owner.clone()));
+        }
+        self.owner = new_owner;
+    }
+}
+
+impl Stream for NameWatchStream {
+    type Item = NameEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(event) = this.pending.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+
+            let msg = match ready!(Pin::new(&mut this.stream).poll_next(cx)) {
+                Some(Ok(msg)) => msg,
+                Some(Err(e)) => {
+                    debug!("Error receiving `NameOwnerChanged` signal: {e}");
+                    continue;
+                }
+                None => return Poll::Ready(None),
+            };
+            if msg.recv_position() < this.after {
+                continue;
+            }
+            let Some(signal) = NameOwnerChanged::from_message(msg) else {
+                continue;
+            };
+            let new_owner = match signal.args() {
+                Ok(args) => args
+                    .new_owner()
+                    .as_ref()
+                    .map(|owner| owner.to_owned().into()),
+                Err(e) => {
+                    debug!("Failed to parse `NameOwnerChanged` signal: {e}");
+                    continue;
+                }
+            };
+            this.handle_owner_change(new_owner);
+        }
+    }
+}
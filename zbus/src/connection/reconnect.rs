@@ -0,0 +1,321 @@
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock, RwLock,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use async_broadcast::Receiver;
+use futures_core::Stream;
+use tracing::{debug, trace, warn};
+use zbus_names::OwnedUniqueName;
+
+use super::{
+    builder::connect_addresses, handshake::Authenticated, AuthMechanism, NameStatus, WeakConnection,
+};
+use crate::{message::Type, timeout::sleep, Address, Connection, OwnedMatchRule};
+
+// The delay before the first reconnection attempt, doubled after each failed attempt.
+const INITIAL_DELAY: Duration = Duration::from_millis(100);
+// The maximum delay between reconnection attempts.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// A change to the state of a [`Connection`].
+///
+/// See [`Connection::receive_state_changes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConnectionState {
+    /// The connection to the peer was lost.
+    ///
+    /// If [automatic reconnection] is enabled, the connection is being re-established. Otherwise,
+    /// this is the last state change of the connection.
+    ///
+    /// [automatic reconnection]: super::Builder::auto_reconnect
+    Disconnected,
+    /// The connection was re-established and its state restored.
+    Reconnected,
+}
+
+/// A [`stream::Stream`] of the [`ConnectionState`] changes of a [`Connection`].
+///
+/// Use [`Connection::receive_state_changes`] to create an instance of this type.
+///
+/// [`stream::Stream`]: futures_core::stream::Stream
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct ConnectionStateStream(pub(super) Receiver<ConnectionState>);
+
+impl Stream for ConnectionStateStream {
+    type Item = ConnectionState;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().0).poll_next(cx)
+    }
+}
+
+// What's needed to connect again to the same peer.
+#[derive(Debug)]
+pub(crate) struct Reconnector {
+    pub(crate) addresses: Vec<Address>,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) auth_mechanism: Option<AuthMechanism>,
+    pub(crate) user_id: Option<u32>,
+    pub(crate) bus: bool,
+}
+
+impl Reconnector {
+    // Keep trying to connect, with an exponential backoff, until it works or the connection is
+    // gone (or closed).
+    pub(super) async fn reconnect(&self, conn: &WeakConnection) -> Option<Authenticated> {
+        let mut delay = INITIAL_DELAY;
+
+        loop {
+            sleep(delay).await;
+            if !conn.upgrade().is_some_and(|conn| !conn.is_closed()) {
+                trace!("Connection is gone, not reconnecting");
+
+                return None;
+            }
+
+            match self.connect().await {
+                Ok(auth) => return Some(auth),
+                Err(e) => {
+                    debug!("Failed to reconnect, retrying in {delay:?}: {e}");
+                    delay = (delay * 2).min(MAX_DELAY);
+                }
+            }
+        }
+    }
+
+    async fn connect(&self) -> crate::Result<Authenticated> {
+        connect_addresses(self.addresses.clone(), self.connect_timeout, |_, stream| {
+            // The GUID in the address isn't checked, as a restarted server has a new one.
+            Authenticated::client(
+                stream,
+                None,
+                self.auth_mechanism,
+                None,
+                self.bus,
+                self.user_id,
+            )
+        })
+        .await
+    }
+}
+
+impl Connection {
+    /// A stream of the [`ConnectionState`] changes of the connection.
+    ///
+    /// Only the changes happening after the creation of the stream are reported.
+    pub fn receive_state_changes(&self) -> ConnectionStateStream {
+        ConnectionStateStream(self.inner.state_receiver.activate_cloned())
+    }
+
+    // Restore the state of the connection on the bus after reconnection.
+    pub(super) async fn restore_state(&self, old_unique_name: Option<OwnedUniqueName>) {
+        if self.is_bus() {
+            self.rekey_match_rules(old_unique_name).await;
+            self.restore_match_rules().await;
+            self.restore_names().await;
+        }
+
+        let _ = self
+            .inner
+            .state_sender
+            .try_broadcast(ConnectionState::Reconnected);
+    }
+
+    // Match rules for messages sent to the old unique name (e.g. the one of the object server)
+    // must now match the ones sent to the new one. The streams still refer to the old rules, so
+    // these are kept as aliases of the new ones.
+    async fn rekey_match_rules(&self, old_unique_name: Option<OwnedUniqueName>) {
+        let (Some(old), Some(new)) = (old_unique_name, self.inner.unique_name.get_owned()) else {
+            return;
+        };
+        let mut subscriptions = self.inner.subscriptions.lock().await;
+        let mut aliases = self.inner.match_rule_aliases.lock().await;
+        let mut senders = self.inner.msg_senders.lock().await;
+        let rules: Vec<_> = subscriptions
+            .keys()
+            .filter(|rule| rule.destination() == Some(&old.as_ref()))
+            .cloned()
+            .collect();
+        for rule in rules {
+            let mut new_rule = rule.clone().into_inner();
+            new_rule.destination = Some(new.inner().clone().into_owned());
+            let new_rule = OwnedMatchRule::from(new_rule);
+            trace!("Replacing match rule `{}` with `{}`", *rule, *new_rule);
+
+            if let Some(subscription) = subscriptions.remove(&rule) {
+                subscriptions.insert(new_rule.clone(), subscription);
+            }
+            if let Some(sender) = senders.remove(&Some(rule.clone())) {
+                senders.insert(Some(new_rule.clone()), sender);
+            }
+            for replacement in aliases.values_mut().filter(|r| **r == rule) {
+                *replacement = new_rule.clone();
+            }
+            aliases.insert(rule, new_rule);
+        }
+    }
+
+    async fn restore_match_rules(&self) {
+        let rules: Vec<_> = self
+            .inner
+            .subscriptions
+            .lock()
+            .await
+            .keys()
+            .filter(|rule| rule.msg_type().unwrap_or(Type::Signal) == Type::Signal)
+            .cloned()
+            .collect();
+        for rule in rules {
+            let res = self
+                .call_method(
+                    Some("org.freedesktop.DBus"),
+                    "/org/freedesktop/DBus",
+                    Some("org.freedesktop.DBus"),
+                    "AddMatch",
+                    &rule,
+                )
+                .await;
+            if let Err(e) = res {
+                warn!("Failed to restore match rule `{}`: {e}", *rule);
+            }
+        }
+    }
+
+    async fn restore_names(&self) {
+        // Replacing the statuses stops the tasks monitoring the previous requests. The names stay
+        // pending until they're successfully requested again, possibly on a later reconnection.
+        let names: Vec<_> = self
+            .inner
+            .registered_names
+            .lock()
+            .await
+            .iter_mut()
+            .map(|(name, (status, flags))| {
+                *status = NameStatus::Pending;
+
+                (name.clone(), *flags)
+            })
+            .collect();
+        for (name, flags) in names {
+            match self.request_name_with_flags(&name, flags).await {
+                Ok(reply) => debug!("Requested name `{name}` again: {reply:?}"),
+                Err(e) => warn!("Failed to request name `{name}` again: {e}"),
+            }
+        }
+    }
+}
+
+// The number of values of a `Latest` that references can be lent to.
+const MAX_RETAINED: usize = 16;
+
+// A value that can be replaced while references to the previous values are still around.
+//
+// The latest value is kept in a single slot, read through `get_owned`. The public API of
+// `Connection` lends references to the unique name and server GUID for as long as the connection
+// lives though, so the first `MAX_RETAINED` values are also retained for `get`. These values are
+// small and only replaced on reconnection, so this bounds the leak to a few kilobytes. Past that,
+// `get` keeps returning the last retained value, so internal users must use `get_owned`.
+#[derive(Debug)]
+pub(super) struct Latest<T> {
+    current: RwLock<Option<Arc<T>>>,
+    retained: [OnceLock<Arc<T>>; MAX_RETAINED],
+    num_retained: AtomicUsize,
+}
+
+impl<T> Latest<T> {
+    pub(super) fn empty() -> Self {
+        Self {
+            current: RwLock::new(None),
+            retained: std::array::from_fn(|_| OnceLock::new()),
+            num_retained: AtomicUsize::new(0),
+        }
+    }
+
+    pub(super) fn new(value: T) -> Self {
+        let latest = Self::empty();
+        latest.replace(value);
+
+        latest
+    }
+
+    // The latest value, if any.
+    pub(super) fn get_owned(&self) -> Option<Arc<T>> {
+        self.current.read().expect("lock poisoned").clone()
+    }
+
+    // The latest retained value, if any.
+    pub(super) fn get(&self) -> Option<&T> {
+        let num_retained = self.num_retained.load(Ordering::Acquire);
+
+        self.retained[num_retained.checked_sub(1)?]
+            .get()
+            .map(|v| &**v)
+    }
+
+    // Set the initial value, returning it back if already set.
+    pub(super) fn set(&self, value: T) -> std::result::Result<(), T> {
+        let mut current = self.current.write().expect("lock poisoned");
+        if current.is_some() {
+            return Err(value);
+        }
+        self.store(&mut current, value);
+
+        Ok(())
+    }
+
+    // Replace the latest value.
+    pub(super) fn replace(&self, value: T) {
+        let mut current = self.current.write().expect("lock poisoned");
+        self.store(&mut current, value);
+    }
+
+    // Replacements are serialized by the `current` lock, held by the caller.
+    fn store(&self, current: &mut Option<Arc<T>>, value: T) {
+        let value = Arc::new(value);
+        let num_retained = self.num_retained.load(Ordering::Relaxed);
+        if num_retained < MAX_RETAINED {
+            // Can't fail since we're the only one setting this slot.
+            let _ = self.retained[num_retained].set(value.clone());
+            self.num_retained.store(num_retained + 1, Ordering::Release);
+        }
+        *current = Some(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Latest, MAX_RETAINED};
+
+    #[test]
+    fn latest() {
+        let latest = Latest::empty();
+        assert_eq!(latest.get(), None);
+        assert_eq!(latest.get_owned(), None);
+        latest.set(1).unwrap();
+        let first = latest.get().unwrap();
+        assert_eq!(latest.set(2), Err(2));
+
+        latest.replace(2);
+        latest.replace(3);
+        assert_eq!(latest.get(), Some(&3));
+        assert_eq!(latest.get_owned().as_deref(), Some(&3));
+        // References to the previous values stay valid.
+        assert_eq!(*first, 1);
+        assert_eq!(latest.set(4), Err(4));
+
+        // Only the owned accessor is accurate past the retained values.
+        for i in 4..=MAX_RETAINED + 1 {
+            latest.replace(i);
+        }
+        assert_eq!(latest.get(), Some(&MAX_RETAINED));
+        assert_eq!(latest.get_owned().as_deref(), Some(&(MAX_RETAINED + 1)));
+    }
+}
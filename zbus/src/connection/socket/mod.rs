@@ -3,6 +3,8 @@ pub mod channel;
 #[cfg(feature = "p2p")]
 pub use channel::Channel;
 
+pub mod record;
+
 mod split;
 pub use split::{BoxedSplit, Split};
 
@@ -0,0 +1,789 @@
+//! Recording and replaying of D-Bus traffic.
+//!
+//! A [`Recorder`] passed to [`Builder::record`] captures all the messages sent and received by a
+//! connection, after the authentication handshake, along with their order and timing. The
+//! resulting [`Capture`] can later be fed back to a connection through a [`Replay`] socket, using
+//! [`Builder::replay`]. This is useful for example to test proxies against the recorded behaviour
+//! of real-world services, without a bus or the services themselves.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use zbus::{
+//!     connection::{
+//!         socket::record::{Capture, Recorder},
+//!         Builder,
+//!     },
+//!     fdo::DBusProxy,
+//! };
+//!
+//! # zbus::block_on(async {
+//! // Record a session of talking to the bus.
+//! let recorder = Recorder::create("get-id.zbuscap")?;
+//! let conn = Builder::session()?.record(recorder.clone()).build().await?;
+//! let id = DBusProxy::new(&conn).await?.get_id().await?;
+//! recorder.flush()?;
+//!
+//! // Replay it later, without a bus.
+//! let capture = Capture::open("get-id.zbuscap")?;
+//! let conn = Builder::replay(&capture).build().await?;
+//! assert_eq!(DBusProxy::new(&conn).await?.get_id().await?, id);
+//! # Ok::<(), zbus::Error>(())
+//! # }).unwrap();
+//! ```
+//!
+//! # Limitations
+//!
+//! File descriptors passed along with the messages are not recorded.
+//!
+//! [`Builder::record`]: crate::connection::Builder::record
+//! [`Builder::replay`]: crate::connection::Builder::replay
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    num::NonZeroU32,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, MutexGuard, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use event_listener::Event;
+use tracing::{trace, warn};
+use zvariant::{serialized, Endian};
+
+use crate::{
+    conn::AuthMechanism,
+    fdo::ConnectionCredentials,
+    message::{self, EndianSig, Message},
+    names::OwnedUniqueName,
+    Error, Guid, OwnedGuid, Result,
+};
+
+// The magic number at the start of capture files, followed by the format version.
+const MAGIC: &[u8; 8] = b"ZBUSCAP\0";
+const VERSION: u32 = 1;
+
+/// The direction of a recorded message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// The message was received from the peer.
+    Received,
+    /// The message was sent to the peer.
+    Sent,
+}
+
+/// Records the messages going through a connection.
+///
+/// This is a cheap handle that can be cloned. Once the connection is established, the messages
+/// are handed over to a dedicated thread that writes them, so a slow writer never holds up the
+/// connection.
+///
+/// Recording failures don't affect the connection: the recording stops, and the error is
+/// reported by [`Recorder::flush`] if it comes from the writer.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Clone)]
+pub struct Recorder {
+    inner: Arc<RecorderInner>,
+}
+
+struct RecorderInner {
+    // The writer, until it's handed over to the writer thread once the recording starts.
+    writer: Mutex<Option<Box<dyn Write + Send>>>,
+    recording: OnceLock<Recording>,
+    // The error the writer thread ran into, if any. Nothing more is written after that.
+    error: Arc<Mutex<Option<io::Error>>>,
+    // Set once recording a message failed, to stop recording.
+    stopped: AtomicBool,
+}
+
+struct Recording {
+    start: Instant,
+    commands: mpsc::Sender<Command>,
+}
+
+enum Command {
+    Write(Vec<u8>),
+    // Flush the writer, and signal the given channel, if any, once done.
+    Flush(Option<mpsc::Sender<()>>),
+}
+
+impl Recorder {
+    /// Create a recorder writing the capture to `writer`.
+    pub fn new<W>(writer: W) -> Self
+    where
+        W: Write + Send + 'static,
+    {
+        Self {
+            inner: Arc::new(RecorderInner {
+                writer: Mutex::new(Some(Box::new(writer))),
+                recording: OnceLock::new(),
+                error: Arc::default(),
+                stopped: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Create a recorder writing the capture to a file at `path`.
+    ///
+    /// The file is created if it doesn't exist, and truncated if it does.
+    pub fn create<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::create(path)?;
+
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    /// Flush the underlying writer.
+    ///
+    /// This blocks until all the messages recorded so far are written.
+    pub fn flush(&self) -> Result<()> {
+        let Some(recording) = self.inner.recording.get() else {
+            return match lock(&self.inner.writer).as_mut() {
+                Some(writer) => writer.flush().map_err(Into::into),
+                None => Ok(()),
+            };
+        };
+        let (done_tx, done_rx) = mpsc::channel();
+        // If the writer thread is gone, so is any pending record.
+        if recording
+            .commands
+            .send(Command::Flush(Some(done_tx)))
+            .is_ok()
+        {
+            let _ = done_rx.recv();
+        }
+
+        self.check_error()
+    }
+
+    // Start the writer thread, writing the header of the capture first. This is called once the
+    // connection is authenticated.
+    pub(crate) fn start(
+        &self,
+        guid: &Guid<'_>,
+        unique_name: Option<&OwnedUniqueName>,
+    ) -> Result<()> {
+        let Some(writer) = lock(&self.inner.writer).take() else {
+            return Err(Error::Failure(
+                "A recorder can only record a single connection".into(),
+            ));
+        };
+        let guid = guid.as_str().as_bytes();
+        let unique_name = unique_name.map(|n| n.as_bytes()).unwrap_or_default();
+
+        // Both the GUID and the unique name are at most 255 bytes long.
+        let mut header = Vec::with_capacity(MAGIC.len() + 6 + guid.len() + unique_name.len());
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.push(guid.len() as u8);
+        header.extend_from_slice(guid);
+        header.push(unique_name.len() as u8);
+        header.extend_from_slice(unique_name);
+
+        let (commands, receiver) = mpsc::channel();
+        // Can't fail since the receiver is still around.
+        let _ = commands.send(Command::Write(header));
+        let error = self.inner.error.clone();
+        thread::Builder::new()
+            .name("zbus::recorder".into())
+            .spawn(move || write_records(writer, receiver, &error))?;
+        let recording = Recording {
+            start: Instant::now(),
+            commands,
+        };
+        // We're the only one to get here, since we took the writer.
+        let _ = self.inner.recording.set(recording);
+
+        Ok(())
+    }
+
+    // Wrap the socket halves of a connection, so all messages going through them are recorded.
+    pub(crate) fn wrap(
+        &self,
+        read: Box<dyn super::ReadHalf>,
+        write: Box<dyn super::WriteHalf>,
+    ) -> (Box<dyn super::ReadHalf>, Box<dyn super::WriteHalf>) {
+        let read = RecordingReadHalf {
+            inner: read,
+            recorder: self.clone(),
+        };
+        let write = RecordingWriteHalf {
+            inner: write,
+            recorder: self.clone(),
+        };
+
+        (Box::new(read), Box::new(write))
+    }
+
+    // Record `msg`, unless the recording stopped. A failure stops the recording, but it's not an
+    // error for the connection.
+    fn record(&self, direction: Direction, msg: &Message) {
+        if self.inner.stopped.load(Ordering::Relaxed) {
+            return;
+        }
+
+        if let Err(e) = self.try_record(direction, msg) {
+            if !self.inner.stopped.swap(true, Ordering::Relaxed) {
+                warn!("Failed to record {direction:?} message, stopping the recording: {e}");
+            }
+        }
+    }
+
+    fn try_record(&self, direction: Direction, msg: &Message) -> Result<()> {
+        self.check_error()?;
+        let Some(recording) = self.inner.recording.get() else {
+            return Ok(());
+        };
+        let time = recording.start.elapsed();
+        let bytes = msg.data().bytes();
+        let len = u32::try_from(bytes.len()).map_err(|_| Error::ExcessData)?;
+        trace!("Recording {direction:?} message: {msg}");
+
+        let mut record = Vec::with_capacity(21 + bytes.len());
+        record.push(match direction {
+            Direction::Received => 0,
+            Direction::Sent => 1,
+        });
+        record.extend_from_slice(&msg.recv_position().get().to_le_bytes());
+        record.extend_from_slice(&(time.as_micros() as u64).to_le_bytes());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(bytes);
+        recording
+            .commands
+            .send(Command::Write(record))
+            .map_err(|_| Error::Failure("The recorder stopped writing".into()))
+    }
+
+    // Ask the writer thread to flush, without waiting for it.
+    fn flush_later(&self) {
+        if let Some(recording) = self.inner.recording.get() {
+            let _ = recording.commands.send(Command::Flush(None));
+        }
+    }
+
+    fn check_error(&self) -> Result<()> {
+        match &*lock(&self.inner.error) {
+            Some(e) => Err(io::Error::new(e.kind(), e.to_string()).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+// The loop of the writer thread, running until all the recorder handles are gone.
+fn write_records(
+    mut writer: Box<dyn Write + Send>,
+    commands: mpsc::Receiver<Command>,
+    error: &Mutex<Option<io::Error>>,
+) {
+    let mut failed = false;
+    for command in commands {
+        let (res, done) = match command {
+            _ if failed => (Ok(()), None),
+            Command::Write(record) => (writer.write_all(&record), None),
+            Command::Flush(done) => (writer.flush(), done),
+        };
+        if let Err(e) = res {
+            *lock(error) = Some(e);
+            failed = true;
+        }
+        if let Some(done) = done {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panic while holding the lock can't leave the writer in a state worse than a failed write.
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+struct RecordingReadHalf {
+    inner: Box<dyn super::ReadHalf>,
+    recorder: Recorder,
+}
+
+#[async_trait::async_trait]
+impl super::ReadHalf for RecordingReadHalf {
+    async fn receive_message(
+        &mut self,
+        seq: u64,
+        already_received_bytes: &mut Vec<u8>,
+        #[cfg(unix)] already_received_fds: &mut Vec<std::os::fd::OwnedFd>,
+    ) -> Result<Message> {
+        let msg = self
+            .inner
+            .receive_message(
+                seq,
+                already_received_bytes,
+                #[cfg(unix)]
+                already_received_fds,
+            )
+            .await?;
+        self.recorder.record(Direction::Received, &msg);
+
+        Ok(msg)
+    }
+
+    fn can_pass_unix_fd(&self) -> bool {
+        self.inner.can_pass_unix_fd()
+    }
+
+    async fn peer_credentials(&mut self) -> io::Result<ConnectionCredentials> {
+        self.inner.peer_credentials().await
+    }
+
+    fn auth_mechanism(&self) -> AuthMechanism {
+        self.inner.auth_mechanism()
+    }
+}
+
+#[derive(Debug)]
+struct RecordingWriteHalf {
+    inner: Box<dyn super::WriteHalf>,
+    recorder: Recorder,
+}
+
+#[async_trait::async_trait]
+impl super::WriteHalf for RecordingWriteHalf {
+    async fn send_message(&mut self, msg: &Message) -> Result<()> {
+        // Record before sending, so that the replies are always recorded after the calls.
+        self.recorder.record(Direction::Sent, msg);
+
+        self.inner.send_message(msg).await
+    }
+
+    #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+    async fn send_zero_byte(&mut self) -> io::Result<Option<usize>> {
+        self.inner.send_zero_byte().await
+    }
+
+    async fn close(&mut self) -> io::Result<()> {
+        let res = self.inner.close().await;
+        self.recorder.flush_later();
+
+        res
+    }
+
+    fn can_pass_unix_fd(&self) -> bool {
+        self.inner.can_pass_unix_fd()
+    }
+
+    async fn peer_credentials(&mut self) -> io::Result<ConnectionCredentials> {
+        self.inner.peer_credentials().await
+    }
+}
+
+/// A message recorded by a [`Recorder`].
+#[derive(Debug, Clone)]
+pub struct Record {
+    direction: Direction,
+    time: Duration,
+    message: Message,
+}
+
+impl Record {
+    /// Whether the message was sent or received.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// The time the message was sent or received at, relative to the start of the recording.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// The message.
+    ///
+    /// For received messages, [`Message::recv_position`] is the same as in the recorded
+    /// connection.
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+}
+
+/// The messages recorded by a [`Recorder`], in the order they were sent or received.
+#[derive(Debug, Clone)]
+pub struct Capture {
+    guid: OwnedGuid,
+    unique_name: Option<OwnedUniqueName>,
+    records: Vec<Record>,
+}
+
+impl Capture {
+    /// Read a capture from `reader`.
+    pub fn read<R>(mut reader: R) -> Result<Self>
+    where
+        R: Read,
+    {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != *MAGIC {
+            return Err(invalid_data("not a zbus capture"));
+        }
+        let version = u32::from_le_bytes(read_array(&mut reader)?);
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported capture format version {version}"
+            )));
+        }
+        let guid = OwnedGuid::from(Guid::try_from(read_string(&mut reader)?)?);
+        let unique_name = match read_string(&mut reader)? {
+            name if name.is_empty() => None,
+            name => Some(OwnedUniqueName::try_from(name)?),
+        };
+
+        let mut records = vec![];
+        loop {
+            let mut direction = [0];
+            if reader.read(&mut direction)? == 0 {
+                break;
+            }
+            let direction = match direction[0] {
+                0 => Direction::Received,
+                1 => Direction::Sent,
+                d => return Err(invalid_data(format!("invalid message direction {d}"))),
+            };
+            let seq = u64::from_le_bytes(read_array(&mut reader)?);
+            let time = Duration::from_micros(u64::from_le_bytes(read_array(&mut reader)?));
+            let len = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+            if len > message::header::MAX_MESSAGE_SIZE {
+                return Err(Error::ExcessData);
+            }
+            let mut bytes = vec![0; len];
+            reader.read_exact(&mut bytes)?;
+            let endian = match bytes.first().copied().map(EndianSig::try_from) {
+                Some(Ok(endian)) => Endian::from(endian),
+                _ => return Err(Error::IncorrectEndian),
+            };
+            let data = serialized::Data::new(bytes, serialized::Context::new_dbus(endian, 0));
+
+            records.push(Record {
+                direction,
+                time,
+                message: Message::from_raw_parts(data, seq)?,
+            });
+        }
+
+        Ok(Self {
+            guid,
+            unique_name,
+            records,
+        })
+    }
+
+    /// Read a capture from the file at `path`.
+    pub fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path)?;
+
+        Self::read(BufReader::new(file))
+    }
+
+    /// The GUID of the server the recorded connection was connected to.
+    pub fn guid(&self) -> &OwnedGuid {
+        &self.guid
+    }
+
+    /// The unique name of the recorded connection, if it was connected to a bus.
+    pub fn unique_name(&self) -> Option<&OwnedUniqueName> {
+        self.unique_name.as_ref()
+    }
+
+    /// The recorded messages.
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+}
+
+/// A socket replaying a [`Capture`].
+///
+/// The connection using this socket, usually created through [`Builder::replay`], receives the
+/// recorded incoming messages, in order. Replies are only received once the connection sent the
+/// corresponding method call, and other messages once the connection sent all the messages that
+/// were sent before them in the recording. This makes the replay deterministic, regardless of the
+/// original timing.
+///
+/// The messages sent by the connection must match the recorded ones. They are matched by their
+/// type, destination, path, interface, member and error name. When several recorded messages
+/// match, the first one with the same body is preferred. Sending a message that doesn't match
+/// any recorded one results in an error. Since the serial numbers of the messages sent by the
+/// connection can differ from the recorded ones, the reply serial numbers of the received
+/// messages are adjusted accordingly.
+///
+/// Once all the recorded messages are received, the socket acts as if the peer stays silent.
+///
+/// See the [module-level documentation](self) for an example.
+///
+/// [`Builder::replay`]: crate::connection::Builder::replay
+#[derive(Debug)]
+pub struct Replay {
+    shared: Arc<ReplayShared>,
+}
+
+#[derive(Debug)]
+struct ReplayShared {
+    state: Mutex<ReplayState>,
+    event: Event,
+}
+
+#[derive(Debug, Default)]
+struct ReplayState {
+    // The messages to be sent, with a flag indicating if they were already sent.
+    sent: Vec<(Message, bool)>,
+    // The number of messages at the start of `sent` that were all sent.
+    sent_prefix: usize,
+    // The messages to be received.
+    received: VecDeque<Received>,
+    // Recorded serial numbers of sent messages, to the actual ones.
+    serials: HashMap<NonZeroU32, NonZeroU32>,
+    closed: bool,
+}
+
+#[derive(Debug)]
+struct Received {
+    msg: Message,
+    // The number of sent messages preceding this one in the recording.
+    preceding: usize,
+    // For replies, the index of the call in `ReplayState::sent`.
+    call: Option<usize>,
+}
+
+impl ReplayState {
+    // Whether `received` can be received, given the messages sent so far.
+    fn can_receive(&self, received: &Received) -> bool {
+        match received.call {
+            Some(call) => self.sent[call].1,
+            None => received.preceding <= self.sent_prefix,
+        }
+    }
+}
+
+impl Replay {
+    /// Create a socket replaying `capture`.
+    pub fn new(capture: &Capture) -> Self {
+        let mut state = ReplayState::default();
+        let mut calls = HashMap::new();
+        for record in capture.records() {
+            let msg = record.message.clone();
+            match record.direction {
+                Direction::Sent => {
+                    calls.insert(msg.primary_header().serial_num(), state.sent.len());
+                    state.sent.push((msg, false));
+                }
+                Direction::Received => {
+                    let call = msg.header().reply_serial().and_then(|s| calls.get(&s));
+                    state.received.push_back(Received {
+                        call: call.copied(),
+                        preceding: state.sent.len(),
+                        msg,
+                    });
+                }
+            }
+        }
+
+        Self {
+            shared: Arc::new(ReplayShared {
+                state: Mutex::new(state),
+                event: Event::new(),
+            }),
+        }
+    }
+}
+
+impl super::Socket for Replay {
+    type ReadHalf = ReplayReader;
+    type WriteHalf = ReplayWriter;
+
+    fn split(self) -> super::Split<Self::ReadHalf, Self::WriteHalf> {
+        super::Split {
+            read: ReplayReader(self.shared.clone()),
+            write: ReplayWriter(self.shared),
+        }
+    }
+}
+
+impl ReplayShared {
+    fn lock(&self) -> MutexGuard<'_, ReplayState> {
+        lock(&self.state)
+    }
+}
+
+/// The reader half of a [`Replay`].
+#[derive(Debug)]
+pub struct ReplayReader(Arc<ReplayShared>);
+
+#[async_trait::async_trait]
+impl super::ReadHalf for ReplayReader {
+    async fn receive_message(
+        &mut self,
+        seq: u64,
+        _already_received_bytes: &mut Vec<u8>,
+        #[cfg(unix)] _already_received_fds: &mut Vec<std::os::fd::OwnedFd>,
+    ) -> Result<Message> {
+        loop {
+            let listener = {
+                let mut state = self.0.lock();
+                match state.received.iter().position(|r| state.can_receive(r)) {
+                    Some(pos) => {
+                        // SAFETY: We just got the position of this message.
+                        let received = state.received.remove(pos).unwrap();
+
+                        return replayed_message(&received.msg, &state.serials, seq);
+                    }
+                    _ if state.closed => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "replay socket closed",
+                        )
+                        .into());
+                    }
+                    _ => self.0.event.listen(),
+                }
+            };
+
+            listener.await;
+        }
+    }
+}
+
+/// The writer half of a [`Replay`].
+#[derive(Debug)]
+pub struct ReplayWriter(Arc<ReplayShared>);
+
+#[async_trait::async_trait]
+impl super::WriteHalf for ReplayWriter {
+    async fn send_message(&mut self, msg: &Message) -> Result<()> {
+        {
+            let mut state = self.0.lock();
+            let matching = state
+                .sent
+                .iter()
+                .enumerate()
+                .filter(|(_, (recorded, sent))| !sent && same_header(recorded, msg))
+                .map(|(i, (recorded, _))| (i, recorded))
+                .collect::<Vec<_>>();
+            let same_body =
+                |recorded: &Message| recorded.body().data().bytes() == msg.body().data().bytes();
+            let Some(i) = matching
+                .iter()
+                .find(|(_, recorded)| same_body(recorded))
+                .or_else(|| matching.first())
+                .map(|(i, _)| *i)
+            else {
+                return Err(Error::Failure(format!(
+                    "Sent message not found in the recording: {msg}"
+                )));
+            };
+
+            let recorded_serial = state.sent[i].0.primary_header().serial_num();
+            state
+                .serials
+                .insert(recorded_serial, msg.primary_header().serial_num());
+            state.sent[i].1 = true;
+            while state.sent.get(state.sent_prefix).is_some_and(|(_, s)| *s) {
+                state.sent_prefix += 1;
+            }
+        }
+        self.0.event.notify(usize::MAX);
+
+        Ok(())
+    }
+
+    async fn close(&mut self) -> io::Result<()> {
+        self.0.lock().closed = true;
+        self.0.event.notify(usize::MAX);
+
+        Ok(())
+    }
+}
+
+// Whether the message headers match, ignoring the serial numbers and the sender.
+fn same_header(recorded: &Message, msg: &Message) -> bool {
+    let (recorded, msg) = (recorded.header(), msg.header());
+
+    recorded.message_type() == msg.message_type()
+        && recorded.destination() == msg.destination()
+        && recorded.path() == msg.path()
+        && recorded.interface() == msg.interface()
+        && recorded.member() == msg.member()
+        && recorded.error_name() == msg.error_name()
+}
+
+// The recorded `msg`, with the reply serial adjusted to the one actually used and the given
+// receive sequence.
+fn replayed_message(
+    msg: &Message,
+    serials: &HashMap<NonZeroU32, NonZeroU32>,
+    seq: u64,
+) -> Result<Message> {
+    let header = msg.header();
+    let reply_serial = header.reply_serial().and_then(|s| serials.get(&s).copied());
+    let data = match reply_serial {
+        Some(reply_serial) if Some(reply_serial) != header.reply_serial() => {
+            let body = msg.body();
+            let builder = message::Builder::from(header.clone()).reply_serial(Some(reply_serial));
+            // SAFETY: The body bytes and signature come from a valid message.
+            let msg = unsafe {
+                builder.build_raw_body(
+                    body.data().bytes(),
+                    body.signature().clone(),
+                    #[cfg(unix)]
+                    vec![],
+                )?
+            };
+
+            msg.data().clone()
+        }
+        _ => msg.data().clone(),
+    };
+
+    Message::from_raw_parts(data, seq)
+}
+
+fn read_array<R, const N: usize>(reader: &mut R) -> Result<[u8; N]>
+where
+    R: Read,
+{
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+
+    Ok(buf)
+}
+
+// Read a string prefixed by its length, as a single byte.
+fn read_string<R>(reader: &mut R) -> Result<String>
+where
+    R: Read,
+{
+    let [len] = read_array(reader)?;
+    let mut string = vec![0; len as usize];
+    reader.read_exact(&mut string)?;
+
+    String::from_utf8(string).map_err(|_| invalid_data("invalid UTF-8 string"))
+}
+
+fn invalid_data<E>(error: E) -> Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error).into()
+}
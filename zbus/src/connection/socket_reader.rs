@@ -1,13 +1,18 @@
 use std::{collections::HashMap, sync::Arc};
 
+use async_broadcast::Sender;
 use event_listener::Event;
-use tracing::{debug, instrument, trace};
+use tracing::{debug, info, instrument, trace, Instrument};
 
 use crate::{
-    async_lock::Mutex, connection::MsgBroadcaster, Executor, Message, OwnedMatchRule, Task,
+    async_lock::Mutex, connection::MsgBroadcaster, message::Type, Executor, Message,
+    OwnedMatchRule, Task,
 };
 
-use super::socket::ReadHalf;
+use super::{
+    handshake::Authenticated, socket::ReadHalf, ConnectionState, MetricsSink, Reconnector,
+    WeakConnection,
+};
 
 #[derive(Debug)]
 pub(crate) struct SocketReader {
@@ -18,15 +23,22 @@ pub(crate) struct SocketReader {
     already_received_fds: Vec<std::os::fd::OwnedFd>,
     prev_seq: u64,
     activity_event: Arc<Event>,
+    state: Sender<ConnectionState>,
+    reconnect: Option<(Reconnector, WeakConnection)>,
+    metrics: Option<Arc<dyn MetricsSink>>,
 }
 
 impl SocketReader {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         socket: Box<dyn ReadHalf>,
         senders: Arc<Mutex<HashMap<Option<OwnedMatchRule>, MsgBroadcaster>>>,
         already_received_bytes: Vec<u8>,
         #[cfg(unix)] already_received_fds: Vec<std::os::fd::OwnedFd>,
         activity_event: Arc<Event>,
+        state: Sender<ConnectionState>,
+        reconnect: Option<(Reconnector, WeakConnection)>,
+        metrics: Option<Arc<dyn MetricsSink>>,
     ) -> Self {
         Self {
             socket,
@@ -36,6 +48,9 @@ impl SocketReader {
             already_received_fds,
             prev_seq: 0,
             activity_event,
+            state,
+            reconnect,
+            metrics,
         }
     }
 
@@ -50,47 +65,74 @@ impl SocketReader {
             trace!("Waiting for message on the socket..");
             let msg = self.read_socket().await;
             match &msg {
-                Ok(msg) => trace!("Message received on the socket: {:?}", msg),
+                Ok(msg) => {
+                    trace!("Message received on the socket: {:?}", msg);
+                    if let Some(metrics) = &self.metrics {
+                        metrics.message_received(msg);
+                    }
+                }
                 Err(e) => trace!("Error reading from the socket: {:?}", e),
             };
 
-            let mut senders = self.senders.lock().await;
+            let senders = self.senders.lock().await;
             for (rule, sender) in &*senders {
-                if let Ok(msg) = &msg {
-                    if let Some(rule) = rule.as_ref() {
-                        match rule.matches(msg) {
-                            Ok(true) => (),
-                            Ok(false) => continue,
-                            Err(e) => {
-                                debug!("Error matching message against rule: {:?}", e);
-
-                                continue;
+                match &msg {
+                    Ok(msg) => {
+                        if let Some(rule) = rule.as_ref() {
+                            match rule.matches(msg) {
+                                Ok(true) => (),
+                                Ok(false) => continue,
+                                Err(e) => {
+                                    debug!("Error matching message against rule: {:?}", e);
+
+                                    continue;
+                                }
                             }
                         }
                     }
+                    // When reconnecting, the streams are kept alive and only the pending method
+                    // calls are failed.
+                    Err(_) if self.reconnect.is_some() && !is_method_reply_rule(rule) => continue,
+                    Err(_) => (),
                 }
 
-                if let Err(e) = sender.broadcast_direct(msg.clone()).await {
-                    // An error would be due to either of these:
-                    //
-                    // 1. the channel is closed.
-                    // 2. No active receivers.
-                    //
-                    // In either case, just log it unless this is the channel for the generic
-                    // unfiltered stream, where the channel is not created on-demand.
-                    if rule.is_some() {
-                        trace!(
-                            "Error broadcasting message to stream for `{:?}`: {:?}",
-                            rule,
-                            e
-                        );
+                match sender.broadcast_direct(msg.clone()).await {
+                    Ok(_) => {
+                        if let Some(metrics) = &self.metrics {
+                            metrics.message_queued(rule.as_ref(), sender.len(), sender.capacity());
+                        }
+                    }
+                    Err(e) => {
+                        // An error would be due to either of these:
+                        //
+                        // 1. the channel is closed.
+                        // 2. No active receivers.
+                        //
+                        // In either case, just log it unless this is the channel for the generic
+                        // unfiltered stream, where the channel is not created on-demand.
+                        if rule.is_some() {
+                            trace!(
+                                "Error broadcasting message to stream for `{:?}`: {:?}",
+                                rule,
+                                e
+                            );
+                        }
+                        if let (Some(metrics), Ok(msg)) = (&self.metrics, &msg) {
+                            metrics.message_dropped(rule.as_ref(), msg);
+                        }
                     }
                 }
             }
             trace!("Broadcasted to all streams: {:?}", msg);
 
             if msg.is_err() {
-                senders.clear();
+                drop(senders);
+                let _ = self.state.try_broadcast(ConnectionState::Disconnected);
+                if self.reconnect().await {
+                    continue;
+                }
+
+                self.senders.lock().await.clear();
                 trace!("Socket reading task stopped");
 
                 return;
@@ -98,6 +140,56 @@ impl SocketReader {
         }
     }
 
+    // Reconnect, if enabled and the connection is still around.
+    async fn reconnect(&mut self) -> bool {
+        let Some((reconnector, conn)) = &self.reconnect else {
+            return false;
+        };
+        let Some(auth) = reconnector.reconnect(conn).await else {
+            return false;
+        };
+        let Some(conn) = conn.upgrade() else {
+            return false;
+        };
+        let Authenticated {
+            socket_read,
+            socket_write,
+            server_guid,
+            unique_name,
+            already_received_bytes,
+            #[cfg(unix)]
+            already_received_fds,
+            ..
+        } = auth;
+
+        // SAFETY: `Authenticated` is always built with this field set to `Some`.
+        self.socket = socket_read.unwrap();
+        self.already_received_bytes = already_received_bytes;
+        #[cfg(unix)]
+        {
+            self.already_received_fds = already_received_fds;
+        }
+        *conn.inner.socket_write.lock().await = socket_write;
+        conn.inner.server_guid.replace(server_guid);
+        let old_unique_name = conn.inner.unique_name.get_owned().as_deref().cloned();
+        if let Some(unique_name) = unique_name {
+            info!("Reconnected as `{unique_name}`");
+            conn.inner.unique_name.replace(unique_name);
+        }
+
+        // This involves method calls, so it can't be done from here.
+        let executor = conn.executor().clone();
+        executor
+            .spawn(
+                async move { conn.restore_state(old_unique_name).await }
+                    .instrument(tracing::info_span!("restore_state")),
+                "restore_state",
+            )
+            .detach();
+
+        true
+    }
+
     #[instrument(skip(self), level = "trace")]
     async fn read_socket(&mut self) -> crate::Result<Message> {
         self.activity_event.notify(usize::MAX);
@@ -116,3 +208,10 @@ impl SocketReader {
         Ok(msg)
     }
 }
+
+// Whether the messages of `rule` are method replies, i.e. the ones awaited by method calls.
+fn is_method_reply_rule(rule: &Option<OwnedMatchRule>) -> bool {
+    rule.as_ref()
+        .and_then(|rule| rule.msg_type())
+        .is_some_and(|msg_type| msg_type == Type::MethodReturn || msg_type == Type::Error)
+}
@@ -0,0 +1,99 @@
+use std::fmt::Debug;
+
+use tracing::Span;
+
+/// Propagation of the trace context across method calls.
+///
+/// `tracing` spans stop at the process boundary. With a propagator set through
+/// [`Builder::trace_propagator`], the context of the current span is sent along with the method
+/// calls that opt in to it and, on the receiving end, the span of the dispatching of a call by the
+/// [`ObjectServer`] becomes a child of the one of the caller. Hence, both peers need a propagator
+/// for this to work.
+///
+/// `zbus` doesn't know about trace or span IDs itself, so this is typically implemented on top of
+/// OpenTelemetry, with `inject` serializing the context of [`Span::current`] in the [W3C Trace
+/// Context] format and `extract` setting the deserialized context as the parent of the given span.
+///
+/// D-Bus has no standard way to carry a trace context, so the context is passed as an extra last
+/// string argument of the method, which is empty when there's no context. This is part of the
+/// signature of the method and hence, only used for the methods that explicitly declare it, through
+/// the `trace_context` attributes of the [`interface`] and [`proxy`] macros. Such arguments are
+/// annotated with `org.zbus.TraceContext` in the introspection data, so other implementations
+/// can pass (or ignore) them as well.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use tracing::Span;
+/// use zbus::connection::{Builder, TracePropagator};
+///
+/// // A propagator that only records the context of the caller.
+/// #[derive(Debug)]
+/// struct Recorder;
+///
+/// impl TracePropagator for Recorder {
+///     fn inject(&self) -> Option<String> {
+///         Some(format!("{:?}", Span::current().id()?))
+///     }
+///
+///     fn extract(&self, context: &str, span: &Span) {
+///         span.record("trace_context", context);
+///     }
+/// }
+///
+/// # zbus::block_on(async {
+/// let conn = Builder::session()?
+///     .trace_propagator(Arc::new(Recorder))
+///     .build()
+///     .await?;
+/// # drop(conn);
+/// # Ok::<(), zbus::Error>(())
+/// # }).unwrap();
+/// ```
+///
+/// The methods then declare the trace context argument, on the service side:
+///
+/// ```
+/// struct Greeter;
+///
+/// #[zbus::interface(name = "org.zbus.Greeter")]
+/// impl Greeter {
+///     fn say_hello(&self, name: &str, #[zbus(trace_context)] _context: &str) -> String {
+///         format!("Hello {name}!")
+///     }
+/// }
+/// ```
+///
+/// and the proxy side:
+///
+/// ```
+/// #[zbus::proxy(
+///     interface = "org.zbus.Greeter",
+///     default_service = "org.zbus.Greeter",
+///     default_path = "/org/zbus/Greeter"
+/// )]
+/// trait Greeter {
+///     #[zbus(trace_context)]
+///     fn say_hello(&self, name: &str) -> zbus::Result<String>;
+/// }
+/// ```
+///
+/// [`Builder::trace_propagator`]: super::Builder::trace_propagator
+/// [`ObjectServer`]: crate::ObjectServer
+/// [`interface`]: macro@crate::interface
+/// [`proxy`]: macro@crate::proxy
+/// [W3C Trace Context]: https://www.w3.org/TR/trace-context/
+pub trait TracePropagator: Send + Sync + Debug {
+    /// The context of the current span, to send along with an outgoing method call.
+    ///
+    /// Returning `None` sends an empty context.
+    fn inject(&self) -> Option<String>;
+
+    /// Set the `context` received along with a method call as the parent of `span`.
+    ///
+    /// `span` is the span of the dispatching of the call and it has a `trace_context` field, that
+    /// can be recorded to keep track of the context without a tracing backend that supports
+    /// remote parents.
+    fn extract(&self, context: &str, span: &Span);
+}
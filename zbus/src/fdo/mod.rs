@@ -22,6 +22,11 @@ pub use object_manager::{
     ObjectManagerProxy,
 };
 
+pub(crate) mod object_manager_client;
+pub use object_manager_client::{
+    AddedStream, ObjectEvent, ObjectEventStream, ObjectManagerClient, RemovedStream,
+};
+
 pub(crate) mod peer;
 pub(crate) use peer::Peer;
 pub use peer::PeerProxy;
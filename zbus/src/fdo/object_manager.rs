@@ -23,9 +23,16 @@ pub type ManagedObjects =
 ///
 /// It is supported, but not recommended, to add this interface at the root path, `/`.
 ///
-/// When added to an `ObjectServer`, the `InterfacesAdded` signal is emitted for all the objects
-/// under the `path` it's added at. You can use this fact to minimize the signal emissions by
-/// populating the entire (sub)tree under `path` before registering an object manager.
+/// The `InterfacesAdded` and `InterfacesRemoved` signals are emitted automatically whenever
+/// interfaces are added to or removed from objects under the `path` it's registered at, through
+/// [`ObjectServer::at`] and [`ObjectServer::remove`]. This also includes all the objects under
+/// `path` when the object manager itself is registered.
+///
+/// Object managers can be nested, in which case each object is managed by its closest ancestor
+/// implementing this interface. When a nested object manager is added or removed, the objects
+/// beneath it move between the two managers, with the corresponding signals emitted by both. To
+/// minimize the signal emissions, register entire (sub)trees at once, through
+/// [`ObjectServer::add_objects`].
 ///
 /// [om]: https://dbus.freedesktop.org/doc/dbus-specification.html#standard-interfaces-objectmanager
 #[derive(Debug, Clone)]
@@ -0,0 +1,659 @@
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Mutex, RwLock},
+    task::{Context, Poll},
+};
+
+use async_broadcast::{broadcast, InactiveReceiver, Receiver, Sender};
+use futures_core::{ready, Stream};
+use futures_lite::{stream, StreamExt};
+use tracing::{debug, trace};
+use zbus_names::OwnedInterfaceName;
+use zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+
+use super::{
+    InterfacesAdded, InterfacesRemoved, ManagedObjects, ObjectManagerProxy, PropertiesChanged,
+};
+use crate::{
+    connection::{NameEvent, NameWatchStream},
+    message::{Message, Sequence, Type},
+    proxy::{Builder, Defaults, PropertiesFeed},
+    Error, MatchRule, MessageStream, Proxy, Result, Task,
+};
+
+// The oldest events are dropped when a stream falls behind, so that the cache is never held up by
+// the streams.
+const EVENTS_CAPACITY: usize = 64;
+
+/// A live, local mirror of the objects of a remote [`ObjectManager`].
+///
+/// On creation, the client fetches all the objects managed by the remote object manager, through
+/// a single `GetManagedObjects` call. Afterwards, it keeps its cache of the objects, their
+/// interfaces and their properties up to date, by tracking the `InterfacesAdded`,
+/// `InterfacesRemoved` and `PropertiesChanged` signals in the background.
+///
+/// On a bus, the client also follows the owner of the destination of the manager. When the service
+/// goes away, the cache is emptied and a removal is reported for each object. When it comes back,
+/// the objects are fetched again and reported as new.
+///
+/// Besides querying the cache ([`ObjectManagerClient::objects`],
+/// [`ObjectManagerClient::object`]), you can:
+///
+/// * Get notified about objects appearing and disappearing, through
+///   [`ObjectManagerClient::receive_events`], or [`ObjectManagerClient::receive_added`] and
+///   [`ObjectManagerClient::receive_removed`] for objects implementing a specific interface.
+/// * Create typed proxies for the cached objects, through [`ObjectManagerClient::proxy`] and
+///   [`ObjectManagerClient::proxies`]. The property caches of these proxies are populated from the
+///   cache of the client, so creating them doesn't involve any D-Bus call to the service, and kept
+///   up to date by the client.
+///
+/// The event streams are not allowed to hold up the cache: a stream that is not polled often enough
+/// misses the oldest events.
+///
+/// This is a cheap handle that can be cloned. The cache is kept up to date for as long as any
+/// of the clones, or of the proxies created through it, is alive. Objects managed by nested object
+/// managers are not included.
+///
+/// [`ObjectManager`]: super::ObjectManager
+///
+/// # Example
+///
+/// ```no_run
+/// use futures_util::StreamExt;
+/// use zbus::{fdo::{ObjectManagerClient, ObjectManagerProxy}, proxy, Connection};
+///
+/// #[proxy(interface = "org.bluez.Device1", default_service = "org.bluez")]
+/// trait Device1 {
+///     #[zbus(property)]
+///     fn name(&self) -> zbus::Result<String>;
+/// }
+///
+/// # zbus::block_on(async {
+/// let connection = Connection::system().await?;
+/// let manager = ObjectManagerProxy::builder(&connection)
+///     .destination("org.bluez")?
+///     .path("/")?
+///     .build()
+///     .await?;
+/// let client = ObjectManagerClient::new(&manager).await?;
+///
+/// // The devices already known.
+/// for device in client.proxies::<Device1Proxy<'static>>() {
+///     println!("Device: {:?}", device.cached_name()?);
+/// }
+/// // The devices appearing from now on.
+/// let mut added = client.receive_added::<Device1Proxy<'static>>();
+/// while let Some(device) = added.next().await {
+///     println!("New device: {:?}", device.cached_name()?);
+/// }
+/// # Ok::<_, zbus::Error>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ObjectManagerClient {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    manager: ObjectManagerProxy<'static>,
+    cache: Arc<Cache>,
+    _task: Task<()>,
+}
+
+#[derive(Debug)]
+struct Cache {
+    manager_path: OwnedObjectPath,
+    objects: RwLock<ManagedObjects>,
+    // The property caches of the proxies created by the client, by path and interface.
+    //
+    // Always locked while holding `objects`, so that a proxy doesn't miss any change made after
+    // its cache was populated.
+    feeds: Mutex<HashMap<(OwnedObjectPath, OwnedInterfaceName), Vec<PropertiesFeed>>>,
+    events: Sender<ObjectEvent>,
+    // Keeps the channel open while there are no streams.
+    _events_receiver: InactiveReceiver<ObjectEvent>,
+}
+
+impl ObjectManagerClient {
+    /// Create a client for the remote object manager behind `manager`.
+    ///
+    /// This fetches all the managed objects before returning.
+    pub async fn new(manager: &ObjectManagerProxy<'_>) -> Result<Self> {
+        let conn = manager.inner().connection();
+        let manager = ObjectManagerProxy::builder(conn)
+            .destination(manager.inner().destination().to_owned())?
+            .path(manager.inner().path().to_owned())?
+            .build()
+            .await?;
+        let manager_path = OwnedObjectPath::from(manager.inner().path().clone());
+
+        // Subscribe before fetching the objects, so that no change is missed.
+        let owners = if conn.is_bus() {
+            let mut owners = conn.watch_name(manager.inner().destination()).await?;
+            // The current owner, which the objects are fetched from below.
+            owners.next().await;
+
+            Some(owners)
+        } else {
+            None
+        };
+        let mut rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .path_namespace(manager_path.clone())?;
+        if conn.is_bus() {
+            rule = rule.sender(manager.inner().destination().clone())?;
+        }
+        let stream = MessageStream::for_match_rule(rule.build(), conn, None).await?;
+        let (objects, after) = fetch_objects(&manager).await?;
+
+        let (mut events, mut events_receiver) = broadcast(EVENTS_CAPACITY);
+        events.set_overflow(true);
+        events_receiver.set_await_active(false);
+        let cache = Arc::new(Cache {
+            manager_path,
+            objects: RwLock::new(objects),
+            feeds: Mutex::default(),
+            events,
+            _events_receiver: events_receiver.deactivate(),
+        });
+        let task_name = format!("object manager client for `{}`", cache.manager_path);
+        let task = conn.executor().spawn(
+            cache
+                .clone()
+                .keep_updated(manager.clone(), stream, owners, after),
+            &task_name,
+        );
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                manager,
+                cache,
+                _task: task,
+            }),
+        })
+    }
+
+    /// The proxy for the remote object manager.
+    pub fn manager(&self) -> &ObjectManagerProxy<'static> {
+        &self.inner.manager
+    }
+
+    /// A snapshot of all the cached objects.
+    pub fn objects(&self) -> ManagedObjects {
+        self.inner
+            .cache
+            .objects
+            .read()
+            .expect("lock poisoned")
+            .clone()
+    }
+
+    /// A snapshot of the interfaces, and their properties, of the object at `path`.
+    ///
+    /// Returns `None` if there is no such object in the cache.
+    pub fn object<'p, P>(
+        &self,
+        path: P,
+    ) -> Option<HashMap<OwnedInterfaceName, HashMap<String, OwnedValue>>>
+    where
+        P: TryInto<ObjectPath<'p>>,
+    {
+        let path = path.try_into().ok()?;
+        let objects = self.inner.cache.objects.read().expect("lock poisoned");
+
+        objects.get(&path).cloned()
+    }
+
+    /// Create a proxy of type `P` for the object at `path`.
+    ///
+    /// The properties cache of the proxy is populated from the cache of the client.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InterfaceNotFound`] if there is no object at `path`, or it doesn't implement the
+    /// interface of `P`, according to the cache.
+    pub fn proxy<'p, P, O>(&self, path: O) -> Result<P>
+    where
+        P: From<Proxy<'static>> + Defaults,
+        O: TryInto<ObjectPath<'p>>,
+        O::Error: Into<Error>,
+    {
+        let path = path.try_into().map_err(Into::into)?;
+        let interface = proxy_interface::<P>();
+        let objects = self.inner.cache.objects.read().expect("lock poisoned");
+        let properties = objects
+            .get(&path)
+            .and_then(|interfaces| interfaces.get(interface.as_str()))
+            .ok_or(Error::InterfaceNotFound)?;
+
+        self.build_proxy(path.into(), interface, properties)
+    }
+
+    /// Create proxies of type `P` for all the cached objects implementing the interface of `P`.
+    pub fn proxies<P>(&self) -> Vec<P>
+    where
+        P: From<Proxy<'static>> + Defaults,
+    {
+        let interface = proxy_interface::<P>();
+        let objects = self.inner.cache.objects.read().expect("lock poisoned");
+
+        objects
+            .iter()
+            .filter_map(|(path, interfaces)| {
+                let properties = interfaces.get(interface.as_str())?;
+
+                self.build_proxy(path.clone(), interface.clone(), properties)
+                    .ok()
+            })
+            .collect()
+    }
+
+    /// Stream of changes to the managed objects.
+    ///
+    /// Only changes happening after this call are reported. When an event is received, the cache
+    /// has already been updated accordingly.
+    pub fn receive_events(&self) -> ObjectEventStream {
+        ObjectEventStream {
+            events: self.inner.cache.events.new_receiver(),
+        }
+    }
+
+    /// Stream of proxies of type `P` for the objects gaining the interface of `P`.
+    ///
+    /// This includes new objects implementing the interface. Only changes happening after this call
+    /// are reported. Use [`ObjectManagerClient::proxies`] for the existing objects.
+    pub fn receive_added<P>(&self) -> AddedStream<P>
+    where
+        P: From<Proxy<'static>> + Defaults,
+    {
+        AddedStream {
+            events: self.inner.cache.events.new_receiver(),
+            client: self.clone(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Stream of the paths of the objects losing the interface of `P`.
+    ///
+    /// This includes objects removed altogether. Only changes happening after this call are
+    /// reported.
+    pub fn receive_removed<P>(&self) -> RemovedStream
+    where
+        P: Defaults,
+    {
+        RemovedStream {
+            events: self.inner.cache.events.new_receiver(),
+            interface: proxy_interface::<P>(),
+        }
+    }
+
+    // The caller must hold the `objects` lock, from which `properties` were taken, so that no
+    // change is missed by the proxy.
+    fn build_proxy<P>(
+        &self,
+        path: OwnedObjectPath,
+        interface: OwnedInterfaceName,
+        properties: &HashMap<String, OwnedValue>,
+    ) -> Result<P>
+    where
+        P: From<Proxy<'static>> + Defaults,
+    {
+        let manager = self.inner.manager.inner();
+        let (proxy, feed) = Builder::<P>::new(manager.connection())
+            .destination(manager.destination().to_owned())?
+            .path(path.clone())?
+            .build_with_properties(properties, self.inner.clone())?;
+        if let Some(feed) = feed {
+            let mut feeds = self.inner.cache.feeds.lock().expect("lock poisoned");
+            let feeds = feeds.entry((path, interface)).or_default();
+            feeds.retain(PropertiesFeed::is_alive);
+            feeds.push(feed);
+        }
+
+        Ok(proxy)
+    }
+}
+
+// Fetch all the managed objects, along with the position of the reply.
+async fn fetch_objects(manager: &ObjectManagerProxy<'_>) -> Result<(ManagedObjects, Sequence)> {
+    let reply = manager
+        .inner()
+        .call_method("GetManagedObjects", &())
+        .await?;
+    let objects: ManagedObjects = reply.body().deserialize()?;
+    trace!(
+        "Got {} objects from the object manager at `{}`",
+        objects.len(),
+        manager.inner().path(),
+    );
+
+    Ok((objects, reply.recv_position()))
+}
+
+enum Update {
+    Signal(Result<Message>),
+    Owner(NameEvent),
+}
+
+impl Cache {
+    async fn keep_updated(
+        self: Arc<Self>,
+        manager: ObjectManagerProxy<'static>,
+        stream: MessageStream,
+        owners: Option<NameWatchStream>,
+        mut after: Sequence,
+    ) {
+        // Without a bus, there are no owner changes. The pending stream keeps the merged stream
+        // going once the (empty) owner stream is done. Signals are preferred, so that the ones
+        // received before an owner change are handled first.
+        let owners = stream::iter(owners).flatten().chain(stream::pending());
+        let mut updates = stream.map(Update::Signal).or(owners.map(Update::Owner));
+        while let Some(update) = updates.next().await {
+            let msg = match update {
+                Update::Signal(Ok(msg)) => msg,
+                Update::Signal(Err(e)) => {
+                    debug!("Error receiving object manager signals: {e}");
+                    continue;
+                }
+                Update::Owner(NameEvent::Vanished) => {
+                    trace!("Object manager at `{}` vanished", self.manager_path);
+                    self.reset(ManagedObjects::new());
+                    continue;
+                }
+                Update::Owner(NameEvent::Appeared(owner)) => {
+                    trace!(
+                        "Object manager at `{}` owned by `{owner}`",
+                        self.manager_path
+                    );
+                    match fetch_objects(&manager).await {
+                        Ok((objects, reply)) => {
+                            self.reset(objects);
+                            after = reply;
+                        }
+                        Err(e) => debug!("Failed to get the managed objects: {e}"),
+                    }
+                    continue;
+                }
+            };
+            // The changes up to the `GetManagedObjects` reply are part of the fetched objects.
+            if msg.recv_position() < after {
+                continue;
+            }
+
+            if let Some(event) = self.update(msg) {
+                self.broadcast(event);
+            }
+        }
+    }
+
+    // Replace all the objects, reporting the old ones as removed and the new ones as added.
+    fn reset(&self, objects: ManagedObjects) {
+        let mut current = self.objects.write().expect("lock poisoned");
+        self.feeds.lock().expect("lock poisoned").clear();
+        let old = std::mem::replace(&mut *current, objects);
+        let removed = old
+            .into_iter()
+            .map(|(path, interfaces)| ObjectEvent::Removed {
+                path,
+                interfaces: interfaces.into_keys().collect(),
+            });
+        let added = current.iter().map(|(path, interfaces)| ObjectEvent::Added {
+            path: path.clone(),
+            interfaces: interfaces.keys().cloned().collect(),
+        });
+        let events: Vec<_> = removed.chain(added).collect();
+        // Release the lock before broadcasting, since the streams access the cache.
+        drop(current);
+
+        for event in events {
+            self.broadcast(event);
+        }
+    }
+
+    fn broadcast(&self, event: ObjectEvent) {
+        trace!("Object manager event: {event:?}");
+        // With overflow enabled, the only error possible is having no streams.
+        let _ = self.events.try_broadcast(event);
+    }
+
+    fn update(&self, msg: Message) -> Option<ObjectEvent> {
+        let header = msg.header();
+        let from_manager = header.path() == Some(&self.manager_path);
+
+        if let Some(signal) = InterfacesAdded::from_message(msg.clone()).filter(|_| from_manager) {
+            let args = signal.args().ok()?;
+            let path = OwnedObjectPath::from(args.object_path().clone());
+            let added = args
+                .interfaces_and_properties()
+                .iter()
+                .map(|(name, properties)| {
+                    let properties = properties
+                        .iter()
+                        .filter_map(|(name, value)| {
+                            let value = OwnedValue::try_from(value).ok()?;
+
+                            Some((name.to_string(), value))
+                        })
+                        .collect();
+
+                    (name.to_owned().into(), properties)
+                })
+                .collect::<Vec<(OwnedInterfaceName, HashMap<String, OwnedValue>)>>();
+            let interfaces = added.iter().map(|(name, _)| name.clone()).collect();
+
+            let mut objects = self.objects.write().expect("lock poisoned");
+            let new = !objects.contains_key(&path);
+            objects.entry(path.clone()).or_default().extend(added);
+
+            return Some(if new {
+                ObjectEvent::Added { path, interfaces }
+            } else {
+                ObjectEvent::InterfacesAdded { path, interfaces }
+            });
+        }
+
+        if let Some(signal) = InterfacesRemoved::from_message(msg.clone()).filter(|_| from_manager)
+        {
+            let args = signal.args().ok()?;
+            let path = OwnedObjectPath::from(args.object_path().clone());
+            let mut objects = self.objects.write().expect("lock poisoned");
+            let object = objects.get_mut(&path)?;
+            let interfaces: Vec<OwnedInterfaceName> = args
+                .interfaces()
+                .iter()
+                .filter(|name| object.remove(name.as_str()).is_some())
+                .map(|name| name.to_owned().into())
+                .collect();
+            if interfaces.is_empty() {
+                return None;
+            }
+            let mut feeds = self.feeds.lock().expect("lock poisoned");
+            for interface in &interfaces {
+                feeds.remove(&(path.clone(), interface.clone()));
+            }
+
+            return Some(if object.is_empty() {
+                objects.remove(&path);
+
+                ObjectEvent::Removed { path, interfaces }
+            } else {
+                ObjectEvent::InterfacesRemoved { path, interfaces }
+            });
+        }
+
+        if let Some(signal) = PropertiesChanged::from_message(msg.clone()) {
+            let args = signal.args().ok()?;
+            let path = header.path()?;
+            let interface = args.interface_name();
+            let mut objects = self.objects.write().expect("lock poisoned");
+            let properties = objects.get_mut(path)?.get_mut(interface.as_str())?;
+            for name in args.invalidated_properties().iter() {
+                properties.remove(*name);
+            }
+            for (name, value) in args.changed_properties() {
+                if let Ok(value) = OwnedValue::try_from(value) {
+                    properties.insert(name.to_string(), value);
+                }
+            }
+
+            let mut feeds = self.feeds.lock().expect("lock poisoned");
+            let key = (path.to_owned().into(), interface.to_owned().into());
+            if let Some(proxies) = feeds.get_mut(&key) {
+                proxies.retain(|feed| {
+                    feed.update(args.changed_properties(), args.invalidated_properties())
+                });
+                if proxies.is_empty() {
+                    feeds.remove(&key);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// A change to the objects managed by a remote object manager.
+///
+/// See [`ObjectManagerClient::receive_events`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ObjectEvent {
+    /// A new object appeared, with the given interfaces.
+    Added {
+        path: OwnedObjectPath,
+        interfaces: Vec<OwnedInterfaceName>,
+    },
+    /// An existing object gained the given interfaces.
+    InterfacesAdded {
+        path: OwnedObjectPath,
+        interfaces: Vec<OwnedInterfaceName>,
+    },
+    /// An existing object lost the given interfaces, but still has others.
+    InterfacesRemoved {
+        path: OwnedObjectPath,
+        interfaces: Vec<OwnedInterfaceName>,
+    },
+    /// An object disappeared, after losing the given (last) interfaces.
+    Removed {
+        path: OwnedObjectPath,
+        interfaces: Vec<OwnedInterfaceName>,
+    },
+}
+
+impl ObjectEvent {
+    /// The path of the object.
+    pub fn path(&self) -> &OwnedObjectPath {
+        match self {
+            Self::Added { path, .. }
+            | Self::InterfacesAdded { path, .. }
+            | Self::InterfacesRemoved { path, .. }
+            | Self::Removed { path, .. } => path,
+        }
+    }
+
+    /// The interfaces added or removed.
+    pub fn interfaces(&self) -> &[OwnedInterfaceName] {
+        match self {
+            Self::Added { interfaces, .. }
+            | Self::InterfacesAdded { interfaces, .. }
+            | Self::InterfacesRemoved { interfaces, .. }
+            | Self::Removed { interfaces, .. } => interfaces,
+        }
+    }
+
+    fn is_added(&self) -> bool {
+        matches!(self, Self::Added { .. } | Self::InterfacesAdded { .. })
+    }
+}
+
+/// A [`stream::Stream`] of [`ObjectEvent`]s.
+///
+/// Use [`ObjectManagerClient::receive_events`] to create an instance of this type.
+///
+/// [`stream::Stream`]: futures_core::stream::Stream
+#[derive(Debug)]
+pub struct ObjectEventStream {
+    events: Receiver<ObjectEvent>,
+}
+
+impl Stream for ObjectEventStream {
+    type Item = ObjectEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+/// A [`stream::Stream`] of typed proxies for objects gaining an interface.
+///
+/// Use [`ObjectManagerClient::receive_added`] to create an instance of this type.
+///
+/// [`stream::Stream`]: futures_core::stream::Stream
+#[derive(Debug)]
+pub struct AddedStream<P> {
+    events: Receiver<ObjectEvent>,
+    client: ObjectManagerClient,
+    phantom: PhantomData<fn() -> P>,
+}
+
+impl<P> Stream for AddedStream<P>
+where
+    P: From<Proxy<'static>> + Defaults,
+{
+    type Item = P;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let interface = proxy_interface::<P>();
+        loop {
+            let Some(event) = ready!(Pin::new(&mut self.events).poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            if !event.is_added() || !event.interfaces().contains(&interface) {
+                continue;
+            }
+            // The interface might already be gone, in which case we have nothing to report.
+            if let Ok(proxy) = self.client.proxy(event.path()) {
+                return Poll::Ready(Some(proxy));
+            }
+        }
+    }
+}
+
+/// A [`stream::Stream`] of the paths of objects losing an interface.
+///
+/// Use [`ObjectManagerClient::receive_removed`] to create an instance of this type.
+///
+/// [`stream::Stream`]: futures_core::stream::Stream
+#[derive(Debug)]
+pub struct RemovedStream {
+    events: Receiver<ObjectEvent>,
+    interface: OwnedInterfaceName,
+}
+
+impl Stream for RemovedStream {
+    type Item = OwnedObjectPath;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let Some(event) = ready!(Pin::new(&mut self.events).poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            if !event.is_added() && event.interfaces().contains(&self.interface) {
+                return Poll::Ready(Some(event.path().clone()));
+            }
+        }
+    }
+}
+
+fn proxy_interface<P>() -> OwnedInterfaceName
+where
+    P: Defaults,
+{
+    P::INTERFACE
+        .clone()
+        .expect("the proxy type must have a default interface")
+        .into()
+}
@@ -77,6 +77,11 @@ pub use match_rule::{MatchRule, OwnedMatchRule};
 pub mod proxy;
 pub use proxy::Proxy;
 
+#[cfg(feature = "p2p")]
+pub mod listener;
+#[cfg(feature = "p2p")]
+pub use listener::Listener;
+
 pub mod object_server;
 pub use object_server::ObjectServer;
 
@@ -86,9 +91,14 @@ pub use utils::*;
 #[macro_use]
 pub mod fdo;
 
+pub mod polkit;
+
 #[cfg(feature = "blocking-api")]
 pub mod blocking;
 
+#[cfg(all(unix, feature = "bus-impl"))]
+pub mod bus;
+
 pub use zbus_macros::{interface, proxy, DBusError};
 
 // Required for the macros to function within this crate.
@@ -0,0 +1,716 @@
+//! Server-side listening for peer-to-peer connections.
+//!
+//! See [`Listener`] for details.
+
+#[cfg(not(feature = "tokio"))]
+use async_io::Async;
+#[cfg(not(feature = "tokio"))]
+use std::net::TcpListener;
+#[cfg(all(unix, not(feature = "tokio")))]
+use std::os::unix::net::UnixListener;
+use std::{
+    fmt::{self, Debug, Formatter},
+    fs::OpenOptions,
+    future::Future,
+    io::Write,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+#[cfg(feature = "tokio")]
+use tokio::net::TcpListener;
+#[cfg(all(unix, feature = "tokio"))]
+use tokio::net::UnixListener;
+#[cfg(feature = "tokio-vsock")]
+use tokio_vsock::VsockListener;
+#[cfg(all(feature = "vsock", not(feature = "tokio")))]
+use vsock::VsockListener;
+
+use futures_core::Stream;
+use tracing::{debug, trace};
+
+#[cfg(unix)]
+use crate::address::transport::{Unix, UnixSocket};
+use crate::{
+    address::transport::{Tcp, Transport},
+    connection,
+    timeout::timeout,
+    Address, Connection, Error, Guid, OwnedGuid, Result,
+};
+
+// The length of the nonce of `nonce-tcp` addresses.
+const NONCE_LEN: usize = 16;
+// How long a peer has to send the nonce, once connected.
+const NONCE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A listening socket, accepting peer-to-peer connections.
+///
+/// The listener binds a [listenable address][la] and performs the server side of the
+/// authentication handshake for each peer that connects to it. A new GUID is generated for the
+/// listener, which is also the GUID of all the accepted connections.
+///
+/// The following addresses can be listened on:
+///
+/// * `unix:path=...` and `unix:abstract=...` (Linux only).
+/// * `unix:dir=...` and `unix:tmpdir=...`, in which case a socket with a random name is created in
+///   the given directory. With `tmpdir`, an abstract socket is used on Linux.
+/// * `tcp:host=...,port=...`. The port can be `0` to let the OS choose a free port.
+/// * `nonce-tcp:host=...,port=...,noncefile=...`. A random nonce is written to a new nonce file
+///   (see [`NonceFile`]), which connecting peers must send before the handshake.
+/// * `vsock:cid=...,port=...`, with either the `vsock` or `tokio-vsock` feature enabled.
+/// * `systemd:` and `systemd:name=...` (Linux only), to use a listening socket passed through
+///   systemd socket activation. See also [`Listener::systemd_socket`].
+///
+/// [`Listener::address`] gives the connectable address of the listener, to share with the peers.
+/// The socket and nonce files created by the listener are removed when it's dropped.
+///
+/// This type is only available when the `p2p` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use futures_util::StreamExt;
+/// use zbus::{connection::Builder, Listener};
+///
+/// # zbus::block_on(async {
+/// let listener = Listener::bind("tcp:host=127.0.0.1,port=0").await?;
+/// let address = listener.address().clone();
+/// println!("Listening on `{address}`");
+///
+/// let client = async move { Builder::address(address)?.p2p().build().await };
+/// let server = async { listener.incoming().next().await.unwrap() };
+/// let (client, server) = futures_util::try_join!(client, server)?;
+/// assert_eq!(client.server_guid(), server.server_guid());
+/// # Ok::<(), zbus::Error>(())
+/// # }).unwrap();
+/// ```
+///
+/// [la]: https://dbus.freedesktop.org/doc/dbus-specification.html#addresses
+pub struct Listener {
+    socket: Socket,
+    address: Address,
+    guid: OwnedGuid,
+    nonce_file: Option<NonceFile>,
+    // The socket file to remove on drop, if any.
+    #[cfg(unix)]
+    socket_path: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+enum Socket {
+    #[cfg(all(unix, not(feature = "tokio")))]
+    Unix(Async<UnixListener>),
+    #[cfg(all(unix, feature = "tokio"))]
+    Unix(UnixListener),
+    #[cfg(not(feature = "tokio"))]
+    Tcp(Async<TcpListener>),
+    #[cfg(feature = "tokio")]
+    Tcp(TcpListener),
+    #[cfg(all(feature = "vsock", not(feature = "tokio")))]
+    Vsock(Async<VsockListener>),
+    #[cfg(feature = "tokio-vsock")]
+    Vsock(VsockListener),
+}
+
+impl Listener {
+    /// Bind the given listenable address.
+    ///
+    /// The GUID of the address, if any, is ignored and a new one is generated.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Unsupported`] if the address can't be listened on, e.g. `unixexec:` addresses, or
+    /// any I/O error from binding the socket.
+    pub async fn bind<A>(address: A) -> Result<Self>
+    where
+        A: TryInto<Address>,
+        A::Error: Into<Error>,
+    {
+        let address = address.try_into().map_err(Into::into)?;
+        let guid = OwnedGuid::from(Guid::generate());
+
+        let (socket, transport, nonce_file) = match address.transport().clone() {
+            #[cfg(unix)]
+            Transport::Unix(unix) => {
+                let (socket, path) = bind_unix(unix.take_path())?;
+
+                (socket, Transport::Unix(Unix::new(path)), None)
+            }
+            Transport::Tcp(tcp) => bind_tcp(tcp).await?,
+            #[cfg(any(
+                all(feature = "vsock", not(feature = "tokio")),
+                feature = "tokio-vsock"
+            ))]
+            Transport::Vsock(vsock) => {
+                let (socket, port) = bind_vsock(vsock.cid(), vsock.port())?;
+                let transport =
+                    Transport::Vsock(crate::address::transport::Vsock::new(vsock.cid(), port));
+
+                (socket, transport, None)
+            }
+            #[cfg(target_os = "linux")]
+            Transport::Systemd(systemd) => {
+                let (socket, transport) = adopt_systemd(systemd.name())?;
+
+                (socket, transport, None)
+            }
+            _ => return Err(Error::Unsupported),
+        };
+        // The socket files of sockets passed by systemd are managed by systemd.
+        #[cfg(target_os = "linux")]
+        let created = !matches!(address.transport(), Transport::Systemd(_));
+        #[cfg(all(unix, not(target_os = "linux")))]
+        let created = true;
+        #[cfg(unix)]
+        let socket_path = match &transport {
+            Transport::Unix(unix) if created => match unix.path() {
+                UnixSocket::File(path) => Some(path.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let address = Address::new(transport).set_guid(guid.clone())?;
+        debug!("Listening on `{address}`");
+
+        Ok(Self {
+            socket,
+            address,
+            guid,
+            nonce_file,
+            #[cfg(unix)]
+            socket_path,
+        })
+    }
+
+    /// Use a listening socket passed through systemd socket activation.
+    ///
+    /// If `name` is given, the socket of that name (as set by `FileDescriptorName=` in the socket
+    /// unit) is used. Otherwise, the first listening socket that was passed and isn't used yet is.
+    /// This is the same as binding a `systemd:` address.
+    ///
+    /// This method is only available on Linux.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Address`] if no suitable socket was passed, or [`Error::Unsupported`] if the socket
+    /// is of a family that isn't supported with the enabled features.
+    #[cfg(target_os = "linux")]
+    pub async fn systemd_socket(name: Option<&str>) -> Result<Self> {
+        let transport = Transport::Systemd(crate::address::transport::Systemd::new(name));
+
+        Self::bind(Address::new(transport)).await
+    }
+
+    /// The address to connect to this listener.
+    ///
+    /// Unlike the address given to [`Listener::bind`], this is always a connectable address (e.g
+    /// `unix:dir=` is resolved to the actual `unix:path=`) and it includes the GUID.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// The GUID of the listener, and all the connections it accepts.
+    pub fn guid(&self) -> &OwnedGuid {
+        &self.guid
+    }
+
+    /// Accept a new peer, and perform the authentication handshake.
+    pub async fn accept(&self) -> Result<Connection> {
+        self.accept_builder().await?.build().await
+    }
+
+    /// Accept a new peer, without performing the authentication handshake.
+    ///
+    /// The returned builder is already set up for the server side of a p2p connection. This allows
+    /// customizing the connection before it's built, e.g. to serve objects right away. For
+    /// `nonce-tcp` listeners, the nonce sent by the peer is already verified.
+    pub async fn accept_builder(&self) -> Result<connection::Builder<'static>> {
+        self.accept_peer().await?.await
+    }
+
+    // Accept a new peer. The returned future completes the part of the accepting that depends on
+    // the peer (i.e. receiving the nonce), so that a slow peer doesn't hold back the others.
+    async fn accept_peer(&self) -> Result<Peer> {
+        let guid = self.guid.clone();
+        let server = move |builder: connection::Builder<'static>| {
+            builder.server(guid).map(connection::Builder::p2p)
+        };
+
+        let peer: BoxFuture<'static, _> = match &self.socket {
+            #[cfg(all(unix, not(feature = "tokio")))]
+            Socket::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                let builder = connection::Builder::unix_stream(stream.into_inner()?);
+
+                Box::pin(std::future::ready(server(builder)))
+            }
+            #[cfg(all(unix, feature = "tokio"))]
+            Socket::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                let builder = connection::Builder::unix_stream(stream);
+
+                Box::pin(std::future::ready(server(builder)))
+            }
+            #[cfg(not(feature = "tokio"))]
+            Socket::Tcp(listener) => {
+                #[allow(unused_mut)]
+                let (mut stream, addr) = listener.accept().await?;
+                trace!("Accepted TCP connection from {addr}");
+                let nonce = self.nonce_file.as_ref().map(|f| f.nonce);
+
+                Box::pin(async move {
+                    if let Some(nonce) = nonce {
+                        let mut received = [0; NONCE_LEN];
+                        let read =
+                            futures_lite::AsyncReadExt::read_exact(&mut stream, &mut received);
+                        timeout(async { read.await.map_err(Into::into) }, NONCE_TIMEOUT).await?;
+                        verify_nonce(&nonce, &received)?;
+                    }
+
+                    server(connection::Builder::tcp_stream(stream.into_inner()?))
+                })
+            }
+            #[cfg(feature = "tokio")]
+            Socket::Tcp(listener) => {
+                let (mut stream, addr) = listener.accept().await?;
+                trace!("Accepted TCP connection from {addr}");
+                let nonce = self.nonce_file.as_ref().map(|f| f.nonce);
+
+                Box::pin(async move {
+                    if let Some(nonce) = nonce {
+                        let mut received = [0; NONCE_LEN];
+                        let read = tokio::io::AsyncReadExt::read_exact(&mut stream, &mut received);
+                        timeout(async { read.await.map_err(Into::into) }, NONCE_TIMEOUT).await?;
+                        verify_nonce(&nonce, &received)?;
+                    }
+
+                    server(connection::Builder::tcp_stream(stream))
+                })
+            }
+            #[cfg(all(feature = "vsock", not(feature = "tokio")))]
+            Socket::Vsock(listener) => {
+                let (stream, _) = listener.read_with(|l| l.accept()).await?;
+                let builder = connection::Builder::vsock_stream(stream);
+
+                Box::pin(std::future::ready(server(builder)))
+            }
+            #[cfg(feature = "tokio-vsock")]
+            Socket::Vsock(listener) => {
+                let (stream, _) = listener.accept().await?;
+                let builder = connection::Builder::vsock_stream(stream);
+
+                Box::pin(std::future::ready(server(builder)))
+            }
+        };
+
+        Ok(peer)
+    }
+
+    /// A stream of the accepted connections.
+    ///
+    /// The handshakes of the peers are performed concurrently, so a slow peer doesn't hold back
+    /// the others. Failures to accept or authenticate a peer are yielded as errors, after which
+    /// the stream carries on accepting peers.
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming {
+            listener: self,
+            accept: None,
+            handshakes: Vec::new(),
+        }
+    }
+}
+
+impl Debug for Listener {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Listener")
+            .field("socket", &self.socket)
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Some(path) = &self.socket_path {
+            if let Err(e) = std::fs::remove_file(path) {
+                debug!("Failed to remove socket file `{}`: {e}", path.display());
+            }
+        }
+    }
+}
+
+/// The nonce file of a `nonce-tcp` server.
+///
+/// A `nonce-tcp` server writes a random nonce to a file, which only the peers allowed to read the
+/// file can then send back as the first thing after connecting. [`Listener`] takes care of this for
+/// `nonce-tcp` addresses, and this type allows doing the same with a hand-rolled server:
+///
+/// ```no_run
+/// use std::net::TcpListener;
+/// use zbus::{connection::Builder, listener::NonceFile, Guid};
+///
+/// # zbus::block_on(async {
+/// let listener = TcpListener::bind("127.0.0.1:4242")?;
+/// let nonce_file = NonceFile::create("/run/user/1000/my-service/nonce")?;
+/// // Share `nonce-tcp:host=127.0.0.1,port=4242,noncefile=/run/user/1000/my-service/nonce`.
+///
+/// let (mut stream, _) = listener.accept()?;
+/// let mut nonce = [0; NonceFile::NONCE_LEN];
+/// std::io::Read::read_exact(&mut stream, &mut nonce)?;
+/// nonce_file.verify(&nonce)?;
+/// let conn = Builder::tcp_stream(stream)
+///     .server(Guid::generate())?
+///     .p2p()
+///     .build()
+///     .await?;
+/// # drop(conn);
+/// # Ok::<(), zbus::Error>(())
+/// # }).unwrap();
+/// ```
+///
+/// The file is removed when the `NonceFile` is dropped.
+pub struct NonceFile {
+    path: PathBuf,
+    nonce: [u8; NONCE_LEN],
+}
+
+impl NonceFile {
+    /// The length of the nonce, in bytes.
+    pub const NONCE_LEN: usize = NONCE_LEN;
+
+    /// Generate a new nonce, and write it to a new file at `path`.
+    ///
+    /// On Unix, the file is only readable and writable by its owner. The nonce is first written
+    /// to a temporary file in the same directory, which is then renamed to `path`, so peers never
+    /// read a partially written nonce. Any existing file at `path` is replaced.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| Error::Address("nonce file path has no file name".to_owned()))?;
+        let mut tmp_name = file_name.to_owned();
+        tmp_name.push(format!(".{}", &Guid::generate().as_str()[..10]));
+        let tmp_path = path.with_file_name(tmp_name);
+
+        let mut nonce = [0; 16];
+        getrandom::fill(&mut nonce)
+            .map_err(|e| Error::Failure(format!("Failed to generate the nonce: {e}")))?;
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let res = options
+            .open(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(&nonce)?;
+                file.sync_all()
+            })
+            .and_then(|_| std::fs::rename(&tmp_path, path));
+        if let Err(e) = res {
+            let _ = std::fs::remove_file(&tmp_path);
+
+            return Err(e.into());
+        }
+        trace!("Created nonce file `{}`", path.display());
+
+        Ok(Self {
+            path: path.to_owned(),
+            nonce,
+        })
+    }
+
+    /// The path of the nonce file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check the nonce sent by a peer.
+    ///
+    /// The comparison takes the same time no matter where the nonces differ.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Handshake`] if `received` is not the nonce.
+    pub fn verify(&self, received: &[u8]) -> Result<()> {
+        verify_nonce(&self.nonce, received)
+    }
+}
+
+impl Debug for NonceFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Leave out the nonce, which is a secret.
+        f.debug_struct("NonceFile")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for NonceFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            debug!("Failed to remove nonce file `{}`: {e}", self.path.display());
+        }
+    }
+}
+
+type BoxFuture<'f, T> = Pin<Box<dyn Future<Output = T> + Send + 'f>>;
+type Peer = BoxFuture<'static, Result<connection::Builder<'static>>>;
+
+/// A [`stream::Stream`] of the connections accepted by a [`Listener`].
+///
+/// Use [`Listener::incoming`] to create an instance of this type.
+///
+/// [`stream::Stream`]: futures_core::stream::Stream
+#[must_use = "streams do nothing unless polled"]
+pub struct Incoming<'l> {
+    listener: &'l Listener,
+    accept: Option<BoxFuture<'l, Result<Peer>>>,
+    handshakes: Vec<BoxFuture<'static, Result<Connection>>>,
+}
+
+impl Debug for Incoming<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Incoming")
+            .field("listener", &self.listener)
+            .field("handshakes", &self.handshakes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Stream for Incoming<'_> {
+    type Item = Result<Connection>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let listener = this.listener;
+            let accept = this
+                .accept
+                .get_or_insert_with(|| Box::pin(listener.accept_peer()));
+            match accept.as_mut().poll(cx) {
+                Poll::Ready(Ok(peer)) => {
+                    this.accept = None;
+                    this.handshakes
+                        .push(Box::pin(async move { peer.await?.build().await }));
+                }
+                Poll::Ready(Err(e)) => {
+                    this.accept = None;
+
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Pending => break,
+            }
+        }
+
+        for i in 0..this.handshakes.len() {
+            if let Poll::Ready(res) = this.handshakes[i].as_mut().poll(cx) {
+                drop(this.handshakes.swap_remove(i));
+
+                return Poll::Ready(Some(res));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(unix)]
+fn bind_unix(path: UnixSocket) -> Result<(Socket, UnixSocket)> {
+    #[cfg(target_os = "linux")]
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::{SocketAddr, UnixListener as StdUnixListener};
+
+    let random_name = || format!("dbus-{}", &Guid::generate().as_str()[..10]);
+    let path = match path {
+        #[cfg(target_os = "linux")]
+        UnixSocket::TmpDir(dir) => {
+            let mut name = dir.into_os_string();
+            name.push("/");
+            name.push(random_name());
+
+            UnixSocket::Abstract(name)
+        }
+        #[cfg(not(target_os = "linux"))]
+        UnixSocket::TmpDir(dir) => UnixSocket::File(dir.join(random_name())),
+        UnixSocket::Dir(dir) => UnixSocket::File(dir.join(random_name())),
+        path => path,
+    };
+    let addr = match &path {
+        UnixSocket::File(path) => SocketAddr::from_pathname(path)?,
+        #[cfg(target_os = "linux")]
+        UnixSocket::Abstract(name) => SocketAddr::from_abstract_name(name.as_encoded_bytes())?,
+        _ => return Err(Error::Unsupported),
+    };
+    let listener = StdUnixListener::bind_addr(&addr)?;
+
+    Ok((unix_socket(listener)?, path))
+}
+
+#[cfg(unix)]
+fn unix_socket(listener: std::os::unix::net::UnixListener) -> Result<Socket> {
+    listener.set_nonblocking(true)?;
+    #[cfg(not(feature = "tokio"))]
+    let listener = Async::new(listener)?;
+    #[cfg(feature = "tokio")]
+    let listener = UnixListener::from_std(listener)?;
+
+    Ok(Socket::Unix(listener))
+}
+
+async fn bind_tcp(mut tcp: Tcp) -> Result<(Socket, Transport, Option<NonceFile>)> {
+    let nonce_file = tcp.take_nonce_file();
+    let family = tcp.family();
+    let addrs = tcp.resolve().await?;
+
+    let mut last_err = Error::Address("no address to bind".into());
+    let mut bound = None;
+    for addr in addrs {
+        match std::net::TcpListener::bind(addr) {
+            Ok(listener) => {
+                bound = Some(listener);
+                break;
+            }
+            Err(e) => last_err = e.into(),
+        }
+    }
+    let listener = bound.ok_or(last_err)?;
+    let port = listener.local_addr()?.port();
+    let socket = tcp_socket(listener)?;
+
+    let nonce = nonce_file
+        .as_deref()
+        .map(|path| NonceFile::create(nonce_file_path(path)?))
+        .transpose()?;
+    let transport = Tcp::new(tcp.host(), port)
+        .set_family(family)
+        .set_nonce_file(nonce_file);
+
+    Ok((socket, Transport::Tcp(transport), nonce))
+}
+
+fn tcp_socket(listener: std::net::TcpListener) -> Result<Socket> {
+    listener.set_nonblocking(true)?;
+    #[cfg(not(feature = "tokio"))]
+    let listener = Async::new(listener)?;
+    #[cfg(feature = "tokio")]
+    let listener = TcpListener::from_std(listener)?;
+
+    Ok(Socket::Tcp(listener))
+}
+
+fn nonce_file_path(path: &[u8]) -> Result<PathBuf> {
+    #[cfg(unix)]
+    let path = {
+        use std::os::unix::ffi::OsStrExt;
+        std::ffi::OsStr::from_bytes(path)
+    };
+    #[cfg(windows)]
+    let path = std::str::from_utf8(path)
+        .map_err(|_| Error::Address("nonce file path is invalid UTF-8".to_owned()))?;
+
+    Ok(PathBuf::from(path))
+}
+
+// Compare in constant time, so the nonce can't be guessed byte by byte from the timing.
+fn verify_nonce(expected: &[u8], received: &[u8]) -> Result<()> {
+    let diff = expected
+        .iter()
+        .zip(received)
+        .fold(0, |diff, (e, r)| diff | (e ^ r));
+    if diff != 0 || expected.len() != received.len() {
+        return Err(Error::Handshake("Invalid nonce".into()));
+    }
+
+    Ok(())
+}
+
+#[cfg(any(
+    all(feature = "vsock", not(feature = "tokio")),
+    feature = "tokio-vsock"
+))]
+fn bind_vsock(cid: u32, port: u32) -> Result<(Socket, u32)> {
+    #[cfg(not(feature = "tokio"))]
+    let listener = Async::new(VsockListener::bind_with_cid_port(cid, port)?)?;
+    #[cfg(feature = "tokio-vsock")]
+    let listener = VsockListener::bind(tokio_vsock::VsockAddr::new(cid, port))?;
+    #[cfg(not(feature = "tokio"))]
+    let port = listener.get_ref().local_addr()?.port();
+    #[cfg(feature = "tokio-vsock")]
+    let port = listener.local_addr()?.port();
+
+    Ok((Socket::Vsock(listener), port))
+}
+
+// Adopt a listening socket passed by systemd, and figure out the address to connect to it.
+#[cfg(target_os = "linux")]
+fn adopt_systemd(name: Option<&str>) -> Result<(Socket, Transport)> {
+    use crate::address::transport::{
+        systemd::{take_fd, SocketKind},
+        TcpTransportFamily,
+    };
+    use rustix::net::AddressFamily;
+    use std::{
+        ffi::OsStr,
+        net::{Ipv4Addr, Ipv6Addr},
+        os::{linux::net::SocketAddrExt, unix::ffi::OsStrExt},
+    };
+
+    let (fd, family) = take_fd(name, SocketKind::Listening)?;
+    match family {
+        AddressFamily::UNIX => {
+            let listener = std::os::unix::net::UnixListener::from(fd);
+            let addr = listener.local_addr()?;
+            let path = match (addr.as_pathname(), addr.as_abstract_name()) {
+                (Some(path), _) => UnixSocket::File(path.to_owned()),
+                (_, Some(name)) => UnixSocket::Abstract(OsStr::from_bytes(name).to_owned()),
+                _ => return Err(Error::Address("systemd passed an unnamed socket".into())),
+            };
+
+            Ok((unix_socket(listener)?, Transport::Unix(Unix::new(path))))
+        }
+        AddressFamily::INET | AddressFamily::INET6 => {
+            let listener = std::net::TcpListener::from(fd);
+            let addr = listener.local_addr()?;
+            // Peers can't connect to the wildcard address, so point them to the loopback one.
+            let (ip, family) = match addr.ip() {
+                std::net::IpAddr::V4(ip) if ip.is_unspecified() => {
+                    (Ipv4Addr::LOCALHOST.into(), TcpTransportFamily::Ipv4)
+                }
+                std::net::IpAddr::V6(ip) if ip.is_unspecified() => {
+                    (Ipv6Addr::LOCALHOST.into(), TcpTransportFamily::Ipv6)
+                }
+                ip @ std::net::IpAddr::V4(_) => (ip, TcpTransportFamily::Ipv4),
+                ip @ std::net::IpAddr::V6(_) => (ip, TcpTransportFamily::Ipv6),
+            };
+            let transport = Tcp::new(&ip.to_string(), addr.port()).set_family(Some(family));
+
+            Ok((tcp_socket(listener)?, Transport::Tcp(transport)))
+        }
+        #[cfg(any(
+            all(feature = "vsock", not(feature = "tokio")),
+            feature = "tokio-vsock"
+        ))]
+        AddressFamily::VSOCK => {
+            use std::os::fd::{FromRawFd, IntoRawFd};
+
+            // SAFETY: We own the fd.
+            let listener = unsafe { VsockListener::from_raw_fd(fd.into_raw_fd()) };
+            #[cfg(not(feature = "tokio"))]
+            let listener = Async::new(listener)?;
+            #[cfg(not(feature = "tokio"))]
+            let addr = listener.get_ref().local_addr()?;
+            #[cfg(feature = "tokio-vsock")]
+            let addr = listener.local_addr()?;
+            let transport = Transport::Vsock(crate::address::transport::Vsock::new(
+                addr.cid(),
+                addr.port(),
+            ));
+
+            Ok((Socket::Vsock(listener), transport))
+        }
+        _ => Err(Error::Unsupported),
+    }
+}
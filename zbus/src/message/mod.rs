@@ -35,6 +35,11 @@ pub struct Sequence {
 impl Sequence {
     /// A sequence number that is higher than any other; used by errors that terminate a stream.
     pub(crate) const LAST: Self = Self { recv_seq: u64::MAX };
+
+    /// The raw sequence number.
+    pub(crate) fn get(self) -> u64 {
+        self.recv_seq
+    }
 }
 
 /// A D-Bus Message.
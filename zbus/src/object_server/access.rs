@@ -0,0 +1,262 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use async_trait::async_trait;
+use tracing::{debug, trace};
+use zbus_names::{InterfaceName, MemberName, OwnedUniqueName};
+use zvariant::{ObjectPath, OwnedObjectPath};
+
+use crate::{
+    fdo::{self, ConnectionCredentials},
+    message::Header,
+    Connection, OwnedGuid,
+};
+
+// The maximum number of callers whose credentials are cached.
+const MAX_CACHED_CREDENTIALS: usize = 256;
+
+/// A policy deciding which method calls the [`ObjectServer`] dispatches.
+///
+/// Set one through [`ObjectServer::set_access_policy`] to authorize the calls to the objects of a
+/// subtree, based on the credentials of the caller and the target of the call. Calls that aren't
+/// allowed are replied to with the returned error, which should typically be
+/// [`fdo::Error::AccessDenied`], without reaching the interface.
+///
+/// This is implemented for closures with the signature of [`AccessPolicy::check`], minus the
+/// `async`, for the policies that don't need to await anything.
+///
+/// # Example
+///
+/// ```no_run
+/// use zbus::{connection::Builder, fdo, object_server::AccessRequest};
+///
+/// # zbus::block_on(async {
+/// let uid = 1000;
+/// let conn = Builder::system()?.build().await?;
+/// conn.object_server()
+///     .set_access_policy("/org/example/Admin", move |req: &AccessRequest<'_>| {
+///         if req.credentials().unix_user_id() == Some(uid) || req.member() == "Status" {
+///             Ok(())
+///         } else {
+///             Err(fdo::Error::AccessDenied(format!("`{}` not allowed", req.member())))
+///         }
+///     })?;
+/// # Ok::<_, zbus::Error>(())
+/// # }).unwrap();
+/// ```
+///
+/// [`ObjectServer`]: super::ObjectServer
+/// [`ObjectServer::set_access_policy`]: super::ObjectServer::set_access_policy
+#[async_trait]
+pub trait AccessPolicy: Send + Sync + 'static {
+    /// Allow the method call described by `request` by returning `Ok(())`, or deny it by
+    /// returning the error to reply with.
+    async fn check(&self, request: &AccessRequest<'_>) -> fdo::Result<()>;
+}
+
+#[async_trait]
+impl<F> AccessPolicy for F
+where
+    F: Fn(&AccessRequest<'_>) -> fdo::Result<()> + Send + Sync + 'static,
+{
+    async fn check(&self, request: &AccessRequest<'_>) -> fdo::Result<()> {
+        self(request)
+    }
+}
+
+/// A method call to be authorized by an [`AccessPolicy`].
+#[derive(Debug)]
+pub struct AccessRequest<'a> {
+    connection: &'a Connection,
+    header: &'a Header<'a>,
+    credentials: &'a ConnectionCredentials,
+    path: &'a ObjectPath<'a>,
+    interface: &'a InterfaceName<'a>,
+    member: &'a MemberName<'a>,
+}
+
+impl<'a> AccessRequest<'a> {
+    /// The connection the call was received on.
+    pub fn connection(&self) -> &'a Connection {
+        self.connection
+    }
+
+    /// The header of the call.
+    pub fn header(&self) -> &'a Header<'a> {
+        self.header
+    }
+
+    /// The credentials of the caller.
+    ///
+    /// On a bus, these are the credentials of the sender of the call, as reported by the bus.
+    /// Otherwise, these are the credentials of the peer.
+    pub fn credentials(&self) -> &'a ConnectionCredentials {
+        self.credentials
+    }
+
+    /// The path of the called object.
+    pub fn path(&self) -> &'a ObjectPath<'a> {
+        self.path
+    }
+
+    /// The called interface.
+    pub fn interface(&self) -> &'a InterfaceName<'a> {
+        self.interface
+    }
+
+    /// The called method.
+    pub fn member(&self) -> &'a MemberName<'a> {
+        self.member
+    }
+}
+
+// A type-erased access policy.
+#[derive(Clone)]
+pub(crate) struct ArcAccessPolicy(Arc<dyn AccessPolicy>);
+
+impl ArcAccessPolicy {
+    pub(crate) fn new<A: AccessPolicy>(policy: A) -> Self {
+        Self(Arc::new(policy))
+    }
+}
+
+impl fmt::Debug for ArcAccessPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArcAccessPolicy").finish_non_exhaustive()
+    }
+}
+
+// The access policies of an object server, along with the credentials of the recent callers.
+#[derive(Debug, Default)]
+pub(super) struct AccessControl {
+    policies: RwLock<HashMap<OwnedObjectPath, ArcAccessPolicy>>,
+    credentials: Mutex<CredentialsCache>,
+}
+
+#[derive(Debug, Default)]
+struct CredentialsCache {
+    // The unique names are only unique for a given bus instance.
+    server_guid: Option<OwnedGuid>,
+    callers: HashMap<OwnedUniqueName, Arc<ConnectionCredentials>>,
+}
+
+impl AccessControl {
+    pub(super) fn set_policy(&self, path: OwnedObjectPath, policy: ArcAccessPolicy) {
+        self.policies
+            .write()
+            .expect("poisoned lock")
+            .insert(path, policy);
+    }
+
+    pub(super) fn remove_policy(&self, path: &ObjectPath<'_>) -> bool {
+        self.policies
+            .write()
+            .expect("poisoned lock")
+            .remove(path)
+            .is_some()
+    }
+
+    // The policy of the closest ancestor of `path` (including itself) that has one.
+    fn policy(&self, path: &ObjectPath<'_>) -> Option<ArcAccessPolicy> {
+        let policies = self.policies.read().expect("poisoned lock");
+        if policies.is_empty() {
+            return None;
+        }
+
+        let mut path = path.as_str();
+        loop {
+            if let Some(policy) = policies.get(&ObjectPath::from_str_unchecked(path)) {
+                return Some(policy.clone());
+            }
+            path = match path.rfind('/') {
+                Some(0) if path.len() > 1 => "/",
+                Some(i) if i > 0 => &path[..i],
+                _ => return None,
+            };
+        }
+    }
+
+    // Check the method call with the given header against the policy of its target, if any.
+    pub(super) async fn check(&self, connection: &Connection, hdr: &Header<'_>) -> fdo::Result<()> {
+        let (Some(path), Some(interface), Some(member)) =
+            (hdr.path(), hdr.interface(), hdr.member())
+        else {
+            return Ok(());
+        };
+        let Some(policy) = self.policy(path) else {
+            return Ok(());
+        };
+        let credentials = self.credentials(connection, hdr).await.map_err(|e| {
+            debug!("Failed to get the credentials of the caller: {e}");
+
+            fdo::Error::AccessDenied("Failed to get the credentials of the caller".into())
+        })?;
+        let request = AccessRequest {
+            connection,
+            header: hdr,
+            credentials: &credentials,
+            path,
+            interface,
+            member,
+        };
+        let res = policy.0.check(&request).await;
+        if let Err(e) = &res {
+            trace!("Call to `{interface}.{member}` at `{path}` denied: {e}");
+        }
+
+        res
+    }
+
+    async fn credentials(
+        &self,
+        connection: &Connection,
+        hdr: &Header<'_>,
+    ) -> crate::Result<Arc<ConnectionCredentials>> {
+        if !connection.is_bus() {
+            return Ok(connection.peer_creds().await?.clone());
+        }
+
+        let sender = hdr.sender().ok_or(crate::Error::MissingField)?;
+        let server_guid = connection.server_guid();
+        {
+            let mut cache = self.credentials.lock().expect("poisoned lock");
+            if cache.server_guid.as_ref() != Some(server_guid) {
+                cache.server_guid = Some(server_guid.clone());
+                cache.callers.clear();
+            }
+            if let Some(credentials) = cache.callers.get(sender.as_str()) {
+                return Ok(credentials.clone());
+            }
+        }
+
+        let credentials: ConnectionCredentials = connection
+            .call_method(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                Some("org.freedesktop.DBus"),
+                "GetConnectionCredentials",
+                sender,
+            )
+            .await?
+            .body()
+            .deserialize()?;
+        let credentials = Arc::new(credentials);
+
+        let mut cache = self.credentials.lock().expect("poisoned lock");
+        if cache.callers.len() >= MAX_CACHED_CREDENTIALS {
+            // Unique names aren't reused, so an arbitrary entry is as good as any.
+            let name = cache.callers.keys().next().cloned();
+            if let Some(name) = name {
+                cache.callers.remove(&name);
+            }
+        }
+        cache
+            .callers
+            .insert(sender.to_owned().into(), credentials.clone());
+
+        Ok(credentials)
+    }
+}
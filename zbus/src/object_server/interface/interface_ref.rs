@@ -1,6 +1,6 @@
 use std::{marker::PhantomData, sync::Arc};
 
-use super::{Interface, InterfaceDeref, InterfaceDerefMut, SignalEmitter};
+use super::{Interface, InterfaceDeref, InterfaceDerefMut, PropertiesTransaction, SignalEmitter};
 use crate::async_lock::RwLock;
 
 /// Wrapper over an interface, along with its corresponding `SignalEmitter`
@@ -105,6 +105,82 @@ where
     }
 }
 
+impl<I> InterfaceRef<I>
+where
+    I: Interface,
+{
+    /// Start a batch of property changes.
+    ///
+    /// All the changes recorded in the returned transaction are emitted in a single
+    /// `PropertiesChanged` signal when it's committed, instead of one signal per property. See
+    /// [`PropertiesTransaction`] for details.
+    ///
+    /// **WARNING:** The same caveats as for [`InterfaceRef::get_mut`] apply, since the
+    /// transaction holds the interface lock until it's committed or dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # use zbus::{Connection, interface};
+    /// struct Player {
+    ///     title: String,
+    ///     position: u64,
+    ///     metadata: Vec<String>,
+    /// }
+    ///
+    /// #[interface(name = "org.zbus.Player")]
+    /// impl Player {
+    ///     #[zbus(property)]
+    ///     fn title(&self) -> &str {
+    ///         &self.title
+    ///     }
+    ///
+    ///     #[zbus(property)]
+    ///     fn position(&self) -> u64 {
+    ///         self.position
+    ///     }
+    ///
+    ///     #[zbus(property(emits_changed_signal = "invalidates"))]
+    ///     fn metadata(&self) -> Vec<String> {
+    ///         self.metadata.clone()
+    ///     }
+    /// }
+    ///
+    /// # zbus::block_on(async {
+    /// # let connection = Connection::session().await?;
+    /// # let path = "/org/zbus/Player";
+    /// # let player = Player { title: String::new(), position: 0, metadata: vec![] };
+    /// # connection.object_server().at(path, player).await?;
+    /// let iface_ref = connection
+    ///     .object_server()
+    ///     .interface::<_, Player>(path)
+    ///     .await?;
+    /// let mut transaction = iface_ref.properties_transaction().await;
+    /// transaction.title = "Hey Jude".into();
+    /// transaction.position = 0;
+    /// transaction.metadata = vec!["The Beatles".into()];
+    /// transaction
+    ///     .changed("Title")?
+    ///     .changed("Position")?
+    ///     .invalidated("Metadata")?;
+    /// // A single signal for all three properties.
+    /// transaction.commit().await?;
+    /// # Ok::<_, Box<dyn Error + Send + Sync>>(())
+    /// # })?;
+    /// #
+    /// # Ok::<_, Box<dyn Error + Send + Sync>>(())
+    /// ```
+    pub async fn properties_transaction(&self) -> PropertiesTransaction<'_, I> {
+        PropertiesTransaction {
+            iface: self.get_mut().await,
+            emitter: &self.emitter,
+            changed: vec![],
+            invalidated: vec![],
+        }
+    }
+}
+
 impl<I> Clone for InterfaceRef<I> {
     fn clone(&self) -> Self {
         Self {
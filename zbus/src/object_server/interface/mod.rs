@@ -4,6 +4,8 @@ mod interface_ref;
 pub use interface_ref::*;
 mod interface_deref;
 pub use interface_deref::*;
+mod properties_transaction;
+pub use properties_transaction::*;
 
 use std::{
     any::{Any, TypeId},
@@ -47,6 +49,42 @@ pub trait Interface: Any + Send + Sync {
         true
     }
 
+    /// The polkit action the caller of the method `name` must be authorized for, if any.
+    ///
+    /// The object server checks the authorization through [`crate::polkit::check_authorization`]
+    /// before dispatching the call, without holding any lock on the interface.
+    fn polkit_action(name: &str) -> Option<&'static str>
+    where
+        Self: Sized,
+    {
+        let _ = name;
+
+        None
+    }
+
+    /// Whether the method `name` takes the trace context of the caller as its last argument.
+    ///
+    /// See [`crate::connection::TracePropagator`] for details.
+    fn has_trace_context(name: &str) -> bool
+    where
+        Self: Sized,
+    {
+        let _ = name;
+
+        false
+    }
+
+    /// How changes of the property `name` are announced. Returns `None` if the property doesn't
+    /// exist.
+    fn property_emits_changed_signal(name: &str) -> Option<PropertyEmitsChangedSignal>
+    where
+        Self: Sized,
+    {
+        let _ = name;
+
+        None
+    }
+
     /// Get a property value. Returns `None` if the property doesn't exist.
     ///
     /// Note: The header parameter will be None when the getter is not being called as part
@@ -146,6 +184,8 @@ pub trait Interface: Any + Send + Sync {
 pub(crate) struct ArcInterface {
     pub instance: Arc<RwLock<dyn Interface>>,
     pub spawn_tasks_for_methods: bool,
+    pub polkit_action: fn(&str) -> Option<&'static str>,
+    pub has_trace_context: fn(&str) -> bool,
 }
 
 impl ArcInterface {
@@ -157,6 +197,8 @@ impl ArcInterface {
         Self {
             instance: Arc::new(RwLock::new(iface)),
             spawn_tasks_for_methods,
+            polkit_action: I::polkit_action,
+            has_trace_context: I::has_trace_context,
         }
     }
 }
@@ -0,0 +1,190 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
+
+use zvariant::Value;
+
+use super::{Interface, InterfaceDerefMut, SignalEmitter};
+use crate::{fdo, Error, Result};
+
+/// How changes of a property are announced, as declared by its
+/// `org.freedesktop.DBus.Property.EmitsChangedSignal` annotation.
+///
+/// See [`Interface::property_emits_changed_signal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropertyEmitsChangedSignal {
+    /// The new value is included in the `PropertiesChanged` signal. This is the default.
+    True,
+    /// Only the name of the property is included in the `PropertiesChanged` signal.
+    Invalidates,
+    /// The property never changes during the lifetime of the object.
+    Const,
+    /// No signal is emitted when the property changes.
+    False,
+}
+
+/// A batch of property changes of an interface, emitted as a single `PropertiesChanged` signal.
+///
+/// Use [`InterfaceRef::properties_transaction`] to create one. The transaction mutably derefs to
+/// the interface, so properties can be updated through it, while the names of the modified
+/// properties are recorded through [`PropertiesTransaction::changed`] and
+/// [`PropertiesTransaction::invalidated`]. Once done, [`PropertiesTransaction::commit`] emits a
+/// single `org.freedesktop.DBus.Properties.PropertiesChanged` signal for all of them.
+///
+/// The current values of the changed properties are fetched through their getters on commit, so
+/// it doesn't matter whether a property is marked as changed before or after it's updated.
+///
+/// The `emits_changed_signal` annotation of each property is honored: properties marked as
+/// changed with `emits_changed_signal = "invalidates"` are only invalidated, while the `const` and
+/// `false` ones, as well as unknown properties, are rejected right away.
+///
+/// Just like [`InterfaceRef::get_mut`], the transaction holds the interface lock until it's
+/// committed or dropped. Dropping a transaction without committing it doesn't emit any signal.
+///
+/// [`InterfaceRef::properties_transaction`]: super::InterfaceRef::properties_transaction
+/// [`InterfaceRef::get_mut`]: super::InterfaceRef::get_mut
+pub struct PropertiesTransaction<'t, I> {
+    pub(super) iface: InterfaceDerefMut<'t, I>,
+    pub(super) emitter: &'t SignalEmitter<'static>,
+    pub(super) changed: Vec<String>,
+    pub(super) invalidated: Vec<String>,
+}
+
+impl<I> PropertiesTransaction<'_, I>
+where
+    I: Interface,
+{
+    /// Mark the property `name` as changed.
+    ///
+    /// Its new value will be included in the signal, unless the property is annotated with
+    /// `emits_changed_signal = "invalidates"`, in which case it's only marked as invalidated.
+    ///
+    /// # Errors
+    ///
+    /// [`fdo::Error::UnknownProperty`] if the interface has no such property, or
+    /// [`Error::Failure`] if the property is annotated with `emits_changed_signal = "const"` or
+    /// `"false"`.
+    pub fn changed(&mut self, name: &str) -> Result<&mut Self> {
+        match emits_changed_signal::<I>(name)? {
+            PropertyEmitsChangedSignal::True => (),
+            PropertyEmitsChangedSignal::Invalidates => return self.invalidated(name),
+            PropertyEmitsChangedSignal::Const | PropertyEmitsChangedSignal::False => {
+                return Err(no_changed_signal(name))
+            }
+        }
+        self.invalidated.retain(|n| n != name);
+        if !self.changed.iter().any(|n| n == name) {
+            self.changed.push(name.to_string());
+        }
+
+        Ok(self)
+    }
+
+    /// Mark the property `name` as invalidated.
+    ///
+    /// Only its name will be included in the signal, not its value. If the property is also
+    /// marked as changed, this is a no-op.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`PropertiesTransaction::changed`].
+    pub fn invalidated(&mut self, name: &str) -> Result<&mut Self> {
+        match emits_changed_signal::<I>(name)? {
+            PropertyEmitsChangedSignal::True | PropertyEmitsChangedSignal::Invalidates => (),
+            PropertyEmitsChangedSignal::Const | PropertyEmitsChangedSignal::False => {
+                return Err(no_changed_signal(name))
+            }
+        }
+        if !self.changed.iter().any(|n| n == name) && !self.invalidated.iter().any(|n| n == name) {
+            self.invalidated.push(name.to_string());
+        }
+
+        Ok(self)
+    }
+
+    /// Release the interface and emit the `PropertiesChanged` signal.
+    ///
+    /// No signal is emitted if no property was marked as changed or invalidated.
+    ///
+    /// # Errors
+    ///
+    /// If the getter of any property marked as changed fails, an error is returned and no signal
+    /// is emitted.
+    pub async fn commit(self) -> Result<()> {
+        let Self {
+            iface,
+            emitter,
+            changed,
+            invalidated,
+        } = self;
+        if changed.is_empty() && invalidated.is_empty() {
+            return Ok(());
+        }
+
+        let connection = emitter.connection();
+        let server = connection.object_server();
+        let mut values = Vec::with_capacity(changed.len());
+        for name in &changed {
+            let value = iface
+                .iface
+                .get(name, server, connection, None, emitter)
+                .await
+                .ok_or_else(|| {
+                    fdo::Error::UnknownProperty(format!("Unknown property `{name}`"))
+                })??;
+            values.push((name.as_str(), value));
+        }
+        // Don't hold the interface while emitting the signal.
+        drop(iface);
+
+        let changed = values
+            .iter()
+            .map(|(name, value)| Ok((*name, Value::try_from(value)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        let invalidated: Vec<_> = invalidated.iter().map(String::as_str).collect();
+
+        fdo::Properties::properties_changed(
+            emitter,
+            I::name(),
+            changed,
+            Cow::Borrowed(&invalidated),
+        )
+        .await
+    }
+}
+
+fn emits_changed_signal<I>(name: &str) -> Result<PropertyEmitsChangedSignal>
+where
+    I: Interface,
+{
+    I::property_emits_changed_signal(name)
+        .ok_or_else(|| fdo::Error::UnknownProperty(format!("Unknown property `{name}`")).into())
+}
+
+fn no_changed_signal(name: &str) -> Error {
+    Error::Failure(format!(
+        "Property `{name}` is annotated as not emitting `PropertiesChanged`"
+    ))
+}
+
+impl<I> Deref for PropertiesTransaction<'_, I>
+where
+    I: Interface,
+{
+    type Target = I;
+
+    fn deref(&self) -> &I {
+        &self.iface
+    }
+}
+
+impl<I> DerefMut for PropertiesTransaction<'_, I>
+where
+    I: Interface,
+{
+    fn deref_mut(&mut self) -> &mut I {
+        &mut self.iface
+    }
+}
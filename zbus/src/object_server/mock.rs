@@ -0,0 +1,702 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+    marker::PhantomData,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tracing::debug;
+use zbus_names::{InterfaceName, MemberName, OwnedErrorName, OwnedMemberName};
+use zvariant::{DynamicType, OwnedValue, Signature, Structure, Value};
+
+use crate::{
+    fdo,
+    message::{self, Header, Message},
+    proxy::Defaults,
+    Connection, DBusError, Error, ObjectServer, Result,
+};
+
+use super::{DispatchResult, Interface, SignalEmitter};
+
+/// A mock interface, for testing D-Bus clients.
+///
+/// Instead of hand-writing a service implementing the interface of the proxy under test, a mock
+/// can be configured at runtime with the method calls it expects (through [`Expectation`]), the
+/// values of its properties, and the signals it emits. Once served, through [`ObjectServer::at`]
+/// like any other interface, it replies to the calls as configured and keeps track of them, so
+/// that they can be verified at the end of the test with [`MockInterface::verify`].
+///
+/// The name of the interface is taken from the proxy type `P`, which is typically generated by
+/// the [`macro@crate::proxy`] macro.
+///
+/// This is a cheap handle that can be cloned, all the clones referring to the same mock. Hence
+/// you typically keep a clone around, to configure and verify the mock after it's served.
+///
+/// Calls to methods without any expectation are rejected with an
+/// `org.freedesktop.DBus.Error.UnknownMethod` error, while calls to a method with expectations
+/// but none of them matching the arguments are rejected with an
+/// `org.freedesktop.DBus.Error.InvalidArgs` error. Both are reported by `verify`.
+///
+/// # Example
+///
+/// ```
+/// use zbus::{
+///     connection::Builder,
+///     fdo,
+///     object_server::{Expectation, MockInterface},
+///     proxy,
+/// };
+///
+/// #[proxy(interface = "org.zbus.MyGreeter", default_path = "/org/zbus/MyGreeter")]
+/// trait MyGreeter {
+///     fn say_hello(&self, name: &str) -> zbus::Result<String>;
+///
+///     #[zbus(property)]
+///     fn greeting_count(&self) -> zbus::Result<u32>;
+///
+///     #[zbus(signal)]
+///     fn greeted(&self, name: &str) -> zbus::Result<()>;
+/// }
+///
+/// # zbus::block_on(async {
+/// let mock = MockInterface::<MyGreeterProxy<'static>>::new();
+/// mock.expect(
+///     Expectation::new("SayHello")?
+///         .with_args(("Maria",))
+///         .returns("Hello Maria!")
+///         .emits("Greeted", ("Maria",))?
+///         .times(1),
+/// );
+/// mock.expect(
+///     Expectation::new("SayHello")?.returns_error(fdo::Error::InvalidArgs("Unknown name".into())),
+/// );
+/// mock.set_property("GreetingCount", 1u32)?;
+///
+/// let service = Builder::session()?
+///     .name("org.zbus.MyGreeterMock")?
+///     .serve_at("/org/zbus/MyGreeter", mock.clone())?
+///     .build()
+///     .await?;
+/// let client = Builder::session()?.build().await?;
+/// let proxy = MyGreeterProxy::builder(&client)
+///     .destination("org.zbus.MyGreeterMock")?
+///     .build()
+///     .await?;
+///
+/// assert_eq!(proxy.say_hello("Maria").await?, "Hello Maria!");
+/// assert!(proxy.say_hello("Joe").await.is_err());
+/// assert_eq!(proxy.greeting_count().await?, 1);
+///
+/// mock.verify()?;
+/// assert_eq!(mock.call_count("SayHello"), 2);
+/// # drop(service);
+/// # Ok::<(), zbus::Error>(())
+/// # }).unwrap();
+/// ```
+pub struct MockInterface<P> {
+    state: Arc<Mutex<State>>,
+    // `fn() -> P` so the mock is `Send` and `Sync`, regardless of `P`.
+    phantom: PhantomData<fn() -> P>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    expectations: Vec<ExpectationState>,
+    properties: HashMap<String, OwnedValue>,
+    calls: Vec<Message>,
+    unexpected: Vec<Message>,
+}
+
+#[derive(Debug)]
+struct ExpectationState {
+    expectation: Expectation,
+    count: usize,
+}
+
+impl<P> MockInterface<P>
+where
+    P: Defaults,
+{
+    /// Create a new mock, without any expectations or properties.
+    ///
+    /// # Panics
+    ///
+    /// When `P` has no default interface name.
+    pub fn new() -> Self {
+        assert!(
+            P::INTERFACE.is_some(),
+            "the proxy type of a mock must have a default interface name"
+        );
+
+        Self {
+            state: Default::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Add an expected method call.
+    ///
+    /// Calls are matched against the expectations in the order they were added. The first
+    /// expectation for the called method, whose arguments match and that isn't saturated yet (see
+    /// [`Expectation::times`]), handles the call. If all the expectations with matching arguments
+    /// are saturated, the last one handles the call and `verify` will report it.
+    pub fn expect(&self, expectation: Expectation) {
+        self.lock().expectations.push(ExpectationState {
+            expectation,
+            count: 0,
+        });
+    }
+
+    /// Set the value of a property.
+    ///
+    /// This doesn't emit the `PropertiesChanged` signal. Use [`MockInterface::change_property`]
+    /// for that.
+    ///
+    /// # Errors
+    ///
+    /// If `value` contains file descriptors that can't be duplicated.
+    pub fn set_property<V>(&self, name: &str, value: V) -> Result<()>
+    where
+        V: Into<Value<'static>>,
+    {
+        let value = OwnedValue::try_from(value.into())?;
+        self.lock().properties.insert(name.to_string(), value);
+
+        Ok(())
+    }
+
+    /// Set the value of a property, emitting the `PropertiesChanged` signal.
+    pub async fn change_property<V>(
+        &self,
+        emitter: &SignalEmitter<'_>,
+        name: &str,
+        value: V,
+    ) -> Result<()>
+    where
+        V: Into<Value<'static>>,
+    {
+        let value = value.into();
+        let changed = HashMap::from([(name, value.try_clone()?)]);
+        self.set_property(name, value)?;
+
+        fdo::Properties::properties_changed(emitter, Self::interface_name(), changed, (&[]).into())
+            .await
+    }
+
+    /// Emit a signal of the interface.
+    pub async fn emit_signal<'m, M, B>(
+        &self,
+        emitter: &SignalEmitter<'_>,
+        signal_name: M,
+        body: &B,
+    ) -> Result<()>
+    where
+        M: TryInto<MemberName<'m>>,
+        M::Error: Into<Error>,
+        B: Serialize + DynamicType,
+    {
+        emitter
+            .emit(Self::interface_name(), signal_name, body)
+            .await
+    }
+
+    /// The number of calls received for the method `method_name`.
+    pub fn call_count(&self, method_name: &str) -> usize {
+        self.lock()
+            .calls
+            .iter()
+            .filter(|m| m.header().member().map(|m| m.as_str()) == Some(method_name))
+            .count()
+    }
+
+    /// All the method calls received, in order.
+    pub fn calls(&self) -> Vec<Message> {
+        self.lock().calls.clone()
+    }
+
+    /// Verify that all the expectations were met and no unexpected call was received.
+    ///
+    /// Returns an [`Error::Failure`] describing all the problems otherwise.
+    pub fn verify(&self) -> Result<()> {
+        let state = self.lock();
+        let mut problems = vec![];
+        for exp in &state.expectations {
+            let expectation = &exp.expectation;
+            match expectation.times {
+                Some(times) if exp.count != times => problems.push(format!(
+                    "`{}` expected to be called {times} time(s), but was called {} time(s)",
+                    expectation.method, exp.count,
+                )),
+                None if exp.count == 0 => problems.push(format!(
+                    "`{}` expected to be called, but was never called",
+                    expectation.method,
+                )),
+                _ => (),
+            }
+        }
+        for msg in &state.unexpected {
+            problems.push(format!("Unexpected call: {msg}"));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Failure(problems.join("\n")))
+        }
+    }
+
+    /// Forget all the expectations, properties and calls.
+    pub fn reset(&self) {
+        *self.lock() = State::default();
+    }
+
+    fn interface_name() -> InterfaceName<'static> {
+        // SAFETY: Checked on creation.
+        P::INTERFACE.clone().unwrap()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // A panic while holding the lock can't leave the state inconsistent.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Find the expectation handling `msg`, if any, and the resulting reply and signals.
+    fn handle_call(&self, msg: &Message) -> Option<(Reply, Vec<(OwnedMemberName, ArcBody)>)> {
+        let header = msg.header();
+        let member = header.member()?;
+        let body = msg.body();
+
+        let mut state = self.lock();
+        state.calls.push(msg.clone());
+        let mut matching = state
+            .expectations
+            .iter_mut()
+            .filter(|e| e.expectation.method == *member)
+            .peekable();
+        if matching.peek().is_none() {
+            state.unexpected.push(msg.clone());
+
+            return None;
+        }
+        let mut matching = matching.filter(|e| e.expectation.matches(&body)).peekable();
+        let handler = match matching.peek() {
+            None => None,
+            Some(_) => {
+                let mut last = None;
+                let mut unsaturated = None;
+                for exp in matching {
+                    if exp.expectation.times.map_or(true, |t| exp.count < t) {
+                        unsaturated = Some(exp);
+                        break;
+                    }
+                    last = Some(exp);
+                }
+
+                unsaturated.or(last)
+            }
+        };
+        let Some(exp) = handler else {
+            state.unexpected.push(msg.clone());
+            let error = fdo::Error::InvalidArgs(format!(
+                "No expectation of the mock matches the arguments of `{member}`"
+            ));
+
+            return Some((
+                Reply::Error(error.name().into(), error.description().map(Into::into)),
+                vec![],
+            ));
+        };
+        exp.count += 1;
+
+        Some((
+            exp.expectation.reply.clone(),
+            exp.expectation.signals.clone(),
+        ))
+    }
+}
+
+impl<P> Default for MockInterface<P>
+where
+    P: Defaults,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> Clone for MockInterface<P> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<P> fmt::Debug for MockInterface<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockInterface")
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<P> Interface for MockInterface<P>
+where
+    P: Defaults + 'static,
+{
+    fn name() -> InterfaceName<'static> {
+        Self::interface_name()
+    }
+
+    fn spawn_tasks_for_methods(&self) -> bool {
+        // Handle the calls in order.
+        false
+    }
+
+    async fn get(
+        &self,
+        property_name: &str,
+        _server: &ObjectServer,
+        _connection: &Connection,
+        _header: Option<&Header<'_>>,
+        _emitter: &SignalEmitter<'_>,
+    ) -> Option<fdo::Result<OwnedValue>> {
+        let state = self.lock();
+        let value = state.properties.get(property_name)?;
+
+        Some(
+            value
+                .try_clone()
+                .map_err(|e| fdo::Error::Failed(e.to_string())),
+        )
+    }
+
+    async fn get_all(
+        &self,
+        _object_server: &ObjectServer,
+        _connection: &Connection,
+        _header: Option<&Header<'_>>,
+        _emitter: &SignalEmitter<'_>,
+    ) -> fdo::Result<HashMap<String, OwnedValue>> {
+        let state = self.lock();
+
+        state
+            .properties
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), value.try_clone()?)))
+            .collect::<zvariant::Result<_>>()
+            .map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+
+    async fn set_mut(
+        &mut self,
+        property_name: &str,
+        value: &Value<'_>,
+        _object_server: &ObjectServer,
+        _connection: &Connection,
+        _header: Option<&Header<'_>>,
+        emitter: &SignalEmitter<'_>,
+    ) -> Option<fdo::Result<()>> {
+        if !self.lock().properties.contains_key(property_name) {
+            return None;
+        }
+        let res = async {
+            let value: Value<'static> = value.try_to_owned()?.into();
+            self.change_property(emitter, property_name, value).await
+        };
+
+        Some(res.await.map_err(Into::into))
+    }
+
+    fn call<'call>(
+        &'call self,
+        _server: &'call ObjectServer,
+        connection: &'call Connection,
+        msg: &'call Message,
+        _name: MemberName<'call>,
+    ) -> DispatchResult<'call> {
+        let Some((reply, signals)) = self.handle_call(msg) else {
+            return DispatchResult::NotFound;
+        };
+
+        DispatchResult::Async(Box::pin(async move {
+            let header = msg.header();
+            if !header
+                .primary()
+                .flags()
+                .contains(message::Flags::NoReplyExpected)
+            {
+                let mut builder = match &reply {
+                    Reply::Return(_) => Message::method_return(&header)?,
+                    Reply::Error(name, _) => Message::error(&header, name)?,
+                };
+                if let Some(sender) = connection.unique_name() {
+                    builder = builder.sender(sender)?;
+                }
+                let reply = match &reply {
+                    Reply::Return(body) => body.build(builder)?,
+                    Reply::Error(_, Some(description)) => builder.build(description)?,
+                    Reply::Error(_, None) => builder.build(&())?,
+                };
+                connection.send(&reply).await?;
+            }
+
+            // SAFETY: The header of a method call always has a path.
+            let path = header.path().unwrap();
+            for (signal_name, body) in signals {
+                let mut builder = Message::signal(path, Self::interface_name(), signal_name)?;
+                if let Some(sender) = connection.unique_name() {
+                    builder = builder.sender(sender)?;
+                }
+                let signal = body.build(builder)?;
+                debug!("Mock emitting signal: {signal}");
+                connection.send(&signal).await?;
+            }
+
+            Ok(())
+        }))
+    }
+
+    fn call_mut<'call>(
+        &'call mut self,
+        _server: &'call ObjectServer,
+        _connection: &'call Connection,
+        _msg: &'call Message,
+        _name: MemberName<'call>,
+    ) -> DispatchResult<'call> {
+        DispatchResult::NotFound
+    }
+
+    fn introspect_to_writer(&self, writer: &mut dyn Write, level: usize) {
+        let state = self.lock();
+        writeln!(
+            writer,
+            r#"{:indent$}<interface name="{}">"#,
+            "",
+            Self::interface_name(),
+            indent = level
+        )
+        .unwrap();
+        let mut methods: Vec<_> = state
+            .expectations
+            .iter()
+            .map(|e| e.expectation.method.as_str())
+            .collect();
+        methods.sort_unstable();
+        methods.dedup();
+        for method in methods {
+            writeln!(
+                writer,
+                r#"{:indent$}<method name="{method}"/>"#,
+                "",
+                indent = level + 2
+            )
+            .unwrap();
+        }
+        let mut properties: Vec<_> = state.properties.iter().collect();
+        properties.sort_unstable_by_key(|(name, _)| name.as_str());
+        for (name, value) in properties {
+            writeln!(
+                writer,
+                r#"{:indent$}<property name="{name}" type="{}" access="readwrite"/>"#,
+                "",
+                value.value_signature(),
+                indent = level + 2
+            )
+            .unwrap();
+        }
+        writeln!(writer, r#"{:indent$}</interface>"#, "", indent = level).unwrap();
+    }
+}
+
+/// An expected method call of a [`MockInterface`].
+///
+/// By default, an expectation matches all calls to the method, is expected to be called at least
+/// once, and replies with an empty body.
+#[derive(Clone)]
+pub struct Expectation {
+    method: OwnedMemberName,
+    matcher: Option<Matcher>,
+    reply: Reply,
+    signals: Vec<(OwnedMemberName, ArcBody)>,
+    times: Option<usize>,
+}
+
+impl Expectation {
+    /// Create an expectation for calls to the method `method_name`.
+    pub fn new<'m, M>(method_name: M) -> Result<Self>
+    where
+        M: TryInto<MemberName<'m>>,
+        M::Error: Into<Error>,
+    {
+        let method = method_name.try_into().map_err(Into::into)?.into();
+
+        Ok(Self {
+            method,
+            matcher: None,
+            reply: Reply::Return(Arc::new(())),
+            signals: vec![],
+            times: None,
+        })
+    }
+
+    /// Only match calls with exactly these arguments.
+    ///
+    /// Similar to [`Message`] bodies, multiple arguments are given as a tuple.
+    pub fn with_args<A>(self, args: A) -> Self
+    where
+        A: Serialize + DynamicType + Send + Sync + 'static,
+    {
+        self.with(move |body| {
+            let expected = Message::method_call("/", "Expected").and_then(|b| b.build(&args));
+
+            expected.is_ok_and(|expected| same_body(body, &expected.body()))
+        })
+    }
+
+    /// Only match calls for which `matcher` returns `true`, given the body of the call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use zbus::object_server::{Expectation, MockInterface};
+    /// # use zbus::proxy;
+    /// # #[proxy(interface = "org.zbus.MyGreeter", default_path = "/org/zbus/MyGreeter")]
+    /// # trait MyGreeter {
+    /// #     fn say_hello(&self, name: &str) -> zbus::Result<String>;
+    /// # }
+    ///
+    /// let mock = MockInterface::<MyGreeterProxy<'static>>::new();
+    /// mock.expect(
+    ///     Expectation::new("SayHello")?
+    ///         .with(|body| {
+    ///             body.deserialize::<&str>()
+    ///                 .is_ok_and(|name| name.starts_with('M'))
+    ///         })
+    ///         .returns("Hello M-person!"),
+    /// );
+    /// # Ok::<(), zbus::Error>(())
+    /// ```
+    pub fn with<F>(mut self, matcher: F) -> Self
+    where
+        F: Fn(&message::Body) -> bool + Send + Sync + 'static,
+    {
+        self.matcher = Some(Arc::new(matcher));
+
+        self
+    }
+
+    /// Reply to matching calls with `body`.
+    ///
+    /// Similar to [`Message`] bodies, multiple return values are given as a tuple.
+    pub fn returns<B>(mut self, body: B) -> Self
+    where
+        B: Serialize + DynamicType + Send + Sync + 'static,
+    {
+        self.reply = Reply::Return(Arc::new(body));
+
+        self
+    }
+
+    /// Reply to matching calls with `error`.
+    pub fn returns_error<E>(mut self, error: E) -> Self
+    where
+        E: DBusError,
+    {
+        let name = error.name().into();
+        let description = error.description().map(Into::into);
+        self.reply = Reply::Error(name, description);
+
+        self
+    }
+
+    /// Emit the signal `signal_name` with the given `body`, after replying to each matching call.
+    ///
+    /// This can be called multiple times to emit multiple signals, in order.
+    pub fn emits<'m, M, B>(mut self, signal_name: M, body: B) -> Result<Self>
+    where
+        M: TryInto<MemberName<'m>>,
+        M::Error: Into<Error>,
+        B: Serialize + DynamicType + Send + Sync + 'static,
+    {
+        let name = signal_name.try_into().map_err(Into::into)?.into();
+        self.signals.push((name, Arc::new(body)));
+
+        Ok(self)
+    }
+
+    /// Expect exactly `times` matching calls.
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+
+        self
+    }
+
+    fn matches(&self, body: &message::Body) -> bool {
+        self.matcher.as_ref().map_or(true, |m| m(body))
+    }
+}
+
+impl fmt::Debug for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Expectation")
+            .field("method", &self.method)
+            .field("times", &self.times)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone)]
+enum Reply {
+    Return(ArcBody),
+    Error(OwnedErrorName, Option<String>),
+}
+
+impl fmt::Debug for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reply::Return(_) => f.write_str("Return"),
+            Reply::Error(name, _) => f.debug_tuple("Error").field(name).finish(),
+        }
+    }
+}
+
+type Matcher = Arc<dyn Fn(&message::Body) -> bool + Send + Sync>;
+
+type ArcBody = Arc<dyn MessageBody>;
+
+// A type-erased message body.
+trait MessageBody: Send + Sync {
+    fn build(&self, builder: message::Builder<'_>) -> Result<Message>;
+}
+
+impl<B> MessageBody for B
+where
+    B: Serialize + DynamicType + Send + Sync,
+{
+    fn build(&self, builder: message::Builder<'_>) -> Result<Message> {
+        builder.build(self)
+    }
+}
+
+// Whether the bodies have the same signature and values, regardless of their encoding.
+fn same_body(body: &message::Body, expected: &message::Body) -> bool {
+    if body.signature() != expected.signature() {
+        return false;
+    }
+    if *body.signature() == Signature::Unit {
+        return true;
+    }
+
+    match (
+        body.deserialize::<Structure<'_>>(),
+        expected.deserialize::<Structure<'_>>(),
+    ) {
+        (Ok(body), Ok(expected)) => body == expected,
+        _ => false,
+    }
+}
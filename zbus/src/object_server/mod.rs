@@ -1,10 +1,10 @@
 //! The object server API.
 
 use std::{collections::HashMap, marker::PhantomData, sync::Arc};
-use tracing::{debug, instrument, trace, trace_span, Instrument};
+use tracing::{debug, field, info_span, trace, trace_span, Instrument, Span};
 
 use zbus_names::InterfaceName;
-use zvariant::{ObjectPath, Value};
+use zvariant::{ObjectPath, OwnedObjectPath, Signature, Str, Structure, Value};
 
 use crate::{
     async_lock::RwLock,
@@ -17,19 +17,34 @@ use crate::{
 
 mod interface;
 pub(crate) use interface::ArcInterface;
-pub use interface::{DispatchResult, Interface, InterfaceDeref, InterfaceDerefMut, InterfaceRef};
+pub use interface::{
+    DispatchResult, Interface, InterfaceDeref, InterfaceDerefMut, InterfaceRef,
+    PropertiesTransaction, PropertyEmitsChangedSignal,
+};
 
 mod signal_emitter;
 pub use signal_emitter::SignalEmitter;
 #[deprecated(since = "5.0.0", note = "Please use `SignalEmitter` instead.")]
 pub type SignalContext<'s> = SignalEmitter<'s>;
 
+mod access;
+use access::AccessControl;
+pub(crate) use access::ArcAccessPolicy;
+pub use access::{AccessPolicy, AccessRequest};
+
 mod dispatch_notifier;
 pub use dispatch_notifier::ResponseDispatchNotifier;
 
+mod mock;
+pub use mock::{Expectation, MockInterface};
+
 mod node;
+use node::ManagedObject;
 pub(crate) use node::Node;
 
+mod objects;
+pub use objects::Objects;
+
 /// An object server, holding server-side D-Bus objects & interfaces.
 ///
 /// Object servers hold interfaces on various object paths, and expose them over D-Bus.
@@ -91,6 +106,7 @@ pub(crate) use node::Node;
 pub struct ObjectServer {
     conn: WeakConnection,
     root: Arc<RwLock<Node>>,
+    access: Arc<AccessControl>,
 }
 
 impl ObjectServer {
@@ -101,6 +117,7 @@ impl ObjectServer {
             root: Arc::new(RwLock::new(Node::new(
                 "/".try_into().expect("zvariant bug"),
             ))),
+            access: Arc::default(),
         }
     }
 
@@ -115,6 +132,13 @@ impl ObjectServer {
     /// However, there are situations where you'd need to register interfaces dynamically and that's
     /// where this method becomes useful.
     ///
+    /// If the object is managed by an object manager (i.e. an ancestor implements
+    /// [`ObjectManager`]), the `InterfacesAdded` signal is emitted by the manager. If `iface` is
+    /// itself an object manager, the objects beneath it, which were managed by another manager,
+    /// are moved over: the previous manager emits `InterfacesRemoved` and the new one
+    /// `InterfacesAdded` for them. Use [`ObjectServer::add_objects`] to register multiple
+    /// interfaces at once, with as few signals as possible.
+    ///
     /// If the interface already exists at this path, returns false.
     pub async fn at<'p, P, I>(&self, path: P, iface: I) -> Result<bool>
     where
@@ -126,6 +150,45 @@ impl ObjectServer {
             .await
     }
 
+    /// Register multiple interfaces, possibly at multiple paths, at once.
+    ///
+    /// This is similar to calling [`ObjectServer::at`] for each of the interfaces, except that
+    /// object managers emit a single `InterfacesAdded` signal per object, covering all the
+    /// interfaces added to it, regardless of the order in which objects and managers were added.
+    /// This is the recommended way to register whole subtrees of objects.
+    ///
+    /// # Errors
+    ///
+    /// If any of the interfaces already exists, an `Error::InterfaceExists` error is returned and
+    /// none of the interfaces are registered.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # use zbus::{Connection, fdo::ObjectManager, interface, object_server::Objects};
+    /// #
+    /// struct Device;
+    ///
+    /// #[interface(name = "org.zbus.Device")]
+    /// impl Device {}
+    ///
+    /// # zbus::block_on(async {
+    /// let connection = Connection::session().await?;
+    /// let objects = Objects::new()
+    ///     .at("/org/zbus/Devices", ObjectManager)?
+    ///     .at("/org/zbus/Devices/0", Device)?
+    ///     .at("/org/zbus/Devices/1", Device)?;
+    /// // Only two `InterfacesAdded` signals, one per device.
+    /// connection.object_server().add_objects(objects).await?;
+    /// # Ok::<_, Box<dyn Error + Send + Sync>>(())
+    /// # })?;
+    /// # Ok::<_, Box<dyn Error + Send + Sync>>(())
+    /// ```
+    pub async fn add_objects(&self, objects: Objects) -> Result<()> {
+        self.add_arc_interfaces(objects.interfaces).await
+    }
+
     pub(crate) async fn add_arc_interface<'p, P>(
         &self,
         path: P,
@@ -137,51 +200,67 @@ impl ObjectServer {
         P::Error: Into<Error>,
     {
         let path = path.try_into().map_err(Into::into)?;
+        let objects = HashMap::from([(path.into(), HashMap::from([(name, arc_iface)]))]);
+
+        match self.add_arc_interfaces(objects).await {
+            Ok(()) => Ok(true),
+            Err(Error::InterfaceExists(_, _)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub(crate) async fn add_arc_interfaces(
+        &self,
+        objects: HashMap<OwnedObjectPath, HashMap<InterfaceName<'static>, ArcInterface>>,
+    ) -> Result<()> {
         let mut root = self.root().write().await;
-        let (node, manager_path) = root.get_child_mut(&path, true);
-        let node = node.unwrap();
-        let added = node.add_arc_interface(name.clone(), arc_iface);
-        if added {
-            if name == ObjectManager::name() {
-                // Just added an object manager. Need to signal all managed objects under it.
-                let emitter = SignalEmitter::new(&self.connection(), path)?;
-                let objects = node.get_managed_objects(self, &self.connection()).await?;
-                for (path, owned_interfaces) in objects {
-                    let interfaces = owned_interfaces
-                        .iter()
-                        .map(|(i, props)| {
-                            let props = props
-                                .iter()
-                                .map(|(k, v)| Ok((k.as_str(), Value::try_from(v)?)))
-                                .collect::<Result<_>>();
-                            Ok((i.into(), props?))
-                        })
-                        .collect::<Result<_>>()?;
-                    ObjectManager::interfaces_added(&emitter, path.into(), interfaces).await?;
+
+        // Check everything first, so that nothing is added on error.
+        for (path, interfaces) in &objects {
+            let node = root.get_child(path);
+            for name in interfaces.keys() {
+                // The standard interfaces (except for `ObjectManager`) are present on all nodes.
+                let exists = match node {
+                    Some(node) => node.interface_lock(name.as_ref()).is_some(),
+                    None => node::is_standard_interface(name) && *name != ObjectManager::name(),
+                };
+                if exists {
+                    return Err(Error::InterfaceExists(name.clone(), path.clone().into()));
                 }
-            } else if let Some(manager_path) = manager_path {
-                let emitter = SignalEmitter::new(&self.connection(), manager_path.clone())?;
-                let mut interfaces = HashMap::new();
-                let owned_props = node
-                    .get_properties(self, &self.connection(), name.clone())
-                    .await?;
-                let props = owned_props
-                    .iter()
-                    .map(|(k, v)| Ok((k.as_str(), Value::try_from(v)?)))
-                    .collect::<Result<_>>()?;
-                interfaces.insert(name, props);
+            }
+        }
+
+        // Objects beneath new object managers might move from another manager.
+        let scope: Vec<_> = objects
+            .iter()
+            .map(|(path, interfaces)| {
+                let recursive = interfaces.contains_key(&ObjectManager::name());
 
-                ObjectManager::interfaces_added(&emitter, path, interfaces).await?;
+                (path.clone(), recursive)
+            })
+            .collect();
+        let before = root.managed_objects(&scope);
+        for (path, interfaces) in objects {
+            // SAFETY: The node is created if it doesn't exist.
+            let node = root.get_child_mut(&path, true).0.unwrap();
+            for (name, iface) in interfaces {
+                node.add_arc_interface(name, iface);
             }
         }
+        let after = root.managed_objects(&scope);
+        drop(root);
 
-        Ok(added)
+        self.emit_object_manager_signals(before, after).await
     }
 
     /// Unregister a D-Bus [`Interface`] at a given path.
     ///
-    /// If there are no more interfaces left at that path, destroys the object as well.
-    /// Returns whether the object was destroyed.
+    /// If there are no more interfaces left at that path, destroys the object as well. Returns
+    /// whether the object was destroyed. The objects beneath it are not affected.
+    ///
+    /// The object manager of the object, if any, emits the `InterfacesRemoved` signal. If an
+    /// object manager is removed, the objects it managed are moved over to the next object manager
+    /// up the tree, if any.
     pub async fn remove<'p, I, P>(&self, path: P) -> Result<bool>
     where
         I: Interface,
@@ -190,16 +269,21 @@ impl ObjectServer {
     {
         let path = path.try_into().map_err(Into::into)?;
         let mut root = self.root.write().await;
-        let (node, manager_path) = root.get_child_mut(&path, false);
-        let node = node.ok_or(Error::InterfaceNotFound)?;
-        if !node.remove_interface(I::name()) {
+        let node = root.get_child(&path).ok_or(Error::InterfaceNotFound)?;
+        if node.interface_lock(I::name()).is_none() {
             return Err(Error::InterfaceNotFound);
         }
-        if let Some(manager_path) = manager_path {
-            let ctxt = SignalEmitter::new(&self.connection(), manager_path.clone())?;
-            ObjectManager::interfaces_removed(&ctxt, path.clone(), (&[I::name()]).into()).await?;
-        }
-        if node.is_empty() {
+        // Objects beneath a removed object manager move to another one.
+        let recursive = I::name() == ObjectManager::name();
+        let scope = [(OwnedObjectPath::from(path.clone()), recursive)];
+        let before = root.managed_objects(&scope);
+
+        // SAFETY: We just checked the node exists.
+        let node = root.get_child_mut(&path, false).0.unwrap();
+        node.remove_interface(I::name());
+        let destroyed = node.is_empty();
+        // The node is still needed to hold the objects beneath it.
+        if destroyed && !node.has_children() {
             let mut path_parts = path.rsplit('/').filter(|i| !i.is_empty());
             let last_part = path_parts.next().unwrap();
             let ppath = ObjectPath::from_string_unchecked(
@@ -209,9 +293,51 @@ impl ObjectServer {
                 .0
                 .unwrap()
                 .remove_node(last_part);
-            return Ok(true);
         }
-        Ok(false)
+        let after = root.managed_objects(&scope);
+        drop(root);
+
+        self.emit_object_manager_signals(before, after).await?;
+
+        Ok(destroyed)
+    }
+
+    /// Set the access policy for the objects at the given path and beneath it.
+    ///
+    /// The method calls to these objects are dispatched only if allowed by the policy, including
+    /// the ones to the standard interfaces. Policies set for paths deeper in the tree take
+    /// precedence. Any policy previously set for `path` is replaced.
+    ///
+    /// The credentials of the callers are retrieved from the bus, which involves a method call for
+    /// each caller that isn't among the recent ones, or from the peer for peer-to-peer
+    /// connections. The call is denied if that fails. See [`AccessPolicy`] for an example.
+    pub fn set_access_policy<'p, P, A>(&self, path: P, policy: A) -> Result<()>
+    where
+        P: TryInto<ObjectPath<'p>>,
+        P::Error: Into<Error>,
+        A: AccessPolicy,
+    {
+        let path = path.try_into().map_err(Into::into)?;
+        self.set_arc_access_policy(path.into(), ArcAccessPolicy::new(policy));
+
+        Ok(())
+    }
+
+    pub(crate) fn set_arc_access_policy(&self, path: OwnedObjectPath, policy: ArcAccessPolicy) {
+        self.access.set_policy(path, policy);
+    }
+
+    /// Remove the access policy set for the given path, if any.
+    ///
+    /// Returns whether there was one. The policies of the other paths are not affected.
+    pub fn remove_access_policy<'p, P>(&self, path: P) -> Result<bool>
+    where
+        P: TryInto<ObjectPath<'p>>,
+        P::Error: Into<Error>,
+    {
+        let path = path.try_into().map_err(Into::into)?;
+
+        Ok(self.access.remove_policy(&path))
     }
 
     /// Get the interface at the given path.
@@ -291,14 +417,22 @@ impl ObjectServer {
 
     async fn dispatch_call_to_iface(
         &self,
-        iface: Arc<RwLock<dyn Interface>>,
+        iface: ArcInterface,
         connection: &Connection,
         msg: &Message,
         hdr: &Header<'_>,
     ) -> fdo::Result<()> {
+        self.access.check(connection, hdr).await?;
+
         let member = hdr
             .member()
             .ok_or_else(|| fdo::Error::Failed("Missing member".into()))?;
+        // Authorization may involve user interaction so make sure no lock on the interface is
+        // held while waiting for it.
+        if let Some(action) = (iface.polkit_action)(member.as_str()) {
+            crate::polkit::check_authorization(connection, hdr, action).await?;
+        }
+        let iface = iface.instance;
         let iface_name = hdr
             .interface()
             .ok_or_else(|| fdo::Error::Failed("Missing interface".into()))?;
@@ -361,7 +495,7 @@ impl ObjectServer {
         // Note that an unknown member will still spawn a task. We should instead gather
         // all the details for the call before spawning.
         // See also https://github.com/z-galaxy/zbus/issues/674 for future of Interface.
-        let _ = hdr
+        let member = hdr
             .member()
             .ok_or_else(|| fdo::Error::Failed("Missing member".into()))?;
 
@@ -376,8 +510,12 @@ impl ObjectServer {
             let iface = node.interface_lock(iface_name.as_ref()).ok_or_else(|| {
                 fdo::Error::UnknownInterface(format!("Unknown interface '{iface_name}'"))
             })?;
-            (iface.instance, iface.spawn_tasks_for_methods)
+            let with_spawn = iface.spawn_tasks_for_methods;
+            (iface, with_spawn)
         };
+        if (iface.has_trace_context)(member.as_str()) {
+            extract_trace_context(connection, msg);
+        }
 
         if with_spawn {
             let executor = connection.executor().clone();
@@ -426,17 +564,21 @@ impl ObjectServer {
     ///   the caller through the associated server connection.
     ///
     /// Returns an error if the message is malformed.
-    #[instrument(skip(self))]
     pub(crate) async fn dispatch_call(&self, msg: &Message, hdr: &Header<'_>) -> Result<()> {
         let conn = self.connection();
+        let span = info_span!("dispatch_call", ?msg, ?hdr, trace_context = field::Empty);
 
-        if let Err(e) = self.dispatch_method_call_try(&conn, msg, hdr).await {
-            debug!("Returning error: {}", e);
-            conn.reply_dbus_error(hdr, e).await?;
-        }
-        trace!("Handled: {}", msg);
+        async {
+            if let Err(e) = self.dispatch_method_call_try(&conn, msg, hdr).await {
+                debug!("Returning error: {}", e);
+                conn.reply_dbus_error(hdr, e).await?;
+            }
+            trace!("Handled: {}", msg);
 
-        Ok(())
+            Ok(())
+        }
+        .instrument(span)
+        .await
     }
 
     pub(crate) fn connection(&self) -> Connection {
@@ -444,6 +586,112 @@ impl ObjectServer {
             .upgrade()
             .expect("ObjectServer can't exist w/o an associated Connection")
     }
+
+    // Emit the `InterfacesAdded` and `InterfacesRemoved` signals for the changes between two
+    // snapshots of the managed objects.
+    async fn emit_object_manager_signals(
+        &self,
+        mut before: HashMap<OwnedObjectPath, ManagedObject>,
+        mut after: HashMap<OwnedObjectPath, ManagedObject>,
+    ) -> Result<()> {
+        let mut paths: Vec<_> = before.keys().chain(after.keys()).cloned().collect();
+        paths.sort_unstable_by(|p1, p2| p1.as_str().cmp(p2.as_str()));
+        paths.dedup();
+
+        let mut removed = vec![];
+        let mut added = vec![];
+        for path in paths {
+            let before = before.remove(&path).unwrap_or_default();
+            let after = after.remove(&path).unwrap_or_default();
+            let moved = before.manager != after.manager;
+            let contains = |object: &ManagedObject, name: &InterfaceName<'_>| {
+                object.interfaces.iter().any(|(n, _)| n == name)
+            };
+
+            if let Some(manager) = &before.manager {
+                let interfaces: Vec<_> = before
+                    .interfaces
+                    .iter()
+                    .filter(|(name, _)| moved || !contains(&after, name))
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                if !interfaces.is_empty() {
+                    removed.push((manager.clone(), path.clone(), interfaces));
+                }
+            }
+            if let Some(manager) = after.manager.clone() {
+                let interfaces: Vec<_> = after
+                    .interfaces
+                    .into_iter()
+                    .filter(|(name, _)| moved || !contains(&before, name))
+                    .collect();
+                if !interfaces.is_empty() {
+                    added.push((manager, path, interfaces));
+                }
+            }
+        }
+
+        let connection = self.connection();
+        for (manager, path, interfaces) in removed {
+            let emitter = SignalEmitter::new(&connection, manager)?;
+            ObjectManager::interfaces_removed(&emitter, path.into(), interfaces.into()).await?;
+        }
+        for (manager, path, interfaces) in added {
+            let object_emitter = SignalEmitter::new(&connection, path.clone())?;
+            let mut owned_interfaces = Vec::with_capacity(interfaces.len());
+            for (name, iface) in interfaces {
+                let props = iface
+                    .instance
+                    .read()
+                    .await
+                    .get_all(self, &connection, None, &object_emitter)
+                    .await?;
+                owned_interfaces.push((name, props));
+            }
+            let interfaces = owned_interfaces
+                .iter()
+                .map(|(name, props)| {
+                    let props = props
+                        .iter()
+                        .map(|(k, v)| Ok((k.as_str(), Value::try_from(v)?)))
+                        .collect::<Result<_>>()?;
+                    Ok((name.clone(), props))
+                })
+                .collect::<Result<_>>()?;
+
+            let emitter = SignalEmitter::new(&connection, manager)?;
+            ObjectManager::interfaces_added(&emitter, path.into(), interfaces).await?;
+        }
+
+        Ok(())
+    }
+}
+
+// Set the trace context passed as the last argument of `msg` as the parent of the current span,
+// the one of the dispatching of the call.
+fn extract_trace_context(connection: &Connection, msg: &Message) {
+    let Some(propagator) = connection.trace_propagator() else {
+        return;
+    };
+    let body = msg.body();
+    let context = match body.signature() {
+        Signature::Str => body.deserialize::<Str<'_>>().ok(),
+        Signature::Structure(fields) if fields.iter().last() == Some(&Signature::Str) => body
+            .deserialize::<Structure<'_>>()
+            .ok()
+            .and_then(|args| match args.into_fields().pop() {
+                Some(Value::Str(context)) => Some(context),
+                _ => None,
+            }),
+        _ => None,
+    };
+    match context {
+        // An empty context is passed by callers without any.
+        Some(context) if !context.is_empty() => {
+            propagator.extract(context.as_str(), &Span::current())
+        }
+        _ => trace!("No trace context in `{msg}`"),
+    }
 }
 
 #[cfg(feature = "blocking-api")]
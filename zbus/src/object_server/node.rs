@@ -71,7 +71,7 @@ impl Node {
                 continue;
             }
 
-            if node.interfaces.contains_key(&ObjectManager::name()) {
+            if node.is_object_manager() {
                 obj_manager_path = Some((*node.path).clone());
             }
 
@@ -100,13 +100,16 @@ impl Node {
         self.interfaces.remove(&interface_name).is_some()
     }
 
+    pub(super) fn has_children(&self) -> bool {
+        !self.children.is_empty()
+    }
+
     pub(super) fn is_empty(&self) -> bool {
-        !self.interfaces.keys().any(|k| {
-            *k != Peer::name()
-                && *k != Introspectable::name()
-                && *k != Properties::name()
-                && *k != ObjectManager::name()
-        })
+        !self.interfaces.keys().any(|k| !is_standard_interface(k))
+    }
+
+    fn is_object_manager(&self) -> bool {
+        self.interfaces.contains_key(&ObjectManager::name())
     }
 
     pub(super) fn remove_node(&mut self, node: &str) -> bool {
@@ -219,29 +222,80 @@ impl Node {
     ) -> fdo::Result<ManagedObjects> {
         let mut managed_objects = ManagedObjects::new();
 
-        // Recursively get all properties of all interfaces of descendants.
+        // Recursively get all properties of all interfaces of descendants, except for the ones
+        // managed by a nested object manager.
         let mut node_list: Vec<_> = self.children.values().collect();
         while let Some(node) = node_list.pop() {
             let mut interfaces = HashMap::new();
-            for iface_name in node.interfaces.keys().filter(|n| {
-                // Filter standard interfaces.
-                *n != &Peer::name()
-                    && *n != &Introspectable::name()
-                    && *n != &Properties::name()
-                    && *n != &ObjectManager::name()
-            }) {
+            for iface_name in node.interfaces.keys().filter(|n| !is_standard_interface(n)) {
                 let props = node
                     .get_properties(object_server, connection, iface_name.clone())
                     .await?;
                 interfaces.insert(iface_name.clone().into(), props);
             }
-            managed_objects.insert(node.path.clone(), interfaces);
-            node_list.extend(node.children.values());
+            if !interfaces.is_empty() {
+                managed_objects.insert(node.path.clone(), interfaces);
+            }
+            if !node.is_object_manager() {
+                node_list.extend(node.children.values());
+            }
         }
 
         Ok(managed_objects)
     }
 
+    /// Snapshot of the objects at the given paths, as seen by their object managers.
+    ///
+    /// For each `(path, recursive)` pair, the object at `path` is included, as well as all the
+    /// objects beneath it if `recursive` is `true`. Paths without an object are skipped.
+    pub(super) fn managed_objects(
+        &self,
+        scope: &[(OwnedObjectPath, bool)],
+    ) -> HashMap<OwnedObjectPath, ManagedObject> {
+        let mut objects = HashMap::new();
+
+        'scope: for (path, recursive) in scope {
+            let mut node = self;
+            let mut manager = None;
+            for i in path.split('/').skip(1).filter(|i| !i.is_empty()) {
+                if node.is_object_manager() {
+                    manager = Some(node.path.clone());
+                }
+                match node.children.get(i) {
+                    Some(n) => node = n,
+                    None => continue 'scope,
+                }
+            }
+
+            let mut node_list = vec![(node, manager)];
+            while let Some((node, manager)) = node_list.pop() {
+                if *recursive {
+                    let manager = if node.is_object_manager() {
+                        Some(node.path.clone())
+                    } else {
+                        manager.clone()
+                    };
+                    node_list.extend(node.children.values().map(|n| (n, manager.clone())));
+                }
+                let interfaces = node
+                    .interfaces
+                    .iter()
+                    .filter(|(name, _)| !is_standard_interface(name))
+                    .map(|(name, iface)| (name.clone(), iface.clone()))
+                    .collect();
+                objects.insert(
+                    node.path.clone(),
+                    ManagedObject {
+                        manager,
+                        interfaces,
+                    },
+                );
+            }
+        }
+
+        objects
+    }
+
     pub(super) async fn get_properties(
         &self,
         object_server: &ObjectServer,
@@ -258,3 +312,19 @@ impl Node {
             .await
     }
 }
+
+/// The interfaces of an object, along with its object manager.
+#[derive(Debug, Default)]
+pub(super) struct ManagedObject {
+    /// The path of the closest ancestor implementing `ObjectManager`, if any.
+    pub manager: Option<OwnedObjectPath>,
+    pub interfaces: Vec<(InterfaceName<'static>, ArcInterface)>,
+}
+
+/// Whether `name` is one of the standard interfaces, which are not reported by object managers.
+pub(super) fn is_standard_interface(name: &InterfaceName<'_>) -> bool {
+    *name == Peer::name()
+        || *name == Introspectable::name()
+        || *name == Properties::name()
+        || *name == ObjectManager::name()
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use zbus_names::InterfaceName;
+use zvariant::{ObjectPath, OwnedObjectPath};
+
+use super::{ArcInterface, Interface};
+use crate::{Error, Result};
+
+/// A set of interfaces, possibly at multiple paths, to register at once.
+///
+/// See [`ObjectServer::add_objects`] for details.
+///
+/// [`ObjectServer::add_objects`]: super::ObjectServer::add_objects
+#[derive(Debug, Default)]
+pub struct Objects {
+    pub(super) interfaces: HashMap<OwnedObjectPath, HashMap<InterfaceName<'static>, ArcInterface>>,
+}
+
+impl Objects {
+    /// Create an empty set of objects.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an interface at the given path.
+    ///
+    /// This will replace any previously added interface with the same name at the same path.
+    pub fn at<'p, P, I>(mut self, path: P, iface: I) -> Result<Self>
+    where
+        I: Interface,
+        P: TryInto<ObjectPath<'p>>,
+        P::Error: Into<Error>,
+    {
+        let path = path.try_into().map_err(Into::into)?;
+        let entry = self.interfaces.entry(path.into()).or_default();
+        entry.insert(I::name(), ArcInterface::new(iface));
+
+        Ok(self)
+    }
+
+    /// Whether no interface was added.
+    pub fn is_empty(&self) -> bool {
+        self.interfaces.is_empty()
+    }
+}
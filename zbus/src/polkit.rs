@@ -0,0 +1,216 @@
+//! Authorization through [polkit].
+//!
+//! polkit is the de facto standard to decide whether the caller of a privileged service is allowed
+//! to do what it asks for. Services declare the actions they support in a policy file and ask the
+//! polkit authority on the system bus whether a subject, typically their caller, is authorized for
+//! one of them, possibly after authenticating the user.
+//!
+//! This module provides a proxy for the authority and [`check_authorization`], that checks the
+//! caller of a method call against an action. The latter can also be declared on methods of
+//! [`macro@crate::interface`] with the `polkit_action` attribute:
+//!
+//! ```no_run
+//! use zbus::interface;
+//!
+//! struct Machine;
+//!
+//! #[interface(name = "org.example.Machine")]
+//! impl Machine {
+//!     #[zbus(polkit_action = "org.example.machine.reboot")]
+//!     async fn reboot(&self) {
+//!         // Only reached if the caller is authorized.
+//!     }
+//! }
+//! ```
+//!
+//! [polkit]: https://www.freedesktop.org/software/polkit/docs/latest/
+
+use enumflags2::{bitflags, BitFlags};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{debug, trace};
+use zbus_names::UniqueName;
+use zvariant::{OwnedValue, Str, Type};
+
+use crate::{
+    fdo,
+    message::{Flags, Header},
+    proxy,
+    proxy::CacheProperties,
+    Connection, Result,
+};
+
+/// A subject of an authorization check.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct Subject {
+    kind: String,
+    details: HashMap<String, OwnedValue>,
+}
+
+impl Subject {
+    /// The subject of the given kind, with the given details.
+    ///
+    /// See the [polkit documentation] for the supported kinds and their details.
+    ///
+    /// [polkit documentation]: https://www.freedesktop.org/software/polkit/docs/latest/eggdbus-interface-org.freedesktop.PolicyKit1.Authority.html#eggdbus-struct-Subject
+    pub fn new(kind: String, details: HashMap<String, OwnedValue>) -> Self {
+        Self { kind, details }
+    }
+
+    /// The process owning the given unique name on the system bus.
+    pub fn system_bus_name(name: &UniqueName<'_>) -> Self {
+        let details = HashMap::from([("name".to_string(), Str::from(name.as_str()).into())]);
+
+        Self::new("system-bus-name".to_string(), details)
+    }
+
+    /// The kind of the subject, e.g. `system-bus-name`.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// The details identifying the subject.
+    pub fn details(&self) -> &HashMap<String, OwnedValue> {
+        &self.details
+    }
+}
+
+/// The flags used by the [`AuthorityProxy::check_authorization`] method.
+#[bitflags]
+#[repr(u32)]
+#[derive(Type, Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum CheckAuthorizationFlags {
+    /// If the subject can obtain the authorization through authentication, and an authentication
+    /// agent is available, then attempt to do so. This may take a while, as the user is asked to
+    /// authenticate.
+    AllowUserInteraction = 0x01,
+}
+
+/// The result of the [`AuthorityProxy::check_authorization`] method.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct AuthorizationResult {
+    is_authorized: bool,
+    is_challenge: bool,
+    details: HashMap<String, String>,
+}
+
+impl AuthorizationResult {
+    /// Whether the subject is authorized for the action.
+    pub fn is_authorized(&self) -> bool {
+        self.is_authorized
+    }
+
+    /// Whether the subject could be authorized for the action, if it authenticates.
+    ///
+    /// This is only relevant if the subject isn't authorized.
+    pub fn is_challenge(&self) -> bool {
+        self.is_challenge
+    }
+
+    /// The details of the result, e.g. whether the authorization was obtained through
+    /// authentication.
+    pub fn details(&self) -> &HashMap<String, String> {
+        &self.details
+    }
+}
+
+/// Proxy for the [`org.freedesktop.PolicyKit1.Authority`][link] interface.
+///
+/// [link]: https://www.freedesktop.org/software/polkit/docs/latest/eggdbus-interface-org.freedesktop.PolicyKit1.Authority.html
+#[proxy(
+    interface = "org.freedesktop.PolicyKit1.Authority",
+    default_service = "org.freedesktop.PolicyKit1",
+    default_path = "/org/freedesktop/PolicyKit1/Authority"
+)]
+pub trait Authority {
+    /// Check whether `subject` is authorized for the action `action_id`.
+    ///
+    /// `details` are passed to the authentication agent. If `cancellation_id` isn't empty, it can
+    /// be passed to [`AuthorityProxy::cancel_check_authorization`] to cancel the check.
+    fn check_authorization(
+        &self,
+        subject: &Subject,
+        action_id: &str,
+        details: &HashMap<&str, &str>,
+        flags: BitFlags<CheckAuthorizationFlags>,
+        cancellation_id: &str,
+    ) -> Result<AuthorizationResult>;
+
+    /// Cancel the authorization check started with `cancellation_id`.
+    fn cancel_check_authorization(&self, cancellation_id: &str) -> Result<()>;
+
+    /// The name of the backend of the authority.
+    #[zbus(property)]
+    fn backend_name(&self) -> Result<String>;
+
+    /// The version of the backend of the authority.
+    #[zbus(property)]
+    fn backend_version(&self) -> Result<String>;
+}
+
+/// Check whether the sender of the method call with the header `hdr` is authorized for the polkit
+/// action `action_id`.
+///
+/// The caller is identified by its unique name on the bus `connection` is connected to, which is
+/// expected to be the system bus, where the authority lives. If the caller set the
+/// [`Flags::AllowInteractiveAuth`] flag on the call, the user may be asked to authenticate, in
+/// which case this only returns once they did or gave up.
+///
+/// Returns `Ok(())` if the caller is authorized. Otherwise, returns the error to reply with:
+/// [`fdo::Error::InteractiveAuthorizationRequired`] if the caller could be authorized through
+/// authentication but didn't allow it, or [`fdo::Error::AccessDenied`]. The latter is also returned
+/// if the authority couldn't be reached or the call has no sender, e.g. on a peer-to-peer
+/// connection.
+pub async fn check_authorization(
+    connection: &Connection,
+    hdr: &Header<'_>,
+    action_id: &str,
+) -> fdo::Result<()> {
+    let Some(sender) = hdr.sender() else {
+        return Err(fdo::Error::AccessDenied(
+            "polkit authorization requires a sender".to_string(),
+        ));
+    };
+    let interactive = hdr.primary().flags().contains(Flags::AllowInteractiveAuth);
+    let flags = if interactive {
+        CheckAuthorizationFlags::AllowUserInteraction.into()
+    } else {
+        BitFlags::empty()
+    };
+
+    let result = async {
+        AuthorityProxy::builder(connection)
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?
+            .check_authorization(
+                &Subject::system_bus_name(sender),
+                action_id,
+                &HashMap::new(),
+                flags,
+                "",
+            )
+            .await
+    }
+    .await
+    .map_err(|e| {
+        debug!("Failed to check the authorization of `{sender}` for `{action_id}`: {e}");
+
+        fdo::Error::AccessDenied(format!(
+            "Failed to check the authorization for `{action_id}`"
+        ))
+    })?;
+    trace!("Authorization of `{sender}` for `{action_id}`: {result:?}");
+
+    if result.is_authorized() {
+        Ok(())
+    } else if result.is_challenge() && !interactive {
+        Err(fdo::Error::InteractiveAuthorizationRequired(format!(
+            "Authentication is required for `{action_id}`"
+        )))
+    } else {
+        Err(fdo::Error::AccessDenied(format!(
+            "Not authorized for `{action_id}`"
+        )))
+    }
+}
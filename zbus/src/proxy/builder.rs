@@ -1,9 +1,16 @@
-use std::{collections::HashSet, marker::PhantomData, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    sync::Arc,
+};
 
 use zbus_names::{BusName, InterfaceName};
-use zvariant::{ObjectPath, Str};
+use zvariant::{ObjectPath, OwnedValue, Str};
 
-use crate::{proxy::ProxyInner, Connection, Error, Proxy, Result};
+use crate::{
+    proxy::{FeedSource, PropertiesFeed, ProxyInner},
+    Connection, Error, Proxy, Result,
+};
 
 /// The properties caching mode.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -111,9 +118,30 @@ impl<'a, T> Builder<'a, T> {
                 cache,
                 uncached_properties,
             )),
+            timeout: None,
         })
     }
 
+    /// Build a proxy from the builder, with already known property values.
+    ///
+    /// Unless caching is disabled, the properties cache is populated with `properties` right away,
+    /// so no D-Bus call is needed to fetch them. The cache is then kept up to date by `source`,
+    /// through the returned feed, instead of the proxy itself. The proxy keeps `source` alive.
+    pub(crate) fn build_with_properties(
+        self,
+        properties: &HashMap<String, OwnedValue>,
+        source: FeedSource,
+    ) -> Result<(T, Option<PropertiesFeed>)>
+    where
+        T: From<Proxy<'a>>,
+    {
+        let proxy = self.build_internal()?;
+        proxy.init_property_cache(Some((properties, source)));
+        let feed = proxy.properties_feed();
+
+        Ok((proxy.into(), feed))
+    }
+
     /// Build a proxy from the builder.
     ///
     /// # Errors
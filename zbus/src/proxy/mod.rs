@@ -12,6 +12,7 @@ use std::{
     pin::Pin,
     sync::{Arc, OnceLock, RwLock, RwLockReadGuard},
     task::{Context, Poll},
+    time::Duration,
 };
 use tracing::{debug, info_span, instrument, trace, Instrument};
 
@@ -21,6 +22,7 @@ use zvariant::{ObjectPath, OwnedValue, Str, Value};
 use crate::{
     fdo::{self, IntrospectableProxy, NameOwnerChanged, PropertiesChangedStream, PropertiesProxy},
     message::{Flags, Message, Sequence, Type},
+    timeout::timeout,
     AsyncDrop, Connection, Error, Executor, MatchRule, MessageStream, OwnedMatchRule, Result, Task,
 };
 
@@ -67,6 +69,8 @@ pub use defaults::Defaults;
 #[derive(Clone, Debug)]
 pub struct Proxy<'a> {
     pub(crate) inner: Arc<ProxyInner<'a>>,
+    // Not shared with the other proxies created from the same builder.
+    timeout: Option<Duration>,
 }
 
 /// This is required to avoid having the Drop impl extend the lifetime 'a, which breaks zbus_xmlgen
@@ -95,7 +99,7 @@ pub(crate) struct ProxyInner<'a> {
     pub(crate) interface: InterfaceName<'a>,
 
     /// Cache of property values.
-    property_cache: Option<OnceLock<(Arc<PropertiesCache>, Task<()>)>>,
+    property_cache: Option<OnceLock<(Arc<PropertiesCache>, CacheUpdater)>>,
     /// Set of properties which do not get cached, by name.
     /// This overrides proxy-level caching behavior.
     uncached_properties: HashSet<Str<'a>>,
@@ -249,6 +253,53 @@ where
     }
 }
 
+/// The source of a [`PropertiesFeed`], e.g. an `ObjectManagerClient`.
+pub(crate) type FeedSource = Arc<dyn fmt::Debug + Send + Sync>;
+
+/// What keeps a properties cache up to date.
+#[derive(Debug)]
+enum CacheUpdater {
+    /// A task of the proxy, tracking the `PropertiesChanged` signals itself.
+    Task { _task: Task<()> },
+    /// The source of a [`PropertiesFeed`], kept alive by the proxy.
+    Feed { _source: FeedSource },
+}
+
+/// Feeds a properties cache with the changes received by someone else than its proxy.
+///
+/// This is for when the changes are already tracked elsewhere, from before the initial values of
+/// the cache were known, so that no change is missed in between.
+#[derive(Debug)]
+pub(crate) struct PropertiesFeed {
+    cache: std::sync::Weak<PropertiesCache>,
+    interface: InterfaceName<'static>,
+    uncached_properties: HashSet<Str<'static>>,
+}
+
+impl PropertiesFeed {
+    /// If the cache is still around.
+    pub(crate) fn is_alive(&self) -> bool {
+        self.cache.strong_count() > 0
+    }
+
+    /// Apply a change to the cache.
+    ///
+    /// Returns `false` if the cache is gone, i.e. the feed is no longer needed.
+    pub(crate) fn update(&self, changed: &HashMap<&str, Value<'_>>, invalidated: &[&str]) -> bool {
+        let Some(cache) = self.cache.upgrade() else {
+            return false;
+        };
+        cache.update_cache(
+            &self.uncached_properties,
+            changed,
+            invalidated,
+            &self.interface,
+        );
+
+        true
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct PropertiesCache {
     values: RwLock<HashMap<String, PropertyValue>>,
@@ -268,13 +319,30 @@ impl PropertiesCache {
         interface: InterfaceName<'static>,
         executor: &Executor<'_>,
         uncached_properties: HashSet<zvariant::Str<'static>>,
-    ) -> (Arc<Self>, Task<()>) {
+        feed: Option<(&HashMap<String, OwnedValue>, FeedSource)>,
+    ) -> (Arc<Self>, CacheUpdater) {
         let cache = Arc::new(PropertiesCache {
             values: Default::default(),
             caching_result: RwLock::new(CachingResult::Caching {
                 ready: Event::new(),
             }),
         });
+        if let Some((values, source)) = feed {
+            match values
+                .iter()
+                .map(|(name, value)| Ok((name.as_str(), Value::try_from(value)?)))
+                .collect::<Result<HashMap<_, _>>>()
+            {
+                Ok(values) => {
+                    cache.update_cache(&uncached_properties, &values, &[], &interface);
+                    *cache.caching_result.write().expect("lock poisoned") =
+                        CachingResult::Cached { result: Ok(()) };
+
+                    return (cache, CacheUpdater::Feed { _source: source });
+                }
+                Err(e) => debug!("Failed to populate the properties cache: {e}"),
+            }
+        }
 
         let cache_clone = cache.clone();
         let task_name = format!("{interface} proxy caching");
@@ -316,7 +384,7 @@ impl PropertiesCache {
         .instrument(info_span!("{}", task_name));
         let task = executor.spawn(proxy_caching, &task_name);
 
-        (cache, task)
+        (cache, CacheUpdater::Task { _task: task })
     }
 
     /// new() runs this in a task it spawns for initialization of properties cache.
@@ -634,6 +702,45 @@ impl<'a> Proxy<'a> {
         &self.inner.interface
     }
 
+    /// A proxy sharing everything with `self`, except for the timeout of its method calls.
+    ///
+    /// This applies to the method calls made through the returned proxy, including the ones to
+    /// get and set properties, from sending the call to receiving the reply. It takes precedence
+    /// over a timeout set on the method through the [`macro@crate::proxy`] macro, which itself
+    /// takes precedence over the timeout of the connection (see
+    /// [`crate::connection::Builder::method_timeout`]). Calls that time out fail with an
+    /// [`Error::InputOutput`] error of kind [`std::io::ErrorKind::TimedOut`].
+    ///
+    /// This is cheap, so it can be used for a single call:
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # zbus::block_on(async {
+    /// # let proxy = zbus::fdo::DBusProxy::new(&zbus::Connection::session().await?).await?;
+    /// let id: String = proxy
+    ///     .inner()
+    ///     .with_timeout(Duration::from_secs(1))
+    ///     .call("GetId", &())
+    ///     .await?;
+    /// # drop(id);
+    /// # Ok::<(), zbus::Error>(())
+    /// # }).unwrap();
+    /// ```
+    ///
+    /// Dropping the future of a call before it completes cancels it: its reply is ignored and
+    /// the resources associated with it are released right away.
+    pub fn with_timeout(&self, timeout: Duration) -> Proxy<'a> {
+        Proxy {
+            inner: self.inner.clone(),
+            timeout: Some(timeout),
+        }
+    }
+
+    /// The timeout set through [`Proxy::with_timeout`], if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
     /// Introspect the associated object, and return the XML description.
     ///
     /// See the [xml](https://docs.rs/zbus_xml) crate for parsing the
@@ -659,6 +766,10 @@ impl<'a> Proxy<'a> {
             // does not have properties
             .cache_properties(CacheProperties::No)
             .build_internal()
+            .map(|proxy| Proxy {
+                timeout: self.timeout,
+                ..proxy
+            })
             .unwrap()
             .into()
     }
@@ -674,6 +785,10 @@ impl<'a> Proxy<'a> {
             // does not have properties
             .cache_properties(CacheProperties::No)
             .build_internal()
+            .map(|proxy| Proxy {
+                timeout: self.timeout,
+                ..proxy
+            })
             .unwrap()
             .into()
     }
@@ -683,6 +798,19 @@ impl<'a> Proxy<'a> {
     /// Use PropertiesCache::ready() to wait for the cache to be populated and to get any errors
     /// encountered in the population.
     pub(crate) fn get_property_cache(&self) -> Option<&Arc<PropertiesCache>> {
+        self.init_property_cache(None)
+    }
+
+    /// Get the cache, starting it with the given property values if needed.
+    ///
+    /// This is for when the property values are already known and their changes are tracked by
+    /// `source`, so the cache neither fetches them nor subscribes to their changes. Instead, the
+    /// proxy keeps `source` alive and the changes are to be applied through
+    /// [`Proxy::properties_feed`].
+    pub(crate) fn init_property_cache(
+        &self,
+        feed: Option<(&HashMap<String, OwnedValue>, FeedSource)>,
+    ) -> Option<&Arc<PropertiesCache>> {
         let cache = match &self.inner.property_cache {
             Some(cache) => cache,
             None => return None,
@@ -698,12 +826,31 @@ impl<'a> Proxy<'a> {
                 .collect();
             let executor = self.connection().executor();
 
-            PropertiesCache::new(proxy, interface, executor, uncached_properties)
+            PropertiesCache::new(proxy, interface, executor, uncached_properties, feed)
         });
 
         Some(cache)
     }
 
+    /// The feed of the properties cache, if it's fed by someone else than the proxy.
+    pub(crate) fn properties_feed(&self) -> Option<PropertiesFeed> {
+        let (cache, updater) = self.inner.property_cache.as_ref()?.get()?;
+        if !matches!(updater, CacheUpdater::Feed { .. }) {
+            return None;
+        }
+
+        Some(PropertiesFeed {
+            cache: Arc::downgrade(cache),
+            interface: self.interface().to_owned(),
+            uncached_properties: self
+                .inner
+                .uncached_properties
+                .iter()
+                .map(|s| s.to_owned())
+                .collect(),
+        })
+    }
+
     /// Get the cached value of the property `property_name`.
     ///
     /// This returns `None` if the property is not in the cache.  This could be because the cache
@@ -820,17 +967,9 @@ impl<'a> Proxy<'a> {
         M::Error: Into<Error>,
         B: serde::ser::Serialize + zvariant::DynamicType,
     {
-        self.inner
-            .inner_without_borrows
-            .conn
-            .call_method(
-                Some(&self.inner.destination),
-                self.inner.path.as_str(),
-                Some(&self.inner.interface),
-                method_name,
-                body,
-            )
+        self.call_method_with_flags(method_name, BitFlags::empty(), body)
             .await
+            .map(|reply| reply.expect("no reply"))
     }
 
     /// Call a method and return the reply body.
@@ -873,24 +1012,50 @@ impl<'a> Proxy<'a> {
     {
         let flags = flags.iter().map(Flags::from).collect::<BitFlags<_>>();
         match self
-            .inner
-            .inner_without_borrows
-            .conn
-            .call_method_raw(
-                Some(self.destination()),
-                self.path(),
-                Some(self.interface()),
-                method_name,
-                flags,
-                body,
-            )
+            .call_method_with_flags(method_name, flags, body)
             .await?
         {
-            Some(reply) => reply.await?.body().deserialize().map(Some),
+            Some(reply) => reply.body().deserialize().map(Some),
             None => Ok(None),
         }
     }
 
+    // Call a method, with the timeout that applies to `self`.
+    async fn call_method_with_flags<'m, M, B>(
+        &self,
+        method_name: M,
+        flags: BitFlags<Flags>,
+        body: &B,
+    ) -> Result<Option<Message>>
+    where
+        M: TryInto<MemberName<'m>>,
+        M::Error: Into<Error>,
+        B: serde::ser::Serialize + zvariant::DynamicType,
+    {
+        let conn = &self.inner.inner_without_borrows.conn;
+        let call = async {
+            match conn
+                .call_method_raw(
+                    Some(self.destination()),
+                    self.path(),
+                    Some(self.interface()),
+                    method_name,
+                    flags,
+                    body,
+                )
+                .await?
+            {
+                Some(reply) => reply.await.map(Some),
+                None => Ok(None),
+            }
+        };
+
+        match self.timeout.or_else(|| conn.method_timeout()) {
+            Some(t) => timeout(call, t).await,
+            None => call.await,
+        }
+    }
+
     /// Call a method without expecting a reply.
     ///
     /// This sets the `NoReplyExpected` flag on the calling message and does not wait for a reply.
@@ -0,0 +1,133 @@
+use async_trait::async_trait;
+use ntest::timeout;
+use test_log::test;
+use zbus::{
+    block_on,
+    connection::Builder,
+    fdo, interface,
+    object_server::{AccessPolicy, AccessRequest},
+    proxy, Result,
+};
+
+struct Vault;
+
+#[interface(name = "org.zbus.AccessTest")]
+impl Vault {
+    fn open(&self) -> &str {
+        "open"
+    }
+
+    fn secret(&self) -> &str {
+        "secret"
+    }
+}
+
+#[proxy(
+    interface = "org.zbus.AccessTest",
+    default_service = "org.zbus.AccessTest"
+)]
+trait Vault {
+    fn open(&self) -> Result<String>;
+
+    fn secret(&self) -> Result<String>;
+}
+
+// Only allow our own process, and not the secrets.
+fn no_secrets(req: &AccessRequest<'_>) -> fdo::Result<()> {
+    assert_eq!(req.interface(), "org.zbus.AccessTest");
+    if req.credentials().process_id() == Some(std::process::id()) && req.member() != "Secret" {
+        Ok(())
+    } else {
+        Err(fdo::Error::AccessDenied(format!(
+            "`{}` denied",
+            req.member()
+        )))
+    }
+}
+
+#[derive(Debug)]
+struct AllowAll;
+
+#[async_trait]
+impl AccessPolicy for AllowAll {
+    async fn check(&self, req: &AccessRequest<'_>) -> fdo::Result<()> {
+        assert!(req.header().sender().is_some());
+
+        Ok(())
+    }
+}
+
+fn assert_denied(res: Result<String>) {
+    match res {
+        Err(zbus::Error::MethodError(name, _, _))
+            if name == "org.freedesktop.DBus.Error.AccessDenied" => {}
+        res => panic!("expected an access denied error, got {res:?}"),
+    }
+}
+
+#[test]
+#[timeout(15000)]
+fn access_policy() {
+    block_on(async {
+        let service = Builder::session()?
+            .name("org.zbus.AccessTest")?
+            .serve_at("/org/zbus/Vault", Vault)?
+            .serve_at("/org/zbus/Vault/Shared", Vault)?
+            .access_policy("/org/zbus/Vault", no_secrets)?
+            .build()
+            .await?;
+        let client = Builder::session()?.build().await?;
+        let vault = VaultProxy::new(&client, "/org/zbus/Vault").await?;
+        assert_eq!(vault.open().await?, "open");
+        assert_denied(vault.secret().await);
+
+        // The policy applies to the whole subtree, unless overridden.
+        let shared = VaultProxy::new(&client, "/org/zbus/Vault/Shared").await?;
+        assert_denied(shared.secret().await);
+        service
+            .object_server()
+            .set_access_policy("/org/zbus/Vault/Shared", AllowAll)?;
+        assert_eq!(shared.secret().await?, "secret");
+        assert_denied(vault.secret().await);
+
+        assert!(service
+            .object_server()
+            .remove_access_policy("/org/zbus/Vault")?);
+        assert!(!service
+            .object_server()
+            .remove_access_policy("/org/zbus/Vault")?);
+        assert_eq!(vault.secret().await?, "secret");
+
+        Ok::<(), zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[cfg(all(unix, feature = "p2p"))]
+#[test]
+#[timeout(15000)]
+fn p2p_access_policy() {
+    #[cfg(not(feature = "tokio"))]
+    use std::os::unix::net::UnixStream;
+    #[cfg(feature = "tokio")]
+    use tokio::net::UnixStream;
+
+    block_on(async {
+        let (p0, p1) = UnixStream::pair().unwrap();
+        let service = Builder::unix_stream(p0)
+            .server(zbus::Guid::generate())?
+            .p2p()
+            .serve_at("/org/zbus/Vault", Vault)?
+            .access_policy("/", no_secrets)?
+            .build();
+        let client = Builder::unix_stream(p1).p2p().build();
+        let (_service, client) = futures_util::try_join!(service, client)?;
+
+        let vault = VaultProxy::new(&client, "/org/zbus/Vault").await?;
+        assert_eq!(vault.open().await?, "open");
+        assert_denied(vault.secret().await);
+
+        Ok::<(), zbus::Error>(())
+    })
+    .unwrap();
+}
@@ -0,0 +1,306 @@
+#![cfg(all(unix, feature = "bus-impl"))]
+
+use std::path::Path;
+
+use enumflags2::BitFlags;
+use futures_util::StreamExt;
+use ntest::timeout;
+use test_log::test;
+use zbus::{
+    block_on,
+    bus::Bus,
+    connection::Builder,
+    fdo::{self, DBusProxy, ReleaseNameReply, RequestNameFlags, RequestNameReply},
+    interface,
+    names::WellKnownName,
+    proxy, Address, Connection, MatchRule, MessageStream, Result,
+};
+
+fn start_bus(dir: &Path) -> Address {
+    let bus = Bus::unix_path(dir.join("bus")).unwrap();
+    let address = bus.address().clone();
+    std::thread::spawn(move || block_on(bus.run()));
+
+    address
+}
+
+async fn connect(address: &Address) -> Result<Connection> {
+    Builder::address(address.clone())?.build().await
+}
+
+#[test]
+#[timeout(15000)]
+fn hello() {
+    let dir = tempfile::tempdir().unwrap();
+    let address = start_bus(dir.path());
+
+    block_on(async {
+        let conn = connect(&address).await?;
+        let unique_name = conn.unique_name().unwrap().clone();
+
+        // Calling `Hello` a second time is not allowed.
+        let res = conn
+            .call_method(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                Some("org.freedesktop.DBus"),
+                "Hello",
+                &(),
+            )
+            .await;
+        assert!(matches!(res, Err(zbus::Error::MethodError(..))));
+
+        let dbus = DBusProxy::new(&conn).await?;
+        assert_eq!(dbus.get_id().await?, *address.guid().unwrap());
+        let names = dbus.list_names().await?;
+        assert!(names.iter().any(|n| n.as_str() == unique_name.as_str()));
+        assert!(names.iter().any(|n| *n == "org.freedesktop.DBus"));
+        let owner = dbus.get_name_owner(unique_name.as_ref().into()).await?;
+        assert_eq!(owner, unique_name);
+        let uid = dbus
+            .get_connection_unix_user(unique_name.as_ref().into())
+            .await?;
+        assert_eq!(uid, rustix::process::geteuid().as_raw());
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[timeout(15000)]
+fn name_ownership() {
+    let dir = tempfile::tempdir().unwrap();
+    let address = start_bus(dir.path());
+
+    block_on(async {
+        let name = WellKnownName::from_static_str("org.zbus.BusTest")?;
+        let conn1 = connect(&address).await?;
+        let conn2 = connect(&address).await?;
+        let dbus1 = DBusProxy::new(&conn1).await?;
+        let dbus2 = DBusProxy::new(&conn2).await?;
+        let mut owner_changes = dbus2
+            .receive_name_owner_changed_with_args(&[(0, name.as_str())])
+            .await?;
+        let mut acquired = dbus2.receive_name_acquired().await?;
+
+        let reply = dbus1.request_name(name.clone(), BitFlags::empty()).await?;
+        assert_eq!(reply, RequestNameReply::PrimaryOwner);
+        let change = owner_changes.next().await.unwrap();
+        let args = change.args()?;
+        assert!(args.old_owner().is_none());
+        assert_eq!(
+            args.new_owner().as_ref(),
+            conn1.unique_name().map(|n| n.inner())
+        );
+
+        // `conn1` didn't allow replacement, so `conn2` is queued.
+        let flags = RequestNameFlags::ReplaceExisting.into();
+        let reply = dbus2.request_name(name.clone(), flags).await?;
+        assert_eq!(reply, RequestNameReply::InQueue);
+        let reply = dbus2
+            .request_name(name.clone(), RequestNameFlags::DoNotQueue.into())
+            .await?;
+        assert_eq!(reply, RequestNameReply::Exists);
+        let reply = dbus2.request_name(name.clone(), flags).await?;
+        assert_eq!(reply, RequestNameReply::InQueue);
+        let owners = dbus2.list_queued_owners(name.clone()).await?;
+        assert_eq!(
+            owners.iter().collect::<Vec<_>>(),
+            [conn1.unique_name().unwrap(), conn2.unique_name().unwrap()]
+        );
+
+        // Once `conn1` releases the name, `conn2` gets it.
+        let reply = dbus2.release_name(name.clone()).await;
+        assert_eq!(reply?, ReleaseNameReply::Released);
+        let reply = dbus2.request_name(name.clone(), flags).await?;
+        assert_eq!(reply, RequestNameReply::InQueue);
+        let reply = dbus1.release_name(name.clone()).await?;
+        assert_eq!(reply, ReleaseNameReply::Released);
+        let change = owner_changes.next().await.unwrap();
+        let new_owner = change.args()?.new_owner().as_ref().map(|n| n.to_owned());
+        assert_eq!(new_owner.as_ref(), conn2.unique_name().map(|n| n.inner()));
+        loop {
+            let signal = acquired.next().await.unwrap();
+            if signal.args()?.name() == &name {
+                break;
+            }
+        }
+        assert!(dbus1.name_has_owner(name.clone().into()).await?);
+
+        // Names are released when the owner disconnects.
+        let dbus3 = DBusProxy::new(&conn1).await?;
+        let mut owner_changes = dbus3
+            .receive_name_owner_changed_with_args(&[(0, name.as_str())])
+            .await?;
+        drop(dbus2);
+        drop(acquired);
+        conn2.close().await?;
+        let change = owner_changes.next().await.unwrap();
+        assert!(change.args()?.new_owner().is_none());
+        assert!(!dbus1.name_has_owner(name.clone().into()).await?);
+
+        let res = dbus1
+            .request_name(
+                WellKnownName::from_static_str("org.freedesktop.DBus")?,
+                BitFlags::empty(),
+            )
+            .await;
+        assert!(matches!(res, Err(fdo::Error::InvalidArgs(_))));
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
+
+struct Greeter;
+
+#[interface(name = "org.zbus.BusTest.Greeter")]
+impl Greeter {
+    fn greet(&self, name: &str) -> String {
+        format!("Hello {name}!")
+    }
+
+    #[zbus(signal)]
+    async fn greeted(emitter: &zbus::object_server::SignalEmitter<'_>, name: &str) -> Result<()>;
+}
+
+#[proxy(
+    interface = "org.zbus.BusTest.Greeter",
+    default_service = "org.zbus.BusTest.Greeter",
+    default_path = "/org/zbus/Greeter"
+)]
+trait Greeter {
+    fn greet(&self, name: &str) -> Result<String>;
+
+    #[zbus(signal)]
+    fn greeted(&self, name: &str) -> Result<()>;
+}
+
+#[test]
+#[timeout(15000)]
+fn routing() {
+    let dir = tempfile::tempdir().unwrap();
+    let address = start_bus(dir.path());
+
+    block_on(async {
+        let service = Builder::address(address.clone())?
+            .name("org.zbus.BusTest.Greeter")?
+            .serve_at("/org/zbus/Greeter", Greeter)?
+            .build()
+            .await?;
+        let client = connect(&address).await?;
+
+        let proxy = GreeterProxy::new(&client).await?;
+        assert_eq!(proxy.greet("bus").await?, "Hello bus!");
+
+        // Signals are routed based on match rules, including ones with a well-known sender.
+        let mut greeted = proxy.receive_greeted().await?;
+        let rule = MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .sender("org.zbus.BusTest.Greeter")?
+            .build();
+        let mut from_greeter = MessageStream::for_match_rule(rule, &client, None).await?;
+        let iface = service
+            .object_server()
+            .interface::<_, Greeter>("/org/zbus/Greeter")
+            .await?;
+        Greeter::greeted(iface.signal_emitter(), "bus").await?;
+        let signal = greeted.next().await.unwrap();
+        assert_eq!(signal.args()?.name(), &"bus");
+        let msg = from_greeter.next().await.unwrap()?;
+        assert_eq!(
+            msg.header().sender(),
+            service.unique_name().map(|n| n.inner())
+        );
+
+        // Calls to names without an owner fail.
+        let proxy = GreeterProxy::builder(&client)
+            .destination("org.zbus.BusTest.Nobody")?
+            .build()
+            .await?;
+        let res = proxy.greet("nobody").await;
+        match res {
+            Err(zbus::Error::MethodError(name, _, _)) => {
+                assert_eq!(name, "org.freedesktop.DBus.Error.ServiceUnknown")
+            }
+            res => panic!("unexpected result: {res:?}"),
+        }
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[timeout(15000)]
+fn reconnect() {
+    use zbus::connection::ConnectionState;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bus");
+    // A bus that can be stopped, dropping all its peers.
+    let start = |path: &Path| {
+        let bus = Bus::unix_path(path).unwrap();
+        let (run, handle) = futures_util::future::abortable(bus.run());
+        let thread = std::thread::spawn(move || {
+            let _ = block_on(run);
+        });
+
+        (handle, thread)
+    };
+    let (bus, thread) = start(&path);
+    let address = Address::try_from(format!("unix:path={}", path.display()).as_str()).unwrap();
+
+    block_on(async {
+        let service = Builder::address(address.clone())?
+            .name("org.zbus.BusTest.Greeter")?
+            .serve_at("/org/zbus/Greeter", Greeter)?
+            .auto_reconnect(true)
+            .build()
+            .await?;
+        let listener = Builder::address(address.clone())?
+            .auto_reconnect(true)
+            .build()
+            .await?;
+        let mut service_states = service.receive_state_changes();
+        let mut listener_states = listener.receive_state_changes();
+        let rule = MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface("org.zbus.BusTest.Greeter")?
+            .build();
+        let mut greeted = MessageStream::for_match_rule(rule, &listener, None).await?;
+
+        // Restart the bus.
+        bus.abort();
+        thread.join().unwrap();
+        for states in [&mut service_states, &mut listener_states] {
+            assert_eq!(states.next().await, Some(ConnectionState::Disconnected));
+        }
+        // The socket file is removed by the bus on shutdown.
+        assert!(!path.exists());
+        let (bus, thread) = start(&path);
+        // Likely to take the previous unique name of the service.
+        let client = connect(&address).await?;
+        for states in [&mut service_states, &mut listener_states] {
+            assert_eq!(states.next().await, Some(ConnectionState::Reconnected));
+        }
+
+        // The name and the objects of the service, and the signal streams are all restored.
+        let proxy = GreeterProxy::new(&client).await?;
+        assert_eq!(proxy.greet("again").await?, "Hello again!");
+        let iface = service
+            .object_server()
+            .interface::<_, Greeter>("/org/zbus/Greeter")
+            .await?;
+        Greeter::greeted(iface.signal_emitter(), "again").await?;
+        let msg = greeted.next().await.unwrap()?;
+        assert_eq!(msg.body().deserialize::<&str>()?, "again");
+
+        bus.abort();
+        thread.join().unwrap();
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
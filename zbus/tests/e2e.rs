@@ -1,12 +1,10 @@
 #![allow(clippy::disallowed_names)]
 
 mod iface_and_proxy;
+#[cfg(unix)]
+mod method_calls;
 
-#[cfg(all(unix, not(feature = "tokio"), feature = "p2p"))]
-use std::os::unix::net::UnixStream;
 use std::time::Duration;
-#[cfg(all(unix, feature = "tokio", feature = "p2p"))]
-use tokio::net::UnixStream;
 
 use ntest::timeout;
 use test_log::test;
@@ -34,6 +32,25 @@ fn iface_and_proxy_unix_p2p() {
     block_on(iface_and_proxy_(true));
 }
 
+/// The builders of the server and client connections of a p2p connection over a Unix socket pair.
+#[cfg(all(unix, feature = "p2p"))]
+fn unix_p2p_builders(guid: zbus::Guid<'_>) -> (connection::Builder<'_>, connection::Builder<'_>) {
+    #[cfg(not(feature = "tokio"))]
+    use std::os::unix::net::UnixStream;
+    #[cfg(feature = "tokio")]
+    use tokio::net::UnixStream;
+
+    let (p0, p1) = UnixStream::pair().unwrap();
+
+    (
+        connection::Builder::unix_stream(p0)
+            .server(guid)
+            .unwrap()
+            .p2p(),
+        connection::Builder::unix_stream(p1).p2p(),
+    )
+}
+
 #[instrument]
 async fn iface_and_proxy_(#[allow(unused)] p2p: bool) {
     let event = event_listener::Event::new();
@@ -59,15 +76,7 @@ async fn iface_and_proxy_(#[allow(unused)] p2p: bool) {
     let (service_conn_builder, client_conn_builder) = if p2p {
         #[cfg(unix)]
         {
-            let (p0, p1) = UnixStream::pair().unwrap();
-
-            (
-                connection::Builder::unix_stream(p0)
-                    .server(guid)
-                    .unwrap()
-                    .p2p(),
-                connection::Builder::unix_stream(p1).p2p(),
-            )
+            unix_p2p_builders(guid)
         }
 
         #[cfg(windows)]
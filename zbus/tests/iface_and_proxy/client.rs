@@ -171,6 +171,7 @@ pub async fn my_iface_test(conn: Connection, event: Event) -> zbus::Result<u32>
     proxy.test_no_reply().await?;
     proxy.test_no_autostart().await?;
     proxy.test_interactive_auth().await?;
+    proxy.test_timeout().await?;
 
     assert_eq!(proxy.r#let().await?, 0);
     proxy.set_let(1).await?;
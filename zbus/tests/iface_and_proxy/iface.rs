@@ -375,6 +375,12 @@ impl MyIface {
             .contains(zbus::message::Flags::AllowInteractiveAuth));
     }
 
+    #[instrument]
+    #[zbus(proxy(timeout = "10s"))]
+    fn test_timeout(&self) {
+        debug!("`TestTimeout` called");
+    }
+
     #[zbus(signal)]
     pub async fn alert_count(emitter: &SignalEmitter<'_>, val: u32) -> zbus::Result<()>;
 
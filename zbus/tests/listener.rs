@@ -0,0 +1,250 @@
+#![cfg(feature = "p2p")]
+
+use futures_util::{future::try_join, StreamExt};
+use ntest::timeout;
+use test_log::test;
+use zbus::{
+    address::transport::{Transport, UnixSocket},
+    block_on,
+    connection::Builder,
+    interface, proxy, Address, Connection, Listener, Result,
+};
+
+struct Greeter;
+
+#[interface(name = "org.zbus.ListenerTest.Greeter")]
+impl Greeter {
+    fn greet(&self, name: &str) -> String {
+        format!("Hello {name}!")
+    }
+}
+
+#[proxy(
+    interface = "org.zbus.ListenerTest.Greeter",
+    default_service = "org.zbus.ListenerTest",
+    default_path = "/org/zbus/Greeter"
+)]
+trait Greeter {
+    fn greet(&self, name: &str) -> Result<String>;
+}
+
+async fn connect(address: Address) -> Result<Connection> {
+    Builder::address(address)?.p2p().build().await
+}
+
+// Connect a client to the listener, and check that they can talk to each other.
+async fn check_listener(listener: &Listener) -> Result<()> {
+    let accept = async {
+        listener
+            .accept_builder()
+            .await?
+            .serve_at("/org/zbus/Greeter", Greeter)?
+            .build()
+            .await
+    };
+    let (client, server) = try_join(connect(listener.address().clone()), accept).await?;
+    assert_eq!(client.server_guid(), listener.guid());
+    assert_eq!(server.server_guid(), listener.guid());
+
+    let proxy = GreeterProxy::new(&client).await?;
+    assert_eq!(proxy.greet("listener").await?, "Hello listener!");
+
+    Ok(())
+}
+
+#[test]
+#[timeout(15000)]
+fn tcp() {
+    block_on(async {
+        let listener = Listener::bind("tcp:host=127.0.0.1,port=0").await?;
+        match listener.address().transport() {
+            Transport::Tcp(tcp) => assert_ne!(tcp.port(), 0),
+            transport => panic!("unexpected transport `{transport}`"),
+        }
+        check_listener(&listener).await?;
+
+        // Multiple peers through the stream of incoming connections.
+        let mut incoming = listener.incoming();
+        let clients = async {
+            let first = connect(listener.address().clone()).await?;
+            let second = connect(listener.address().clone()).await?;
+
+            Ok::<_, zbus::Error>((first, second))
+        };
+        let servers = async {
+            let first = incoming.next().await.unwrap()?;
+            let second = incoming.next().await.unwrap()?;
+
+            Ok::<_, zbus::Error>((first, second))
+        };
+        let (clients, servers) = try_join(clients, servers).await?;
+        for conn in [clients.0, clients.1, servers.0, servers.1] {
+            assert_eq!(conn.server_guid(), listener.guid());
+        }
+
+        Ok::<(), zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[timeout(15000)]
+fn address_fallback() {
+    block_on(async {
+        let listener = Listener::bind("tcp:host=127.0.0.1,port=0").await?;
+        let dead = "unix:path=/zbus-nonexistent/socket";
+
+        // The first address that works is used.
+        let client = async {
+            Builder::addresses([dead.try_into()?, listener.address().clone()])?
+                .connect_timeout(std::time::Duration::from_secs(5))
+                .p2p()
+                .build()
+                .await
+        };
+        let (client, server) = try_join(client, listener.accept()).await?;
+        assert_eq!(client.server_guid(), server.server_guid());
+
+        // Otherwise, the failures of all the addresses are reported.
+        let res = Builder::addresses([dead, "unix:path=/zbus-nonexistent/other"])?
+            .p2p()
+            .build()
+            .await;
+        match res {
+            Err(zbus::Error::Failure(e)) => {
+                assert!(e.contains("/zbus-nonexistent/socket"), "{e}");
+                assert!(e.contains("/zbus-nonexistent/other"), "{e}");
+            }
+            res => panic!("unexpected result: {res:?}"),
+        }
+
+        // A peer that accepts the connection but never completes the handshake is given up on
+        // after the connect timeout as well.
+        let silent = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let silent = format!("tcp:host=127.0.0.1,port={}", silent.local_addr()?.port());
+        let client = async {
+            Builder::addresses([silent.as_str().try_into()?, listener.address().clone()])?
+                .connect_timeout(std::time::Duration::from_millis(500))
+                .p2p()
+                .build()
+                .await
+        };
+        let (client, server) = try_join(client, listener.accept()).await?;
+        assert_eq!(client.server_guid(), server.server_guid());
+        let res = Builder::addresses([silent.as_str(), dead])?
+            .connect_timeout(std::time::Duration::from_millis(500))
+            .p2p()
+            .build()
+            .await;
+        match res {
+            Err(zbus::Error::Failure(e)) => assert!(e.contains("timed out"), "{e}"),
+            res => panic!("unexpected result: {res:?}"),
+        }
+
+        Ok::<(), zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[timeout(15000)]
+fn nonce_tcp() {
+    block_on(async {
+        let dir = tempfile::tempdir().unwrap();
+        let nonce_file = dir.path().join("nonce");
+        let address = format!(
+            "nonce-tcp:host=127.0.0.1,port=0,noncefile={}",
+            nonce_file.display()
+        );
+        let listener = Listener::bind(address.as_str()).await?;
+        assert_eq!(std::fs::read(&nonce_file).unwrap().len(), 16);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = std::fs::metadata(&nonce_file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        check_listener(&listener).await?;
+
+        // A peer that doesn't send the nonce doesn't hold back the others.
+        let port = match listener.address().transport() {
+            Transport::Tcp(tcp) => tcp.port(),
+            transport => panic!("unexpected transport `{transport}`"),
+        };
+        let _silent = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let mut incoming = listener.incoming();
+        let client = connect(listener.address().clone());
+        let server = async { incoming.next().await.unwrap() };
+        let (client, server) = try_join(client, server).await?;
+        assert_eq!(client.server_guid(), server.server_guid());
+        drop(incoming);
+
+        // A peer without the right nonce is rejected.
+        std::fs::write(&nonce_file, [0u8; 16]).unwrap();
+        let client = async {
+            // The server closes the socket, so the handshake fails.
+            assert!(connect(listener.address().clone()).await.is_err());
+
+            Ok::<_, zbus::Error>(())
+        };
+        let res = try_join(client, listener.accept()).await;
+        assert!(matches!(res, Err(zbus::Error::Handshake(_))), "{res:?}");
+
+        // The nonce file goes away with the listener.
+        drop(listener);
+        assert!(!nonce_file.exists());
+
+        Ok::<(), zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+#[timeout(15000)]
+fn unix() {
+    block_on(async {
+        let dir = tempfile::tempdir().unwrap();
+
+        // A socket file, removed along with the listener.
+        let path = dir.path().join("socket");
+        let listener = Listener::bind(format!("unix:path={}", path.display()).as_str()).await?;
+        check_listener(&listener).await?;
+        assert!(path.exists());
+        drop(listener);
+        assert!(!path.exists());
+
+        // A socket with a random name in a directory.
+        let address = format!("unix:dir={}", dir.path().display());
+        let listener = Listener::bind(address.as_str()).await?;
+        match listener.address().transport() {
+            Transport::Unix(unix) => match unix.path() {
+                UnixSocket::File(path) => assert!(path.starts_with(dir.path())),
+                path => panic!("unexpected socket `{path}`"),
+            },
+            transport => panic!("unexpected transport `{transport}`"),
+        }
+        check_listener(&listener).await?;
+
+        #[cfg(target_os = "linux")]
+        {
+            let address = format!("unix:tmpdir={}", dir.path().display());
+            let listener = Listener::bind(address.as_str()).await?;
+            match listener.address().transport() {
+                Transport::Unix(unix) => {
+                    assert!(matches!(unix.path(), UnixSocket::Abstract(_)))
+                }
+                transport => panic!("unexpected transport `{transport}`"),
+            }
+            check_listener(&listener).await?;
+        }
+
+        // Only listenable addresses are supported.
+        let res = Listener::bind("unixexec:path=/bin/true").await;
+        assert!(matches!(res, Err(zbus::Error::Unsupported)));
+
+        Ok::<(), zbus::Error>(())
+    })
+    .unwrap();
+}
@@ -0,0 +1,149 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures_util::StreamExt;
+use ntest::timeout;
+use test_log::test;
+use zbus::{
+    block_on, connection::MetricsSink, interface, proxy, Error, Guid, Message, MessageStream,
+    OwnedMatchRule, Result,
+};
+
+use crate::unix_p2p_builders;
+
+struct Service;
+
+#[interface(name = "org.zbus.MetricsTest")]
+impl Service {
+    fn fast(&self) -> u32 {
+        42
+    }
+
+    async fn slow(&self) {
+        futures_util::future::pending::<()>().await;
+    }
+
+    fn null(&self) -> zvariant::OwnedFd {
+        let file = std::fs::File::open("/dev/null").unwrap();
+
+        std::os::fd::OwnedFd::from(file).into()
+    }
+}
+
+#[proxy(
+    interface = "org.zbus.MetricsTest",
+    default_service = "org.zbus.MetricsTest",
+    default_path = "/org/zbus/MetricsTest"
+)]
+trait Service {
+    fn fast(&self) -> Result<u32>;
+
+    #[zbus(timeout = "100ms")]
+    fn slow(&self) -> Result<()>;
+
+    fn null(&self) -> Result<zvariant::OwnedFd>;
+}
+
+#[derive(Debug, Default)]
+struct Metrics {
+    sent: usize,
+    received: usize,
+    received_bytes: usize,
+    received_fds: usize,
+    // The queue depth and capacity of the unfiltered streams.
+    queued: Vec<(usize, usize)>,
+    dropped: usize,
+    // The member, and whether a reply was received.
+    calls: Vec<(String, bool)>,
+}
+
+#[derive(Debug, Default)]
+struct Sink(Mutex<Metrics>);
+
+impl MetricsSink for Sink {
+    fn message_sent(&self, _msg: &Message) {
+        self.0.lock().unwrap().sent += 1;
+    }
+
+    fn message_received(&self, msg: &Message) {
+        let mut metrics = self.0.lock().unwrap();
+        metrics.received += 1;
+        metrics.received_bytes += msg.data().len();
+        metrics.received_fds += msg.data().fds().len();
+    }
+
+    fn message_queued(&self, rule: Option<&OwnedMatchRule>, depth: usize, capacity: usize) {
+        if rule.is_none() {
+            self.0.lock().unwrap().queued.push((depth, capacity));
+        }
+    }
+
+    fn message_dropped(&self, _rule: Option<&OwnedMatchRule>, _msg: &Message) {
+        self.0.lock().unwrap().dropped += 1;
+    }
+
+    fn method_call_completed(&self, call: &Message, reply: Option<&Message>, latency: Duration) {
+        let header = call.header();
+        assert_eq!(header.interface().unwrap(), "org.zbus.MetricsTest");
+        if reply.is_some() {
+            assert!(latency < Duration::from_secs(5));
+        }
+        let member = header.member().unwrap().to_string();
+        self.0.lock().unwrap().calls.push((member, reply.is_some()));
+    }
+}
+
+#[test]
+#[timeout(15000)]
+fn metrics() {
+    block_on(async {
+        let sink = Arc::new(Sink::default());
+        let (service, client) = unix_p2p_builders(Guid::generate());
+        let (_service, client) = futures_util::try_join!(
+            service.serve_at("/org/zbus/MetricsTest", Service)?.build(),
+            client.max_queued(8).metrics_sink(sink.clone()).build(),
+        )?;
+        let proxy = ServiceProxy::new(&client).await?;
+
+        assert_eq!(proxy.fast().await?, 42);
+        {
+            let metrics = sink.0.lock().unwrap();
+            assert_eq!(metrics.sent, 1);
+            assert_eq!(metrics.received, 1);
+            assert!(metrics.received_bytes > 0);
+            assert_eq!(metrics.received_fds, 0);
+            assert_eq!(metrics.calls, [("Fast".to_string(), true)]);
+        }
+
+        drop(proxy.null().await?);
+        assert_eq!(sink.0.lock().unwrap().received_fds, 1);
+
+        // Timed out calls are reported without a reply.
+        assert!(proxy.slow().await.is_err());
+        {
+            let metrics = sink.0.lock().unwrap();
+            assert_eq!(metrics.calls.last().unwrap(), &("Slow".to_string(), false));
+            // No one listens to the unfiltered streams. The replies are only dropped from them
+            // after being handed over to the proxy, but that's long done after the timeout.
+            assert_eq!(metrics.dropped, 2);
+        }
+
+        // The queue depth of the unfiltered streams is reported against `max_queued`.
+        let mut stream = MessageStream::from(&client);
+        assert_eq!(proxy.fast().await?, 42);
+        stream.next().await.unwrap()?;
+        // Messages are handled one at a time, so the queuing of the previous reply is reported
+        // by the time the next one arrives.
+        drop(stream);
+        assert_eq!(proxy.fast().await?, 42);
+        let metrics = sink.0.lock().unwrap();
+        assert_eq!(metrics.queued, [(1, 8)]);
+        assert_eq!(metrics.sent, 5);
+        assert_eq!(metrics.received, 4);
+
+        Ok::<(), Error>(())
+    })
+    .unwrap();
+}
@@ -0,0 +1,5 @@
+#[cfg(feature = "p2p")]
+mod metrics;
+#[cfg(feature = "p2p")]
+mod timeout;
+mod trace;
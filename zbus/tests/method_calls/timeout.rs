@@ -0,0 +1,112 @@
+use std::{io::ErrorKind, sync::Arc, time::Duration};
+
+use event_listener::Event;
+use ntest::timeout;
+use test_log::test;
+use zbus::{block_on, interface, proxy, proxy::MethodFlags, Connection, Error, Guid, Result};
+
+use crate::unix_p2p_builders;
+
+struct Service {
+    // Notified to let the pending `Slow` calls reply.
+    release: Arc<Event>,
+}
+
+#[interface(name = "org.zbus.TimeoutTest")]
+impl Service {
+    async fn slow(&self) {
+        self.release.listen().await;
+    }
+
+    fn fast(&self) -> u32 {
+        42
+    }
+}
+
+#[proxy(
+    interface = "org.zbus.TimeoutTest",
+    default_service = "org.zbus.TimeoutTest",
+    default_path = "/org/zbus/TimeoutTest"
+)]
+trait Service {
+    #[zbus(timeout = "100ms")]
+    fn slow(&self) -> Result<()>;
+
+    #[zbus(name = "Slow")]
+    fn slow_without_timeout(&self) -> Result<()>;
+
+    fn fast(&self) -> Result<u32>;
+}
+
+async fn connect(release: Arc<Event>) -> Result<(Connection, Connection)> {
+    let (service, client) = unix_p2p_builders(Guid::generate());
+    let service = service
+        .serve_at("/org/zbus/TimeoutTest", Service { release })?
+        .build();
+    let client = client.method_timeout(Duration::from_secs(60)).build();
+
+    futures_util::try_join!(service, client)
+}
+
+fn assert_timed_out<T: std::fmt::Debug>(res: Result<T>) {
+    match res {
+        Err(Error::InputOutput(e)) if e.kind() == ErrorKind::TimedOut => (),
+        res => panic!("expected a timeout, got {res:?}"),
+    }
+}
+
+#[test]
+#[timeout(15000)]
+fn per_call_timeout() {
+    block_on(async {
+        let release = Arc::new(Event::new());
+        let (_service, client) = connect(release.clone()).await?;
+        let proxy = ServiceProxy::new(&client).await?;
+        assert_eq!(proxy.inner().timeout(), None);
+
+        // The timeout of the method overrides the one of the connection.
+        assert_timed_out(proxy.slow().await);
+
+        // And the timeout of the proxy overrides both.
+        let short = proxy.with_timeout(Duration::from_millis(50));
+        assert_eq!(short.inner().timeout(), Some(Duration::from_millis(50)));
+        assert_timed_out(short.slow_without_timeout().await);
+        let res = short
+            .inner()
+            .call_with_flags::<_, _, ()>("Slow", MethodFlags::AllowInteractiveAuth.into(), &())
+            .await;
+        assert_timed_out(res);
+        let long = proxy.with_timeout(Duration::from_secs(60));
+        assert_eq!(long.fast().await?, 42);
+
+        Ok::<(), Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[timeout(15000)]
+fn cancelled_calls() {
+    block_on(async {
+        let release = Arc::new(Event::new());
+        let (_service, client) = connect(release.clone()).await?;
+        let proxy = ServiceProxy::builder(&client)
+            .build()
+            .await?
+            .with_timeout(Duration::from_millis(10));
+
+        // Many more calls than the connection queues replies for, all timing out.
+        for _ in 0..32 {
+            assert_timed_out(proxy.slow_without_timeout().await);
+        }
+
+        // Their replies now arrive, with no one waiting for them anymore.
+        release.notify(usize::MAX);
+        let proxy = proxy.with_timeout(Duration::from_secs(5));
+        assert_eq!(proxy.fast().await?, 42);
+        assert_eq!(proxy.fast().await?, 42);
+
+        Ok::<(), Error>(())
+    })
+    .unwrap();
+}
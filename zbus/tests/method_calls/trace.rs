@@ -0,0 +1,124 @@
+use std::sync::{Arc, Mutex};
+
+use ntest::timeout;
+use test_log::test;
+use tracing::Span;
+use zbus::{
+    block_on,
+    connection::{Builder, TracePropagator},
+    interface, proxy, Error, Result,
+};
+#[cfg(feature = "p2p")]
+use zbus::{Connection, Guid};
+
+#[cfg(feature = "p2p")]
+use crate::unix_p2p_builders;
+
+const CONTEXT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+struct Service;
+
+#[interface(name = "org.zbus.TraceTest")]
+impl Service {
+    fn context(&self, #[zbus(trace_context)] context: &str) -> String {
+        context.to_string()
+    }
+}
+
+#[proxy(
+    interface = "org.zbus.TraceTest",
+    default_service = "org.zbus.TraceTest",
+    default_path = "/org/zbus/TraceTest"
+)]
+trait Service {
+    #[zbus(trace_context)]
+    fn context(&self) -> Result<String>;
+}
+
+#[derive(Debug, Default)]
+struct Propagator {
+    extracted: Mutex<Vec<String>>,
+}
+
+impl TracePropagator for Propagator {
+    fn inject(&self) -> Option<String> {
+        Some(CONTEXT.to_string())
+    }
+
+    fn extract(&self, context: &str, span: &Span) {
+        span.record("trace_context", context);
+        self.extracted.lock().unwrap().push(context.to_string());
+    }
+}
+
+#[test]
+#[timeout(15000)]
+fn bus() {
+    block_on(async {
+        let service = Arc::new(Propagator::default());
+        let _service_conn = Builder::session()?
+            .name("org.zbus.TraceTest")?
+            .serve_at("/org/zbus/TraceTest", Service)?
+            .trace_propagator(service.clone())
+            .build()
+            .await?;
+        let client = Builder::session()?
+            .trace_propagator(Arc::new(Propagator::default()))
+            .build()
+            .await?;
+
+        // The context is a regular argument, so the bus passes it through.
+        let context = ServiceProxy::new(&client).await?.context().await?;
+        assert_eq!(context, CONTEXT);
+        assert_eq!(*service.extracted.lock().unwrap(), [CONTEXT]);
+
+        Ok::<(), Error>(())
+    })
+    .unwrap();
+}
+
+#[cfg(feature = "p2p")]
+async fn connect(
+    service: Option<Arc<Propagator>>,
+    client: Option<Arc<Propagator>>,
+) -> Result<(Connection, Connection)> {
+    let (service_builder, mut client_builder) = unix_p2p_builders(Guid::generate());
+    let mut service_builder = service_builder.serve_at("/org/zbus/TraceTest", Service)?;
+    if let Some(propagator) = service {
+        service_builder = service_builder.trace_propagator(propagator);
+    }
+    if let Some(propagator) = client {
+        client_builder = client_builder.trace_propagator(propagator);
+    }
+
+    futures_util::try_join!(service_builder.build(), client_builder.build())
+}
+
+#[cfg(feature = "p2p")]
+#[test]
+#[timeout(15000)]
+fn trace_context() {
+    block_on(async {
+        // Both peers propagate the context.
+        let service = Arc::new(Propagator::default());
+        let (_service_conn, client) = connect(Some(service.clone()), Some(Arc::default())).await?;
+        let proxy = ServiceProxy::new(&client).await?;
+        assert_eq!(proxy.context().await?, CONTEXT);
+        assert_eq!(*service.extracted.lock().unwrap(), [CONTEXT]);
+
+        // The service doesn't care about the context.
+        let (_service_conn, client) = connect(None, Some(Arc::default())).await?;
+        let proxy = ServiceProxy::new(&client).await?;
+        assert_eq!(proxy.context().await?, CONTEXT);
+
+        // The client doesn't send any context.
+        let service = Arc::new(Propagator::default());
+        let (_service_conn, client) = connect(Some(service.clone()), None).await?;
+        let proxy = ServiceProxy::new(&client).await?;
+        assert_eq!(proxy.context().await?, "");
+        assert!(service.extracted.lock().unwrap().is_empty());
+
+        Ok::<(), Error>(())
+    })
+    .unwrap();
+}
@@ -0,0 +1,220 @@
+use futures_util::StreamExt;
+use ntest::timeout;
+use test_log::test;
+use zbus::{
+    block_on,
+    connection::Builder,
+    fdo,
+    object_server::{Expectation, MockInterface},
+    proxy, Connection, Result,
+};
+
+#[proxy(interface = "org.zbus.MockTest", default_path = "/org/zbus/MockTest")]
+trait MockTest {
+    fn add(&self, a: u32, b: u32) -> Result<u32>;
+
+    fn ping(&self) -> Result<()>;
+
+    fn lookup(&self, key: &str) -> Result<(String, bool)>;
+
+    #[zbus(property)]
+    fn level(&self) -> Result<u8>;
+    #[zbus(property)]
+    fn set_level(&self, level: u8) -> Result<()>;
+
+    #[zbus(signal)]
+    fn added(&self, sum: u32) -> Result<()>;
+}
+
+async fn serve(name: &str, mock: &MockInterface<MockTestProxy<'static>>) -> Result<Connection> {
+    Builder::session()?
+        .name(name)?
+        .serve_at("/org/zbus/MockTest", mock.clone())?
+        .build()
+        .await
+}
+
+async fn proxy<'p>(client: &Connection, name: &'p str) -> Result<MockTestProxy<'p>> {
+    MockTestProxy::builder(client)
+        .destination(name)?
+        .build()
+        .await
+}
+
+fn error_name<T: std::fmt::Debug>(res: Result<T>) -> String {
+    match res {
+        Err(zbus::Error::MethodError(name, _, _)) => name.to_string(),
+        res => panic!("unexpected result: {res:?}"),
+    }
+}
+
+#[test]
+#[timeout(15000)]
+fn methods() {
+    block_on(async {
+        let name = "org.zbus.MockTest.Methods";
+        let mock = MockInterface::<MockTestProxy<'static>>::new();
+        mock.expect(
+            Expectation::new("Add")?
+                .with_args((1u32, 2u32))
+                .returns(3u32),
+        );
+        mock.expect(
+            Expectation::new("Add")?
+                .with(|body| body.deserialize::<(u32, u32)>().is_ok_and(|(a, _)| a > 100))
+                .returns_error(fdo::Error::LimitsExceeded("Too big".into()))
+                .times(1),
+        );
+        mock.expect(Expectation::new("Ping")?.times(2));
+        mock.expect(Expectation::new("Lookup")?.returns(("value", true)));
+
+        let _service = serve(name, &mock).await?;
+        let client = Builder::session()?.build().await?;
+        let proxy = proxy(&client, name).await?;
+
+        assert_eq!(proxy.add(1, 2).await?, 3);
+        assert_eq!(proxy.add(1, 2).await?, 3);
+        let res = proxy.add(101, 2).await;
+        match res {
+            Err(zbus::Error::MethodError(name, description, _)) => {
+                assert_eq!(name, "org.freedesktop.DBus.Error.LimitsExceeded");
+                assert_eq!(description.as_deref(), Some("Too big"));
+            }
+            res => panic!("unexpected result: {res:?}"),
+        }
+        proxy.ping().await?;
+        proxy.ping().await?;
+        assert_eq!(proxy.lookup("key").await?, ("value".to_string(), true));
+
+        mock.verify()?;
+        assert_eq!(mock.call_count("Add"), 3);
+        assert_eq!(mock.call_count("Ping"), 2);
+        let calls = mock.calls();
+        assert_eq!(calls.len(), 6);
+        assert_eq!(calls[5].body().deserialize::<&str>()?, "key");
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[timeout(15000)]
+fn verify_failures() {
+    block_on(async {
+        let name = "org.zbus.MockTest.Failures";
+        let mock = MockInterface::<MockTestProxy<'static>>::new();
+        mock.expect(
+            Expectation::new("Add")?
+                .with_args((1u32, 2u32))
+                .returns(3u32),
+        );
+        mock.expect(Expectation::new("Ping")?.times(1));
+
+        let _service = serve(name, &mock).await?;
+        let client = Builder::session()?.build().await?;
+        let proxy = proxy(&client, name).await?;
+
+        // No expectation matching the arguments.
+        let res = proxy.add(2, 2).await;
+        assert_eq!(error_name(res), "org.freedesktop.DBus.Error.InvalidArgs");
+        // No expectation at all for the method.
+        let res = proxy.lookup("key").await;
+        assert_eq!(error_name(res), "org.freedesktop.DBus.Error.UnknownMethod");
+        // One call too many.
+        proxy.ping().await?;
+        proxy.ping().await?;
+
+        let Err(zbus::Error::Failure(report)) = mock.verify() else {
+            panic!("verification should fail");
+        };
+        assert!(report.contains("`Add` expected to be called"), "{report}");
+        assert!(
+            report.contains("`Ping` expected to be called 1 time(s)"),
+            "{report}"
+        );
+        assert!(report.contains("Unexpected call"), "{report}");
+        assert_eq!(report.lines().count(), 4, "{report}");
+
+        mock.reset();
+        mock.verify()?;
+        assert_eq!(mock.call_count("Ping"), 0);
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[timeout(15000)]
+fn properties_and_signals() {
+    block_on(async {
+        let name = "org.zbus.MockTest.Properties";
+        let mock = MockInterface::<MockTestProxy<'static>>::new();
+        mock.set_property("Level", 4u8)?;
+        mock.expect(
+            Expectation::new("Add")?
+                .returns(7u32)
+                .emits("Added", (7u32,))?
+                .emits("Added", (8u32,))?,
+        );
+
+        let service = serve(name, &mock).await?;
+        let client = Builder::session()?.build().await?;
+        let proxy = proxy(&client, name).await?;
+
+        assert_eq!(proxy.level().await?, 4);
+        let mut level_changed = proxy.receive_level_changed().await;
+        // The initial value.
+        assert_eq!(level_changed.next().await.unwrap().get().await?, 4);
+        proxy.set_level(5).await?;
+        assert_eq!(level_changed.next().await.unwrap().get().await?, 5);
+
+        let iface = service
+            .object_server()
+            .interface::<_, MockInterface<MockTestProxy<'static>>>("/org/zbus/MockTest")
+            .await?;
+        mock.change_property(iface.signal_emitter(), "Level", 6u8)
+            .await?;
+        assert_eq!(level_changed.next().await.unwrap().get().await?, 6);
+
+        // Scripted signals.
+        let mut added = proxy.receive_added().await?;
+        assert_eq!(proxy.add(3, 4).await?, 7);
+        assert_eq!(*added.next().await.unwrap().args()?.sum(), 7);
+        assert_eq!(*added.next().await.unwrap().args()?.sum(), 8);
+        mock.emit_signal(iface.signal_emitter(), "Added", &(9u32,))
+            .await?;
+        assert_eq!(*added.next().await.unwrap().args()?.sum(), 9);
+
+        // The introspection data reflects the configuration of the mock.
+        let introspectable = fdo::IntrospectableProxy::builder(&client)
+            .destination(name)?
+            .path("/org/zbus/MockTest")?
+            .build()
+            .await?;
+        let xml = introspectable.introspect().await?;
+        assert!(xml.contains(r#"<interface name="org.zbus.MockTest">"#));
+        assert!(xml.contains(r#"<method name="Add"/>"#));
+        assert!(xml.contains(r#"<property name="Level" type="y" access="readwrite"/>"#));
+
+        mock.verify()?;
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn invalid_configuration() -> Result<()> {
+    assert!(matches!(
+        Expectation::new("Not a method"),
+        Err(zbus::Error::Names(_))
+    ));
+    assert!(matches!(
+        Expectation::new("Add")?.emits("Not a signal", ()),
+        Err(zbus::Error::Names(_))
+    ));
+
+    Ok(())
+}
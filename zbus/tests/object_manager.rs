@@ -0,0 +1,267 @@
+use futures_util::StreamExt;
+use ntest::timeout;
+use test_log::test;
+use zbus::{
+    block_on,
+    connection::Builder,
+    fdo::{ObjectManager, ObjectManagerProxy},
+    interface,
+    message::Type,
+    object_server::Objects,
+    Connection, MatchRule, MessageStream, Result,
+};
+
+struct Device(u32);
+
+#[interface(name = "org.zbus.ObjectManagerTest.Device")]
+impl Device {
+    #[zbus(property)]
+    fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+struct Battery;
+
+#[interface(name = "org.zbus.ObjectManagerTest.Battery")]
+impl Battery {}
+
+const DEVICE: &str = "org.zbus.ObjectManagerTest.Device";
+const BATTERY: &str = "org.zbus.ObjectManagerTest.Battery";
+
+/// A received `InterfacesAdded` or `InterfacesRemoved` signal.
+#[derive(Debug, PartialEq)]
+struct Change {
+    manager: String,
+    added: bool,
+    object: String,
+    interfaces: Vec<String>,
+}
+
+fn change(manager: &str, added: bool, object: &str, interfaces: &[&str]) -> Change {
+    Change {
+        manager: manager.to_string(),
+        added,
+        object: object.to_string(),
+        interfaces: interfaces.iter().map(|i| i.to_string()).collect(),
+    }
+}
+
+async fn changes(service: &Connection, client: &Connection) -> Result<MessageStream> {
+    let rule = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender(service.unique_name().unwrap())?
+        .interface("org.freedesktop.DBus.ObjectManager")?
+        .build();
+
+    MessageStream::for_match_rule(rule, client, None).await
+}
+
+async fn next_change(stream: &mut MessageStream) -> Result<Change> {
+    let msg = stream.next().await.unwrap()?;
+    let header = msg.header();
+    let manager = header.path().unwrap().to_string();
+    let (added, object, mut interfaces) = match header.member().unwrap().as_str() {
+        "InterfacesAdded" => {
+            let signal = zbus::fdo::InterfacesAdded::from_message(msg.clone()).unwrap();
+            let args = signal.args()?;
+            let interfaces = args
+                .interfaces_and_properties()
+                .keys()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>();
+
+            (true, args.object_path().to_string(), interfaces)
+        }
+        "InterfacesRemoved" => {
+            let signal = zbus::fdo::InterfacesRemoved::from_message(msg.clone()).unwrap();
+            let args = signal.args()?;
+            let interfaces = args.interfaces().iter().map(|i| i.to_string()).collect();
+
+            (false, args.object_path().to_string(), interfaces)
+        }
+        member => panic!("unexpected signal `{member}`"),
+    };
+    interfaces.sort();
+
+    Ok(Change {
+        manager,
+        added,
+        object,
+        interfaces,
+    })
+}
+
+#[test]
+#[timeout(15000)]
+fn automatic_signals() {
+    block_on(async {
+        let service = Builder::session()?.build().await?;
+        let client = Builder::session()?.build().await?;
+        let mut stream = changes(&service, &client).await?;
+        let server = service.object_server();
+
+        // Objects added beneath a manager, including through intermediate nodes.
+        server.at("/org/zbus/om", ObjectManager).await?;
+        server.at("/org/zbus/om/a/dev", Device(1)).await?;
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om", true, "/org/zbus/om/a/dev", &[DEVICE])
+        );
+        server.at("/org/zbus/om/a/dev", Battery).await?;
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om", true, "/org/zbus/om/a/dev", &[BATTERY])
+        );
+
+        // A nested manager takes over the objects beneath it.
+        server.at("/org/zbus/om/a", ObjectManager).await?;
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change(
+                "/org/zbus/om",
+                false,
+                "/org/zbus/om/a/dev",
+                &[BATTERY, DEVICE]
+            )
+        );
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change(
+                "/org/zbus/om/a",
+                true,
+                "/org/zbus/om/a/dev",
+                &[BATTERY, DEVICE]
+            )
+        );
+        let inner = ObjectManagerProxy::builder(&client)
+            .destination(service.unique_name().unwrap())?
+            .path("/org/zbus/om/a")?
+            .build()
+            .await?;
+        let objects = inner.get_managed_objects().await?;
+        assert_eq!(objects.len(), 1);
+        let outer = ObjectManagerProxy::builder(&client)
+            .destination(service.unique_name().unwrap())?
+            .path("/org/zbus/om")?
+            .build()
+            .await?;
+        // Neither the intermediate node, nor the objects of the nested manager.
+        assert!(outer.get_managed_objects().await?.is_empty());
+
+        // Removing an interface.
+        assert!(!server.remove::<Battery, _>("/org/zbus/om/a/dev").await?);
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om/a", false, "/org/zbus/om/a/dev", &[BATTERY])
+        );
+
+        // Removing the nested manager hands the objects back to the outer one.
+        server.remove::<ObjectManager, _>("/org/zbus/om/a").await?;
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om/a", false, "/org/zbus/om/a/dev", &[DEVICE])
+        );
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om", true, "/org/zbus/om/a/dev", &[DEVICE])
+        );
+
+        // Destroying an object doesn't affect the objects beneath it.
+        server.at("/org/zbus/om/b", Device(2)).await?;
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om", true, "/org/zbus/om/b", &[DEVICE])
+        );
+        server.at("/org/zbus/om/b/c", Device(3)).await?;
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om", true, "/org/zbus/om/b/c", &[DEVICE])
+        );
+        assert!(server.remove::<Device, _>("/org/zbus/om/b").await?);
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om", false, "/org/zbus/om/b", &[DEVICE])
+        );
+        let objects = outer.get_managed_objects().await?;
+        let mut paths: Vec<_> = objects.keys().map(|p| p.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["/org/zbus/om/a/dev", "/org/zbus/om/b/c"]);
+        server.remove::<Device, _>("/org/zbus/om/b/c").await?;
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om", false, "/org/zbus/om/b/c", &[DEVICE])
+        );
+
+        // Unmanaged objects don't cause any signal, so the next one is for the managed object.
+        server.at("/org/zbus/unmanaged", Device(4)).await?;
+        server.remove::<Device, _>("/org/zbus/unmanaged").await?;
+        server.remove::<Device, _>("/org/zbus/om/a/dev").await?;
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om", false, "/org/zbus/om/a/dev", &[DEVICE])
+        );
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[timeout(15000)]
+fn batched_signals() {
+    block_on(async {
+        let service = Builder::session()?.build().await?;
+        let client = Builder::session()?.build().await?;
+        let mut stream = changes(&service, &client).await?;
+        let server = service.object_server();
+
+        let objects = Objects::new()
+            .at("/org/zbus/om/dev0", Device(0))?
+            .at("/org/zbus/om/dev0", Battery)?
+            .at("/org/zbus/om/dev1", Device(1))?
+            .at("/org/zbus/om", ObjectManager)?
+            .at("/org/zbus/om/nested", ObjectManager)?
+            .at("/org/zbus/om/nested/dev2", Device(2))?;
+        server.add_objects(objects).await?;
+        // One signal per object, each from its own manager.
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change(
+                "/org/zbus/om",
+                true,
+                "/org/zbus/om/dev0",
+                &[BATTERY, DEVICE]
+            )
+        );
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om", true, "/org/zbus/om/dev1", &[DEVICE])
+        );
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change(
+                "/org/zbus/om/nested",
+                true,
+                "/org/zbus/om/nested/dev2",
+                &[DEVICE]
+            )
+        );
+
+        // Nothing is added if any interface already exists.
+        let objects = Objects::new()
+            .at("/org/zbus/om/dev3", Device(3))?
+            .at("/org/zbus/om/dev1", Device(1))?;
+        let res = server.add_objects(objects).await;
+        assert!(matches!(res, Err(zbus::Error::InterfaceExists(_, _))));
+        assert!(!server.at("/org/zbus/om/dev1", Device(1)).await?);
+        server.at("/org/zbus/om/dev3", Battery).await?;
+        assert_eq!(
+            next_change(&mut stream).await?,
+            change("/org/zbus/om", true, "/org/zbus/om/dev3", &[BATTERY])
+        );
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
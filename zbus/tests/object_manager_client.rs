@@ -0,0 +1,252 @@
+use futures_util::StreamExt;
+use ntest::timeout;
+use test_log::test;
+use zbus::{
+    block_on,
+    connection::Builder,
+    fdo::{ObjectEvent, ObjectManager, ObjectManagerClient, ObjectManagerProxy},
+    interface,
+    object_server::Objects,
+    proxy,
+    zvariant::OwnedObjectPath,
+};
+
+struct Device(u32);
+
+#[interface(name = "org.zbus.ObjectManagerClientTest.Device")]
+impl Device {
+    #[zbus(property)]
+    fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+struct Battery;
+
+#[interface(name = "org.zbus.ObjectManagerClientTest.Battery")]
+impl Battery {}
+
+#[proxy(
+    interface = "org.zbus.ObjectManagerClientTest.Device",
+    default_service = "org.zbus.ObjectManagerClientTest"
+)]
+trait Device {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<u32>;
+}
+
+#[proxy(
+    interface = "org.zbus.ObjectManagerClientTest.Battery",
+    default_service = "org.zbus.ObjectManagerClientTest"
+)]
+trait Battery {}
+
+const DEVICE: &str = "org.zbus.ObjectManagerClientTest.Device";
+const BATTERY: &str = "org.zbus.ObjectManagerClientTest.Battery";
+
+fn path(path: &str) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(path).unwrap()
+}
+
+#[test]
+#[timeout(15000)]
+fn object_manager_client() {
+    block_on(async {
+        let service = Builder::session()?.build().await?;
+        let server = service.object_server();
+        let objects = Objects::new()
+            .at("/org/zbus/om", ObjectManager)?
+            .at("/org/zbus/om/dev0", Device(0))?
+            .at("/org/zbus/om/dev0", Battery)?
+            .at("/org/zbus/om/nested", ObjectManager)?
+            .at("/org/zbus/om/nested/dev1", Device(1))?;
+        server.add_objects(objects).await?;
+
+        let conn = Builder::session()?.build().await?;
+        let manager = ObjectManagerProxy::builder(&conn)
+            .destination(service.unique_name().unwrap())?
+            .path("/org/zbus/om")?
+            .build()
+            .await?;
+        let client = ObjectManagerClient::new(&manager).await?;
+        let mut events = client.receive_events();
+        let mut added = client.receive_added::<DeviceProxy<'static>>();
+        let mut removed = client.receive_removed::<DeviceProxy<'static>>();
+
+        // The initial objects, without the ones of the nested manager.
+        let objects = client.objects();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(client.object("/org/zbus/om/dev0").unwrap().len(), 2);
+        let devices = client.proxies::<DeviceProxy<'static>>();
+        assert_eq!(devices.len(), 1);
+        // Served from the cache of the client.
+        assert_eq!(devices[0].cached_id()?, Some(0));
+        assert!(matches!(
+            client.proxy::<DeviceProxy<'static>, _>("/org/zbus/om/nested/dev1"),
+            Err(zbus::Error::InterfaceNotFound)
+        ));
+
+        // New objects and interfaces.
+        server.at("/org/zbus/om/dev2", Device(2)).await?;
+        assert_eq!(
+            events.next().await.unwrap(),
+            ObjectEvent::Added {
+                path: path("/org/zbus/om/dev2"),
+                interfaces: vec![DEVICE.try_into()?],
+            }
+        );
+        let dev2 = added.next().await.unwrap();
+        assert_eq!(dev2.inner().path(), "/org/zbus/om/dev2");
+        assert_eq!(dev2.cached_id()?, Some(2));
+        server.at("/org/zbus/om/dev2", Battery).await?;
+        assert_eq!(
+            events.next().await.unwrap(),
+            ObjectEvent::InterfacesAdded {
+                path: path("/org/zbus/om/dev2"),
+                interfaces: vec![BATTERY.try_into()?],
+            }
+        );
+        client.proxy::<BatteryProxy<'static>, _>("/org/zbus/om/dev2")?;
+
+        // Property changes are tracked.
+        let iface_ref = server.interface::<_, Device>("/org/zbus/om/dev2").await?;
+        iface_ref.get_mut().await.0 = 3;
+        iface_ref
+            .get()
+            .await
+            .id_changed(iface_ref.signal_emitter())
+            .await?;
+        // Signals are handled in order, so once this one is seen, so is the property change.
+        server.at("/org/zbus/om/dev3", Battery).await?;
+        let event = events.next().await.unwrap();
+        assert_eq!(event.path(), &path("/org/zbus/om/dev3"));
+        let properties = &client.object("/org/zbus/om/dev2").unwrap()[DEVICE];
+        assert_eq!(u32::try_from(&properties["Id"])?, 3);
+        let device = client.proxy::<DeviceProxy<'static>, _>("/org/zbus/om/dev2")?;
+        assert_eq!(device.cached_id()?, Some(3));
+        // The existing proxies are updated along with the cache of the client.
+        assert_eq!(dev2.cached_id()?, Some(3));
+
+        // Removed interfaces and objects.
+        server.remove::<Device, _>("/org/zbus/om/dev2").await?;
+        assert_eq!(
+            events.next().await.unwrap(),
+            ObjectEvent::InterfacesRemoved {
+                path: path("/org/zbus/om/dev2"),
+                interfaces: vec![DEVICE.try_into()?],
+            }
+        );
+        assert_eq!(removed.next().await.unwrap(), path("/org/zbus/om/dev2"));
+        server.remove::<Battery, _>("/org/zbus/om/dev2").await?;
+        let event = events.next().await.unwrap();
+        assert!(matches!(event, ObjectEvent::Removed { .. }));
+        assert_eq!(event.path(), &path("/org/zbus/om/dev2"));
+        assert!(client.object("/org/zbus/om/dev2").is_none());
+        server.remove::<Device, _>("/org/zbus/om/dev0").await?;
+        assert_eq!(removed.next().await.unwrap(), path("/org/zbus/om/dev0"));
+        assert_eq!(client.objects().len(), 2);
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[timeout(15000)]
+fn object_manager_client_stalled_stream() {
+    block_on(async {
+        let service = Builder::session()?.build().await?;
+        let server = service.object_server();
+        server.at("/org/zbus/om", ObjectManager).await?;
+
+        let conn = Builder::session()?.build().await?;
+        let manager = ObjectManagerProxy::builder(&conn)
+            .destination(service.unique_name().unwrap())?
+            .path("/org/zbus/om")?
+            .build()
+            .await?;
+        let client = ObjectManagerClient::new(&manager).await?;
+        // Never polled.
+        let _stalled = client.receive_events();
+        let mut events = client.receive_events();
+
+        // More events than the streams can hold.
+        for i in 0..100 {
+            server.at(format!("/org/zbus/om/dev{i}"), Device(i)).await?;
+        }
+        let last = path("/org/zbus/om/dev99");
+        while let Some(event) = events.next().await {
+            if event.path() == &last {
+                break;
+            }
+        }
+        assert_eq!(client.objects().len(), 100);
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+#[timeout(15000)]
+fn object_manager_client_service_restart() {
+    block_on(async {
+        const NAME: &str = "org.zbus.ObjectManagerClientTest.Restart";
+        let service = Builder::session()?
+            .serve_at("/org/zbus/om", ObjectManager)?
+            .serve_at("/org/zbus/om/dev0", Device(0))?
+            .name(NAME)?
+            .build()
+            .await?;
+
+        let conn = Builder::session()?.build().await?;
+        let manager = ObjectManagerProxy::builder(&conn)
+            .destination(NAME)?
+            .path("/org/zbus/om")?
+            .build()
+            .await?;
+        let client = ObjectManagerClient::new(&manager).await?;
+        let mut events = client.receive_events();
+        assert_eq!(client.objects().len(), 1);
+
+        // The objects of the old instance are gone along with it.
+        service.close().await?;
+        assert_eq!(
+            events.next().await.unwrap(),
+            ObjectEvent::Removed {
+                path: path("/org/zbus/om/dev0"),
+                interfaces: vec![DEVICE.try_into()?],
+            }
+        );
+        assert!(client.objects().is_empty());
+
+        // The objects of the new instance are fetched.
+        let service = Builder::session()?
+            .serve_at("/org/zbus/om", ObjectManager)?
+            .serve_at("/org/zbus/om/dev1", Device(1))?
+            .name(NAME)?
+            .build()
+            .await?;
+        assert_eq!(
+            events.next().await.unwrap(),
+            ObjectEvent::Added {
+                path: path("/org/zbus/om/dev1"),
+                interfaces: vec![DEVICE.try_into()?],
+            }
+        );
+        let device = client.proxy::<DeviceProxy<'static>, _>("/org/zbus/om/dev1")?;
+        assert_eq!(device.cached_id()?, Some(1));
+
+        // And kept up to date.
+        service
+            .object_server()
+            .at("/org/zbus/om/dev2", Device(2))
+            .await?;
+        let event = events.next().await.unwrap();
+        assert!(matches!(event, ObjectEvent::Added { .. }));
+        assert_eq!(event.path(), &path("/org/zbus/om/dev2"));
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
@@ -0,0 +1,131 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use ntest::timeout;
+use test_log::test;
+use zbus::{
+    block_on,
+    connection::Builder,
+    interface,
+    polkit::{CheckAuthorizationFlags, Subject},
+    proxy, Result,
+};
+
+// A stand-in for the polkit authority, recording the subjects it's asked about.
+#[derive(Default)]
+struct Authority {
+    subjects: Arc<Mutex<Vec<String>>>,
+}
+
+#[interface(name = "org.freedesktop.PolicyKit1.Authority")]
+impl Authority {
+    fn check_authorization(
+        &self,
+        subject: Subject,
+        action_id: &str,
+        _details: HashMap<String, String>,
+        flags: u32,
+        _cancellation_id: &str,
+    ) -> ((bool, bool, HashMap<String, String>),) {
+        assert_eq!(subject.kind(), "system-bus-name");
+        let name = String::try_from(subject.details()["name"].clone()).unwrap();
+        self.subjects.lock().unwrap().push(name);
+
+        let interactive = flags & CheckAuthorizationFlags::AllowUserInteraction as u32 != 0;
+        let (authorized, challenge) = match action_id {
+            "org.zbus.polkit.allowed" => (true, false),
+            "org.zbus.polkit.auth" => (interactive, !interactive),
+            _ => (false, false),
+        };
+
+        ((authorized, challenge, HashMap::new()),)
+    }
+}
+
+struct Service;
+
+#[interface(name = "org.zbus.PolkitTest")]
+impl Service {
+    #[zbus(polkit_action = "org.zbus.polkit.allowed")]
+    fn allowed(&self, arg: u32) -> u32 {
+        arg
+    }
+
+    #[zbus(polkit_action = "org.zbus.polkit.auth")]
+    async fn auth(&self) -> &str {
+        "authenticated"
+    }
+
+    #[zbus(polkit_action = "org.zbus.polkit.denied")]
+    fn denied(&mut self) {
+        unreachable!("not authorized");
+    }
+}
+
+#[proxy(
+    interface = "org.zbus.PolkitTest",
+    default_service = "org.zbus.PolkitTest",
+    default_path = "/org/zbus/PolkitTest"
+)]
+trait Service {
+    fn allowed(&self, arg: u32) -> Result<u32>;
+
+    fn auth(&self) -> Result<String>;
+
+    #[zbus(name = "Auth", allow_interactive_auth)]
+    fn auth_interactive(&self) -> Result<String>;
+
+    fn denied(&self) -> Result<()>;
+}
+
+fn assert_error<T: std::fmt::Debug>(res: Result<T>, expected: &str) {
+    match res {
+        Err(zbus::Error::MethodError(name, _, _)) if name == expected => {}
+        res => panic!("expected a `{expected}` error, got {res:?}"),
+    }
+}
+
+#[test]
+#[timeout(15000)]
+fn polkit_action() {
+    block_on(async {
+        let subjects = Arc::new(Mutex::new(vec![]));
+        let authority = Authority {
+            subjects: subjects.clone(),
+        };
+        let _authority = Builder::session()?
+            .name("org.freedesktop.PolicyKit1")?
+            .serve_at("/org/freedesktop/PolicyKit1/Authority", authority)?
+            .build()
+            .await?;
+        let _service = Builder::session()?
+            .name("org.zbus.PolkitTest")?
+            .serve_at("/org/zbus/PolkitTest", Service)?
+            .build()
+            .await?;
+        let client = Builder::session()?.build().await?;
+        let proxy = ServiceProxy::new(&client).await?;
+
+        assert_eq!(proxy.allowed(42).await?, 42);
+        assert_eq!(
+            *subjects.lock().unwrap(),
+            [client.unique_name().unwrap().to_string()]
+        );
+
+        assert_error(
+            proxy.auth().await,
+            "org.freedesktop.DBus.Error.InteractiveAuthorizationRequired",
+        );
+        assert_eq!(proxy.auth_interactive().await?, "authenticated");
+
+        assert_error(
+            proxy.denied().await,
+            "org.freedesktop.DBus.Error.AccessDenied",
+        );
+
+        Ok::<(), zbus::Error>(())
+    })
+    .unwrap();
+}
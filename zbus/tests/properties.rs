@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use futures_util::StreamExt;
+use ntest::timeout;
+use test_log::test;
+use zbus::{block_on, connection::Builder, fdo, interface, zvariant::Value};
+
+struct Player {
+    title: String,
+    position: u64,
+    metadata: Vec<String>,
+    track_count: u32,
+}
+
+#[interface(name = "org.zbus.PropertiesTest.Player")]
+impl Player {
+    #[zbus(property)]
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    #[zbus(property)]
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    #[zbus(property(emits_changed_signal = "invalidates"))]
+    fn metadata(&self) -> Vec<String> {
+        self.metadata.clone()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn track_count(&self) -> u32 {
+        self.track_count
+    }
+}
+
+#[test]
+#[timeout(15000)]
+fn properties_transaction() {
+    block_on(async {
+        let name = "org.zbus.PropertiesTest";
+        let path = "/org/zbus/Player";
+        let player = Player {
+            title: "Help!".into(),
+            position: 42,
+            metadata: vec![],
+            track_count: 12,
+        };
+        let service = Builder::session()?
+            .name(name)?
+            .serve_at(path, player)?
+            .build()
+            .await?;
+        let client = Builder::session()?.build().await?;
+        let properties = fdo::PropertiesProxy::builder(&client)
+            .destination(name)?
+            .path(path)?
+            .build()
+            .await?;
+        let mut changes = properties.receive_properties_changed().await?;
+
+        let iface_ref = service
+            .object_server()
+            .interface::<_, Player>(path)
+            .await?;
+        let mut transaction = iface_ref.properties_transaction().await;
+        // Marking a property as changed before updating it is fine.
+        transaction.changed("Title")?;
+        transaction.title = "Hey Jude".into();
+        transaction.position = 0;
+        transaction.metadata = vec!["The Beatles".into()];
+        transaction
+            .changed("Position")?
+            .changed("Metadata")?
+            .changed("Title")?;
+        transaction.commit().await?;
+
+        let signal = changes.next().await.unwrap();
+        let args = signal.args()?;
+        assert_eq!(args.interface_name(), "org.zbus.PropertiesTest.Player");
+        let expected = HashMap::from([
+            ("Title", Value::from("Hey Jude")),
+            ("Position", Value::from(0u64)),
+        ]);
+        assert_eq!(args.changed_properties(), &expected);
+        // Only invalidated, as per its annotation.
+        assert_eq!(**args.invalidated_properties(), ["Metadata"]);
+
+        // Empty transactions don't emit anything, while unknown properties and the ones that
+        // don't emit changes are rejected right away.
+        iface_ref.properties_transaction().await.commit().await?;
+        let mut transaction = iface_ref.properties_transaction().await;
+        let res = transaction.changed("Unknown").map(|_| ());
+        assert!(
+            matches!(res, Err(zbus::Error::FDO(ref e)) if matches!(**e, fdo::Error::UnknownProperty(_))),
+            "{res:?}"
+        );
+        let res = transaction.changed("TrackCount").map(|_| ());
+        assert!(matches!(res, Err(zbus::Error::Failure(_))), "{res:?}");
+        let res = transaction.invalidated("TrackCount").map(|_| ());
+        assert!(matches!(res, Err(zbus::Error::Failure(_))), "{res:?}");
+        drop(transaction);
+
+        // Changed wins over invalidated.
+        let mut transaction = iface_ref.properties_transaction().await;
+        transaction.position = 1;
+        transaction.invalidated("Position")?.changed("Position")?;
+        transaction.invalidated("Position")?;
+        transaction.commit().await?;
+        let signal = changes.next().await.unwrap();
+        let args = signal.args()?;
+        let expected = HashMap::from([("Position", Value::from(1u64))]);
+        assert_eq!(args.changed_properties(), &expected);
+        assert!(args.invalidated_properties().is_empty());
+
+        Ok::<_, zbus::Error>(())
+    })
+    .unwrap();
+}
@@ -0,0 +1,117 @@
+use ntest::timeout;
+use test_log::test;
+use zbus::{
+    block_on,
+    connection::{
+        socket::record::{Capture, Direction, Recorder},
+        Builder,
+    },
+    fdo::DBusProxy,
+    Result,
+};
+
+#[test]
+#[timeout(15000)]
+fn record_and_replay() {
+    block_on(async {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture");
+
+        let recorder = Recorder::create(&path)?;
+        let conn = Builder::session()?.record(recorder.clone()).build().await?;
+        let unique_name = conn.unique_name().unwrap().clone();
+        let dbus = DBusProxy::new(&conn).await?;
+        let id = dbus.get_id().await?;
+        let owner = dbus.get_name_owner(unique_name.as_ref().into()).await?;
+        assert_eq!(owner, unique_name);
+        recorder.flush()?;
+
+        let capture = Capture::open(&path)?;
+        let records = capture.records();
+        // `Hello` is part of the handshake, so it's not recorded.
+        assert_eq!(capture.unique_name(), Some(&unique_name));
+        let sent: Vec<_> = records
+            .iter()
+            .filter(|r| r.direction() == Direction::Sent)
+            .map(|r| r.message().header().member().unwrap().to_string())
+            .collect();
+        assert_eq!(sent, ["GetId", "GetNameOwner"]);
+        let received: Vec<_> = records
+            .iter()
+            .filter(|r| r.direction() == Direction::Received)
+            .collect();
+        assert!(received
+            .windows(2)
+            .all(|r| r[0].message().recv_position() < r[1].message().recv_position()));
+        assert!(records.windows(2).all(|r| r[0].time() <= r[1].time()));
+
+        // Replaying gives the same results, without any bus involved.
+        let replayed = Builder::replay(&capture).build().await?;
+        assert_eq!(replayed.unique_name(), Some(&unique_name));
+        let dbus = DBusProxy::new(&replayed).await?;
+        assert_eq!(
+            dbus.get_name_owner(unique_name.as_ref().into()).await?,
+            owner
+        );
+        assert_eq!(dbus.get_id().await?, id);
+
+        // Calls that weren't recorded fail.
+        let res = dbus.list_names().await;
+        assert!(matches!(
+            res,
+            Err(zbus::fdo::Error::ZBus(zbus::Error::Failure(_)))
+        ));
+
+        Ok::<(), zbus::Error>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn invalid_capture() {
+    let res = Capture::read(&b"NOTACAPTURE"[..]);
+    assert!(matches!(res, Err(zbus::Error::InputOutput(_))));
+}
+
+#[test]
+#[timeout(15000)]
+fn single_connection() -> Result<()> {
+    block_on(async {
+        let recorder = Recorder::new(Vec::new());
+        let _conn = Builder::session()?.record(recorder.clone()).build().await?;
+        let res = Builder::session()?.record(recorder).build().await;
+        assert!(matches!(res, Err(zbus::Error::Failure(_))));
+
+        Ok(())
+    })
+}
+
+// A writer that always fails.
+struct BrokenWriter;
+
+impl std::io::Write for BrokenWriter {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+#[timeout(15000)]
+fn recording_failure() -> Result<()> {
+    block_on(async {
+        let recorder = Recorder::new(BrokenWriter);
+        let conn = Builder::session()?.record(recorder.clone()).build().await?;
+        // The connection keeps working, even though nothing can be recorded.
+        let dbus = DBusProxy::new(&conn).await?;
+        dbus.get_id().await?;
+        dbus.get_id().await?;
+
+        assert!(matches!(recorder.flush(), Err(zbus::Error::InputOutput(_))));
+
+        Ok(())
+    })
+}
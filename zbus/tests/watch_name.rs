@@ -0,0 +1,68 @@
+use futures_util::StreamExt;
+use ntest::timeout;
+use test_log::test;
+use zbus::{
+    block_on,
+    connection::{NameEvent, WatchNameFlags},
+    fdo::RequestNameFlags,
+    Connection,
+};
+
+#[test]
+#[timeout(15000)]
+fn watch_name() {
+    block_on(async {
+        let name = "org.zbus.WatchNameTest";
+        let conn = Connection::session().await?;
+        let first = Connection::session().await?;
+        first
+            .request_name_with_flags(name, RequestNameFlags::AllowReplacement.into())
+            .await?;
+        let first_owner = first.unique_name().unwrap().clone();
+
+        // The initial owner.
+        let mut watch = conn.watch_name(name).await?;
+        assert_eq!(
+            watch.next().await.unwrap(),
+            NameEvent::Appeared(first_owner.clone())
+        );
+        assert_eq!(watch.owner(), Some(&first_owner));
+
+        // A direct handover is reported as the name vanishing and reappearing.
+        let second = Connection::session().await?;
+        second
+            .request_name_with_flags(name, RequestNameFlags::ReplaceExisting.into())
+            .await?;
+        let second_owner = second.unique_name().unwrap().clone();
+        assert_eq!(watch.next().await.unwrap(), NameEvent::Vanished);
+        assert_eq!(
+            watch.next().await.unwrap(),
+            NameEvent::Appeared(second_owner.clone())
+        );
+
+        // Unique names vanish when the peer disconnects.
+        let mut unique_watch = conn.watch_name(first_owner.as_str()).await?;
+        assert_eq!(
+            unique_watch.next().await.unwrap(),
+            NameEvent::Appeared(first_owner)
+        );
+        drop(first);
+        assert_eq!(unique_watch.next().await.unwrap(), NameEvent::Vanished);
+
+        second.release_name(name).await?;
+        assert_eq!(watch.next().await.unwrap(), NameEvent::Vanished);
+        assert_eq!(watch.owner(), None);
+
+        // Failing to start a service isn't fatal.
+        let mut watch = conn
+            .watch_name_with_flags(
+                "org.zbus.WatchNameTest.NotActivatable",
+                WatchNameFlags::AutoStart.into(),
+            )
+            .await?;
+        assert_eq!(watch.next().await.unwrap(), NameEvent::Vanished);
+
+        Ok::<(), zbus::Error>(())
+    })
+    .unwrap();
+}
@@ -51,6 +51,7 @@ def_attrs! {
             }
         },
         out_args [str],
+        polkit_action str,
         proxy {
             // Keep this in sync with proxy's method attributes.
             // TODO: Find a way to share code with proxy module.
@@ -60,7 +61,8 @@ def_attrs! {
                 blocking_object str,
                 no_reply none,
                 no_autostart none,
-                allow_interactive_auth none
+                allow_interactive_auth none,
+                timeout str
             }
         }
     };
@@ -70,7 +72,8 @@ def_attrs! {
         connection none,
         header none,
         signal_context none,
-        signal_emitter none
+        signal_emitter none,
+        trace_context none
     };
 }
 
@@ -130,6 +133,10 @@ struct MethodInfo {
     member_name: String,
     /// The proxy method attributes, if any.
     proxy_attrs: Option<ProxyMethodAttributes>,
+    /// The polkit action the caller must be authorized for, if any.
+    polkit_action: Option<String>,
+    /// Whether the last argument is the trace context of the caller.
+    has_trace_context: bool,
     /// The method output type.
     output: ReturnType,
     /// The cfg attributes of the method.
@@ -212,6 +219,13 @@ impl MethodInfo {
         if is_signal && !is_async {
             return Err(Error::new_spanned(method, "signals must be async"));
         }
+        if attrs.polkit_action.is_some() && method_type != MethodType::Other {
+            return Err(Error::new_spanned(
+                method,
+                "`polkit_action` can only be specified on methods",
+            ));
+        }
+        let has_trace_context = trace_context_arg(&typed_inputs, method_type)?;
         let method_await = if is_async {
             quote! { .await }
         } else {
@@ -280,6 +294,8 @@ impl MethodInfo {
             reply,
             member_name,
             proxy_attrs: attrs.proxy.clone(),
+            polkit_action: attrs.polkit_action.clone(),
+            has_trace_context,
             output: output.clone(),
             cfg_attrs: cfg_attrs.iter().cloned().cloned().collect(),
             doc_attrs: doc_attrs.iter().cloned().cloned().collect(),
@@ -300,6 +316,8 @@ pub fn expand(args: Punctuated<Meta, Token![,]>, mut input: ItemImpl) -> syn::Re
     let mut get_all = quote!();
     let mut call_dispatch = quote!();
     let mut call_mut_dispatch = quote!();
+    let mut polkit_actions = quote!();
+    let mut trace_context_methods = quote!();
     let mut introspect = quote!();
     let mut generated_signals = quote!();
     let mut signals_trait_methods = quote!();
@@ -437,6 +455,8 @@ pub fn expand(args: Punctuated<Meta, Token![,]>, mut input: ItemImpl) -> syn::Re
             args_names,
             reply,
             member_name,
+            polkit_action,
+            has_trace_context,
             cfg_attrs,
             ..
         } = method_info;
@@ -781,6 +801,18 @@ pub fn expand(args: Punctuated<Meta, Token![,]>, mut input: ItemImpl) -> syn::Re
                 introspect.extend(doc_comments);
                 introspect.extend(introspect_method(&member_name, &intro_args));
 
+                if has_trace_context {
+                    trace_context_methods.extend(quote! {
+                        #(#cfg_attrs)*
+                        #member_name => true,
+                    });
+                }
+                if let Some(action) = polkit_action {
+                    polkit_actions.extend(quote! {
+                        #(#cfg_attrs)*
+                        #member_name => ::std::option::Option::Some(#action),
+                    });
+                }
                 let m = quote! {
                     #(#cfg_attrs)*
                     #member_name => {
@@ -817,6 +849,21 @@ pub fn expand(args: Punctuated<Meta, Token![,]>, mut input: ItemImpl) -> syn::Re
         }
     }
 
+    let emits_changed_signal_dispatch = properties.iter().map(|(name, p)| {
+        let variant = match p.emits_changed_signal {
+            PropertyEmitsChangedSignal::True => quote!(True),
+            PropertyEmitsChangedSignal::Invalidates => quote!(Invalidates),
+            PropertyEmitsChangedSignal::Const => quote!(Const),
+            PropertyEmitsChangedSignal::False => quote!(False),
+        };
+
+        quote! {
+            #name => ::std::option::Option::Some(
+                #zbus::object_server::PropertyEmitsChangedSignal::#variant,
+            ),
+        }
+    });
+    let emits_changed_signal_dispatch = quote!(#(#emits_changed_signal_dispatch)*);
     introspect_properties(&mut introspect, properties)?;
 
     let generics = &input.generics;
@@ -883,6 +930,29 @@ pub fn expand(args: Punctuated<Meta, Token![,]>, mut input: ItemImpl) -> syn::Re
                 #with_spawn
             }
 
+            fn polkit_action(__zbus__method_name: &str) -> ::std::option::Option<&'static str> {
+                match __zbus__method_name {
+                    #polkit_actions
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn has_trace_context(__zbus__method_name: &str) -> bool {
+                match __zbus__method_name {
+                    #trace_context_methods
+                    _ => false,
+                }
+            }
+
+            fn property_emits_changed_signal(
+                __zbus__property_name: &str,
+            ) -> ::std::option::Option<#zbus::object_server::PropertyEmitsChangedSignal> {
+                match __zbus__property_name {
+                    #emits_changed_signal_dispatch
+                    _ => ::std::option::Option::None,
+                }
+            }
+
             async fn get(
                 &self,
                 __zbus__property_name: &str,
@@ -1014,6 +1084,8 @@ fn get_args_from_inputs(
                 header,
                 signal_emitter,
                 signal_context,
+                // Deserialized like any other argument.
+                trace_context: _,
             } = ArgAttributes::parse(&input.attrs)?;
 
             if object_server {
@@ -1182,6 +1254,30 @@ fn introspect_input_args<'i>(
             let arg_name = quote!(#ident).to_string();
             let arg_name = arg_name.strip_prefix("r#").unwrap_or(arg_name.as_str());
             let dir = if is_signal { "" } else { " direction=\"in\"" };
+            let is_trace_context = ArgAttributes::parse(attrs).is_ok_and(|a| a.trace_context);
+            if is_trace_context {
+                let annot_name = "org.zbus.TraceContext";
+                let format_str = format!(
+                    "{}<arg name=\"{arg_name}\" type=\"{}\"{dir}>\n\
+                        {}<annotation name=\"{annot_name}\" value=\"true\"/>\n\
+                    {}</arg>",
+                    "{:indent$}", "{}", "{:annot_indent$}", "{:indent$}",
+                );
+
+                return Some(quote!(
+                    #(#cfg_attrs)*
+                    ::std::writeln!(
+                        writer,
+                        #format_str,
+                        "",
+                        <#ty>::SIGNATURE,
+                        "",
+                        "",
+                        indent = level,
+                        annot_indent = level + 2,
+                    ).unwrap();
+                ));
+            }
             let format_str = format!(
                 "{}<arg name=\"{arg_name}\" type=\"{}\"{dir}/>",
                 "{:indent$}", "{}",
@@ -1193,6 +1289,33 @@ fn introspect_input_args<'i>(
         })
 }
 
+// Whether the method takes the trace context of the caller, which must be its last argument.
+fn trace_context_arg(inputs: &[PatType], method_type: MethodType) -> syn::Result<bool> {
+    let mut regular_args = inputs.iter().filter(|input| !is_special_arg(&input.attrs));
+    let Some(pos) = regular_args
+        .clone()
+        .position(|input| ArgAttributes::parse(&input.attrs).is_ok_and(|a| a.trace_context))
+    else {
+        return Ok(false);
+    };
+    // SAFETY: We just found it.
+    let input = regular_args.nth(pos).unwrap();
+    if method_type != MethodType::Other {
+        return Err(Error::new_spanned(
+            input,
+            "`trace_context` can only be specified on method arguments",
+        ));
+    }
+    if regular_args.next().is_some() {
+        return Err(Error::new_spanned(
+            input,
+            "`trace_context` can only be specified on the last argument",
+        ));
+    }
+
+    Ok(true)
+}
+
 fn count_regular_args(inputs: &[PatType]) -> usize {
     inputs
         .iter()
@@ -1473,6 +1596,7 @@ impl Proxy {
                     && !a.header
                     && !a.signal_context
                     && !a.signal_emitter
+                    && !a.trace_context
             })
             .cloned()
             .collect();
@@ -1523,6 +1647,7 @@ impl Proxy {
 
                 quote! { property(#emits_changed_signal), }
             }
+            MethodType::Other if method_info.has_trace_context => quote!(trace_context,),
             MethodType::Other => quote!(),
         });
         if let Some(attrs) = method_info.proxy_attrs {
@@ -1544,6 +1669,9 @@ impl Proxy {
             if attrs.allow_interactive_auth {
                 proxy_method_attrs.extend(quote! { allow_interactive_auth, });
             }
+            if let Some(timeout) = attrs.timeout {
+                proxy_method_attrs.extend(quote! { timeout = #timeout, });
+            }
         }
         let cfg_attrs = method_info.cfg_attrs;
         let doc_attrs = method_info.doc_attrs;
@@ -89,6 +89,20 @@ mod utils;
 /// * `allow_interactive_auth` - declare a method call that is allowed to trigger an interactive
 ///   prompt for authorization or confirmation from the receiver.
 ///
+/// * `timeout` - set the timeout of the method call, e.g. `timeout = "5s"`. The supported units are
+///   `ms`, `s`, `m` and `h`. This overrides the timeout of the connection (see
+///   [`zbus::connection::Builder::method_timeout`]), while a timeout set with the generated
+///   `with_timeout` method of the proxy overrides this one. See [`zbus::Proxy::with_timeout`] for
+///   details. Since the proxy has its own `with_timeout` method, a method of the trait can't be
+///   named like that, e.g. for a D-Bus method named `WithTimeout`. Name it differently and use the
+///   `name` attribute instead.
+///
+/// * `trace_context` - pass the trace context of the caller, as injected by the
+///   [`zbus::connection::TracePropagator`] of the connection, as an extra last string argument of
+///   the method call. It's empty if there's no propagator or no context. The method of the service
+///   must declare this argument, e.g. with the `trace_context` argument attribute of
+///   [`macro@interface`].
+///
 /// * `object` - methods or properties that return an [`ObjectPath`] can be annotated with the
 ///   `object` attribute to specify the proxy object to be constructed from the returned
 ///   [`ObjectPath`].
@@ -198,6 +212,9 @@ mod utils;
 ///
 /// [`zbus_polkit`]: https://docs.rs/zbus_polkit/1.0.0/zbus_polkit/policykit1/index.html
 /// [`zbus::Proxy`]: https://docs.rs/zbus/latest/zbus/proxy/struct.Proxy.html
+/// [`zbus::Proxy::with_timeout`]: https://docs.rs/zbus/latest/zbus/proxy/struct.Proxy.html#method.with_timeout
+/// [`zbus::connection::Builder::method_timeout`]: https://docs.rs/zbus/latest/zbus/connection/struct.Builder.html#method.method_timeout
+/// [`zbus::connection::TracePropagator`]: https://docs.rs/zbus/latest/zbus/connection/trait.TracePropagator.html
 /// [`zbus::message::Message`]: https://docs.rs/zbus/latest/zbus/message/struct.Message.html
 /// [`zbus::proxy::PropertyStream`]: https://docs.rs/zbus/latest/zbus/proxy/struct.PropertyStream.html
 /// [`zbus::blocking::Proxy`]: https://docs.rs/zbus/latest/zbus/blocking/proxy/struct.Proxy.html
@@ -281,6 +298,11 @@ pub fn proxy(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// * `out_args` - When returning multiple values from a method, naming the out arguments become
 ///   important. You can use `out_args` to specify their names.
 ///
+/// * `polkit_action` - require the caller to be authorized for the given polkit action, e.g.
+///   `polkit_action = "org.example.service.reboot"`, before the method is called. Unauthorized
+///   calls are replied to with an error instead. See [`zbus::polkit::check_authorization`] for
+///   details. Only supported on methods and only meaningful on the system bus.
+///
 /// * `proxy` - Use this to specify the [`macro@proxy`]-specific method sub-attributes (e.g
 ///   `object`). The common sub-attributes (e.g `name`) are automatically forworded to the
 ///   [`macro@proxy`] macro. Moreover, you can use `visibility` sub-attribute to specify the
@@ -316,6 +338,11 @@ pub fn proxy(attr: TokenStream, item: TokenStream) -> TokenStream {
 ///   external property access.
 /// * `signal_emitter` - This marks the method argument to receive a [`SignalEmitter`] instance,
 ///   which is needed for emitting signals the easy way.
+/// * `trace_context` - This marks the last argument of a method as the trace context of the caller.
+///   Unlike the above, it's a regular string argument of the D-Bus method, annotated with
+///   `org.zbus.TraceContext` in the introspection data. If not empty, it's extracted as the parent
+///   of the span of the dispatching of the call, by the [`zbus::connection::TracePropagator`] of
+///   the connection, if any. The generated proxy sets it automatically.
 ///
 /// # Example
 ///
@@ -387,6 +414,8 @@ pub fn proxy(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// [`SignalEmitter`]: https://docs.rs/zbus/latest/zbus/object_server/struct.SignalEmitter.html
 /// [`Interface`]: https://docs.rs/zbus/latest/zbus/object_server/trait.Interface.html
 /// [dbus_emits_changed_signal]: https://dbus.freedesktop.org/doc/dbus-specification.html#introspection-format
+/// [`zbus::polkit::check_authorization`]: https://docs.rs/zbus/latest/zbus/polkit/fn.check_authorization.html
+/// [`zbus::connection::TracePropagator`]: https://docs.rs/zbus/latest/zbus/connection/trait.TracePropagator.html
 #[proc_macro_attribute]
 pub fn interface(attr: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_macro_input!(attr with Punctuated<Meta, Token![,]>::parse_terminated);
@@ -38,7 +38,9 @@ def_attrs! {
         blocking_object str,
         no_reply none,
         no_autostart none,
-        allow_interactive_auth none
+        allow_interactive_auth none,
+        timeout str,
+        trace_context none
     };
 }
 
@@ -221,6 +223,25 @@ pub fn create_proxy(
             let is_signal = method_attrs.signal;
             let is_property = property.is_some();
             let has_inputs = m.sig.inputs.len() > 1;
+            if (is_signal || is_property) && method_attrs.timeout.is_some() {
+                return Err(Error::new(
+                    m.span(),
+                    "`timeout` attribute is only supported on methods",
+                ));
+            }
+            if (is_signal || is_property) && method_attrs.trace_context {
+                return Err(Error::new(
+                    m.span(),
+                    "`trace_context` attribute is only supported on methods",
+                ));
+            }
+            if !is_signal && r#_stripped_rust_method_name == "with_timeout" {
+                return Err(Error::new(
+                    m.sig.ident.span(),
+                    "`with_timeout` is reserved for setting the timeout of the proxy, rename the \
+                    method and set its D-Bus name through the `name` attribute instead",
+                ));
+            }
 
             let dbus_member_name = method_attrs.name.clone().unwrap_or_else(|| {
                 case::pascal_or_camel_case(
@@ -426,6 +447,13 @@ pub fn create_proxy(
                 &mut self.0
             }
 
+            /// A proxy sharing everything with `self`, except for the timeout of its method calls.
+            ///
+            /// See `zbus::Proxy::with_timeout` for details.
+            pub fn with_timeout(&self, timeout: ::std::time::Duration) -> Self {
+                Self(self.0.with_timeout(timeout))
+            }
+
             #methods
         }
 
@@ -500,13 +528,30 @@ fn gen_proxy_method_call(
         .iter()
         .filter(|a| !a.path().is_ident("zbus"))
         .collect();
-    let args: Vec<_> = m
+    let mut args: Vec<_> = m
         .sig
         .inputs
         .iter()
         .filter_map(typed_arg)
         .filter_map(pat_ident)
+        .map(|ident| quote!(#ident))
         .collect();
+    // The trace context is passed as an extra last argument, empty if there's none.
+    let trace_context = method_attrs.trace_context.then(|| {
+        let connection = if *blocking {
+            quote!(self.0.connection().inner())
+        } else {
+            quote!(self.0.connection())
+        };
+        args.push(quote!(__zbus__trace_context));
+
+        quote! {
+            let __zbus__trace_context: ::std::string::String = #connection
+                .trace_propagator()
+                .and_then(|p| p.inject())
+                .unwrap_or_default();
+        }
+    });
 
     let proxy_object = method_attrs.object.as_ref().map(|o| {
         if *blocking {
@@ -560,6 +605,23 @@ fn gen_proxy_method_call(
         _ => None,
     };
 
+    // The timeout of `with_timeout` takes precedence over the one of the method.
+    let (proxy, set_timeout) = match &method_attrs.timeout {
+        Some(timeout) => {
+            let millis = parse_timeout(timeout).map_err(|e| Error::new(m.span(), e))?;
+            let proxy = quote! { __zbus_proxy };
+            let set_timeout = quote! {
+                let #proxy = self.0.with_timeout(::std::option::Option::unwrap_or(
+                    self.0.timeout(),
+                    ::std::time::Duration::from_millis(#millis),
+                ));
+            };
+
+            (proxy, set_timeout)
+        }
+        None => (quote! { self.0 }, quote! {}),
+    };
+
     let mut method = parse_str::<Ident>(rust_method_name)?;
     method.set_span(Span::call_site());
     let inputs = &m.sig.inputs;
@@ -610,8 +672,10 @@ fn gen_proxy_method_call(
         Ok(quote! {
             #(#other_attrs)*
             pub #usage #signature {
+                #set_timeout
+                #trace_context
                 let object_path: #zbus::zvariant::OwnedObjectPath =
-                    self.0.call(
+                    #proxy.call(
                         #dbus_member_name,
                         &#zbus::zvariant::DynamicTuple((#(#args,)*)),
                     )
@@ -647,7 +711,9 @@ fn gen_proxy_method_call(
                 Ok(quote! {
                     #(#other_attrs)*
                     pub #usage #signature {
-                        self.0.call_with_flags::<_, _, ()>(#dbus_member_name, #method_flags, #body)#wait?;
+                        #set_timeout
+                        #trace_context
+                        #proxy.call_with_flags::<_, _, ()>(#dbus_member_name, #method_flags, #body)#wait?;
                         ::std::result::Result::Ok(())
                     }
                 })
@@ -655,7 +721,9 @@ fn gen_proxy_method_call(
                 Ok(quote! {
                     #(#other_attrs)*
                     pub #usage #signature {
-                        let reply = self.0.call_with_flags(#dbus_member_name, #method_flags, #body)#wait?;
+                        #set_timeout
+                        #trace_context
+                        let reply = #proxy.call_with_flags(#dbus_member_name, #method_flags, #body)#wait?;
 
                         // SAFETY: This unwrap() cannot fail due to the guarantees in
                         // call_with_flags, which can only return Ok(None) if the
@@ -671,7 +739,9 @@ fn gen_proxy_method_call(
             Ok(quote! {
                 #(#other_attrs)*
                 pub #usage #signature {
-                    let reply = self.0.call(#dbus_member_name, #body)#wait?;
+                    #set_timeout
+                    #trace_context
+                    let reply = #proxy.call(#dbus_member_name, #body)#wait?;
                     ::std::result::Result::Ok(reply)
                 }
             })
@@ -679,6 +749,27 @@ fn gen_proxy_method_call(
     }
 }
 
+// Parse a timeout such as `500ms`, `5s`, `2m` or `1h`, into milliseconds.
+fn parse_timeout(timeout: &str) -> Result<u64, String> {
+    let invalid = || {
+        format!("invalid timeout `{timeout}`, expected a number followed by `ms`, `s`, `m` or `h`")
+    };
+    let unit_start = timeout
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (value, unit) = timeout.split_at(unit_start);
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    let factor = match unit {
+        "ms" => 1,
+        "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        _ => return Err(invalid()),
+    };
+
+    value.checked_mul(factor).ok_or_else(invalid)
+}
+
 fn gen_proxy_property(
     property_name: &str,
     rust_method_name: &str,
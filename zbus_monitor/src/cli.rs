@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct Args {
+    /// Monitor the system bus.
+    #[clap(long, conflicts_with_all = ["session", "address"])]
+    pub system: bool,
+
+    /// Monitor the session bus of the current user. This is the default.
+    #[clap(long, conflicts_with = "address")]
+    pub session: bool,
+
+    /// Monitor the bus at the specified address.
+    #[clap(long)]
+    pub address: Option<String>,
+
+    /// Also write the monitored messages to the specified file, in the pcap format.
+    #[clap(long)]
+    pub pcap: Option<PathBuf>,
+
+    /// Match rules (e.g. "type='signal',interface='org.freedesktop.DBus'") describing the
+    /// messages to monitor. If none is provided, all messages going through the bus are monitored.
+    pub match_rules: Vec<String>,
+}
@@ -0,0 +1,10 @@
+//! Library part of the `zbus-monitor` tool.
+//!
+//! This provides the pretty-printing of messages through [`PrettyMessage`] and the writing of
+//! captures in the pcap format through [`PcapWriter`].
+
+mod pcap;
+mod pretty;
+
+pub use pcap::{PcapWriter, LINKTYPE_DBUS};
+pub use pretty::PrettyMessage;
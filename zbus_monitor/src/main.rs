@@ -0,0 +1,61 @@
+#![deny(rust_2018_idioms)]
+
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    time::SystemTime,
+};
+
+use clap::Parser;
+use zbus::{
+    blocking::{connection, fdo::MonitoringProxy, Connection, MessageIterator},
+    MatchRule,
+};
+
+use zbus_monitor::{PcapWriter, PrettyMessage};
+
+mod cli;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = cli::Args::parse();
+
+    let rules = args
+        .match_rules
+        .iter()
+        .map(|rule| MatchRule::try_from(rule.as_str()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let conn = match args.address {
+        Some(address) => connection::Builder::address(&*address)?.build()?,
+        None if args.system => Connection::system()?,
+        None => Connection::session()?,
+    };
+    let mut pcap = args
+        .pcap
+        .map(|path| PcapWriter::new(BufWriter::new(File::create(path)?)))
+        .transpose()?;
+
+    // Create the iterator before becoming a monitor, so we don't miss any messages.
+    let messages = MessageIterator::from(&conn);
+    MonitoringProxy::new(&conn)?.become_monitor(&rules, 0)?;
+    let own_name = conn.unique_name().map(|name| name.to_owned());
+
+    let mut stdout = std::io::stdout().lock();
+    for msg in messages {
+        let msg = msg?;
+        // Skip the reply to `BecomeMonitor` and the `NameLost` signal for our unique name.
+        let header = msg.header();
+        let destination = header.destination().map(|d| d.as_str());
+        if destination.is_some() && destination == own_name.as_ref().map(|n| n.as_str()) {
+            continue;
+        }
+
+        writeln!(stdout, "{}", PrettyMessage(&msg))?;
+        if let Some(pcap) = &mut pcap {
+            pcap.write_message(&msg, SystemTime::now())?;
+            pcap.flush()?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::{
+    io::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use zbus::message::Message;
+
+/// The pcap link-layer header type for D-Bus messages.
+///
+/// See [the list of link-layer header types](https://www.tcpdump.org/linktypes.html).
+pub const LINKTYPE_DBUS: u32 = 231;
+
+// The maximum size of a D-Bus message.
+const SNAPLEN: u32 = 128 * 1024 * 1024;
+
+/// Writer of captures of D-Bus messages in the pcap format.
+///
+/// The captured packets are the messages, as they were sent on the wire. File descriptors
+/// passed along with them are not captured. The resulting files can be inspected with tools
+/// understanding the `DBUS` link-layer header type, like Wireshark.
+///
+/// See [the format description](https://www.tcpdump.org/manpages/pcap-savefile.5.html).
+#[derive(Debug)]
+pub struct PcapWriter<W> {
+    writer: W,
+}
+
+impl<W> PcapWriter<W>
+where
+    W: Write,
+{
+    /// Create a new writer, writing the file header to `writer` right away.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(24);
+        // Magic number, in native byte order, telling readers the byte order of the file.
+        header.extend_from_slice(&0xa1b2c3d4u32.to_ne_bytes());
+        // Version 2.4 of the format.
+        header.extend_from_slice(&2u16.to_ne_bytes());
+        header.extend_from_slice(&4u16.to_ne_bytes());
+        // Timestamps are in UTC and their accuracy is unknown.
+        header.extend_from_slice(&0i32.to_ne_bytes());
+        header.extend_from_slice(&0u32.to_ne_bytes());
+        header.extend_from_slice(&SNAPLEN.to_ne_bytes());
+        header.extend_from_slice(&LINKTYPE_DBUS.to_ne_bytes());
+        writer.write_all(&header)?;
+
+        Ok(Self { writer })
+    }
+
+    /// Write `msg` as a packet captured at `timestamp`.
+    pub fn write_message(&mut self, msg: &Message, timestamp: SystemTime) -> io::Result<()> {
+        let bytes = msg.data().bytes();
+        let len = u32::try_from(bytes.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message too large"))?;
+        let since_epoch = timestamp.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        let mut header = Vec::with_capacity(16);
+        // pcap timestamps are 32-bit and will overflow in 2106.
+        header.extend_from_slice(&(since_epoch.as_secs() as u32).to_ne_bytes());
+        header.extend_from_slice(&since_epoch.subsec_micros().to_ne_bytes());
+        // Captured and original lengths.
+        header.extend_from_slice(&len.to_ne_bytes());
+        header.extend_from_slice(&len.to_ne_bytes());
+        self.writer.write_all(&header)?;
+        self.writer.write_all(bytes)
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Consume the writer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
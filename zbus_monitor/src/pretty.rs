@@ -0,0 +1,90 @@
+use std::fmt::{self, Display, Formatter};
+
+use zbus::{
+    message::{Message, Type},
+    zvariant::Structure,
+};
+
+/// A [`Display`] wrapper for a [`Message`], printing its header fields and its body.
+///
+/// The first line contains the fields of the primary header. The other header fields follow,
+/// one per line, and finally the body, decoded as [`zbus::zvariant::Value`] and printed in the
+/// GVariant text format.
+///
+/// # Example
+///
+/// ```
+/// use zbus::message::Message;
+/// use zbus_monitor::PrettyMessage;
+///
+/// # fn main() -> zbus::Result<()> {
+/// let msg = Message::method_call("/org/zbus/Test", "Ping")?
+///     .destination("org.zbus.Test")?
+///     .build(&("pong", 42u32))?;
+/// let output = PrettyMessage(&msg).to_string();
+/// assert!(output.starts_with("‣ Type=method_call"));
+/// assert!(output.contains("  Member=Ping\n"));
+/// assert!(output.ends_with("  Body=(\"pong\", uint32 42)\n"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct PrettyMessage<'m>(pub &'m Message);
+
+impl Display for PrettyMessage<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let msg = self.0;
+        let primary = msg.primary_header();
+        let ty = match primary.msg_type() {
+            Type::MethodCall => "method_call",
+            Type::MethodReturn => "method_return",
+            Type::Error => "error",
+            Type::Signal => "signal",
+        };
+        writeln!(
+            f,
+            "‣ Type={ty}  Endian={}  Flags={}  Version={}  Serial={}",
+            primary.endian_sig() as u8 as char,
+            primary.flags().bits(),
+            primary.protocol_version(),
+            primary.serial_num(),
+        )?;
+
+        let header = msg.header();
+        if let Some(sender) = header.sender() {
+            writeln!(f, "  Sender={sender}")?;
+        }
+        if let Some(destination) = header.destination() {
+            writeln!(f, "  Destination={destination}")?;
+        }
+        if let Some(path) = header.path() {
+            writeln!(f, "  Path={path}")?;
+        }
+        if let Some(interface) = header.interface() {
+            writeln!(f, "  Interface={interface}")?;
+        }
+        if let Some(member) = header.member() {
+            writeln!(f, "  Member={member}")?;
+        }
+        if let Some(error_name) = header.error_name() {
+            writeln!(f, "  ErrorName={error_name}")?;
+        }
+        if let Some(reply_serial) = header.reply_serial() {
+            writeln!(f, "  ReplySerial={reply_serial}")?;
+        }
+        if let Some(unix_fds) = header.unix_fds() {
+            writeln!(f, "  UnixFDs={unix_fds}")?;
+        }
+
+        let body = msg.body();
+        let signature = body.signature();
+        if *signature == zbus::zvariant::Signature::Unit {
+            return Ok(());
+        }
+        writeln!(f, "  Signature={signature}")?;
+        match body.deserialize::<Structure<'_>>() {
+            Ok(body) => writeln!(f, "  Body={body}"),
+            Err(e) => writeln!(f, "  Body=<failed to decode: {e}>"),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use zbus::message::Message;
+use zbus_monitor::{PcapWriter, PrettyMessage, LINKTYPE_DBUS};
+
+#[test]
+fn pretty_print() -> zbus::Result<()> {
+    let call = Message::method_call("/org/zbus/Test", "Ping")?
+        .interface("org.zbus.Test")?
+        .destination("org.zbus.Test")?
+        .sender(":1.42")?
+        .build(&("pong", vec![1u8, 2]))?;
+    let serial = call.primary_header().serial_num();
+    let endian = call.primary_header().endian_sig() as u8 as char;
+    assert_eq!(
+        PrettyMessage(&call).to_string(),
+        format!(
+            "‣ Type=method_call  Endian={endian}  Flags=0  Version=1  Serial={serial}\n  \
+            Sender=:1.42\n  Destination=org.zbus.Test\n  Path=/org/zbus/Test\n  \
+            Interface=org.zbus.Test\n  Member=Ping\n  Signature=(say)\n  \
+            Body=(\"pong\", [byte 0x01, 0x02])\n"
+        )
+    );
+
+    // No body.
+    let reply = Message::method_return(&call.header())?.build(&())?;
+    let output = PrettyMessage(&reply).to_string();
+    assert!(output.starts_with("‣ Type=method_return"));
+    assert!(output.ends_with(&format!("  ReplySerial={serial}\n")));
+
+    let error = Message::error(&call.header(), "org.zbus.Error.Failed")?.build(&("oops",))?;
+    let output = PrettyMessage(&error).to_string();
+    assert!(output.contains("  ErrorName=org.zbus.Error.Failed\n"));
+    assert!(output.ends_with("  Signature=s\n  Body=(\"oops\",)\n"));
+
+    Ok(())
+}
+
+#[test]
+fn pcap() -> Result<(), Box<dyn std::error::Error>> {
+    let msg = Message::signal("/org/zbus/Test", "org.zbus.Test", "Changed")?.build(&(7u32,))?;
+    let mut writer = PcapWriter::new(vec![])?;
+    let timestamp = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+    writer.write_message(&msg, timestamp)?;
+    writer.write_message(&msg, SystemTime::now())?;
+    let capture = writer.into_inner();
+
+    let u32_at = |pos: usize| u32::from_ne_bytes(capture[pos..pos + 4].try_into().unwrap());
+    let u16_at = |pos: usize| u16::from_ne_bytes(capture[pos..pos + 2].try_into().unwrap());
+    assert_eq!(u32_at(0), 0xa1b2c3d4);
+    assert_eq!((u16_at(4), u16_at(6)), (2, 4));
+    assert_eq!(u32_at(20), LINKTYPE_DBUS);
+
+    let bytes = msg.data().bytes();
+    assert_eq!(u32_at(24), 1_700_000_000);
+    assert_eq!(u32_at(28), 123_456);
+    assert_eq!(u32_at(32) as usize, bytes.len());
+    assert_eq!(u32_at(36) as usize, bytes.len());
+    assert_eq!(&capture[40..40 + bytes.len()], bytes);
+    assert_eq!(capture.len(), 24 + 2 * (16 + bytes.len()));
+
+    Ok(())
+}
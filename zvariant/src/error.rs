@@ -67,6 +67,9 @@ pub enum Error {
     EmptyStructure,
     /// Invalid object path.
     InvalidObjectPath,
+    /// Error from parsing a value in the GVariant text format, at the given byte offset of the
+    /// text.
+    TextParse(usize, String),
 }
 
 impl PartialEq for Error {
@@ -92,6 +95,9 @@ impl PartialEq for Error {
             (Error::SignatureParse(e1), Error::SignatureParse(e2)) => e1 == e2,
             (Error::EmptyStructure, Error::EmptyStructure) => true,
             (Error::InvalidObjectPath, Error::InvalidObjectPath) => true,
+            (Error::TextParse(pos1, msg1), Error::TextParse(pos2, msg2)) => {
+                pos1 == pos2 && msg1 == msg2
+            }
             (_, _) => false,
         }
     }
@@ -136,6 +142,7 @@ impl fmt::Display for Error {
             Error::SignatureParse(e) => write!(f, "{e}"),
             Error::EmptyStructure => write!(f, "Attempted to create an empty structure"),
             Error::InvalidObjectPath => write!(f, "Invalid object path"),
+            Error::TextParse(pos, msg) => write!(f, "Invalid value text at offset {pos}: {msg}"),
         }
     }
 }
@@ -161,6 +168,7 @@ impl Clone for Error {
             Error::SignatureParse(e) => Error::SignatureParse(*e),
             Error::EmptyStructure => Error::EmptyStructure,
             Error::InvalidObjectPath => Error::InvalidObjectPath,
+            Error::TextParse(pos, msg) => Error::TextParse(*pos, msg.clone()),
         }
     }
 }
@@ -2,3 +2,5 @@ mod de;
 pub(crate) use de::*;
 mod ser;
 pub use ser::*;
+mod reader;
+pub use reader::Reader;
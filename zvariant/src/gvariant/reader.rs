@@ -0,0 +1,404 @@
+use std::ops::Range;
+
+#[cfg(unix)]
+use std::os::fd::BorrowedFd;
+
+use serde::Deserialize;
+
+use crate::{
+    framing_offset_size::FramingOffsetSize,
+    serialized::{Context, Format},
+    utils::*,
+    Error, Result, Signature,
+};
+
+/// Random access to GVariant-encoded data.
+///
+/// A reader gives access to the elements of arrays, the entries of dictionaries and the fields of
+/// structures through the framing offsets of the GVariant format, without deserializing any of
+/// their siblings. Indexing into an array takes constant time, whatever the size of its elements.
+/// Only the values that are accessed are then deserialized, borrowing from the encoded bytes.
+///
+/// Since the reader only borrows the bytes, they can come from a memory-mapped file, so that only
+/// the pages that are accessed are read from the disk. File descriptors are not supported.
+///
+/// # Examples
+///
+/// ```
+/// use zvariant::{gvariant::Reader, serialized::Context, to_bytes, Type, LE};
+///
+/// let refs: Vec<(&str, u32)> = (0..1000).map(|i| ("ref", i)).collect();
+/// let ctxt = Context::new_gvariant(LE, 0);
+/// let encoded = to_bytes(ctxt, &(42u8, &refs)).unwrap();
+///
+/// let reader = Reader::new(&encoded, ctxt, <(u8, Vec<(&str, u32)>)>::SIGNATURE).unwrap();
+/// let refs = reader.field(1).unwrap();
+/// assert_eq!(refs.len().unwrap(), 1000);
+/// let r = refs.element(999).unwrap();
+/// assert_eq!(r.deserialize::<(&str, u32)>().unwrap(), ("ref", 999));
+/// assert_eq!(r.field(1).unwrap().deserialize::<u32>().unwrap(), 999);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Reader<'r> {
+    bytes: &'r [u8],
+    // The range of the value in `bytes`.
+    range: Range<usize>,
+    ctxt: Context,
+    signature: Signature,
+}
+
+impl<'r> Reader<'r> {
+    /// Create a reader for the value with the given signature, encoded in `bytes`.
+    ///
+    /// The value is expected to span all of `bytes`. `ctxt` must be a GVariant context.
+    pub fn new<S>(bytes: &'r [u8], ctxt: Context, signature: S) -> Result<Self>
+    where
+        S: TryInto<Signature>,
+        S::Error: Into<Error>,
+    {
+        let signature = signature.try_into().map_err(Into::into)?;
+        if ctxt.format() != Format::GVariant {
+            return Err(Error::IncompatibleFormat(signature, ctxt.format()));
+        }
+        let padding = padding_for_n_bytes(ctxt.position(), signature.alignment(Format::GVariant));
+        if padding > bytes.len() {
+            return Err(Error::OutOfBounds);
+        }
+
+        Ok(Self::with_range(
+            bytes,
+            ctxt,
+            padding..bytes.len(),
+            signature,
+        ))
+    }
+
+    // `ctxt` is the context of the start of `bytes`, not of `range`.
+    pub(crate) fn with_range(
+        bytes: &'r [u8],
+        ctxt: Context,
+        range: Range<usize>,
+        signature: Signature,
+    ) -> Self {
+        Self {
+            bytes,
+            range,
+            ctxt,
+            signature,
+        }
+    }
+
+    /// The signature of the value.
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The encoded bytes of the value.
+    pub fn bytes(&self) -> &'r [u8] {
+        &self.bytes[self.range.clone()]
+    }
+
+    pub(crate) fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Deserialize the value.
+    pub fn deserialize<T>(&self) -> Result<T>
+    where
+        T: Deserialize<'r>,
+    {
+        let ctxt = Context::new_gvariant(self.ctxt.endian(), self.abs_pos(self.range.start));
+        #[cfg(unix)]
+        let mut de =
+            super::Deserializer::<BorrowedFd<'_>>::new(self.bytes(), None, &self.signature, ctxt)?;
+        #[cfg(not(unix))]
+        let mut de = super::Deserializer::<()>::new(self.bytes(), &self.signature, ctxt)?;
+
+        T::deserialize(&mut de)
+    }
+
+    /// The number of elements of the array, or entries of the dictionary.
+    pub fn len(&self) -> Result<usize> {
+        self.array_layout().map(|layout| layout.len)
+    }
+
+    /// Whether the array or dictionary is empty.
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// The element at `index` of the array, or the entry at `index` of the dictionary.
+    ///
+    /// Dictionary entries are structures, with the key and the value as fields.
+    pub fn element(&self, index: usize) -> Result<Self> {
+        let layout = self.array_layout()?;
+        if index >= layout.len {
+            return Err(Error::Message(format!(
+                "No element at index {index} in an array of {} elements",
+                layout.len
+            )));
+        }
+
+        let range = match layout.offsets {
+            None => {
+                let start = self.range.start + index * layout.element_size;
+
+                start..start + layout.element_size
+            }
+            Some((size, offsets_start)) => {
+                let read = |i: usize| -> Result<usize> {
+                    let offset = read_offset(self.bytes, size, offsets_start + i * size as usize)?;
+
+                    Ok(self.range.start + offset)
+                };
+                let end = read(index)?;
+                let start = match index {
+                    0 => self.range.start,
+                    _ => read(index - 1)?,
+                };
+                let alignment = layout.element_signature.alignment(Format::GVariant);
+                let start = start + padding_for_n_bytes(self.abs_pos(start), alignment);
+                if start > end || end > offsets_start {
+                    return Err(serde::de::Error::invalid_length(
+                        end - self.range.start,
+                        &format!("<= {}", offsets_start - self.range.start).as_str(),
+                    ));
+                }
+
+                start..end
+            }
+        };
+
+        Ok(self.child(range, layout.element_signature))
+    }
+
+    /// The field at `index` of the structure.
+    ///
+    /// This only reads the framing offsets of the fields before it, if any.
+    pub fn field(&self, index: usize) -> Result<Self> {
+        let Signature::Structure(fields) = &self.signature else {
+            return Err(Error::SignatureMismatch(
+                self.signature.clone(),
+                "a structure".to_string(),
+            ));
+        };
+
+        // The end of variable-sized fields is given by the framing offsets at the end of the
+        // structure, in reverse order, except for the last field that spans up to the offsets.
+        let offset_size = FramingOffsetSize::for_encoded_container(self.range.len());
+        let mut offsets_end = self.range.end;
+        let mut pos = self.range.start;
+        let num_fields = fields.iter().count();
+        for (i, field) in fields.iter().enumerate() {
+            let start =
+                pos + padding_for_n_bytes(self.abs_pos(pos), field.alignment(Format::GVariant));
+            let end = if field.is_fixed_sized() {
+                start + fixed_size(field)
+            } else if i == num_fields - 1 {
+                offsets_end
+            } else {
+                offsets_end = offsets_end
+                    .checked_sub(offset_size as usize)
+                    .filter(|end| *end >= start)
+                    .ok_or(Error::MissingFramingOffset)?;
+
+                self.range.start + read_offset(self.bytes, offset_size, offsets_end)?
+            };
+            if start > end || end > offsets_end {
+                return Err(serde::de::Error::invalid_length(
+                    end - self.range.start,
+                    &format!("<= {}", offsets_end - self.range.start).as_str(),
+                ));
+            }
+            if i == index {
+                return Ok(self.child(start..end, field.clone()));
+            }
+            pos = end;
+        }
+
+        Err(Error::Message(format!(
+            "No field at index {index} in a `{}` structure",
+            self.signature
+        )))
+    }
+
+    /// The value of the entry with the given key in the dictionary, if any.
+    ///
+    /// This goes through the entries of the dictionary, only deserializing their keys, until it
+    /// finds a match.
+    pub fn get<K>(&self, key: K) -> Result<Option<Self>>
+    where
+        K: Deserialize<'r> + PartialEq,
+    {
+        if !matches!(self.signature, Signature::Dict { .. }) {
+            return Err(Error::SignatureMismatch(
+                self.signature.clone(),
+                "a dict".to_string(),
+            ));
+        }
+
+        for i in 0..self.len()? {
+            let entry = self.element(i)?;
+            if entry.field(0)?.deserialize::<K>()? == key {
+                return entry.field(1).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The value contained in the variant.
+    pub fn variant(&self) -> Result<Self> {
+        if self.signature != Signature::Variant {
+            return Err(Error::SignatureMismatch(
+                self.signature.clone(),
+                "a variant".to_string(),
+            ));
+        }
+
+        // The signature is at the end, after a nul byte.
+        let bytes = self.bytes();
+        let separator = bytes
+            .len()
+            .checked_sub(1)
+            .and_then(|last| bytes[..last].iter().rposition(|b| *b == b'\0'))
+            .ok_or_else(|| {
+                <Error as serde::de::Error>::invalid_value(
+                    serde::de::Unexpected::Bytes(bytes),
+                    &"nul byte separator between Variant's value & signature",
+                )
+            })?;
+        let signature = Signature::from_bytes(&bytes[separator + 1..])?;
+        let start = self.range.start;
+
+        Ok(self.child(start..start + separator, signature))
+    }
+
+    /// The value contained in the maybe, if any.
+    pub fn maybe(&self) -> Result<Option<Self>> {
+        let Signature::Maybe(child) = &self.signature else {
+            return Err(Error::SignatureMismatch(
+                self.signature.clone(),
+                "a maybe".to_string(),
+            ));
+        };
+        if self.range.is_empty() {
+            return Ok(None);
+        }
+
+        // Variable-sized values are followed by a nul byte.
+        let child = child.signature();
+        let end = if child.is_fixed_sized() {
+            self.range.end
+        } else {
+            self.range.end - 1
+        };
+
+        Ok(Some(self.child(self.range.start..end, child.clone())))
+    }
+
+    fn array_layout(&self) -> Result<ArrayLayout> {
+        let element_signature = match &self.signature {
+            Signature::Array(child) => child.signature().clone(),
+            Signature::Dict { key, value } => {
+                Signature::structure([key.signature().clone(), value.signature().clone()])
+            }
+            _ => {
+                return Err(Error::SignatureMismatch(
+                    self.signature.clone(),
+                    "an array or dict".to_string(),
+                ))
+            }
+        };
+        let len = self.range.len();
+
+        if element_signature.is_fixed_sized() {
+            let element_size = fixed_size(&element_signature);
+            if len % element_size != 0 {
+                return Err(serde::de::Error::invalid_length(
+                    len,
+                    &format!("a multiple of {element_size}").as_str(),
+                ));
+            }
+
+            return Ok(ArrayLayout {
+                element_signature,
+                len: len / element_size,
+                element_size,
+                offsets: None,
+            });
+        }
+        if len == 0 {
+            return Ok(ArrayLayout {
+                element_signature,
+                len: 0,
+                element_size: 0,
+                offsets: None,
+            });
+        }
+
+        // The last offset tells us the start of offsets, which is also the end of the elements.
+        let size = FramingOffsetSize::for_encoded_container(len);
+        let offsets_start = read_offset(self.bytes, size, self.range.end - size as usize)?;
+        if offsets_start > len || (len - offsets_start) % size as usize != 0 {
+            return Err(Error::MissingFramingOffset);
+        }
+
+        Ok(ArrayLayout {
+            element_signature,
+            len: (len - offsets_start) / size as usize,
+            element_size: 0,
+            offsets: Some((size, self.range.start + offsets_start)),
+        })
+    }
+
+    fn child(&self, range: Range<usize>, signature: Signature) -> Self {
+        Self::with_range(self.bytes, self.ctxt, range, signature)
+    }
+
+    fn abs_pos(&self, pos: usize) -> usize {
+        self.ctxt.position() + pos
+    }
+}
+
+#[derive(Debug)]
+struct ArrayLayout {
+    element_signature: Signature,
+    len: usize,
+    // Size of fixed-sized elements.
+    element_size: usize,
+    // Size and position of the framing offsets of variable-sized elements.
+    offsets: Option<(FramingOffsetSize, usize)>,
+}
+
+fn read_offset(bytes: &[u8], size: FramingOffsetSize, pos: usize) -> Result<usize> {
+    let bytes = subslice(bytes, pos..pos + size as usize)?;
+
+    Ok(size.read_last_offset_from_buffer(bytes))
+}
+
+// The size of the encoding of a fixed-sized type.
+fn fixed_size(signature: &Signature) -> usize {
+    match signature {
+        Signature::Unit | Signature::U8 | Signature::Bool => 1,
+        Signature::I16 | Signature::U16 => 2,
+        Signature::I32 | Signature::U32 => 4,
+        #[cfg(unix)]
+        Signature::Fd => 4,
+        Signature::I64 | Signature::U64 | Signature::F64 => 8,
+        Signature::Structure(fields) => {
+            let mut size = 0;
+            for field in fields.iter() {
+                size += padding_for_n_bytes(size, field.alignment(Format::GVariant));
+                size += fixed_size(field);
+            }
+            if size == 0 {
+                // Empty structures are encoded as a single byte.
+                return 1;
+            }
+
+            size + padding_for_n_bytes(size, signature.alignment(Format::GVariant))
+        }
+        _ => unreachable!("`{signature}` is not fixed-sized"),
+    }
+}
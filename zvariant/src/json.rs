@@ -0,0 +1,514 @@
+//! Lossless mapping between [`Value`] and JSON.
+//!
+//! Unlike serializing a [`Value`] with `serde_json`, the mapping defined here doesn't lose any type
+//! information, as long as the JSON is decoded with the signature of the encoded value:
+//!
+//! | D-Bus type | JSON |
+//! | --- | --- |
+//! | `b` | boolean |
+//! | `y`, `n`, `q`, `i`, `u`, `x`, `t` | number |
+//! | `d` | number, or one of the strings `"NaN"`, `"Infinity"` and `"-Infinity"` |
+//! | `s`, `o`, `g` | string |
+//! | `h` | number, the index of the file descriptor in [`Json::fds`] |
+//! | `v` | object with a `"signature"` string and a `"value"` |
+//! | `a…` | array |
+//! | `a{…}` | object, with the keys in their JSON form as strings if they aren't strings, e.g. `"42"` |
+//! | `(…)` | array of the fields |
+//! | `m…` | `null` if nothing, otherwise the value, wrapped in an array if it's itself a maybe |
+//!
+//! Variants carry their signature, so [`from_json`] only needs the signature of the outermost
+//! value to restore the exact types, e.g. integer widths or object paths, of the whole value.
+//!
+//! File descriptors are only meaningful in the process that owns them, so they're kept out of the
+//! JSON. Just like in the D-Bus wire format, they're encoded as an index into a separate list, that
+//! [`to_json`] returns along with the JSON and [`from_json_fds`] takes to resolve the indices.
+//!
+//! # Example
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use zvariant::{json, ObjectPath, Signature, Value};
+//!
+//! let path = ObjectPath::try_from("/org/example").unwrap();
+//! let value = Value::new((
+//!     HashMap::from([(7u16, Value::new(path))]),
+//!     vec![-1i64, 1],
+//! ));
+//!
+//! let json = json::to_json(&value).into_value();
+//! assert_eq!(
+//!     json.to_string(),
+//!     r#"[{"7":{"signature":"o","value":"/org/example"}},[-1,1]]"#,
+//! );
+//!
+//! let signature = Signature::try_from("(a{qv}ax)").unwrap();
+//! assert_eq!(json::from_json(&json, &signature).unwrap(), value);
+//! ```
+//!
+//! [`Value`]: enum@Value
+
+#[cfg(not(unix))]
+use std::marker::PhantomData;
+use std::ops::Deref;
+#[cfg(unix)]
+use std::os::fd::AsFd;
+
+use serde_json::{Map, Value as JsonValue};
+
+#[cfg(unix)]
+use crate::Fd;
+#[cfg(feature = "gvariant")]
+use crate::Maybe;
+use crate::{
+    container_depths::ContainerDepths, Array, Dict, Error, ObjectPath, Result, Signature, Str,
+    StructureBuilder, Value,
+};
+
+/// The JSON encoding of a [`Value`], as returned by [`to_json`].
+///
+/// On Unix platforms, it also contains the list of file descriptors, whose indexes are included in
+/// the JSON.
+///
+/// [`Value`]: enum@Value
+#[derive(Debug)]
+pub struct Json<'v> {
+    value: JsonValue,
+    #[cfg(unix)]
+    fds: Vec<Fd<'v>>,
+    #[cfg(not(unix))]
+    phantom: PhantomData<&'v ()>,
+}
+
+impl<'v> Json<'v> {
+    /// The JSON value.
+    pub fn value(&self) -> &JsonValue {
+        &self.value
+    }
+
+    /// Consume `self` and return the JSON value.
+    pub fn into_value(self) -> JsonValue {
+        self.value
+    }
+
+    /// The file descriptors that are referenced by the JSON value.
+    ///
+    /// This method is only available on Unix platforms.
+    #[cfg(unix)]
+    pub fn fds(&self) -> &[Fd<'v>] {
+        &self.fds
+    }
+
+    /// Consume `self` and return the JSON value and the file descriptors it references.
+    ///
+    /// This method is only available on Unix platforms.
+    #[cfg(unix)]
+    pub fn into_parts(self) -> (JsonValue, Vec<Fd<'v>>) {
+        (self.value, self.fds)
+    }
+
+    fn encode(&mut self, value: &'v Value<'_>) -> JsonValue {
+        match value {
+            Value::U8(v) => (*v).into(),
+            Value::Bool(v) => (*v).into(),
+            Value::I16(v) => (*v).into(),
+            Value::U16(v) => (*v).into(),
+            Value::I32(v) => (*v).into(),
+            Value::U32(v) => (*v).into(),
+            Value::I64(v) => (*v).into(),
+            Value::U64(v) => (*v).into(),
+            Value::F64(v) => match serde_json::Number::from_f64(*v) {
+                Some(n) => n.into(),
+                None if v.is_nan() => "NaN".into(),
+                None if *v > 0. => "Infinity".into(),
+                None => "-Infinity".into(),
+            },
+            Value::Str(v) => v.as_str().into(),
+            Value::Signature(v) => v.to_string().into(),
+            Value::ObjectPath(v) => v.as_str().into(),
+            Value::Value(v) => {
+                let mut variant = Map::new();
+                variant.insert("signature".into(), v.value_signature().to_string().into());
+                variant.insert("value".into(), self.encode(v));
+
+                variant.into()
+            }
+            Value::Array(array) => array.iter().map(|v| self.encode(v)).collect(),
+            Value::Dict(dict) => dict
+                .iter()
+                .map(|(k, v)| {
+                    let key = match self.encode(k) {
+                        JsonValue::String(s) => s,
+                        k => k.to_string(),
+                    };
+
+                    (key, self.encode(v))
+                })
+                .collect::<Map<_, _>>()
+                .into(),
+            Value::Structure(structure) => {
+                structure.fields().iter().map(|v| self.encode(v)).collect()
+            }
+            #[cfg(feature = "gvariant")]
+            Value::Maybe(maybe) => match maybe.inner() {
+                None => JsonValue::Null,
+                // Otherwise, `just nothing` and `nothing` would be the same.
+                Some(v) if matches!(maybe.value_signature(), Signature::Maybe(_)) => {
+                    JsonValue::Array(vec![self.encode(v)])
+                }
+                Some(v) => self.encode(v),
+            },
+            #[cfg(unix)]
+            Value::Fd(fd) => {
+                self.fds.push(Fd::from(fd.as_fd()));
+
+                (self.fds.len() - 1).into()
+            }
+        }
+    }
+}
+
+impl Deref for Json<'_> {
+    type Target = JsonValue;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// Encode `value` as JSON.
+///
+/// See the [module documentation](self) for the mapping. The file descriptors of the returned
+/// [`Json`] borrow from `value`.
+pub fn to_json<'v>(value: &'v Value<'_>) -> Json<'v> {
+    let mut json = Json {
+        value: JsonValue::Null,
+        #[cfg(unix)]
+        fds: vec![],
+        #[cfg(not(unix))]
+        phantom: PhantomData,
+    };
+    json.value = json.encode(value);
+
+    json
+}
+
+/// Decode the JSON encoding of a value of type `signature`.
+///
+/// See the [module documentation](self) for the mapping. The strings of the returned value borrow
+/// from `json`.
+///
+/// Use [`from_json_fds`] if the value contains file descriptors.
+///
+/// # Errors
+///
+/// If `json` isn't the encoding of a value of type `signature`.
+pub fn from_json<'j>(json: &'j JsonValue, signature: &Signature) -> Result<Value<'j>> {
+    let decoder = Decoder {
+        #[cfg(unix)]
+        fds: &[],
+        #[cfg(not(unix))]
+        phantom: PhantomData,
+    };
+
+    decoder.decode(json, signature, ContainerDepths::default())
+}
+
+/// Decode the JSON encoding of a value of type `signature`, with the file descriptors it
+/// references.
+///
+/// `fds` is the list of file descriptors returned by [`to_json`] along with `json`. The file
+/// descriptors of the returned value borrow from it.
+///
+/// This function is only available on Unix platforms.
+///
+/// # Errors
+///
+/// If `json` isn't the encoding of a value of type `signature`, or if it references a file
+/// descriptor that isn't in `fds`.
+#[cfg(unix)]
+pub fn from_json_fds<'j>(
+    json: &'j JsonValue,
+    signature: &Signature,
+    fds: &'j [Fd<'_>],
+) -> Result<Value<'j>> {
+    Decoder { fds }.decode(json, signature, ContainerDepths::default())
+}
+
+struct Decoder<'j, 'f> {
+    #[cfg(unix)]
+    fds: &'j [Fd<'f>],
+    #[cfg(not(unix))]
+    phantom: PhantomData<(&'j (), &'f ())>,
+}
+
+impl<'j> Decoder<'j, '_> {
+    fn decode(
+        &self,
+        json: &'j JsonValue,
+        signature: &Signature,
+        depths: ContainerDepths,
+    ) -> Result<Value<'j>> {
+        let mismatch = || Error::Message(format!("Invalid JSON for `{signature}`: `{json}`"));
+        let int = || json.as_i64().ok_or_else(mismatch);
+        let uint = || json.as_u64().ok_or_else(mismatch);
+        let str = || json.as_str().ok_or_else(mismatch);
+
+        let value = match signature {
+            Signature::Bool => Value::Bool(json.as_bool().ok_or_else(mismatch)?),
+            Signature::U8 => Value::U8(uint()?.try_into().map_err(|_| mismatch())?),
+            Signature::I16 => Value::I16(int()?.try_into().map_err(|_| mismatch())?),
+            Signature::U16 => Value::U16(uint()?.try_into().map_err(|_| mismatch())?),
+            Signature::I32 => Value::I32(int()?.try_into().map_err(|_| mismatch())?),
+            Signature::U32 => Value::U32(uint()?.try_into().map_err(|_| mismatch())?),
+            Signature::I64 => Value::I64(int()?),
+            Signature::U64 => Value::U64(uint()?),
+            Signature::F64 => match json {
+                JsonValue::Number(n) => Value::F64(n.as_f64().ok_or_else(mismatch)?),
+                JsonValue::String(s) => match s.parse::<f64>() {
+                    Ok(v) if !v.is_finite() => Value::F64(v),
+                    _ => return Err(mismatch()),
+                },
+                _ => return Err(mismatch()),
+            },
+            Signature::Str | Signature::ObjectPath | Signature::Signature => {
+                self.decode_key(str()?, signature)?
+            }
+            Signature::Variant => {
+                let depths = depths.inc_variant()?;
+                let variant = json
+                    .as_object()
+                    .filter(|variant| variant.len() == 2)
+                    .ok_or_else(mismatch)?;
+                let (Some(JsonValue::String(value_signature)), Some(value)) =
+                    (variant.get("signature"), variant.get("value"))
+                else {
+                    return Err(mismatch());
+                };
+                let value_signature = Signature::try_from(value_signature.as_str())?;
+
+                Value::Value(Box::new(self.decode(value, &value_signature, depths)?))
+            }
+            #[cfg(unix)]
+            Signature::Fd => self.fd(uint()?.try_into().map_err(|_| mismatch())?)?,
+            Signature::Array(child) => {
+                let depths = depths.inc_array()?;
+                let mut array = Array::new_full_signature(signature);
+                for element in json.as_array().ok_or_else(mismatch)? {
+                    array.append(self.decode(element, child, depths)?)?;
+                }
+
+                Value::Array(array)
+            }
+            Signature::Dict { key, value } => {
+                let depths = depths.inc_array()?;
+                let mut dict = Dict::new_full_signature(signature);
+                for (k, v) in json.as_object().ok_or_else(mismatch)? {
+                    dict.append(self.decode_key(k, key)?, self.decode(v, value, depths)?)?;
+                }
+
+                Value::Dict(dict)
+            }
+            Signature::Structure(fields) => {
+                let depths = depths.inc_structure()?;
+                let json_fields = json
+                    .as_array()
+                    .filter(|json_fields| json_fields.len() == fields.len())
+                    .ok_or_else(mismatch)?;
+                let mut builder = StructureBuilder::new();
+                for (field, field_signature) in json_fields.iter().zip(fields.iter()) {
+                    builder.push_value(self.decode(field, field_signature, depths)?);
+                }
+
+                Value::Structure(builder.build_with_signature(signature))
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(child) => {
+                let depths = depths.inc_maybe()?;
+                let maybe = match (json, child.signature()) {
+                    (JsonValue::Null, _) => Maybe::nothing_full_signature(signature),
+                    (JsonValue::Array(just), Signature::Maybe(_)) if just.len() == 1 => {
+                        Maybe::just_full_signature(self.decode(&just[0], child, depths)?, signature)
+                    }
+                    (_, Signature::Maybe(_)) => return Err(mismatch()),
+                    (json, _) => {
+                        Maybe::just_full_signature(self.decode(json, child, depths)?, signature)
+                    }
+                };
+
+                Value::Maybe(maybe)
+            }
+            Signature::Unit => return Err(mismatch()),
+        };
+
+        Ok(value)
+    }
+
+    // Decode a dict key, i.e. a basic value in the form of a string.
+    fn decode_key(&self, key: &'j str, signature: &Signature) -> Result<Value<'j>> {
+        let mismatch = || Error::Message(format!("Invalid JSON for `{signature}`: `{key:?}`"));
+        fn parse<T: std::str::FromStr>(key: &str, mismatch: impl Fn() -> Error) -> Result<T> {
+            key.parse().map_err(|_| mismatch())
+        }
+
+        let value = match signature {
+            Signature::Str => Value::Str(Str::from(key)),
+            Signature::ObjectPath => Value::ObjectPath(ObjectPath::try_from(key)?),
+            Signature::Signature => Value::Signature(Signature::try_from(key)?),
+            Signature::Bool => Value::Bool(parse(key, mismatch)?),
+            Signature::U8 => Value::U8(parse(key, mismatch)?),
+            Signature::I16 => Value::I16(parse(key, mismatch)?),
+            Signature::U16 => Value::U16(parse(key, mismatch)?),
+            Signature::I32 => Value::I32(parse(key, mismatch)?),
+            Signature::U32 => Value::U32(parse(key, mismatch)?),
+            Signature::I64 => Value::I64(parse(key, mismatch)?),
+            Signature::U64 => Value::U64(parse(key, mismatch)?),
+            Signature::F64 => Value::F64(parse(key, mismatch)?),
+            #[cfg(unix)]
+            Signature::Fd => self.fd(parse(key, mismatch)?)?,
+            _ => return Err(mismatch()),
+        };
+
+        Ok(value)
+    }
+
+    #[cfg(unix)]
+    fn fd(&self, index: usize) -> Result<Value<'j>> {
+        let fd = self.fds.get(index).ok_or(Error::UnknownFd)?;
+
+        Ok(Value::Fd(Fd::from(fd.as_fd())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::signature;
+
+    #[track_caller]
+    fn round_trip(value: Value<'_>, expected: &str) {
+        let json = to_json(&value);
+        assert_eq!(json.to_string(), expected);
+        let json: JsonValue = serde_json::from_str(expected).unwrap();
+        assert_eq!(from_json(&json, value.value_signature()).unwrap(), value);
+    }
+
+    #[test]
+    fn basic() {
+        round_trip(
+            Value::new((
+                255_u8,
+                true,
+                -1_i16,
+                65535_u16,
+                -1,
+                1_u32,
+                i64::MIN,
+                u64::MAX,
+            )),
+            "[255,true,-1,65535,-1,1,-9223372036854775808,18446744073709551615]",
+        );
+        round_trip(
+            Value::new(vec![1.5, 1., f64::INFINITY, f64::NEG_INFINITY]),
+            r#"[1.5,1.0,"Infinity","-Infinity"]"#,
+        );
+        let json = to_json(&Value::F64(f64::NAN));
+        assert_eq!(*json, "NaN");
+        let nan = f64::try_from(from_json(&json, &Signature::F64).unwrap()).unwrap();
+        assert!(nan.is_nan());
+        round_trip(
+            Value::new((
+                "a \"string\"",
+                ObjectPath::from_static_str("/a/path").unwrap(),
+                signature!("a{sv}"),
+            )),
+            r#"["a \"string\"","/a/path","a{sv}"]"#,
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fds() {
+        let (stdin, stdout) = (std::io::stdin(), std::io::stdout());
+        let value = Value::new(HashMap::from([(Fd::from(&stdin), Fd::from(&stdout))]));
+        let json = to_json(&value);
+        assert_eq!(json.to_string(), r#"{"0":1}"#);
+        assert_eq!(json.fds().len(), 2);
+        assert_eq!(
+            from_json_fds(&json, value.value_signature(), json.fds()).unwrap(),
+            value,
+        );
+
+        let json = serde_json::json!({"signature": "h", "value": 2});
+        assert_eq!(
+            from_json_fds(&json, &Signature::Variant, &[Fd::from(&stdin)]).unwrap_err(),
+            Error::UnknownFd,
+        );
+        assert_eq!(
+            from_json(&json, &Signature::Variant).unwrap_err(),
+            Error::UnknownFd,
+        );
+    }
+
+    #[test]
+    fn containers() {
+        round_trip(
+            Value::new((vec![b"ab".to_vec()], vec![] as Vec<String>)),
+            "[[[97,98]],[]]",
+        );
+        round_trip(
+            Value::new(Value::new(Value::new(42_u64))),
+            r#"{"signature":"v","value":{"signature":"t","value":42}}"#,
+        );
+        round_trip(Value::new(HashMap::from([(-1_i16, "a")])), r#"{"-1":"a"}"#);
+        round_trip(
+            Value::new(HashMap::from([(false, Value::new(vec![1.5]))])),
+            r#"{"false":{"signature":"ad","value":[1.5]}}"#,
+        );
+        let mut dict = Dict::new(&Signature::F64, &Signature::Str);
+        dict.append(Value::F64(-0.5), Value::new("a")).unwrap();
+        dict.append(Value::F64(f64::INFINITY), Value::new("b"))
+            .unwrap();
+        round_trip(Value::Dict(dict), r#"{"-0.5":"a","Infinity":"b"}"#);
+
+        #[cfg(all(feature = "gvariant", not(feature = "option-as-array")))]
+        round_trip(
+            Value::new((
+                None::<i16>,
+                Some(7_i16),
+                None::<Option<i16>>,
+                Some(None::<i16>),
+                Some(Some(7_i16)),
+            )),
+            "[null,7,null,[null],[7]]",
+        );
+    }
+
+    #[test]
+    fn mismatch() {
+        let error = |json: &str, signature: &str| {
+            let json: JsonValue = serde_json::from_str(json).unwrap();
+            let signature = Signature::try_from(signature).unwrap();
+            from_json(&json, &signature).unwrap_err()
+        };
+
+        assert!(matches!(error("256", "y"), Error::Message(_)));
+        assert!(matches!(error("-1", "u"), Error::Message(_)));
+        assert!(matches!(error("1.5", "i"), Error::Message(_)));
+        assert!(matches!(error(r#""1.5""#, "d"), Error::Message(_)));
+        assert!(matches!(error(r#"{"x":1}"#, "a{ii}"), Error::Message(_)));
+        assert!(matches!(error("[1]", "(ii)"), Error::Message(_)));
+        assert!(matches!(error("1", "v"), Error::Message(_)));
+        assert!(matches!(
+            error(r#"{"signature":"i","value":"1"}"#, "v"),
+            Error::Message(_)
+        ));
+        assert_eq!(error(r#""a""#, "o"), Error::InvalidObjectPath);
+        #[cfg(unix)]
+        {
+            assert!(matches!(error("-1", "h"), Error::Message(_)));
+            assert!(matches!(error(r#"{"a":1}"#, "a{hi}"), Error::Message(_)));
+        }
+    }
+}
@@ -47,6 +47,8 @@ mod de;
 pub mod dbus;
 #[cfg(feature = "gvariant")]
 pub mod gvariant;
+#[cfg(feature = "json")]
+pub mod json;
 
 pub mod signature;
 pub use signature::Signature;
@@ -85,6 +87,8 @@ mod into_value;
 mod owned_value;
 pub use owned_value::*;
 
+mod text;
+
 #[cfg(feature = "gvariant")]
 mod framing_offset_size;
 #[cfg(feature = "gvariant")]
@@ -98,7 +102,9 @@ pub use as_value::Deserialize as DeserializeValue;
 #[deprecated(since = "5.5.0", note = "Use `as_value::Serialize` instead.")]
 pub use as_value::Serialize as SerializeValue;
 
-pub use zvariant_derive::{signature, DeserializeDict, OwnedValue, SerializeDict, Type, Value};
+pub use zvariant_derive::{
+    signature, DeserializeDict, OwnedValue, SerializeDict, Type, Value, View,
+};
 
 // Required for the macros to function within this crate.
 extern crate self as zvariant;
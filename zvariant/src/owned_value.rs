@@ -1,5 +1,5 @@
 use serde::{Deserialize, Deserializer, Serialize};
-use std::{borrow::Borrow, collections::HashMap, hash::BuildHasher};
+use std::{borrow::Borrow, collections::HashMap, hash::BuildHasher, str::FromStr};
 
 use crate::{
     Array, Dict, NoneValue, ObjectPath, Optional, OwnedObjectPath, Signature, Str, Structure, Type,
@@ -25,6 +25,44 @@ impl OwnedValue {
         self.0.try_clone().map(Self)
     }
 
+    /// Parse a value from the [GVariant text format], as produced by the `Display` implementation
+    /// of [`Value`].
+    ///
+    /// The value is parsed as a value of type `signature` or, if `None`, of the type inferred
+    /// from the text, the same way GLib does: integers are `i` and floating point numbers are `d`
+    /// unless a type keyword (e.g `uint32 7`) or annotation (e.g `@u 7`) says otherwise, and the
+    /// type of the values of a container is unified, e.g. `[int16 1, 2]` is an `an`. If the type
+    /// can't be inferred, e.g for an empty array without annotation, an error is returned.
+    ///
+    /// File descriptors can't be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use zvariant::{OwnedValue, Signature, Value};
+    ///
+    /// let value = OwnedValue::parse(r#"{"answer": <uint32 42>, "pi": <3.14>}"#, None).unwrap();
+    /// assert_eq!(value.value_signature(), "a{sv}");
+    /// let dict = HashMap::<String, OwnedValue>::try_from(value).unwrap();
+    /// assert_eq!(u32::try_from(&dict["answer"]).unwrap(), 42);
+    ///
+    /// let signature = Signature::try_from("(qas)").unwrap();
+    /// let value = OwnedValue::parse("(7, ['a', 'b'])", Some(&signature)).unwrap();
+    /// assert_eq!(*value, Value::new((7u16, vec!["a", "b"])));
+    ///
+    /// // What's printed can be parsed back.
+    /// let value = Value::new((b"bytes\0".to_vec(), vec![0i64], vec![] as Vec<String>));
+    /// assert_eq!(value.to_string(), r#"(b"bytes", [int64 0], @as [])"#);
+    /// assert_eq!(*value.to_string().parse::<OwnedValue>().unwrap(), value);
+    /// ```
+    ///
+    /// [GVariant text format]: https://docs.gtk.org/glib/gvariant-text-format.html
+    /// [`Value`]: enum@Value
+    pub fn parse(text: &str, signature: Option<&Signature>) -> crate::Result<Self> {
+        crate::text::parse(text, signature)
+    }
+
     pub(crate) fn into_inner(self) -> Value<'static> {
         self.0
     }
@@ -275,6 +313,17 @@ impl<'a> Borrow<Value<'a>> for OwnedValue {
     }
 }
 
+impl FromStr for OwnedValue {
+    type Err = crate::Error;
+
+    /// Parse a value from the GVariant text format, inferring its type.
+    ///
+    /// See [`OwnedValue::parse`] for details.
+    fn from_str(text: &str) -> crate::Result<Self> {
+        Self::parse(text, None)
+    }
+}
+
 impl<'de> Deserialize<'de> for OwnedValue {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
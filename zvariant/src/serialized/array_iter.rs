@@ -0,0 +1,63 @@
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+
+use crate::{
+    serialized::{Data, Elements, View},
+    Result, Signature,
+};
+
+/// An iterator over the elements of a serialized array.
+///
+/// Each element is only deserialized when the iterator is advanced and no collection is allocated
+/// for the array, so this is suited for very large arrays. The elements can borrow from the
+/// underlying [`Data`].
+///
+/// Use [`Data::array_iter`] or [`Data::array_iter_for_signature`] to create an instance. Once an
+/// element fails to deserialize, the error is returned and the iteration ends.
+#[derive(Debug)]
+pub struct ArrayIter<'d, 'bytes, 'fds, T> {
+    elements: Elements<'d, 'bytes, 'fds>,
+    done: bool,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<'d, 'bytes, 'fds, T> ArrayIter<'d, 'bytes, 'fds, T>
+where
+    T: Deserialize<'d>,
+{
+    pub(super) fn new(data: &'d Data<'bytes, 'fds>, element_signature: Signature) -> Result<Self> {
+        let elements = View::new(data, Signature::array(element_signature))?.elements()?;
+
+        Ok(Self {
+            elements,
+            done: false,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<'d, T> Iterator for ArrayIter<'d, '_, '_, T>
+where
+    T: Deserialize<'d>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let element = self
+            .elements
+            .next()
+            .map(|element| element.and_then(|element| element.deserialize()));
+        if !matches!(element, Some(Ok(_))) {
+            self.done = true;
+        }
+
+        element
+    }
+}
+
+impl<'d, T> std::iter::FusedIterator for ArrayIter<'d, '_, '_, T> where T: Deserialize<'d> {}
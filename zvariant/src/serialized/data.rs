@@ -9,8 +9,10 @@ use std::{
 use serde::{de::DeserializeSeed, Deserialize};
 
 use crate::{
+    container_depths::ContainerDepths,
     de::Deserializer,
-    serialized::{Context, Format},
+    serialized::{ArrayIter, Context, Format, View},
+    utils::subslice,
     DynamicDeserialize, DynamicType, Error, Result, Signature, Type,
 };
 
@@ -229,6 +231,28 @@ impl<'bytes, 'fds> Data<'bytes, 'fds> {
     {
         let signature = signature.try_into().map_err(Into::into)?;
 
+        self.deserialize_range(0..self.len(), &signature, ContainerDepths::default())
+    }
+
+    /// Deserialize `T` from the bytes in `range`, starting at the given container depths.
+    ///
+    /// Unlike deserializing from a [`Data::slice`], the value borrows from `self`.
+    pub(super) fn deserialize_range<'d, T>(
+        &'d self,
+        range: Range<usize>,
+        signature: &Signature,
+        container_depths: ContainerDepths,
+    ) -> Result<(T, usize)>
+    where
+        T: Deserialize<'d>,
+    {
+        let bytes = subslice(self.bytes(), range.clone())?;
+        let context = Context::new(
+            self.context.format(),
+            self.context.endian(),
+            self.context.position() + range.start,
+        );
+
         #[cfg(unix)]
         let fds = &self.inner.fds;
         let mut de = match self.context.format() {
@@ -236,35 +260,31 @@ impl<'bytes, 'fds> Data<'bytes, 'fds> {
             Format::GVariant => {
                 #[cfg(unix)]
                 {
-                    crate::gvariant::Deserializer::new(
-                        self.bytes(),
-                        Some(fds),
-                        &signature,
-                        self.context,
-                    )
+                    crate::gvariant::Deserializer::new(bytes, Some(fds), signature, context)
                 }
                 #[cfg(not(unix))]
                 {
-                    crate::gvariant::Deserializer::<()>::new(self.bytes(), &signature, self.context)
+                    crate::gvariant::Deserializer::<()>::new(bytes, signature, context)
                 }
             }
-            .map(Deserializer::GVariant)?,
+            .map(|mut de| {
+                de.0.container_depths = container_depths;
+                Deserializer::GVariant(de)
+            })?,
             Format::DBus => {
                 #[cfg(unix)]
                 {
-                    crate::dbus::Deserializer::new(
-                        self.bytes(),
-                        Some(fds),
-                        &signature,
-                        self.context,
-                    )
+                    crate::dbus::Deserializer::new(bytes, Some(fds), signature, context)
                 }
                 #[cfg(not(unix))]
                 {
-                    crate::dbus::Deserializer::<()>::new(self.bytes(), &signature, self.context)
+                    crate::dbus::Deserializer::<()>::new(bytes, signature, context)
                 }
             }
-            .map(Deserializer::DBus)?,
+            .map(|mut de| {
+                de.0.container_depths = container_depths;
+                Deserializer::DBus(de)
+            })?,
         };
 
         T::deserialize(&mut de).map(|t| match de {
@@ -346,6 +366,72 @@ impl<'bytes, 'fds> Data<'bytes, 'fds> {
             Deserializer::DBus(de) => (t, de.0.pos),
         })
     }
+
+    /// A lazily-decoded [`View`] of the value with the given signature at the start of `self`.
+    ///
+    /// In the GVariant format, the value is expected to span all of `self`.
+    pub fn view<'d, S>(&'d self, signature: S) -> Result<View<'d, 'bytes, 'fds>>
+    where
+        S: TryInto<Signature>,
+        S::Error: Into<Error>,
+    {
+        let signature = signature.try_into().map_err(Into::into)?;
+
+        View::new(self, signature)
+    }
+
+    /// Iterate over the elements of the array at the start of `self`, deserializing them lazily.
+    ///
+    /// This is useful for very large arrays, as it avoids decoding the whole array into a
+    /// collection up front. Elements can still borrow from `self`. For dictionaries, `T` is the
+    /// type of an entry, i-e a `(K, V)` tuple.
+    ///
+    /// In the GVariant format, the array is expected to span all of `self`. Use [`Data::slice`]
+    /// if it's followed by other data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use zvariant::{serialized::Context, to_bytes, LE};
+    ///
+    /// let ctxt = Context::new_dbus(LE, 0);
+    /// let encoded = to_bytes(ctxt, &vec![("hello", 1u32), ("world", 2)]).unwrap();
+    /// let mut iter = encoded.array_iter::<(&str, u32)>().unwrap();
+    /// assert_eq!(iter.next().unwrap().unwrap(), ("hello", 1));
+    /// assert_eq!(iter.next().unwrap().unwrap(), ("world", 2));
+    /// assert!(iter.next().is_none());
+    ///
+    /// let encoded = to_bytes(ctxt, &HashMap::from([(42u8, "answer")])).unwrap();
+    /// for entry in encoded.array_iter::<(u8, &str)>().unwrap() {
+    ///     assert_eq!(entry.unwrap(), (42, "answer"));
+    /// }
+    /// ```
+    pub fn array_iter<'d, T>(&'d self) -> Result<ArrayIter<'d, 'bytes, 'fds, T>>
+    where
+        T: Deserialize<'d> + Type,
+    {
+        self.array_iter_for_signature(T::SIGNATURE)
+    }
+
+    /// Iterate over the elements of the array at the start of `self`, with the given element
+    /// signature.
+    ///
+    /// Use this method instead of [`Data::array_iter`] if the elements don't implement [`Type`].
+    /// For dictionaries, the element signature is the one of a `(K, V)` structure.
+    pub fn array_iter_for_signature<'d, S, T>(
+        &'d self,
+        element_signature: S,
+    ) -> Result<ArrayIter<'d, 'bytes, 'fds, T>>
+    where
+        T: Deserialize<'d>,
+        S: TryInto<Signature>,
+        S::Error: Into<Error>,
+    {
+        let signature = element_signature.try_into().map_err(Into::into)?;
+
+        ArrayIter::new(self, signature)
+    }
 }
 
 impl<'bytes> Data<'bytes, 'static> {
@@ -1,5 +1,9 @@
+mod array_iter;
+pub use array_iter::ArrayIter;
 mod data;
 pub use data::Data;
+mod view;
+pub use view::{Elements, View};
 mod size;
 pub use size::Size;
 mod written;
@@ -0,0 +1,474 @@
+use std::ops::Range;
+
+use serde::Deserialize;
+
+#[cfg(feature = "gvariant")]
+use crate::gvariant::Reader;
+use crate::{
+    container_depths::ContainerDepths,
+    serialized::{Data, Format},
+    utils::*,
+    Error, Result, Signature,
+};
+
+/// A lazily-decoded view of a serialized value.
+///
+/// Instead of deserializing the whole value, a view locates the parts of the value that are
+/// accessed, i-e structure fields, array elements, dictionary entries and variant values, in the
+/// encoded bytes. Only those parts are then deserialized. Containers are skipped over using the
+/// array lengths in the D-Bus format, and the framing offsets in the GVariant format.
+///
+/// Use [`Data::view`] to create a view of a value. See the [`View`](macro@crate::View) derive
+/// macro for typed views of structures.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use zvariant::{serialized::Context, to_bytes, Type, Value, LE};
+///
+/// let dict: HashMap<&str, Value<'_>> = HashMap::from([
+///     ("name", Value::from("zbus")),
+///     ("stars", Value::from(1000u32)),
+/// ]);
+/// let ctxt = Context::new_dbus(LE, 0);
+/// let encoded = to_bytes(ctxt, &(42u8, &dict)).unwrap();
+///
+/// let view = encoded.view(<(u8, HashMap<&str, Value<'_>>)>::SIGNATURE).unwrap();
+/// let dict = view.field(1).unwrap();
+/// let name = dict.get("name").unwrap().unwrap().variant().unwrap();
+/// assert_eq!(name.deserialize::<&str>().unwrap(), "zbus");
+/// assert!(dict.get("forks").unwrap().is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct View<'d, 'bytes, 'fds> {
+    data: &'d Data<'bytes, 'fds>,
+    range: Range<usize>,
+    signature: Signature,
+    container_depths: ContainerDepths,
+}
+
+impl<'d, 'bytes, 'fds> View<'d, 'bytes, 'fds> {
+    pub(super) fn new(data: &'d Data<'bytes, 'fds>, signature: Signature) -> Result<Self> {
+        let container_depths = ContainerDepths::default();
+        let range = match data.context().format() {
+            #[cfg(feature = "gvariant")]
+            Format::GVariant => {
+                let alignment = signature.alignment(Format::GVariant);
+                let start = skip_padding(data, 0, alignment, data.len())?;
+
+                start..data.len()
+            }
+            Format::DBus => dbus_value(data, 0, &signature, data.len(), container_depths)?,
+        };
+
+        Ok(Self {
+            data,
+            range,
+            signature,
+            container_depths,
+        })
+    }
+
+    /// The signature of the value.
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The encoded bytes of the value.
+    pub fn bytes(&self) -> &'d [u8] {
+        &self.data.bytes()[self.range.clone()]
+    }
+
+    /// Deserialize the value.
+    pub fn deserialize<T>(&self) -> Result<T>
+    where
+        T: Deserialize<'d>,
+    {
+        self.data
+            .deserialize_range(self.range.clone(), &self.signature, self.container_depths)
+            .map(|(value, _)| value)
+    }
+
+    /// The field at `index` of the structure.
+    ///
+    /// Dictionary entries, as returned by [`View::elements`], are structures as well, with the key
+    /// and the value as fields.
+    pub fn field(&self, index: usize) -> Result<Self> {
+        let Signature::Structure(fields) = &self.signature else {
+            return Err(Error::SignatureMismatch(
+                self.signature.clone(),
+                "a structure".to_string(),
+            ));
+        };
+        let container_depths = self.container_depths.inc_structure()?;
+
+        #[cfg(feature = "gvariant")]
+        if self.data.context().format() == Format::GVariant {
+            let field = self.gvariant_reader().field(index)?;
+
+            return Ok(self.child(field.range(), field.signature().clone(), container_depths));
+        }
+
+        let mut pos = self.range.start;
+        for (i, field) in fields.iter().enumerate() {
+            let range = dbus_value(self.data, pos, field, self.range.end, container_depths)?;
+            if i == index {
+                return Ok(self.child(range, field.clone(), container_depths));
+            }
+            pos = range.end;
+        }
+
+        Err(Error::Message(format!(
+            "No field at index {index} in a `{}` structure",
+            self.signature
+        )))
+    }
+
+    /// An iterator over the elements of the array, or the entries of the dictionary.
+    pub fn elements(&self) -> Result<Elements<'d, 'bytes, 'fds>> {
+        let element_signature = match &self.signature {
+            Signature::Array(child) => child.signature().clone(),
+            Signature::Dict { key, value } => {
+                Signature::structure([key.signature().clone(), value.signature().clone()])
+            }
+            _ => {
+                return Err(Error::SignatureMismatch(
+                    self.signature.clone(),
+                    "an array or dict".to_string(),
+                ))
+            }
+        };
+
+        Elements::new(self, element_signature)
+    }
+
+    /// The value of the entry with the given key in the dictionary, if any.
+    ///
+    /// This walks the entries of the dictionary, only deserializing their keys, until it finds a
+    /// match.
+    pub fn get<K>(&self, key: K) -> Result<Option<Self>>
+    where
+        K: Deserialize<'d> + PartialEq,
+    {
+        if !matches!(self.signature, Signature::Dict { .. }) {
+            return Err(Error::SignatureMismatch(
+                self.signature.clone(),
+                "a dict".to_string(),
+            ));
+        }
+
+        for entry in self.elements()? {
+            let entry = entry?;
+            if entry.field(0)?.deserialize::<K>()? == key {
+                return entry.field(1).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The value contained in the variant.
+    pub fn variant(&self) -> Result<Self> {
+        if self.signature != Signature::Variant {
+            return Err(Error::SignatureMismatch(
+                self.signature.clone(),
+                "a variant".to_string(),
+            ));
+        }
+        let container_depths = self.container_depths.inc_variant()?;
+
+        let (range, signature) = match self.data.context().format() {
+            #[cfg(feature = "gvariant")]
+            Format::GVariant => {
+                let value = self.gvariant_reader().variant()?;
+
+                (value.range(), value.signature().clone())
+            }
+            Format::DBus => {
+                let (signature, value_start) = dbus_variant_signature(self.data, &self.range)?;
+                let range = dbus_value(
+                    self.data,
+                    value_start,
+                    &signature,
+                    self.range.end,
+                    container_depths,
+                )?;
+
+                (range, signature)
+            }
+        };
+
+        Ok(self.child(range, signature, container_depths))
+    }
+
+    #[cfg(feature = "gvariant")]
+    fn gvariant_reader(&self) -> Reader<'d> {
+        Reader::with_range(
+            self.data.bytes(),
+            self.data.context(),
+            self.range.clone(),
+            self.signature.clone(),
+        )
+    }
+
+    fn child(
+        &self,
+        range: Range<usize>,
+        signature: Signature,
+        container_depths: ContainerDepths,
+    ) -> Self {
+        Self {
+            data: self.data,
+            range,
+            signature,
+            container_depths,
+        }
+    }
+}
+
+/// An iterator over the elements of an array [`View`].
+///
+/// Use [`View::elements`] to create an instance. Once an element can't be located, the error is
+/// returned and the iteration ends.
+#[derive(Debug)]
+pub struct Elements<'d, 'bytes, 'fds> {
+    array: View<'d, 'bytes, 'fds>,
+    element_signature: Signature,
+    element_alignment: usize,
+    container_depths: ContainerDepths,
+    // Position of the next element (or its padding).
+    pos: usize,
+    // End of the elements.
+    end: usize,
+    // Random access to the elements (GVariant-specific).
+    #[cfg(feature = "gvariant")]
+    gvariant: Option<GVariantElements<'d>>,
+    done: bool,
+}
+
+#[cfg(feature = "gvariant")]
+#[derive(Debug)]
+struct GVariantElements<'d> {
+    reader: Reader<'d>,
+    // Index of the next element.
+    index: usize,
+    len: usize,
+}
+
+impl<'d, 'bytes, 'fds> Elements<'d, 'bytes, 'fds> {
+    fn new(array: &View<'d, 'bytes, 'fds>, element_signature: Signature) -> Result<Self> {
+        let format = array.data.context().format();
+        let mut elements = Self {
+            array: array.clone(),
+            element_alignment: element_signature.alignment(format),
+            element_signature,
+            container_depths: array.container_depths.inc_array()?,
+            pos: array.range.start,
+            end: array.range.end,
+            #[cfg(feature = "gvariant")]
+            gvariant: None,
+            done: false,
+        };
+
+        match format {
+            #[cfg(feature = "gvariant")]
+            Format::GVariant => {
+                let reader = array.gvariant_reader();
+                let len = reader.len()?;
+                elements.gvariant = Some(GVariantElements {
+                    reader,
+                    index: 0,
+                    len,
+                });
+            }
+            Format::DBus => {
+                // The range of the array was located already, so the length is valid.
+                let data = array.data;
+                let start = array.range.start + 4;
+                elements.pos = skip_padding(data, start, elements.element_alignment, elements.end)?;
+            }
+        }
+
+        Ok(elements)
+    }
+
+    fn next_element(&mut self) -> Result<Option<Range<usize>>> {
+        #[cfg(feature = "gvariant")]
+        if let Some(gvariant) = &mut self.gvariant {
+            if gvariant.index == gvariant.len {
+                return Ok(None);
+            }
+            let element = gvariant.reader.element(gvariant.index)?;
+            gvariant.index += 1;
+
+            return Ok(Some(element.range()));
+        }
+
+        if self.pos == self.end {
+            return Ok(None);
+        }
+        let range = dbus_value(
+            self.array.data,
+            self.pos,
+            &self.element_signature,
+            self.end,
+            self.container_depths,
+        )?;
+        self.pos = range.end;
+
+        Ok(Some(range))
+    }
+}
+
+impl<'d, 'bytes, 'fds> Iterator for Elements<'d, 'bytes, 'fds> {
+    type Item = Result<View<'d, 'bytes, 'fds>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let range = match self.next_element() {
+            Ok(Some(range)) => range,
+            Ok(None) => {
+                self.done = true;
+
+                return None;
+            }
+            Err(e) => {
+                self.done = true;
+
+                return Some(Err(e));
+            }
+        };
+
+        Some(Ok(self.array.child(
+            range,
+            self.element_signature.clone(),
+            self.container_depths,
+        )))
+    }
+}
+
+impl std::iter::FusedIterator for Elements<'_, '_, '_> {}
+
+// Skip the padding before `pos` for the given alignment, returning the position after it.
+fn skip_padding(data: &Data<'_, '_>, pos: usize, alignment: usize, end: usize) -> Result<usize> {
+    let padding = padding_for_n_bytes(data.context().position() + pos, alignment);
+    let padding_end = pos + padding;
+    if padding_end > end {
+        return Err(serde::de::Error::invalid_length(
+            end,
+            &format!(">= {padding_end}").as_str(),
+        ));
+    }
+    if let Some(&byte) = data[pos..padding_end].iter().find(|b| **b != 0) {
+        return Err(Error::PaddingNot0(byte));
+    }
+
+    Ok(padding_end)
+}
+
+// Locate the D-Bus encoded value starting at `pos` (before any padding) and ending at most at
+// `end`.
+//
+// Arrays are skipped over using their length, so only structures and variants are walked.
+fn dbus_value(
+    data: &Data<'_, '_>,
+    pos: usize,
+    signature: &Signature,
+    end: usize,
+    container_depths: ContainerDepths,
+) -> Result<Range<usize>> {
+    let start = skip_padding(data, pos, signature.alignment(Format::DBus), end)?;
+    let read_len = |size: usize| -> Result<usize> {
+        if start + size > end {
+            return Err(serde::de::Error::invalid_length(
+                end - start,
+                &format!(">= {size}").as_str(),
+            ));
+        }
+        let bytes = &data[start..start + size];
+        match size {
+            1 => Ok(bytes[0] as usize),
+            _ => Ok(data.context().endian().read_u32(bytes) as usize),
+        }
+    };
+
+    let value_end = match signature {
+        Signature::Unit => start,
+        Signature::U8 => start + 1,
+        Signature::I16 | Signature::U16 => start + 2,
+        Signature::Bool | Signature::I32 | Signature::U32 => start + 4,
+        #[cfg(unix)]
+        Signature::Fd => start + 4,
+        Signature::I64 | Signature::U64 | Signature::F64 => start + 8,
+        // Length, string and trailing nul byte.
+        Signature::Str | Signature::ObjectPath => start + 4 + read_len(4)? + 1,
+        Signature::Signature => start + 1 + read_len(1)? + 1,
+        Signature::Variant => {
+            let (signature, value_start) = dbus_variant_signature(data, &(start..end))?;
+
+            dbus_value(
+                data,
+                value_start,
+                &signature,
+                end,
+                container_depths.inc_variant()?,
+            )?
+            .end
+        }
+        Signature::Array(_) | Signature::Dict { .. } => {
+            let len = read_len(4)?;
+            let element_alignment = match signature {
+                Signature::Array(child) => child.alignment(Format::DBus),
+                _ => DICT_ENTRY_ALIGNMENT_DBUS,
+            };
+            // D-Bus expects the padding for the first element even if the array is empty.
+            skip_padding(data, start + 4, element_alignment, end)? + len
+        }
+        Signature::Structure(fields) => {
+            let container_depths = container_depths.inc_structure()?;
+            let mut pos = start;
+            for field in fields.iter() {
+                pos = dbus_value(data, pos, field, end, container_depths)?.end;
+            }
+
+            pos
+        }
+        #[cfg(feature = "gvariant")]
+        Signature::Maybe(_) => {
+            return Err(Error::SignatureMismatch(
+                signature.clone(),
+                "a D-Bus type".to_string(),
+            ))
+        }
+    };
+    if value_end > end {
+        return Err(serde::de::Error::invalid_length(
+            end - start,
+            &format!(">= {}", value_end - start).as_str(),
+        ));
+    }
+
+    Ok(start..value_end)
+}
+
+// Parse the signature of the D-Bus encoded variant in `range`, returning it and the position of
+// the value.
+fn dbus_variant_signature(data: &Data<'_, '_>, range: &Range<usize>) -> Result<(Signature, usize)> {
+    let sig_len = *subslice(data.bytes(), range.start)? as usize;
+    let sig_start = range.start + 1;
+    let sig_end = sig_start + sig_len;
+    if sig_end >= range.end {
+        return Err(serde::de::Error::invalid_length(
+            range.end - range.start,
+            &format!("> {}", sig_end - range.start).as_str(),
+        ));
+    }
+    let signature = Signature::from_bytes(&data[sig_start..sig_end])?;
+
+    // Skip the trailing nul byte.
+    Ok((signature, sig_end + 1))
+}
@@ -0,0 +1,908 @@
+//! Parser for the GVariant text format, the reverse of the `Display` implementation of [`Value`].
+//!
+//! Implemented based on [GLib's parser](https://gitlab.gnome.org/GNOME/glib/-/blob/e1d47f0b0d0893ac9171e24cc7bf635495376546/glib/gvariant-parser.c).
+//!
+//! The text is first parsed into a tree of [`Node`]s. If no signature is given, the type of the
+//! value is then inferred from the tree, the same way GLib does: literals only constrain their type
+//! to a set of types (e.g any numeric type for numbers), the constraints of the elements of a
+//! container are unified and, eventually, the remaining sets are resolved to their default type
+//! (e.g `i` for integers). Finally, the value is built from the tree for the signature.
+
+#[cfg(feature = "gvariant")]
+use crate::Maybe;
+use crate::{
+    container_depths::ContainerDepths, Array, Dict, Error, ObjectPath, OwnedValue, Result,
+    Signature, Str, StructureBuilder, Value,
+};
+
+/// Parse `text` as a value of type `signature` or, if `None`, of the inferred type.
+pub(crate) fn parse(text: &str, signature: Option<&Signature>) -> Result<OwnedValue> {
+    let mut parser = Parser { text, pos: 0 };
+    let node = parser.value(ContainerDepths::default(), true)?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error(parser.pos, "unexpected trailing characters"));
+    }
+
+    let value = match signature {
+        Some(signature) => build(&node, signature)?,
+        None => build_inferred(&node)?,
+    };
+
+    Ok(OwnedValue(value))
+}
+
+// A parsed value, not yet bound to a type.
+#[derive(Debug)]
+struct Node {
+    // The offset of the value in the text.
+    pos: usize,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Bool(bool),
+    // The literal is only interpreted once the type is known.
+    Number(String),
+    Str(String),
+    Bytes(Vec<u8>),
+    Array(Vec<Node>),
+    Dict(Vec<(Node, Node)>),
+    Structure(Vec<Node>),
+    Variant(Box<Node>),
+    #[cfg(feature = "gvariant")]
+    Nothing,
+    #[cfg(feature = "gvariant")]
+    Just(Box<Node>),
+    // A value with a type keyword (e.g `int16 5`) or annotation (e.g `@n 5`).
+    Typed(Signature, Box<Node>),
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, pos: usize, msg: impl Into<String>) -> Error {
+        Error::TextParse(pos, msg.into())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    // Skip the whitespace and `c`, if it's next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(self.pos, format!("expected `{c}`")))
+        }
+    }
+
+    // The value at the current position. A value can only have one type keyword or annotation,
+    // hence `typed`.
+    fn value(&mut self, depths: ContainerDepths, typed: bool) -> Result<Node> {
+        self.skip_whitespace();
+        let pos = self.pos;
+        let kind = match self.peek() {
+            None => return Err(self.error(pos, "expected a value")),
+            Some('[') => {
+                self.pos += 1;
+                Kind::Array(self.array(depths.inc_array()?)?)
+            }
+            Some('{') => {
+                self.pos += 1;
+                Kind::Dict(self.dict(depths.inc_array()?)?)
+            }
+            Some('(') => {
+                self.pos += 1;
+                Kind::Structure(self.structure(depths.inc_structure()?)?)
+            }
+            Some('<') => {
+                self.pos += 1;
+                let value = self.value(depths.inc_variant()?, true)?;
+                self.expect('>')?;
+
+                Kind::Variant(Box::new(value))
+            }
+            Some('@') if !typed => return Err(self.error(pos, "a value can only have one type")),
+            Some('@') => {
+                self.pos += 1;
+                let signature = self.signature()?;
+
+                Kind::Typed(signature, Box::new(self.value(depths, false)?))
+            }
+            Some(q @ ('"' | '\'')) => {
+                self.pos += 1;
+                let bytes = self.string(q)?;
+                let s = String::from_utf8(bytes)
+                    .map_err(|_| self.error(pos, "strings must be valid UTF-8"))?;
+
+                Kind::Str(s)
+            }
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => {
+                Kind::Number(self.number())
+            }
+            Some(c) if c.is_ascii_alphabetic() => self.word(depths, typed)?,
+            Some(c) => return Err(self.error(pos, format!("unexpected `{c}`"))),
+        };
+
+        Ok(Node { pos, kind })
+    }
+
+    fn word(&mut self, depths: ContainerDepths, typed: bool) -> Result<Kind> {
+        let pos = self.pos;
+        let len = self.text[pos..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(self.text.len() - pos);
+        let word = &self.text[pos..pos + len];
+        self.pos += len;
+
+        if let Some(signature) = keyword_signature(word) {
+            if !typed {
+                return Err(self.error(pos, "a value can only have one type"));
+            }
+            return Ok(Kind::Typed(signature, Box::new(self.value(depths, false)?)));
+        }
+
+        Ok(match word {
+            "true" => Kind::Bool(true),
+            "false" => Kind::Bool(false),
+            #[cfg(feature = "gvariant")]
+            "nothing" => Kind::Nothing,
+            #[cfg(feature = "gvariant")]
+            "just" => Kind::Just(Box::new(self.value(depths.inc_maybe()?, true)?)),
+            "b" if matches!(self.peek(), Some('"' | '\'')) => {
+                let q = self.next().expect("peeked");
+                let mut bytes = self.string(q)?;
+                // Byte strings are nul-terminated.
+                bytes.push(0);
+
+                Kind::Bytes(bytes)
+            }
+            w if w.eq_ignore_ascii_case("inf") || w.eq_ignore_ascii_case("nan") => {
+                Kind::Number(w.to_string())
+            }
+            _ => return Err(self.error(pos, format!("unknown keyword `{word}`"))),
+        })
+    }
+
+    fn array(&mut self, depths: ContainerDepths) -> Result<Vec<Node>> {
+        let mut elements = vec![];
+        if self.eat(']') {
+            return Ok(elements);
+        }
+        loop {
+            elements.push(self.value(depths, true)?);
+            if !self.eat(',') {
+                self.expect(']')?;
+
+                return Ok(elements);
+            }
+        }
+    }
+
+    fn dict(&mut self, depths: ContainerDepths) -> Result<Vec<(Node, Node)>> {
+        let mut entries = vec![];
+        if self.eat('}') {
+            return Ok(entries);
+        }
+        loop {
+            let key = self.value(depths, true)?;
+            self.expect(':')?;
+            let value = self.value(depths, true)?;
+            entries.push((key, value));
+            if !self.eat(',') {
+                self.expect('}')?;
+
+                return Ok(entries);
+            }
+        }
+    }
+
+    fn structure(&mut self, depths: ContainerDepths) -> Result<Vec<Node>> {
+        let mut fields = vec![];
+        if self.eat(')') {
+            return Ok(fields);
+        }
+        loop {
+            fields.push(self.value(depths, true)?);
+            // The trailing comma is required for single-field structures only.
+            if !self.eat(',') {
+                self.expect(')')?;
+
+                return Ok(fields);
+            }
+            if self.eat(')') {
+                return Ok(fields);
+            }
+        }
+    }
+
+    // The type of an annotation, i.e after the `@`.
+    fn signature(&mut self) -> Result<Signature> {
+        let rest = &self.text[self.pos..];
+        let len = single_type_len(rest)
+            .ok_or_else(|| self.error(self.pos, "expected a type after `@`"))?;
+        let signature = Signature::try_from(&rest[..len])
+            .map_err(|e| self.error(self.pos, format!("invalid type: {e}")))?;
+        self.pos += len;
+
+        Ok(signature)
+    }
+
+    fn number(&mut self) -> String {
+        let start = self.pos;
+        let mut prev = '\0';
+        while let Some(c) = self.peek() {
+            let exponent_sign = matches!(c, '+' | '-') && matches!(prev, 'e' | 'E');
+            let sign = matches!(c, '+' | '-') && self.pos == start;
+            if !(c.is_ascii_alphanumeric() || c == '.' || exponent_sign || sign) {
+                break;
+            }
+            prev = c;
+            self.pos += 1;
+        }
+
+        self.text[start..self.pos].to_string()
+    }
+
+    // The contents of a string literal, after the opening `quote`.
+    fn string(&mut self, quote: char) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        loop {
+            let pos = self.pos;
+            match self.next() {
+                None => return Err(self.error(pos, "unterminated string")),
+                Some(c) if c == quote => return Ok(bytes),
+                Some('\\') => self.escape(&mut bytes)?,
+                Some(c) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+    }
+
+    // The escape sequence after a `\`, supporting the ones of both GLib and Rust.
+    fn escape(&mut self, bytes: &mut Vec<u8>) -> Result<()> {
+        let pos = self.pos - 1;
+        let c = match self.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('a') => '\x07',
+            Some('b') => '\x08',
+            Some('f') => '\x0c',
+            Some('v') => '\x0b',
+            Some(c @ ('\\' | '"' | '\'')) => c,
+            Some(d @ '0'..='7') => {
+                let mut byte = d.to_digit(8).expect("octal digit");
+                for _ in 0..2 {
+                    match self.peek().and_then(|c| c.to_digit(8)) {
+                        Some(d) if byte * 8 + d <= 0xff => {
+                            byte = byte * 8 + d;
+                            self.pos += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                bytes.push(byte as u8);
+
+                return Ok(());
+            }
+            Some('x') => {
+                let byte = self.hex_digits(2, 2)?;
+                bytes.push(byte as u8);
+
+                return Ok(());
+            }
+            Some('u') if self.peek() == Some('{') => {
+                self.pos += 1;
+                let c = self.hex_digits(1, 6)?;
+                if self.next() != Some('}') {
+                    return Err(self.error(pos, "expected `}` in unicode escape"));
+                }
+
+                char::from_u32(c).ok_or_else(|| self.error(pos, "invalid unicode escape"))?
+            }
+            Some('u') => {
+                let c = self.hex_digits(4, 4)?;
+                char::from_u32(c).ok_or_else(|| self.error(pos, "invalid unicode escape"))?
+            }
+            Some('U') => {
+                let c = self.hex_digits(8, 8)?;
+                char::from_u32(c).ok_or_else(|| self.error(pos, "invalid unicode escape"))?
+            }
+            _ => return Err(self.error(pos, "invalid escape sequence")),
+        };
+        bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+
+        Ok(())
+    }
+
+    fn hex_digits(&mut self, min: usize, max: usize) -> Result<u32> {
+        let start = self.pos;
+        let len = self.text[start..]
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(self.text.len() - start)
+            .min(max);
+        if len < min {
+            return Err(self.error(start, "expected hexadecimal digits"));
+        }
+        self.pos += len;
+
+        Ok(u32::from_str_radix(&self.text[start..self.pos], 16).expect("hex digits"))
+    }
+}
+
+// The type of the GVariant type keywords.
+fn keyword_signature(word: &str) -> Option<Signature> {
+    Some(match word {
+        "boolean" => Signature::Bool,
+        "byte" => Signature::U8,
+        "int16" => Signature::I16,
+        "uint16" => Signature::U16,
+        "int32" => Signature::I32,
+        "uint32" => Signature::U32,
+        "int64" => Signature::I64,
+        "uint64" => Signature::U64,
+        "double" => Signature::F64,
+        "string" => Signature::Str,
+        "objectpath" => Signature::ObjectPath,
+        "signature" => Signature::Signature,
+        #[cfg(unix)]
+        "handle" => Signature::Fd,
+        _ => return None,
+    })
+}
+
+// The length of the single complete type at the start of `s`, if any.
+fn single_type_len(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth = depth.checked_sub(1)?,
+            // Prefixes of the following type.
+            'a' | 'm' => continue,
+            c if c.is_ascii_alphabetic() => (),
+            _ => return None,
+        }
+        if depth == 0 {
+            return Some(i + 1);
+        }
+    }
+
+    None
+}
+
+// The set of types a value could be of.
+#[derive(Debug, Clone, PartialEq)]
+enum Pattern {
+    // Any type, e.g for the elements of an empty array.
+    Any,
+    // Any numeric type.
+    Integer,
+    // A floating point number.
+    Float,
+    // A string, an object path or a signature.
+    String,
+    // A single basic type.
+    Basic(Signature),
+    Array(Box<Pattern>),
+    Dict(Box<Pattern>, Box<Pattern>),
+    Structure(Vec<Pattern>),
+    #[cfg(feature = "gvariant")]
+    Maybe(Box<Pattern>),
+}
+
+impl Pattern {
+    fn from_signature(signature: &Signature) -> Self {
+        match signature {
+            Signature::Array(child) => Pattern::Array(Box::new(Self::from_signature(child))),
+            Signature::Dict { key, value } => Pattern::Dict(
+                Box::new(Self::from_signature(key)),
+                Box::new(Self::from_signature(value)),
+            ),
+            Signature::Structure(fields) => {
+                Pattern::Structure(fields.iter().map(Self::from_signature).collect())
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(child) => Pattern::Maybe(Box::new(Self::from_signature(child))),
+            _ => Pattern::Basic(signature.clone()),
+        }
+    }
+
+    // The patterns of the values that can be of both `self` and `other`.
+    fn unify(self, other: Self) -> Option<Self> {
+        use Pattern::*;
+
+        Some(match (self, other) {
+            (Any, p) | (p, Any) => p,
+            (Integer, Integer) => Integer,
+            (Integer | Float, Integer | Float) => Float,
+            (Integer, Basic(s)) | (Basic(s), Integer) if is_numeric(&s) => Basic(s),
+            (Float, Basic(Signature::F64)) | (Basic(Signature::F64), Float) => {
+                Basic(Signature::F64)
+            }
+            (String, String) => String,
+            (String, Basic(s)) | (Basic(s), String)
+                if matches!(
+                    s,
+                    Signature::Str | Signature::ObjectPath | Signature::Signature
+                ) =>
+            {
+                Basic(s)
+            }
+            (Basic(s1), Basic(s2)) if s1 == s2 => Basic(s1),
+            (Array(p1), Array(p2)) => Array(Box::new(p1.unify(*p2)?)),
+            // An empty array can be an empty dict.
+            (Array(p), Dict(k, v)) | (Dict(k, v), Array(p)) if *p == Any => Dict(k, v),
+            (Dict(k1, v1), Dict(k2, v2)) => {
+                Dict(Box::new(k1.unify(*k2)?), Box::new(v1.unify(*v2)?))
+            }
+            (Structure(f1), Structure(f2)) if f1.len() == f2.len() => Structure(
+                f1.into_iter()
+                    .zip(f2)
+                    .map(|(p1, p2)| p1.unify(p2))
+                    .collect::<Option<_>>()?,
+            ),
+            #[cfg(feature = "gvariant")]
+            (Maybe(p1), Maybe(p2)) => Maybe(Box::new(p1.unify(*p2)?)),
+            // The `just` is optional.
+            #[cfg(feature = "gvariant")]
+            (Maybe(p1), p2) | (p2, Maybe(p1)) => Maybe(Box::new(p1.unify(p2)?)),
+            _ => return None,
+        })
+    }
+
+    // The default type of the pattern.
+    fn signature(&self) -> Option<Signature> {
+        Some(match self {
+            Pattern::Any => return None,
+            Pattern::Integer => Signature::I32,
+            Pattern::Float => Signature::F64,
+            Pattern::String => Signature::Str,
+            Pattern::Basic(s) => s.clone(),
+            Pattern::Array(p) => Signature::array(p.signature()?),
+            Pattern::Dict(k, v) => {
+                let key = k.signature()?;
+                if matches!(
+                    key,
+                    Signature::Variant
+                        | Signature::Array(_)
+                        | Signature::Dict { .. }
+                        | Signature::Structure(_)
+                ) {
+                    return None;
+                }
+                #[cfg(feature = "gvariant")]
+                if matches!(key, Signature::Maybe(_)) {
+                    return None;
+                }
+
+                Signature::dict(key, v.signature()?)
+            }
+            Pattern::Structure(fields) if fields.is_empty() => return None,
+            Pattern::Structure(fields) => Signature::structure(
+                fields
+                    .iter()
+                    .map(Pattern::signature)
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+            #[cfg(feature = "gvariant")]
+            Pattern::Maybe(p) => Signature::maybe(p.signature()?),
+        })
+    }
+}
+
+fn is_numeric(signature: &Signature) -> bool {
+    matches!(
+        signature,
+        Signature::U8
+            | Signature::I16
+            | Signature::U16
+            | Signature::I32
+            | Signature::U32
+            | Signature::I64
+            | Signature::U64
+            | Signature::F64
+    )
+}
+
+fn is_float_literal(literal: &str) -> bool {
+    let digits = literal.trim_start_matches(['-', '+']);
+    if digits.starts_with("0x") || digits.starts_with("0X") {
+        return false;
+    }
+
+    digits.contains(['.', 'e', 'E'])
+        || digits.eq_ignore_ascii_case("inf")
+        || digits.eq_ignore_ascii_case("nan")
+}
+
+fn infer(node: &Node) -> Result<Pattern> {
+    let mismatch = |node: &Node| {
+        Error::TextParse(
+            node.pos,
+            "value doesn't match the type of the other ones".into(),
+        )
+    };
+
+    Ok(match &node.kind {
+        Kind::Bool(_) => Pattern::Basic(Signature::Bool),
+        Kind::Number(literal) if is_float_literal(literal) => Pattern::Float,
+        Kind::Number(_) => Pattern::Integer,
+        Kind::Str(_) => Pattern::String,
+        Kind::Bytes(_) => Pattern::Array(Box::new(Pattern::Basic(Signature::U8))),
+        Kind::Array(elements) => {
+            let mut pattern = Pattern::Any;
+            for element in elements {
+                pattern = pattern
+                    .unify(infer(element)?)
+                    .ok_or_else(|| mismatch(element))?;
+            }
+
+            Pattern::Array(Box::new(pattern))
+        }
+        Kind::Dict(entries) => {
+            let (mut key_pattern, mut value_pattern) = (Pattern::Any, Pattern::Any);
+            for (key, value) in entries {
+                key_pattern = key_pattern
+                    .unify(infer(key)?)
+                    .ok_or_else(|| mismatch(key))?;
+                value_pattern = value_pattern
+                    .unify(infer(value)?)
+                    .ok_or_else(|| mismatch(value))?;
+            }
+
+            Pattern::Dict(Box::new(key_pattern), Box::new(value_pattern))
+        }
+        Kind::Structure(fields) => {
+            Pattern::Structure(fields.iter().map(infer).collect::<Result<_>>()?)
+        }
+        Kind::Variant(_) => Pattern::Basic(Signature::Variant),
+        #[cfg(feature = "gvariant")]
+        Kind::Nothing => Pattern::Maybe(Box::new(Pattern::Any)),
+        #[cfg(feature = "gvariant")]
+        Kind::Just(value) => Pattern::Maybe(Box::new(infer(value)?)),
+        Kind::Typed(signature, value) => Pattern::from_signature(signature)
+            .unify(infer(value)?)
+            .ok_or_else(|| type_mismatch(value, signature))?,
+    })
+}
+
+fn type_mismatch(node: &Node, signature: &Signature) -> Error {
+    Error::TextParse(node.pos, format!("expected a value of type `{signature}`"))
+}
+
+// Build the value of `node`, inferring its type.
+fn build_inferred(node: &Node) -> Result<Value<'static>> {
+    let signature = infer(node)?
+        .signature()
+        .ok_or_else(|| Error::TextParse(node.pos, "unable to infer the type".into()))?;
+
+    build(node, &signature)
+}
+
+// Build the value of `node` as a value of type `signature`.
+fn build(node: &Node, signature: &Signature) -> Result<Value<'static>> {
+    let value = match (&node.kind, signature) {
+        (Kind::Typed(s, value), _) if s == signature => build(value, signature)?,
+        #[cfg(feature = "gvariant")]
+        (Kind::Nothing, Signature::Maybe(_)) => {
+            Value::Maybe(Maybe::nothing_full_signature(signature))
+        }
+        #[cfg(feature = "gvariant")]
+        (Kind::Just(value), Signature::Maybe(child)) => {
+            Value::Maybe(Maybe::just_full_signature(build(value, child)?, signature))
+        }
+        // The `just` is optional.
+        #[cfg(feature = "gvariant")]
+        (_, Signature::Maybe(child)) => {
+            Value::Maybe(Maybe::just_full_signature(build(node, child)?, signature))
+        }
+        (Kind::Bool(b), Signature::Bool) => Value::Bool(*b),
+        (Kind::Number(literal), s) if is_numeric(s) => number(node.pos, literal, s)?,
+        (Kind::Str(s), Signature::Str) => Value::Str(Str::from(s.clone())),
+        (Kind::Str(s), Signature::ObjectPath) => Value::ObjectPath(
+            ObjectPath::try_from(s.clone())
+                .map_err(|_| Error::TextParse(node.pos, "invalid object path".into()))?,
+        ),
+        (Kind::Str(s), Signature::Signature) => Value::Signature(
+            Signature::try_from(s.as_str())
+                .map_err(|_| Error::TextParse(node.pos, "invalid signature".into()))?,
+        ),
+        (Kind::Bytes(bytes), Signature::Array(child)) if *child.signature() == Signature::U8 => {
+            let mut array = Array::new_full_signature(signature);
+            for byte in bytes {
+                array.append(Value::U8(*byte))?;
+            }
+
+            Value::Array(array)
+        }
+        (Kind::Array(elements), Signature::Array(child)) => {
+            let mut array = Array::new_full_signature(signature);
+            for element in elements {
+                array.append(build(element, child)?)?;
+            }
+
+            Value::Array(array)
+        }
+        (Kind::Array(elements), Signature::Dict { .. }) if elements.is_empty() => {
+            Value::Dict(Dict::new_full_signature(signature))
+        }
+        (Kind::Dict(entries), Signature::Dict { key, value }) => {
+            let mut dict = Dict::new_full_signature(signature);
+            for (k, v) in entries {
+                dict.append(build(k, key)?, build(v, value)?)?;
+            }
+
+            Value::Dict(dict)
+        }
+        (Kind::Structure(fields), Signature::Structure(field_signatures))
+            if !fields.is_empty() && fields.len() == field_signatures.len() =>
+        {
+            let mut builder = StructureBuilder::new();
+            for (field, field_signature) in fields.iter().zip(field_signatures.iter()) {
+                builder.push_value(build(field, field_signature)?);
+            }
+
+            Value::Structure(builder.build_with_signature(signature))
+        }
+        (Kind::Variant(value), Signature::Variant) => {
+            Value::Value(Box::new(build_inferred(value)?))
+        }
+        #[cfg(unix)]
+        (Kind::Number(_), Signature::Fd) => {
+            return Err(Error::TextParse(
+                node.pos,
+                "file descriptors can't be parsed".into(),
+            ))
+        }
+        _ => return Err(type_mismatch(node, signature)),
+    };
+
+    Ok(value)
+}
+
+// The numeric value of `literal`, of type `signature`.
+fn number(pos: usize, literal: &str, signature: &Signature) -> Result<Value<'static>> {
+    let out_of_range =
+        || Error::TextParse(pos, format!("invalid `{signature}` number `{literal}`"));
+    let float = is_float_literal(literal);
+    if float && *signature != Signature::F64 {
+        return Err(out_of_range());
+    }
+    let int = || integer(literal).ok_or_else(out_of_range);
+
+    Ok(match signature {
+        Signature::U8 => Value::U8(int()?.try_into().map_err(|_| out_of_range())?),
+        Signature::I16 => Value::I16(int()?.try_into().map_err(|_| out_of_range())?),
+        Signature::U16 => Value::U16(int()?.try_into().map_err(|_| out_of_range())?),
+        Signature::I32 => Value::I32(int()?.try_into().map_err(|_| out_of_range())?),
+        Signature::U32 => Value::U32(int()?.try_into().map_err(|_| out_of_range())?),
+        Signature::I64 => Value::I64(int()?.try_into().map_err(|_| out_of_range())?),
+        Signature::U64 => Value::U64(int()?.try_into().map_err(|_| out_of_range())?),
+        Signature::F64 if float => Value::F64(literal.parse().map_err(|_| out_of_range())?),
+        Signature::F64 => Value::F64(int()? as f64),
+        _ => unreachable!("not a numeric type"),
+    })
+}
+
+// The value of an integer literal: decimal, hexadecimal (`0x` prefix) or octal (`0` prefix).
+fn integer(literal: &str) -> Option<i128> {
+    let (negative, digits) = match literal.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, literal.strip_prefix('+').unwrap_or(literal)),
+    };
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let magnitude = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        if !hex.starts_with(|c: char| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        u64::from_str_radix(hex, 16)
+    } else if let Some(octal) = digits.strip_prefix('0').filter(|o| !o.is_empty()) {
+        if !octal.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        u64::from_str_radix(octal, 8)
+    } else {
+        digits.parse()
+    }
+    .ok()? as i128;
+
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{signature, MaxDepthExceeded};
+
+    #[track_caller]
+    fn round_trip(value: Value<'_>) {
+        let text = value.to_string();
+        let parsed = parse(&text, None).unwrap_or_else(|e| panic!("`{text}`: {e}"));
+        assert_eq!(*parsed, value, "{text}");
+        let parsed = parse(&text, Some(value.value_signature())).unwrap();
+        assert_eq!(*parsed, value, "{text}");
+    }
+
+    #[test]
+    fn display_round_trip() {
+        round_trip(Value::new((
+            255_u8,
+            true,
+            -1_i16,
+            65535_u16,
+            -1,
+            1_u32,
+            -9223372036854775808_i64,
+            18446744073709551615_u64,
+            (-1., 1.0, 11000000000., 1.1e-10),
+        )));
+        round_trip(Value::new(vec![
+            "", " ", "a", r#"""#, "'", "a'b", "a'\"b", "\\", "\n'\"",
+        ]));
+        round_trip(Value::new(vec![
+            "\x07\x08\x09\x0A\x0B\x0C\x0D",
+            "\x7F",
+            char::from_u32(0xD8000).unwrap().to_string().as_str(),
+        ]));
+        round_trip(Value::new((
+            vec![signature!(""), signature!("(ysa{sd})")],
+            vec![
+                ObjectPath::from_static_str("/").unwrap(),
+                ObjectPath::from_static_str("/a/path").unwrap(),
+            ],
+            vec![
+                Value::new(0_u8),
+                Value::new((Value::new(51), Value::new(Value::new(1_u32)))),
+            ],
+        )));
+        round_trip(Value::new(vec![] as Vec<Vec<i64>>));
+        round_trip(Value::new(vec![vec![0_i16, 1_i16], vec![2_i16, 3_i16]]));
+        round_trip(Value::new(vec![
+            b"Hello".to_vec(),
+            b"Hell\0o".to_vec(),
+            b"Hello\0".to_vec(),
+            b"\0".to_vec(),
+            b"\n'\"\0".to_vec(),
+            b"\\\0".to_vec(),
+        ]));
+        round_trip(Value::new(HashMap::<bool, bool>::new()));
+        round_trip(Value::new(HashMap::from([
+            (32_u16, 64_i64),
+            (100_u16, 200_i64),
+        ])));
+        round_trip(Value::new(((true,), (true, false), (true, true, false))));
+        round_trip(Value::new(HashMap::from([(
+            "dimension",
+            Value::new((vec![2.4, 1.], Value::new((3_u8, "Hello!")))),
+        )])));
+        round_trip(Value::new(vec![f64::INFINITY, f64::NEG_INFINITY]));
+
+        #[cfg(all(feature = "gvariant", not(feature = "option-as-array")))]
+        {
+            round_trip(Value::new((
+                (Some(0_i16), Some(Some(0_i16)), Some(Some(Some(0_i16)))),
+                (None::<i16>, Some(None::<i16>), Some(Some(None::<i16>))),
+                (None::<Option<i16>>, Some(None::<Option<i16>>)),
+            )));
+            round_trip(Value::new((
+                None::<bool>,
+                Some(HashMap::from([("size", Value::new((800, 600)))])),
+                Some(Some(200_i16)),
+            )));
+        }
+    }
+
+    #[test]
+    fn glib_syntax() {
+        let parsed = |text| parse(text, None).unwrap().into_inner();
+
+        assert_eq!(parsed("'single'"), Value::new("single"));
+        assert_eq!(parsed(r#""A\U00000042\x43\104""#), Value::new("ABCD"));
+        assert_eq!(parsed("  [ 1 ,2,3 ]  "), Value::new(vec![1, 2, 3]));
+        assert_eq!(parsed("[1, int64 2]"), Value::new(vec![1_i64, 2]));
+        assert_eq!(parsed("[1, 2.5]"), Value::new(vec![1., 2.5]));
+        assert_eq!(
+            parsed("@au [0x10, 010, +1]"),
+            Value::new(vec![16_u32, 8, 1])
+        );
+        assert_eq!(parsed("double 3"), Value::new(3.));
+        assert_eq!(parsed("(1)"), Value::new((1,)));
+        assert_eq!(
+            parsed("@a{sv} []"),
+            Value::new(HashMap::<&str, Value<'_>>::new())
+        );
+        assert_eq!(parsed("b'\\xff\\001'"), Value::new(vec![0xff_u8, 1, 0]));
+        assert_eq!(
+            parsed("[objectpath '/a', '/b']"),
+            Value::new(vec![
+                ObjectPath::from_static_str("/a").unwrap(),
+                ObjectPath::from_static_str("/b").unwrap(),
+            ])
+        );
+
+        #[cfg(all(feature = "gvariant", not(feature = "option-as-array")))]
+        {
+            assert_eq!(parsed("just 5"), Value::new(Some(5)));
+            assert_eq!(
+                parsed("[just 5, 6, nothing]"),
+                Value::new(vec![Some(5), Some(6), None])
+            );
+            assert_eq!(parsed("@mmb just nothing"), Value::new(Some(None::<bool>)));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text, signature: Option<&str>| {
+            let signature = signature.map(|s| Signature::try_from(s).unwrap());
+            match parse(text, signature.as_ref()) {
+                Err(Error::TextParse(pos, _)) => pos,
+                res => panic!("`{text}`: expected a parse error, got {res:?}"),
+            }
+        };
+
+        assert_eq!(error("", None), 0);
+        assert_eq!(error("[1, 2", None), 5);
+        assert_eq!(error("[1, 'a']", None), 4);
+        assert_eq!(error("[]", None), 0);
+        assert_eq!(error("()", None), 0);
+        assert_eq!(error("1 2", None), 2);
+        assert_eq!(error("int16 1.5", None), 6);
+        assert_eq!(error("byte 256", None), 5);
+        assert_eq!(error("@n @n 1", None), 3);
+        assert_eq!(error("int16 uint16 1", None), 6);
+        assert_eq!(error("'unterminated", None), 13);
+        assert_eq!(error(r#""\q""#, None), 1);
+        assert_eq!(error("objectpath 'a'", None), 11);
+        assert_eq!(error("{[1]: 2}", None), 0);
+        assert_eq!(error("foo", None), 0);
+        assert_eq!(error("5", Some("s")), 0);
+        assert_eq!(error("5", Some("v")), 0);
+        assert_eq!(error("(1, 2)", Some("(i)")), 0);
+        #[cfg(unix)]
+        assert_eq!(error("handle 0", None), 7);
+
+        let nested = format!("{}1{}", "[".repeat(100), "]".repeat(100));
+        assert_eq!(
+            parse(&nested, None),
+            Err(Error::MaxDepthExceeded(MaxDepthExceeded::Array))
+        );
+    }
+}
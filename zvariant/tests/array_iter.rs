@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use zvariant::{
+    serialized::{Context, Format},
+    to_bytes, Error, Result, BE, LE,
+};
+
+#[test]
+fn array_iter() {
+    let records: Vec<_> = (0..1000u32)
+        .map(|i| (i, format!("record {i}"), i % 2 == 0))
+        .collect();
+
+    for format in [
+        Format::DBus,
+        #[cfg(feature = "gvariant")]
+        Format::GVariant,
+    ] {
+        // Start at a non-zero position to test the padding.
+        let ctxt = Context::new(format, BE, 4);
+        let encoded = to_bytes(ctxt, &records).unwrap();
+        let mut count = 0;
+        let iter = encoded.array_iter::<(u32, &str, bool)>().unwrap();
+        for (record, (i, s, b)) in iter.zip(&records) {
+            assert_eq!(record.unwrap(), (*i, s.as_str(), *b));
+            count += 1;
+        }
+        assert_eq!(count, records.len());
+
+        // Fixed-sized elements.
+        let ctxt = Context::new(format, LE, 0);
+        let encoded = to_bytes(ctxt, &vec![(1u8, 2u32), (3, 4)]).unwrap();
+        let elements: Vec<(u8, u32)> = encoded
+            .array_iter()
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(elements, [(1, 2), (3, 4)]);
+
+        // Dicts.
+        let dict = HashMap::from([("one", vec![1u64]), ("two", vec![2, 2])]);
+        let encoded = to_bytes(ctxt, &dict).unwrap();
+        let decoded: HashMap<&str, Vec<u64>> = encoded
+            .array_iter()
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(decoded, dict);
+
+        // Empty arrays.
+        let encoded = to_bytes(ctxt, &Vec::<(u64, &str)>::new()).unwrap();
+        assert!(encoded
+            .array_iter::<(u64, &str)>()
+            .unwrap()
+            .next()
+            .is_none());
+    }
+}
+
+#[test]
+fn array_iter_errors() {
+    let ctxt = Context::new_dbus(LE, 0);
+    let encoded = to_bytes(ctxt, &vec!["hello", "world"]).unwrap();
+
+    // Errors end the iteration.
+    let mut iter = encoded.array_iter::<bool>().unwrap();
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+
+    // The array length can't exceed the data.
+    let encoded = encoded.slice(..encoded.len() - 1);
+    assert!(matches!(
+        encoded.array_iter::<&str>(),
+        Err(Error::Message(_))
+    ));
+}
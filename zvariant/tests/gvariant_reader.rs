@@ -0,0 +1,123 @@
+#![cfg(feature = "gvariant")]
+
+use std::collections::HashMap;
+
+use zvariant::{gvariant::Reader, serialized::Context, to_bytes, Error, Type, Value, LE};
+
+#[test]
+fn reader_arrays() {
+    let ctxt = Context::new_gvariant(LE, 0);
+
+    // Fixed-sized elements.
+    let numbers: Vec<u32> = (0..100).collect();
+    let encoded = to_bytes(ctxt, &numbers).unwrap();
+    let reader = Reader::new(&encoded, ctxt, <Vec<u32>>::SIGNATURE).unwrap();
+    assert_eq!(reader.len().unwrap(), 100);
+    assert_eq!(
+        reader.element(42).unwrap().deserialize::<u32>().unwrap(),
+        42
+    );
+    assert!(matches!(reader.element(100), Err(Error::Message(_))));
+
+    // Variable-sized elements.
+    let strings = vec!["a", "", "hello", "world"];
+    let encoded = to_bytes(ctxt, &strings).unwrap();
+    let reader = Reader::new(&encoded, ctxt, <Vec<&str>>::SIGNATURE).unwrap();
+    assert_eq!(reader.len().unwrap(), 4);
+    for (i, s) in strings.iter().enumerate() {
+        assert_eq!(
+            reader.element(i).unwrap().deserialize::<&str>().unwrap(),
+            *s
+        );
+    }
+    assert!(matches!(reader.element(4), Err(Error::Message(_))));
+
+    // Empty arrays.
+    let encoded = to_bytes(ctxt, &Vec::<&str>::new()).unwrap();
+    let reader = Reader::new(&encoded, ctxt, <Vec<&str>>::SIGNATURE).unwrap();
+    assert!(reader.is_empty().unwrap());
+    assert!(matches!(reader.element(0), Err(Error::Message(_))));
+
+    // Not an array.
+    let encoded = to_bytes(ctxt, &7u32).unwrap();
+    let reader = Reader::new(&encoded, ctxt, u32::SIGNATURE).unwrap();
+    assert!(matches!(
+        reader.element(0),
+        Err(Error::SignatureMismatch(_, _))
+    ));
+}
+
+#[test]
+fn reader_structs_and_dicts() {
+    type Record<'s> = (
+        u8,
+        &'s str,
+        u64,
+        Vec<(u16, &'s str)>,
+        HashMap<&'s str, Value<'s>>,
+        Option<&'s str>,
+        Option<u32>,
+    );
+    let ctxt = Context::new_gvariant(LE, 0);
+    let value: Record<'_> = (
+        1u8,
+        "variable",
+        2u64,
+        vec![(3u16, "three")],
+        HashMap::from([
+            ("size", Value::from(42u32)),
+            ("label", Value::from("seven")),
+        ]),
+        Some("maybe"),
+        None::<u32>,
+    );
+    let encoded = to_bytes(ctxt, &value).unwrap();
+    let reader = Reader::new(&encoded, ctxt, Record::SIGNATURE).unwrap();
+
+    assert_eq!(reader.field(0).unwrap().deserialize::<u8>().unwrap(), 1);
+    assert_eq!(
+        reader.field(1).unwrap().deserialize::<&str>().unwrap(),
+        "variable"
+    );
+    assert_eq!(reader.field(2).unwrap().deserialize::<u64>().unwrap(), 2);
+    let entry = reader.field(3).unwrap().element(0).unwrap();
+    assert_eq!(
+        entry.field(1).unwrap().deserialize::<&str>().unwrap(),
+        "three"
+    );
+    assert!(matches!(reader.field(7), Err(Error::Message(_))));
+
+    let dict = reader.field(4).unwrap();
+    assert_eq!(dict.len().unwrap(), 2);
+    let label = dict.get("label").unwrap().unwrap();
+    assert_eq!(label.signature(), "v");
+    let label = label.variant().unwrap();
+    assert_eq!(label.signature(), "s");
+    assert_eq!(label.deserialize::<&str>().unwrap(), "seven");
+    let size = dict.get("size").unwrap().unwrap().variant().unwrap();
+    assert_eq!(size.deserialize::<u32>().unwrap(), 42);
+    assert!(dict.get("missing").unwrap().is_none());
+
+    let just = reader.field(5).unwrap().maybe().unwrap().unwrap();
+    assert_eq!(just.deserialize::<&str>().unwrap(), "maybe");
+    assert!(reader.field(6).unwrap().maybe().unwrap().is_none());
+}
+
+#[test]
+fn reader_errors() {
+    let ctxt = Context::new_dbus(LE, 0);
+    let encoded = to_bytes(ctxt, &7u32).unwrap();
+    assert!(matches!(
+        Reader::new(&encoded, ctxt, u32::SIGNATURE),
+        Err(Error::IncompatibleFormat(_, _))
+    ));
+
+    // The framing offset points past the end of the data.
+    let ctxt = Context::new_gvariant(LE, 0);
+    let encoded = to_bytes(ctxt, &vec!["hello", "world"]).unwrap();
+    let mut bytes = encoded.to_vec();
+    let last = bytes.len() - 1;
+    bytes[last] = 0xff;
+    let reader = Reader::new(&bytes, ctxt, <Vec<&str>>::SIGNATURE).unwrap();
+    assert!(reader.element(1).is_err());
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use zvariant::{
+    serialized::{Context, Format},
+    to_bytes, Error, Type, Value, View, BE, LE,
+};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize, Type, View)]
+struct Record<'s> {
+    id: u32,
+    tags: Vec<&'s str>,
+    point: (u8, i64),
+    properties: HashMap<&'s str, Value<'s>>,
+    name: &'s str,
+}
+
+#[test]
+fn view() {
+    view_for_format(Format::DBus);
+    #[cfg(feature = "gvariant")]
+    view_for_format(Format::GVariant);
+}
+
+fn view_for_format(format: Format) {
+    let record = Record {
+        id: 7,
+        tags: vec!["a", "bc", "def"],
+        point: (1, -1),
+        properties: HashMap::from([
+            ("size", Value::from(42u64)),
+            ("label", Value::from("seven")),
+            ("nested", Value::Value(Box::new(Value::from(true)))),
+        ]),
+        name: "record",
+    };
+
+    // Start at a non-zero position to test the padding.
+    let ctxt = Context::new(format, BE, 3);
+    let encoded = to_bytes(ctxt, &record).unwrap();
+    let view = RecordView::new(&encoded).unwrap();
+    assert_eq!(view.name().unwrap(), "record");
+    assert_eq!(view.point().unwrap(), (1, -1));
+    assert_eq!(view.tags().unwrap(), record.tags);
+    assert_eq!(view.id().unwrap(), 7);
+
+    let properties = view.as_view().field(3).unwrap();
+    let label = properties.get("label").unwrap().unwrap();
+    assert_eq!(label.signature(), "v");
+    assert_eq!(
+        label.variant().unwrap().deserialize::<&str>().unwrap(),
+        "seven"
+    );
+    let nested = properties.get("nested").unwrap().unwrap();
+    let nested = nested.variant().unwrap().variant().unwrap();
+    assert!(nested.deserialize::<bool>().unwrap());
+    assert!(properties.get("missing").unwrap().is_none());
+
+    let tags = view.as_view().field(1).unwrap();
+    let tags: Vec<&str> = tags
+        .elements()
+        .unwrap()
+        .map(|tag| tag.and_then(|tag| tag.deserialize()))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(tags, record.tags);
+
+    assert!(matches!(view.as_view().field(5), Err(Error::Message(_))));
+    assert!(matches!(
+        view.as_view().field(0).unwrap().field(0),
+        Err(Error::SignatureMismatch(_, _))
+    ));
+
+    // Fixed-sized elements.
+    let ctxt = Context::new(format, LE, 0);
+    let encoded = to_bytes(ctxt, &vec![(1u8, 2u32), (3, 4)]).unwrap();
+    let view = encoded.view(<Vec<(u8, u32)>>::SIGNATURE).unwrap();
+    let second = view.elements().unwrap().nth(1).unwrap().unwrap();
+    assert_eq!(second.field(1).unwrap().deserialize::<u32>().unwrap(), 4);
+
+    // The signature is checked.
+    let view = encoded.view(<Vec<(u8, u32)>>::SIGNATURE).unwrap();
+    assert!(matches!(
+        RecordView::try_from(view),
+        Err(Error::SignatureMismatch(_, _))
+    ));
+}
+
+#[test]
+fn view_invalid() {
+    let ctxt = Context::new_dbus(LE, 0);
+    let encoded = to_bytes(ctxt, &(1u32, "hello")).unwrap();
+
+    // The string is longer than the data.
+    let encoded = encoded.slice(..encoded.len() - 2);
+    assert!(encoded.view(<(u32, &str)>::SIGNATURE).is_err());
+}
@@ -19,6 +19,7 @@ mod signature;
 mod r#type;
 mod utils;
 mod value;
+mod view;
 
 /// Derive macro to add [`Type`] implementation to structs and enums.
 ///
@@ -551,6 +552,84 @@ pub fn owned_value_macro_derive(input: TokenStream) -> TokenStream {
         .into()
 }
 
+/// Generates a lazily-decoded view type for structs.
+///
+/// For a struct `Foo`, this generates a `FooView` type that wraps a [`View`] of a serialized
+/// `Foo`, with a method for each field. Fields are only located and deserialized when their
+/// method is called, instead of deserializing the whole struct. This is most useful for large
+/// structs, when only some of the fields are needed. The struct must also implement [`Type`].
+///
+/// Lifetimes of the struct are replaced by the one of the serialized data, so borrowed fields
+/// are returned without copying them.
+///
+/// # Examples
+///
+/// ```
+/// use zvariant::{serialized::Context, to_bytes, Type, View, LE};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize, Type, View)]
+/// struct Package<'s> {
+///     name: &'s str,
+///     files: Vec<&'s str>,
+///     size: u64,
+/// }
+///
+/// let package = Package {
+///     name: "zbus",
+///     files: vec!["Cargo.toml", "src/lib.rs"],
+///     size: 42,
+/// };
+/// let ctxt = Context::new_dbus(LE, 0);
+/// let encoded = to_bytes(ctxt, &package).unwrap();
+///
+/// let view = PackageView::new(&encoded).unwrap();
+/// // The files are skipped over to get to the size.
+/// assert_eq!(view.size().unwrap(), 42);
+/// assert_eq!(view.name().unwrap(), "zbus");
+/// ```
+///
+/// Structs serialized as `a{sv}` dictionaries through [`SerializeDict`] and [`DeserializeDict`]
+/// are supported as well. The entries of the dictionary are then walked to find the requested
+/// field, only deserializing the keys on the way. Just like with [`DeserializeDict`], `Option`
+/// fields are optional:
+///
+/// ```
+/// use zvariant::{serialized::Context, to_bytes, SerializeDict, Type, View, LE};
+///
+/// #[derive(SerializeDict, Type, View)]
+/// #[zvariant(signature = "a{sv}", rename_all = "PascalCase")]
+/// struct Unit {
+///     id: String,
+///     active_state: String,
+///     description: Option<String>,
+/// }
+///
+/// let unit = Unit {
+///     id: "dbus.service".to_string(),
+///     active_state: "active".to_string(),
+///     description: None,
+/// };
+/// let ctxt = Context::new_dbus(LE, 0);
+/// let encoded = to_bytes(ctxt, &unit).unwrap();
+///
+/// let view = UnitView::new(&encoded).unwrap();
+/// assert_eq!(view.active_state().unwrap(), "active");
+/// assert_eq!(view.description().unwrap(), None);
+/// ```
+///
+/// [`View`]: https://docs.rs/zvariant/latest/zvariant/serialized/struct.View.html
+/// [`Type`]: https://docs.rs/zvariant/latest/zvariant/trait.Type.html
+/// [`SerializeDict`]: macro@SerializeDict
+/// [`DeserializeDict`]: macro@DeserializeDict
+#[proc_macro_derive(View, attributes(zbus, zvariant))]
+pub fn view_macro_derive(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).unwrap();
+    view::expand_derive(ast)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
 /// Constructs a const [`Signature`] with compile-time validation.
 ///
 /// This macro creates a `Signature` from a string literal at compile time, validating
@@ -0,0 +1,221 @@
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    spanned::Spanned, Data, DeriveInput, Error, Fields, GenericArgument, GenericParam,
+    PathArguments, Type,
+};
+use zvariant_utils::macros;
+
+use crate::utils::*;
+
+/// Implements a lazily-decoded view type for structs.
+pub fn expand_derive(input: DeriveInput) -> Result<TokenStream, Error> {
+    let StructAttributes {
+        signature,
+        rename_all,
+        crate_path: crate_attr,
+        ..
+    } = StructAttributes::parse(&input.attrs)?;
+    let crate_path = parse_crate_path(crate_attr.as_deref())?;
+    let zv = zvariant_path(crate_path.as_ref());
+    let dict = match signature.as_deref() {
+        None => false,
+        Some("dict" | "a{sv}") => true,
+        Some(_) => {
+            return Err(Error::new(
+                input.span(),
+                "views are only supported for structures and `a{sv}` dictionaries",
+            ))
+        }
+    };
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) if !fields.named.is_empty() => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "views are only supported for structs with named fields",
+                ))
+            }
+        },
+        _ => return Err(Error::new(input.span(), "only structs supported")),
+    };
+
+    // The view borrows from the serialized data, so all lifetimes of the struct are replaced by
+    // the one of the data, and the `Type` implementation is looked up with `'static` lifetimes.
+    let mut static_lifetimes = vec![];
+    for param in &input.generics.params {
+        match param {
+            GenericParam::Lifetime(_) => static_lifetimes.push(quote! { 'static }),
+            _ => {
+                return Err(Error::new(
+                    param.span(),
+                    "views are not supported for generic structs",
+                ))
+            }
+        }
+    }
+    let name = &input.ident;
+    let ty = if static_lifetimes.is_empty() {
+        quote! { #name }
+    } else {
+        quote! { #name<#(#static_lifetimes),*> }
+    };
+    let view_name = format_ident!("{}View", name);
+    let vis = &input.vis;
+
+    let mut accessors = Vec::with_capacity(fields.len());
+    for (i, field) in fields.iter().enumerate() {
+        let ident = field.ident.as_ref().unwrap();
+        if ident == "new" || ident == "as_view" {
+            return Err(Error::new(
+                ident.span(),
+                format!("field name `{ident}` conflicts with a method of the view"),
+            ));
+        }
+        let ty = replace_lifetimes(&field.ty, "'d")?;
+        let doc = format!(" The `{ident}` field, decoded on demand.");
+
+        let accessor = if dict {
+            let FieldAttributes { rename, .. } = FieldAttributes::parse(&field.attrs)?;
+            let key = rename_identifier(
+                ident.to_string(),
+                field.span(),
+                rename,
+                rename_all.as_deref(),
+            )?;
+
+            match option_inner_ty(&ty) {
+                Some(inner_ty) => quote! {
+                    #[doc = #doc]
+                    #vis fn #ident(&self) -> #zv::Result<::std::option::Option<#inner_ty>> {
+                        match self.view.get(#key)? {
+                            ::std::option::Option::Some(value) => {
+                                value.variant()?.deserialize().map(::std::option::Option::Some)
+                            }
+                            ::std::option::Option::None => ::std::result::Result::Ok(
+                                ::std::option::Option::None,
+                            ),
+                        }
+                    }
+                },
+                None => quote! {
+                    #[doc = #doc]
+                    #vis fn #ident(&self) -> #zv::Result<#ty> {
+                        self.view
+                            .get(#key)?
+                            .ok_or_else(|| {
+                                <#zv::Error as #zv::export::serde::de::Error>::missing_field(#key)
+                            })?
+                            .variant()?
+                            .deserialize()
+                    }
+                },
+            }
+        } else {
+            quote! {
+                #[doc = #doc]
+                #vis fn #ident(&self) -> #zv::Result<#ty> {
+                    self.view.field(#i)?.deserialize()
+                }
+            }
+        };
+        accessors.push(accessor);
+    }
+
+    let doc = format!(" A lazily-decoded view of a serialized [`{name}`].");
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(::std::fmt::Debug, ::std::clone::Clone)]
+        #vis struct #view_name<'d, 'bytes, 'fds> {
+            view: #zv::serialized::View<'d, 'bytes, 'fds>,
+        }
+
+        #[allow(dead_code)]
+        impl<'d, 'bytes, 'fds> #view_name<'d, 'bytes, 'fds> {
+            /// Create a view of the value at the start of `data`.
+            #vis fn new(data: &'d #zv::serialized::Data<'bytes, 'fds>) -> #zv::Result<Self> {
+                ::std::convert::TryFrom::try_from(data.view(<#ty as #zv::Type>::SIGNATURE)?)
+            }
+
+            /// The untyped view of the value.
+            #vis fn as_view(&self) -> &#zv::serialized::View<'d, 'bytes, 'fds> {
+                &self.view
+            }
+
+            #(#accessors)*
+        }
+
+        impl<'d, 'bytes, 'fds> ::std::convert::TryFrom<#zv::serialized::View<'d, 'bytes, 'fds>>
+            for #view_name<'d, 'bytes, 'fds>
+        {
+            type Error = #zv::Error;
+
+            fn try_from(
+                view: #zv::serialized::View<'d, 'bytes, 'fds>,
+            ) -> #zv::Result<Self> {
+                let signature = <#ty as #zv::Type>::SIGNATURE;
+                if view.signature() != signature {
+                    return ::std::result::Result::Err(#zv::Error::SignatureMismatch(
+                        ::std::clone::Clone::clone(view.signature()),
+                        ::std::string::ToString::to_string(signature),
+                    ));
+                }
+
+                ::std::result::Result::Ok(Self { view })
+            }
+        }
+    })
+}
+
+/// The type wrapped in an `Option`, if `ty` is one.
+fn option_inner_ty(ty: &Type) -> Option<&Type> {
+    if !macros::ty_is_option(ty) {
+        return None;
+    }
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    match &path.path.segments.last()?.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Replaces all the non-`'static` lifetimes in `ty` with `lifetime`.
+fn replace_lifetimes(ty: &Type, lifetime: &str) -> Result<Type, Error> {
+    fn replace(tokens: TokenStream, lifetime: &str) -> TokenStream {
+        let mut replaced = TokenStream::new();
+        let mut tokens = tokens.into_iter();
+        while let Some(token) = tokens.next() {
+            match token {
+                TokenTree::Punct(punct) if punct.as_char() == '\'' => match tokens.next() {
+                    Some(TokenTree::Ident(ident)) if ident != "static" => {
+                        syn::Lifetime::new(lifetime, ident.span()).to_tokens(&mut replaced);
+                    }
+                    next => {
+                        replaced.extend([TokenTree::Punct(punct)]);
+                        replaced.extend(next);
+                    }
+                },
+                TokenTree::Group(group) => {
+                    let mut new_group =
+                        Group::new(group.delimiter(), replace(group.stream(), lifetime));
+                    new_group.set_span(group.span());
+                    replaced.extend([TokenTree::Group(new_group)]);
+                }
+                token => replaced.extend([token]),
+            }
+        }
+
+        replaced
+    }
+
+    syn::parse2(replace(ty.to_token_stream(), lifetime))
+        .map_err(|e| Error::new(Span::call_site(), e))
+}
@@ -40,3 +40,16 @@ struct Test {
     #[serde(with = "as_value")]
     field_c: ::std::vec::Vec<u8>,
 }
+
+#[derive(Type, ::zvariant_derive::View)]
+struct ViewStruct {
+    name: ::std::string::String,
+    blob: ::std::vec::Vec<u8>,
+}
+
+#[derive(Type, ::zvariant_derive::View)]
+#[zvariant(signature = "a{sv}")]
+struct ViewDict {
+    name: ::std::string::String,
+    blob: ::std::option::Option<::std::vec::Vec<u8>>,
+}
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 use zvariant::{
     as_value::{self, optional},
     serialized::{Context, Format},
-    OwnedValue, Type, Value, LE,
+    OwnedValue, SerializeDict, Type, Value, View, LE,
 };
 
 #[test]
@@ -88,6 +88,35 @@ fn derive_dict() {
     assert_eq!(Test::SIGNATURE, "a{sv}")
 }
 
+#[test]
+fn derive_view() {
+    #[derive(SerializeDict, Type, View)]
+    #[zvariant(signature = "dict", rename_all = "camelCase")]
+    struct Test {
+        field_a: Option<u32>,
+        #[zvariant(rename = "field-b")]
+        field_b: String,
+        field_c: Vec<u8>,
+    }
+
+    let test = Test {
+        field_a: None,
+        field_b: "foo".to_string(),
+        field_c: vec![1, 2, 3],
+    };
+    let ctxt = Context::new(Format::DBus, LE, 0);
+    let serialized = zvariant::to_bytes(ctxt, &test).unwrap();
+    let view = TestView::new(&serialized).unwrap();
+    assert_eq!(view.field_c().unwrap(), [1, 2, 3]);
+    assert_eq!(view.field_b().unwrap(), "foo");
+    assert_eq!(view.field_a().unwrap(), None);
+
+    // Non-optional fields are required.
+    let serialized = zvariant::to_bytes(ctxt, &HashMap::<&str, Value<'_>>::new()).unwrap();
+    let view = TestView::new(&serialized).unwrap();
+    assert!(matches!(view.field_b(), Err(zvariant::Error::Message(_))));
+}
+
 #[test]
 #[ignore]
 fn issues_311() {